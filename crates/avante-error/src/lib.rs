@@ -0,0 +1,68 @@
+use mlua::prelude::*;
+use std::fmt;
+
+// Failures of the native modules. Functions report the bare cause, the Lua boundary adds the
+// module and the exported function, e.g. `avante_tokenizers: encode: Tokenizer not initialized`
+#[derive(Debug, Clone)]
+pub enum Error {
+    Parse(String),
+    Render(String),
+    Network(String),
+    Download(String),
+    Io(String),
+    NotInitialized(String),
+    NotFound(String),
+    Unsupported(String),
+    InvalidArgument(String),
+    // A configured bound was hit, e.g. the number of files or open sessions
+    LimitExceeded(String),
+    Cancelled,
+    Operation(&'static str, Box<Error>),
+}
+
+impl Error {
+    #[must_use]
+    pub fn during(self, operation: &'static str) -> Self {
+        Error::Operation(operation, Box::new(self))
+    }
+
+    // Error raised to Lua when the exported `operation` of `module` fails
+    pub fn into_lua(self, module: &str, operation: &'static str) -> LuaError {
+        LuaError::RuntimeError(format!("{module}: {}", self.during(operation)))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(message)
+            | Error::Render(message)
+            | Error::Network(message)
+            | Error::Download(message)
+            | Error::Io(message)
+            | Error::NotInitialized(message)
+            | Error::NotFound(message)
+            | Error::Unsupported(message)
+            | Error::InvalidArgument(message)
+            | Error::LimitExceeded(message) => write!(f, "{message}"),
+            Error::Cancelled => write!(f, "cancelled"),
+            Error::Operation(operation, error) => write!(f, "{operation}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_lua() {
+        let error = Error::NotInitialized("Tokenizer not initialized".to_string())
+            .into_lua("avante_tokenizers", "encode");
+        assert!(error
+            .to_string()
+            .contains("avante_tokenizers: encode: Tokenizer not initialized"));
+    }
+}
@@ -1,70 +1,821 @@
+use avante_error::Error;
 use htmd::HtmlToMarkdown;
+use html5ever::serialize::{serialize, SerializeOpts, TraversalScope};
+use html5ever::tendril::TendrilSink;
+use html5ever::{parse_document, ParseOpts};
+use markup5ever_rcdom::{Handle, NodeData, RcDom, SerializableHandle};
 use mlua::prelude::*;
-use std::error::Error;
+use std::cell::Cell;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
-#[derive(Debug)]
-enum MyError {
-    HtmlToMd(String),
-    Request(String),
+// Prefix of the errors raised to Lua
+const LUA_MODULE: &str = "avante_html2md";
+
+fn request_error(error: impl std::fmt::Display) -> Error {
+    Error::Network(format!("Request error: {error}"))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum LinkStyle {
+    // `[text](url)`
+    #[default]
+    Inline,
+    // `[text][1]` with the `[1]: url` definitions collected at the end
+    Reference,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ConvertOptions {
+    link_style: LinkStyle,
+    // Drop comments inside fenced code blocks to save tokens on heavily annotated examples
+    strip_code_comments: bool,
+    // Convert only these elements with their content and drop the rest, everything when empty
+    keep_only_tags: Vec<String>,
+    // Convert only the main content, found the same way as the text of `analyze_page`
+    main_content: bool,
+}
+
+// An optional field of a Lua options table, a value of the wrong type is an invalid argument
+fn get_option<T: mlua::FromLua>(options: &LuaTable, key: &str) -> Result<Option<T>, Error> {
+    options
+        .get::<Option<T>>(key)
+        .map_err(|e| Error::InvalidArgument(format!("Invalid {key}: {e}")))
 }
 
-impl std::fmt::Display for MyError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            MyError::HtmlToMd(e) => write!(f, "HTML to Markdown error: {e}"),
-            MyError::Request(e) => write!(f, "Request error: {e}"),
+impl ConvertOptions {
+    fn from_lua(options: Option<LuaTable>) -> Result<Self, Error> {
+        let mut convert_options = ConvertOptions::default();
+        let Some(options) = options else {
+            return Ok(convert_options);
+        };
+        if let Some(link_style) = get_option::<String>(&options, "link_style")? {
+            convert_options.link_style = match link_style.as_str() {
+                "inline" => LinkStyle::Inline,
+                "reference" => LinkStyle::Reference,
+                _ => {
+                    return Err(Error::InvalidArgument(format!(
+                        "Invalid link_style: {link_style}, expected inline or reference"
+                    )))
+                }
+            };
+        }
+        if let Some(strip_code_comments) = get_option(&options, "strip_code_comments")? {
+            convert_options.strip_code_comments = strip_code_comments;
+        }
+        if let Some(keep_only_tags) = get_option(&options, "keep_only_tags")? {
+            convert_options.keep_only_tags = keep_only_tags;
         }
+        if let Some(main_content) = get_option(&options, "main_content")? {
+            convert_options.main_content = main_content;
+        }
+        Ok(convert_options)
     }
 }
 
-impl Error for MyError {}
-
-fn do_html2md(html: &str) -> Result<String, MyError> {
+fn do_html2md(html: &str, options: &ConvertOptions) -> Result<String, Error> {
+    let link_style = match options.link_style {
+        LinkStyle::Inline => htmd::options::LinkStyle::Inlined,
+        LinkStyle::Reference => htmd::options::LinkStyle::Referenced,
+    };
+    let skip_tags = if options.main_content {
+        SKIPPED_TEXT_TAGS.to_vec()
+    } else {
+        vec!["script", "style", "header", "footer"]
+    };
     let converter = HtmlToMarkdown::builder()
-        .skip_tags(vec!["script", "style", "header", "footer"])
+        .skip_tags(skip_tags)
+        .options(htmd::options::Options {
+            link_style,
+            ..Default::default()
+        })
         .build();
+    let main_content;
+    let html = if options.main_content {
+        main_content = extract_main_content(html)?;
+        main_content.as_str()
+    } else {
+        html
+    };
+    let kept;
+    let html = if options.keep_only_tags.is_empty() {
+        html
+    } else {
+        kept = keep_only_tags(html, &options.keep_only_tags)?;
+        kept.as_str()
+    };
     let md = converter
         .convert(html)
-        .map_err(|e| MyError::HtmlToMd(e.to_string()))?;
+        .map_err(|e| Error::Parse(format!("HTML to Markdown error: {e}")))?;
+    if options.strip_code_comments {
+        return Ok(strip_code_comments(&md));
+    }
     Ok(md)
 }
 
-fn do_fetch_md(url: &str) -> Result<String, MyError> {
+// The elements named in `tags` with everything inside them, text and markup outside of them is
+// dropped. A clean article is the `<p>`, `<h1>` and `<li>` elements without the page around them.
+// The filtering runs on the html5ever DOM that htmd converts, as htmd's handlers only see the
+// already converted Markdown of the children.
+fn keep_only_tags(html: &str, tags: &[String]) -> Result<String, Error> {
+    let dom = parse_document(RcDom::default(), ParseOpts::default()).one(html);
+    let mut kept = Vec::new();
+    collect_kept_elements(&dom.document, tags, &mut kept);
+    serialize_nodes(&kept, &TraversalScope::IncludeNode)
+}
+
+// The HTML of `nodes` one per line, with the nodes themselves or only their children
+fn serialize_nodes(nodes: &[Handle], traversal_scope: &TraversalScope) -> Result<String, Error> {
+    let mut buffer = Vec::new();
+    for node in nodes {
+        let options = SerializeOpts {
+            traversal_scope: traversal_scope.clone(),
+            ..Default::default()
+        };
+        serialize(
+            &mut buffer,
+            &SerializableHandle::from(node.clone()),
+            options,
+        )
+        .map_err(|e| Error::Parse(format!("HTML serialization error: {e}")))?;
+        buffer.push(b'\n');
+    }
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+// The outermost elements named in `tags`, their descendants come along with them
+fn collect_kept_elements(node: &Handle, tags: &[String], kept: &mut Vec<Handle>) {
+    if let NodeData::Element { name, .. } = &node.data {
+        if tags.iter().any(|tag| tag.eq_ignore_ascii_case(&name.local)) {
+            kept.push(node.clone());
+            return;
+        }
+    }
+    for child in node.children.borrow().iter() {
+        collect_kept_elements(child, tags, kept);
+    }
+}
+
+// How comments and strings look in the code of a fence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CommentSyntax {
+    // Starts a comment running to the end of the line
+    line: &'static str,
+    // Opens and closes a comment that may span lines
+    block: Option<(&'static str, &'static str)>,
+    // Characters opening and closing a string
+    quotes: &'static str,
+    // `'` only opens a character literal closed right after, otherwise it is a Rust lifetime or
+    // part of a Haskell name such as `foldl'`
+    char_literals: bool,
+    // Shells only start a comment at the beginning of a word, `$#` is a variable, and take single
+    // quoted strings literally
+    shell: bool,
+}
+
+const HASH_COMMENTS: CommentSyntax = CommentSyntax {
+    line: "#",
+    block: None,
+    quotes: "\"'",
+    char_literals: false,
+    shell: false,
+};
+
+const SLASH_COMMENTS: CommentSyntax = CommentSyntax {
+    line: "//",
+    block: Some(("/*", "*/")),
+    quotes: "\"'",
+    char_literals: false,
+    shell: false,
+};
+
+// Taken from the info string of a fence, ```` ```python ````, as the repo-map language
+// detection lives in a separate cdylib
+fn comment_syntax(language: &str) -> Option<CommentSyntax> {
+    match language.to_ascii_lowercase().as_str() {
+        "python" | "py" | "r" | "elixir" | "yaml" | "yml" | "toml" | "dockerfile" | "makefile" => {
+            Some(HASH_COMMENTS)
+        }
+        "ruby" | "rb" | "perl" => Some(CommentSyntax {
+            quotes: "\"'`",
+            ..HASH_COMMENTS
+        }),
+        "sh" | "bash" | "zsh" | "shell" => Some(CommentSyntax {
+            quotes: "\"'`",
+            shell: true,
+            ..HASH_COMMENTS
+        }),
+        "c" | "h" | "cpp" | "c++" | "java" | "kotlin" | "swift" | "scala" | "csharp" | "cs"
+        | "php" | "zig" => Some(SLASH_COMMENTS),
+        "go" | "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" | "dart" => {
+            Some(CommentSyntax {
+                quotes: "\"'`",
+                ..SLASH_COMMENTS
+            })
+        }
+        "rust" | "rs" => Some(CommentSyntax {
+            char_literals: true,
+            ..SLASH_COMMENTS
+        }),
+        "lua" => Some(CommentSyntax {
+            line: "--",
+            block: Some(("--[[", "]]")),
+            ..HASH_COMMENTS
+        }),
+        "sql" => Some(CommentSyntax {
+            line: "--",
+            ..SLASH_COMMENTS
+        }),
+        "haskell" | "hs" => Some(CommentSyntax {
+            line: "--",
+            block: Some(("{-", "-}")),
+            char_literals: true,
+            ..SLASH_COMMENTS
+        }),
+        _ => None,
+    }
+}
+
+fn starts_at(chars: &[char], index: usize, token: &str) -> bool {
+    token
+        .chars()
+        .enumerate()
+        .all(|(offset, c)| chars.get(index + offset) == Some(&c))
+}
+
+// `'x'` or `'\n'` as opposed to the `'a` of a lifetime
+fn is_char_literal(chars: &[char], index: usize) -> bool {
+    match chars.get(index + 1) {
+        Some('\\') => true,
+        Some(_) => chars.get(index + 2) == Some(&'\''),
+        None => false,
+    }
+}
+
+// The code of a line without its comments, `None` when the line held nothing but a comment.
+// Quotes are tracked so that `"#fff"` or `"http://"` stay intact.
+fn strip_line_comment(line: &str, syntax: CommentSyntax, in_block: &mut bool) -> Option<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let shebang = syntax.line == "#" && line.trim_start().starts_with("#!");
+    let mut code = String::new();
+    let mut quote = None;
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        if *in_block {
+            match syntax.block {
+                Some((_, close)) if starts_at(&chars, index, close) => {
+                    *in_block = false;
+                    index += close.chars().count();
+                }
+                _ => index += 1,
+            }
+            continue;
+        }
+        if let Some(open) = quote {
+            code.push(c);
+            if c == '\\' && !(syntax.shell && open == '\'') {
+                if let Some(next) = chars.get(index + 1) {
+                    code.push(*next);
+                    index += 1;
+                }
+            } else if c == open {
+                quote = None;
+            }
+            index += 1;
+            continue;
+        }
+        if syntax.quotes.contains(c)
+            && (c != '\'' || !syntax.char_literals || is_char_literal(&chars, index))
+        {
+            quote = Some(c);
+        } else if let Some((open, _)) = syntax
+            .block
+            .filter(|(open, _)| starts_at(&chars, index, open))
+        {
+            *in_block = true;
+            index += open.chars().count();
+            continue;
+        } else if starts_at(&chars, index, syntax.line) && !shebang {
+            let word_start = index == 0
+                || chars[index - 1].is_whitespace()
+                || matches!(chars[index - 1], ';' | '&' | '|' | '(' | ')');
+            if !syntax.shell || word_start {
+                break;
+            }
+        }
+        code.push(c);
+        index += 1;
+    }
+    let code = code.trim_end();
+    if code.trim().is_empty() && !line.trim().is_empty() {
+        return None;
+    }
+    Some(code.to_string())
+}
+
+// Drop line and block comments inside fenced code blocks whose language is known
+fn strip_code_comments(md: &str) -> String {
+    let mut lines = Vec::new();
+    let mut fence: Option<(String, Option<CommentSyntax>)> = None;
+    let mut in_block = false;
+    for line in md.lines() {
+        let trimmed = line.trim_start();
+        match &fence {
+            None => {
+                let marker_len = trimmed
+                    .chars()
+                    .take_while(|c| *c == '`' || *c == '~')
+                    .count();
+                if marker_len >= 3 {
+                    let marker = trimmed[..marker_len].to_string();
+                    let language = trimmed[marker_len..].split_whitespace().next();
+                    fence = Some((marker, language.and_then(comment_syntax)));
+                    in_block = false;
+                }
+                lines.push(line.to_string());
+            }
+            Some((marker, syntax)) => {
+                if trimmed.starts_with(marker.as_str())
+                    && trimmed.trim_start_matches(&marker[..1]).trim().is_empty()
+                {
+                    fence = None;
+                    lines.push(line.to_string());
+                    continue;
+                }
+                match syntax {
+                    Some(syntax) => {
+                        if let Some(code) = strip_line_comment(line, *syntax, &mut in_block) {
+                            lines.push(code);
+                        }
+                    }
+                    None => lines.push(line.to_string()),
+                }
+            }
+        }
+    }
+    let mut stripped = lines.join("\n");
+    if md.ends_with('\n') {
+        stripped.push('\n');
+    }
+    stripped
+}
+
+// Readability-style guess at the main content: the inner HTML of the <article> elements, then
+// <main>, then <body>. Each element ends at its own closing tag, so several articles keep the page
+// between them out.
+fn extract_main_content(html: &str) -> Result<String, Error> {
+    let dom = parse_document(RcDom::default(), ParseOpts::default()).one(html);
+    for tag in ["article", "main", "body"] {
+        let mut elements = Vec::new();
+        collect_kept_elements(&dom.document, &[tag.to_string()], &mut elements);
+        if !elements.is_empty() {
+            return serialize_nodes(&elements, &TraversalScope::ChildrenOnly(None));
+        }
+    }
+    Ok(html.to_string())
+}
+
+const SKIPPED_TEXT_TAGS: [&str; 9] = [
+    "script", "style", "noscript", "nav", "header", "footer", "aside", "svg", "template",
+];
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn html_to_text(html: &str) -> String {
+    let lowercase = html.to_ascii_lowercase();
+    let mut text = String::new();
+    let mut position = 0;
+    while let Some(offset) = html[position..].find('<') {
+        let tag_start = position + offset;
+        text.push_str(&html[position..tag_start]);
+        let Some(tag_length) = html[tag_start..].find('>') else {
+            position = html.len();
+            break;
+        };
+        let tag_end = tag_start + tag_length + 1;
+        let tag_name = lowercase[tag_start + 1..tag_end - 1]
+            .split(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+            .next()
+            .unwrap_or_default();
+        position = tag_end;
+        if SKIPPED_TEXT_TAGS.contains(&tag_name) {
+            let closing_tag = format!("</{tag_name}");
+            position = lowercase[tag_end..]
+                .find(&closing_tag)
+                .and_then(|i| {
+                    lowercase[tag_end + i..]
+                        .find('>')
+                        .map(|j| tag_end + i + j + 1)
+                })
+                .unwrap_or(html.len());
+        }
+        text.push(' ');
+    }
+    text.push_str(&html[position..]);
+    decode_entities(&text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{4e00}'..='\u{9fff}'
+        | '\u{3400}'..='\u{4dbf}'
+        | '\u{3040}'..='\u{30ff}'
+        | '\u{ac00}'..='\u{d7af}'
+        | '\u{f900}'..='\u{faff}')
+}
+
+// Every CJK character counts as a word, other words are runs of alphanumeric characters
+fn count_words(text: &str) -> usize {
+    let mut count = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_cjk(c) {
+            count += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                count += 1;
+            }
+            in_word = true;
+        } else if c != '\'' && c != '-' {
+            in_word = false;
+        }
+    }
+    count
+}
+
+const TRIGRAM_PROFILES: [(&str, [&str; 24]); 4] = [
+    (
+        "en",
+        [
+            " th", "the", "he ", "and", " an", "nd ", "ing", " to", "to ", "ng ", "er ", " in",
+            "ed ", " of", "of ", "ion", "is ", "at ", "on ", "tio", "ent", " is", "hat", "for",
+        ],
+    ),
+    (
+        "de",
+        [
+            "en ", "er ", "ich", "der", " de", "ie ", "sch", "ein", "die", " di", "che", "und",
+            " un", "den", " ei", "cht", "ch ", "ine", "gen", " da", "ung", " zu", "ter", "eit",
+        ],
+    ),
+    (
+        "fr",
+        [
+            "es ", " de", "de ", "le ", "ent", " le", "nt ", "la ", " la", "ion", "les", " pa",
+            "re ", "on ", "tio", " et", "et ", "que", " qu", "ne ", "des", "our", "par", "ans",
+        ],
+    ),
+    (
+        "es",
+        [
+            " de", "de ", "os ", "la ", " la", "el ", " el", "ent", "as ", "en ", " co", "que",
+            " qu", "ue ", "aci", "ien", "ado", " en", " se", "con", "los", " lo", "nte", "ión",
+        ],
+    ),
+];
+
+// Script ranges settle CJK and Cyrillic text, trigram profiles separate Latin languages
+fn detect_language(text: &str) -> &'static str {
+    let mut letters = 0usize;
+    let mut han = 0usize;
+    let mut kana = 0usize;
+    let mut hangul = 0usize;
+    let mut cyrillic = 0usize;
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        match c {
+            '\u{3040}'..='\u{30ff}' => kana += 1,
+            '\u{ac00}'..='\u{d7af}' => hangul += 1,
+            '\u{0400}'..='\u{04ff}' => cyrillic += 1,
+            c if is_cjk(c) => han += 1,
+            _ => {}
+        }
+    }
+    if letters < 10 {
+        return "und";
+    }
+    if kana * 10 > letters {
+        return "ja";
+    }
+    if hangul * 3 > letters {
+        return "ko";
+    }
+    if han * 3 > letters {
+        return "zh";
+    }
+    if cyrillic * 2 > letters {
+        return "ru";
+    }
+
+    let normalized = format!(
+        " {} ",
+        text.to_lowercase()
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    let chars = normalized.chars().collect::<Vec<_>>();
+    let trigrams = chars
+        .windows(3)
+        .map(|window| window.iter().collect::<String>())
+        .collect::<Vec<_>>();
+    if trigrams.is_empty() {
+        return "und";
+    }
+    let mut scores = TRIGRAM_PROFILES
+        .iter()
+        .map(|(language, profile)| {
+            let hits = trigrams
+                .iter()
+                .filter(|trigram| profile.contains(&trigram.as_str()))
+                .count();
+            (*language, hits)
+        })
+        .collect::<Vec<_>>();
+    scores.sort_by_key(|&(_, hits)| std::cmp::Reverse(hits));
+    let (best_language, best_hits) = scores[0];
+    let runner_up_hits = scores[1].1;
+    // Require a minimum density and a clear margin over the runner-up
+    if best_hits * 20 < trigrams.len() || best_hits * 4 < runner_up_hits * 5 {
+        return "und";
+    }
+    best_language
+}
+
+struct PageAnalysis {
+    text: String,
+    word_count: usize,
+    reading_time_minutes: usize,
+    language: &'static str,
+}
+
+fn do_analyze_page(html: &str) -> Result<PageAnalysis, Error> {
+    let text = html_to_text(&extract_main_content(html)?);
+    let word_count = count_words(&text);
+    let language = detect_language(&text);
+    // CJK readers get through characters faster than Latin readers get through words
+    let words_per_minute = match language {
+        "zh" | "ja" | "ko" => 400,
+        _ => 230,
+    };
+    Ok(PageAnalysis {
+        reading_time_minutes: word_count.div_ceil(words_per_minute),
+        text,
+        word_count,
+        language,
+    })
+}
+
+// Bounds on how long a fetch may wait for a connection or a stalled read
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+// How often a waiting fetch checks whether it was cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+fn build_client() -> Result<reqwest::blocking::Client, Error> {
     let mut headers = reqwest::header::HeaderMap::new();
     headers.insert(
         reqwest::header::USER_AGENT,
         reqwest::header::HeaderValue::from_static("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/103.0.0.0 Safari/537.36"),
     );
-    let client = reqwest::blocking::Client::builder()
+    reqwest::blocking::Client::builder()
         .default_headers(headers)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(READ_TIMEOUT)
         .build()
-        .map_err(|e| MyError::Request(e.to_string()))?;
-    let response = client
-        .get(url)
-        .send()
-        .map_err(|e| MyError::Request(e.to_string()))?;
-    let body = response
-        .text()
-        .map_err(|e| MyError::Request(e.to_string()))?;
+        .map_err(request_error)
+}
+
+fn do_fetch_md(url: &str, options: &ConvertOptions) -> Result<String, Error> {
+    let client = build_client()?;
+    let response = client.get(url).send().map_err(request_error)?;
+    let body = response.text().map_err(request_error)?;
     let html = body.trim().to_string();
-    let md = do_html2md(&html)?;
+    let md = do_html2md(&html, options)?;
     Ok(md)
 }
 
+// A fetch running on a background thread. The worker checks the cancellation flag while waiting
+// for the response and between chunks, so a cancelled download stops and drops the response
+// stream promptly.
+struct FetchTask {
+    cancelled: AtomicBool,
+    delivered: AtomicBool,
+    downloaded: AtomicUsize,
+    result: Mutex<Option<Result<String, Error>>>,
+}
+
+impl FetchTask {
+    fn spawn(url: String, options: ConvertOptions) -> Arc<Self> {
+        let task = Arc::new(FetchTask {
+            cancelled: AtomicBool::new(false),
+            delivered: AtomicBool::new(false),
+            downloaded: AtomicUsize::new(0),
+            result: Mutex::new(None),
+        });
+        let worker = Arc::clone(&task);
+        thread::spawn(move || {
+            let result = do_fetch_md_streaming(&url, &options, &worker);
+            *worker.result.lock().unwrap() = Some(result);
+        });
+        task
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    fn is_delivered(&self) -> bool {
+        self.delivered.load(Ordering::SeqCst)
+    }
+
+    fn downloaded(&self) -> usize {
+        self.downloaded.load(Ordering::SeqCst)
+    }
+
+    // Returns the outcome exactly once: the fetch result, or a cancellation error if the task was
+    // cancelled before the result was delivered. Every later call returns None.
+    fn take_outcome(&self) -> Option<Result<String, Error>> {
+        if self.is_cancelled() {
+            if self.delivered.swap(true, Ordering::SeqCst) {
+                return None;
+            }
+            return Some(Err(Error::Cancelled));
+        }
+        let mut result = self.result.lock().unwrap();
+        if result.is_none() || self.delivered.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        result.take()
+    }
+}
+
+// Sends the request on a helper thread so a cancelled task doesn't wait for a slow connect or
+// response headers. The abandoned helper exits once the client timeouts expire.
+fn send_cancellable(
+    request: reqwest::blocking::RequestBuilder,
+    task: &FetchTask,
+) -> Result<reqwest::blocking::Response, Error> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(request.send());
+    });
+    loop {
+        if task.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        match receiver.recv_timeout(CANCEL_POLL_INTERVAL) {
+            Ok(response) => return response.map_err(request_error),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(request_error("request thread exited"))
+            }
+        }
+    }
+}
+
+fn do_fetch_md_streaming(
+    url: &str,
+    options: &ConvertOptions,
+    task: &FetchTask,
+) -> Result<String, Error> {
+    let client = build_client()?;
+    let mut response = send_cancellable(client.get(url), task)?;
+    let mut body = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        if task.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        let read = response.read(&mut chunk).map_err(request_error)?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read]);
+        task.downloaded.fetch_add(read, Ordering::SeqCst);
+    }
+    let html = String::from_utf8_lossy(&body);
+    do_html2md(html.trim(), options)
+}
+
+// The Lua side drives the handle with `poll()` from a timer, so callbacks always run on the main
+// thread. `callback(md, err)` fires exactly once, `on_progress(bytes)` whenever bytes arrived.
+struct FetchHandle {
+    task: Arc<FetchTask>,
+    callback: LuaFunction,
+    on_progress: Option<LuaFunction>,
+    reported_bytes: Cell<usize>,
+}
+
+impl FetchHandle {
+    fn poll(&self) -> LuaResult<bool> {
+        if let Some(on_progress) = &self.on_progress {
+            let downloaded = self.task.downloaded();
+            if downloaded != self.reported_bytes.get() && !self.task.is_delivered() {
+                self.reported_bytes.set(downloaded);
+                on_progress.call::<()>(downloaded)?;
+            }
+        }
+        match self.task.take_outcome() {
+            Some(Ok(md)) => self.callback.call::<()>((md, LuaNil))?,
+            Some(Err(e)) => self
+                .callback
+                .call::<()>((LuaNil, e.during("fetch_md_async").to_string()))?,
+            None => {}
+        }
+        Ok(self.task.is_delivered())
+    }
+}
+
+impl LuaUserData for FetchHandle {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("poll", |_, this, ()| this.poll());
+        methods.add_method("cancel", |_, this, ()| {
+            this.task.cancel();
+            this.poll()
+        });
+        methods.add_method("downloaded", |_, this, ()| Ok(this.task.downloaded()));
+    }
+}
+
 #[mlua::lua_module]
 fn avante_html2md(lua: &Lua) -> LuaResult<LuaTable> {
     let exports = lua.create_table()?;
     exports.set(
         "fetch_md",
-        lua.create_function(move |_, url: String| -> LuaResult<String> {
-            do_fetch_md(&url).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
+        lua.create_function(
+            move |_, (url, options): (String, Option<LuaTable>)| -> LuaResult<String> {
+                ConvertOptions::from_lua(options)
+                    .and_then(|options| do_fetch_md(&url, &options))
+                    .map_err(|e| e.into_lua(LUA_MODULE, "fetch_md"))
+            },
+        )?,
+    )?;
+    exports.set(
+        "fetch_md_async",
+        lua.create_function(
+            move |_,
+                  (url, callback, on_progress, options): (
+                String,
+                LuaFunction,
+                Option<LuaFunction>,
+                Option<LuaTable>,
+            )| {
+                let options = ConvertOptions::from_lua(options)
+                    .map_err(|e| e.into_lua(LUA_MODULE, "fetch_md_async"))?;
+                Ok(FetchHandle {
+                    task: FetchTask::spawn(url, options),
+                    callback,
+                    on_progress,
+                    reported_bytes: Cell::new(0),
+                })
+            },
+        )?,
+    )?;
+    exports.set(
+        "analyze_page",
+        lua.create_function(move |lua, html: String| -> LuaResult<LuaTable> {
+            let analysis =
+                do_analyze_page(&html).map_err(|e| e.into_lua(LUA_MODULE, "analyze_page"))?;
+            let result = lua.create_table()?;
+            result.set("text", analysis.text)?;
+            result.set("word_count", analysis.word_count)?;
+            result.set("reading_time_minutes", analysis.reading_time_minutes)?;
+            result.set("language", analysis.language)?;
+            Ok(result)
         })?,
     )?;
     exports.set(
         "html2md",
-        lua.create_function(move |_, html: String| -> LuaResult<String> {
-            do_html2md(&html).map_err(|e| mlua::Error::RuntimeError(e.to_string()))
-        })?,
+        lua.create_function(
+            move |_, (html, options): (String, Option<LuaTable>)| -> LuaResult<String> {
+                ConvertOptions::from_lua(options)
+                    .and_then(|options| do_html2md(&html, &options))
+                    .map_err(|e| e.into_lua(LUA_MODULE, "html2md"))
+            },
+        )?,
     )?;
     Ok(exports)
 }
@@ -72,10 +823,336 @@ fn avante_html2md(lua: &Lua) -> LuaResult<LuaTable> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    // Serves `chunks` pieces of HTML with a delay between each and reports how many were sent
+    // before the client went away.
+    fn spawn_slow_server(chunks: usize, delay: Duration) -> (String, mpsc::Receiver<usize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let chunk = "<p>hello</p>".repeat(100);
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                chunk.len() * chunks
+            );
+            let mut sent = 0;
+            if stream.write_all(header.as_bytes()).is_ok() {
+                for _ in 0..chunks {
+                    if stream.write_all(chunk.as_bytes()).is_err() || stream.flush().is_err() {
+                        break;
+                    }
+                    sent += 1;
+                    thread::sleep(delay);
+                }
+            }
+            let _ = sender.send(sent);
+        });
+        (format!("http://{address}/"), receiver)
+    }
+
+    fn wait_for(condition: impl Fn() -> bool) {
+        let started = Instant::now();
+        while !condition() {
+            assert!(started.elapsed() < Duration::from_secs(10), "timed out");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_analyze_page_english() {
+        let html = r"<html><head><title>Ignored</title><style>p { color: red; }</style></head>
+            <body><nav>Home | About</nav><article><h1>The quick guide</h1>
+            <p>This is the main article and it explains how the tool works in practice.</p>
+            <p>Most of the time the defaults are fine, but you can tune them for your needs.</p>
+            </article><footer>Copyright</footer></body></html>";
+        let analysis = do_analyze_page(html).unwrap();
+        assert_eq!(analysis.language, "en");
+        assert!(analysis.text.starts_with("The quick guide"));
+        assert!(!analysis.text.contains("Copyright"));
+        assert!(!analysis.text.contains("Home"));
+        assert_eq!(analysis.word_count, 33);
+        assert_eq!(analysis.reading_time_minutes, 1);
+    }
+
+    #[test]
+    fn test_analyze_page_chinese() {
+        let html = "<html><body><main><p>这是一个用于测试的中文段落，我们希望能够正确识别语言。</p></main></body></html>";
+        let analysis = do_analyze_page(html).unwrap();
+        assert_eq!(analysis.language, "zh");
+        assert_eq!(analysis.word_count, 25);
+        assert_eq!(analysis.reading_time_minutes, 1);
+    }
+
+    #[test]
+    fn test_analyze_page_undetermined() {
+        let analysis = do_analyze_page("<p>42</p>").unwrap();
+        assert_eq!(analysis.language, "und");
+        assert_eq!(analysis.word_count, 1);
+    }
+
+    #[test]
+    fn test_fetch_task_completes_once() {
+        let (url, _) = spawn_slow_server(3, Duration::from_millis(1));
+        let task = FetchTask::spawn(url, ConvertOptions::default());
+        wait_for(|| task.result.lock().unwrap().is_some());
+        let md = task.take_outcome().unwrap().unwrap();
+        assert!(md.contains("hello"));
+        assert!(task.take_outcome().is_none());
+        task.cancel();
+        assert!(task.take_outcome().is_none());
+    }
+
+    #[test]
+    fn test_fetch_task_cancel_stops_download() {
+        let chunks = 200;
+        let (url, sent) = spawn_slow_server(chunks, Duration::from_millis(20));
+        let task = FetchTask::spawn(url, ConvertOptions::default());
+        wait_for(|| task.downloaded() > 0);
+        task.cancel();
+        assert!(matches!(task.take_outcome(), Some(Err(Error::Cancelled))));
+        assert!(task.take_outcome().is_none());
+        let sent = sent.recv_timeout(Duration::from_secs(3)).unwrap();
+        assert!(sent < chunks);
+    }
+
+    #[test]
+    fn test_fetch_task_cancel_while_waiting_for_response() {
+        // Accepts the connection but never answers, so the request hangs in `send()`
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            thread::sleep(Duration::from_secs(60));
+            drop(stream);
+        });
+        let task = FetchTask::spawn(url, ConvertOptions::default());
+        thread::sleep(Duration::from_millis(100));
+        task.cancel();
+        wait_for(|| task.result.lock().unwrap().is_some());
+        assert!(matches!(
+            task.result.lock().unwrap().as_ref(),
+            Some(Err(Error::Cancelled))
+        ));
+    }
+
+    #[test]
+    fn test_fetch_task_multibyte_split_across_chunks() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            // `é` is two bytes, the first one ends the first chunk
+            let body = "<p>café crème</p>".as_bytes();
+            let split = body.iter().position(|byte| *byte == 0xc3).unwrap() + 1;
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(header.as_bytes()).unwrap();
+            stream.write_all(&body[..split]).unwrap();
+            stream.flush().unwrap();
+            thread::sleep(Duration::from_millis(50));
+            stream.write_all(&body[split..]).unwrap();
+        });
+        let task = FetchTask::spawn(format!("http://{address}/"), ConvertOptions::default());
+        wait_for(|| task.result.lock().unwrap().is_some());
+        let md = task.take_outcome().unwrap().unwrap();
+        assert!(md.contains("café crème"));
+        assert!(!md.contains('\u{FFFD}'));
+    }
+
+    #[test]
+    fn test_link_style_reference() {
+        let html = r#"<p>Read <a href="https://example.com/a">first</a> and <a href="https://example.com/b">second</a>.</p>"#;
+
+        let inline = do_html2md(html, &ConvertOptions::default()).unwrap();
+        assert!(
+            inline.contains("[first](https://example.com/a)"),
+            "{inline}"
+        );
+
+        let options = ConvertOptions {
+            link_style: LinkStyle::Reference,
+            ..Default::default()
+        };
+        let md = do_html2md(html, &options).unwrap();
+        assert!(md.contains("[first][1]"), "{md}");
+        assert!(md.contains("[second][2]"), "{md}");
+        assert!(!md.contains("](https://"), "{md}");
+        let footer = md.split_once("[1]: ").map(|(_, footer)| footer).unwrap();
+        assert!(footer.starts_with("https://example.com/a"), "{md}");
+        assert!(footer.contains("[2]: https://example.com/b"), "{md}");
+    }
+
+    #[test]
+    fn test_strip_code_comments() {
+        let html = r##"<p>Use <code>#</code> for headings.</p>
+<pre><code class="language-python"># Add two numbers
+def add(a, b):  # the sum
+    color = "#fff"
+    return a + b
+</code></pre>"##;
+
+        let md = do_html2md(html, &ConvertOptions::default()).unwrap();
+        assert!(md.contains("# Add two numbers"), "{md}");
+
+        let options = ConvertOptions {
+            strip_code_comments: true,
+            ..Default::default()
+        };
+        let md = do_html2md(html, &options).unwrap();
+        assert!(!md.contains("Add two numbers"), "{md}");
+        assert!(!md.contains("the sum"), "{md}");
+        assert!(md.contains("def add(a, b):\n"), "{md}");
+        assert!(md.contains("color = \"#fff\""), "{md}");
+        assert!(md.contains("Use `#` for headings."), "{md}");
+    }
+
+    #[test]
+    fn test_strip_code_comments_by_language() {
+        let md = "```rust
+fn first<'a>(s: &'a str) -> &'a str { s } // the first
+let quote = '\"'; // a quote
+let escaped = '\\''; // escaped
+```
+```bash
+echo $# args # count
+echo 'it''s \\' ${#files[@]} # literal
+```
+```lua
+local a = 1 --[[ inline ]] + 2
+--[[
+block
+]]
+local s = \"--kept\" -- dropped
+```
+```haskell
+{- header
+-}
+total = foldl' (+) 0 xs -- sum
+dash = '-' -- a dash
+```
+";
+        let stripped = strip_code_comments(md);
+        assert_eq!(
+            stripped,
+            "```rust
+fn first<'a>(s: &'a str) -> &'a str { s }
+let quote = '\"';
+let escaped = '\\'';
+```
+```bash
+echo $# args
+echo 'it''s \\' ${#files[@]}
+```
+```lua
+local a = 1  + 2
+local s = \"--kept\"
+```
+```haskell
+total = foldl' (+) 0 xs
+dash = '-'
+```
+"
+        );
+    }
+
+    #[test]
+    fn test_keep_only_tags() {
+        let html = r#"<html><body><nav><a href="/">Home</a></nav><div class="ad">Buy now</div>
+<h1>Getting started</h1><p>Install the <em>plugin</em> first.</p><aside>Related posts</aside>
+<ul><li>Open a file</li><li>Ask a question</li></ul><footer>Copyright</footer>
+<div title="a > b"><!-- <p>Commented out</p> --><script>if (a > b) { render("<p>Scripted</p>"); }</script>
+<p title="x > y">Read the <a href="/docs?a>b">docs</a>.</p></div></body></html>"#;
+
+        let md = do_html2md(html, &ConvertOptions::default()).unwrap();
+        assert!(md.contains("Buy now"), "{md}");
+
+        let options = ConvertOptions {
+            keep_only_tags: vec!["h1".to_string(), "p".to_string(), "li".to_string()],
+            ..Default::default()
+        };
+        let md = do_html2md(html, &options).unwrap();
+        assert!(md.contains("Getting started"), "{md}");
+        assert!(md.contains("plugin"), "{md}");
+        assert!(md.contains("Open a file"), "{md}");
+        assert!(md.contains("Ask a question"), "{md}");
+        assert!(md.contains("Read the [docs]"), "{md}");
+        for dropped in [
+            "Home",
+            "Buy now",
+            "Related posts",
+            "Copyright",
+            "Commented out",
+            "Scripted",
+            "a > b",
+        ] {
+            assert!(!md.contains(dropped), "{md}");
+        }
+    }
+
+    #[test]
+    fn test_main_content() {
+        let html = r"<html><body><nav>Home | About</nav><article><h1>The guide</h1>
+<p>How the tool works.</p><aside>Related posts</aside></article><footer>Copyright</footer>
+</body></html>";
+        let options = ConvertOptions {
+            main_content: true,
+            ..Default::default()
+        };
+        let md = do_html2md(html, &options).unwrap();
+        assert!(md.contains("The guide"), "{md}");
+        assert!(md.contains("How the tool works."), "{md}");
+        for dropped in ["Home", "Related posts", "Copyright"] {
+            assert!(!md.contains(dropped), "{md}");
+        }
+        assert_eq!(
+            do_analyze_page(html).unwrap().text,
+            "The guide How the tool works."
+        );
+
+        let html = r"<html><body><article><p>First post.</p></article><div>Advertisement</div>
+<article><p>Second post.</p><article><p>Nested reply.</p></article></article></body></html>";
+        let md = do_html2md(html, &options).unwrap();
+        assert!(md.contains("First post."), "{md}");
+        assert!(md.contains("Second post."), "{md}");
+        assert!(md.contains("Nested reply."), "{md}");
+        assert!(!md.contains("Advertisement"), "{md}");
+        assert_eq!(
+            do_analyze_page(html).unwrap().text,
+            "First post. Second post. Nested reply."
+        );
+    }
 
     #[test]
     fn test_fetch_md() {
-        let md = do_fetch_md("https://github.com/yetone/avante.nvim").unwrap();
+        let md = do_fetch_md(
+            "https://github.com/yetone/avante.nvim",
+            &ConvertOptions::default(),
+        )
+        .unwrap();
         println!("{md}");
     }
+
+    #[test]
+    fn test_lua_error_prefix() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+        let error = do_fetch_md(&url, &ConvertOptions::default()).unwrap_err();
+        assert!(matches!(error, Error::Network(_)));
+        assert!(error
+            .into_lua(LUA_MODULE, "fetch_md")
+            .to_string()
+            .contains("avante_html2md: fetch_md: Request error: "));
+    }
 }
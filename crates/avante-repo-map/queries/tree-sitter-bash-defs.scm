@@ -0,0 +1,6 @@
+;; Capture functions and top-level variable declarations, nested functions are skipped later
+
+(function_definition) @function
+
+(program
+  (declaration_command) @declaration)
@@ -0,0 +1,5 @@
+;; Capture top-level forms, `ns`, `def`, `defn`, `defprotocol` and friends are told apart by
+;; their first symbol
+
+(source
+  (list_lit) @form)
@@ -0,0 +1,5 @@
+(class_declaration) @cr_type
+
+(method_definition) @cr_method
+
+(assignment) @cr_constant
@@ -0,0 +1,17 @@
+;; Capture classes, structs and interfaces with their methods and fields, free functions, enums
+;; and module-level variables
+
+[
+  (class_declaration)
+  (struct_declaration)
+  (interface_declaration)
+] @d_type
+
+(enum_declaration) @d_enum
+
+(function_declaration) @d_function
+
+[
+  (variable_declaration)
+  (auto_declaration)
+] @d_variable
@@ -0,0 +1,44 @@
+;; Capture top-level classes, mixins, enums and functions, and their members
+
+(source_file
+  (class_declaration) @class)
+
+(source_file
+  (mixin_declaration) @class)
+
+(source_file
+  (enum_declaration) @enum)
+
+(source_file
+  (function_declaration
+    (function_signature) @function))
+
+(class_body
+  (class_member
+    (method_declaration
+      (method_signature) @method)))
+
+(class_body
+  (class_member
+    (declaration
+      (constructor_signature) @method)))
+
+(class_body
+  (class_member
+    (declaration
+      (constant_constructor_signature) @method)))
+
+(class_body
+  (class_member
+    (declaration
+      (factory_constructor_signature) @method)))
+
+(class_body
+  (class_member
+    (declaration
+      (function_signature) @method)))
+
+(class_body
+  (class_member
+    (declaration
+      (initialized_identifier_list)) @class_variable))
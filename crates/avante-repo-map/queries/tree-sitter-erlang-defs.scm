@@ -0,0 +1,10 @@
+;; Capture the module attribute, records and function declarations
+
+(source_file
+  (module_attribute) @module)
+
+(source_file
+  (record_decl) @record)
+
+(source_file
+  (fun_decl) @fun_decl)
@@ -0,0 +1,12 @@
+;; Capture namespaces, modules, module level let bindings and type definitions
+
+[
+  (namespace)
+  (named_module)
+  (module_defn)
+] @fsharp_module
+
+(value_declaration
+  (function_or_value_defn) @fsharp_let)
+
+(type_definition) @fsharp_type
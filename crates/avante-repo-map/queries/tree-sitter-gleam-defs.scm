@@ -0,0 +1,10 @@
+;; Capture module-level functions, custom types and constants, `pub` is checked in code
+
+(source_file
+  (function) @gl_function)
+
+(source_file
+  (type_definition) @gl_type)
+
+(source_file
+  (constant) @gl_constant)
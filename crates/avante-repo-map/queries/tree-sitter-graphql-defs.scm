@@ -0,0 +1,11 @@
+;; Capture object, interface and input types with their fields, and enums
+
+[
+  (object_type_definition)
+  (interface_type_definition)
+  (input_object_type_definition)
+] @graphql_type
+
+(enum_type_definition) @enum
+
+(union_type_definition) @graphql_union
@@ -0,0 +1,22 @@
+;; Capture classes with their methods and fields, script functions and the top-level
+;; calls of build scripts such as `dependencies { ... }`
+
+(class_declaration) @groovy_class
+
+[
+  (function_definition)
+  (method_declaration)
+] @groovy_function
+
+(class_body
+  (field_declaration) @groovy_field)
+
+(program
+  (expression_statement
+    [
+      (method_invocation)
+      (juxt_function_call)
+    ] @groovy_call))
+
+(program
+  (juxt_function_call) @groovy_call)
@@ -0,0 +1,17 @@
+;; Capture top-level type signatures, function equations, data and newtype declarations, and
+;; type classes
+
+(declarations
+  (signature) @function)
+
+(declarations
+  (data_type) @enum)
+
+(declarations
+  (newtype) @enum)
+
+(declarations
+  (class) @class)
+
+(declarations
+  (function) @function)
@@ -0,0 +1,5 @@
+;; Capture top-level resource, module, variable and output blocks
+
+(config_file
+  (body
+    (block) @block))
@@ -0,0 +1,13 @@
+;; Capture modules, structs, functions in both long and short form, and const bindings
+
+(module_definition) @module
+
+(struct_definition) @struct_definition
+
+(function_definition) @function_definition
+
+(assignment
+  .
+  (call_expression)) @function_definition
+
+(const_statement) @const_statement
@@ -0,0 +1,37 @@
+;; Capture top-level classes, objects, functions and properties, and their members
+
+(source_file
+  (class_declaration) @class)
+
+(source_file
+  (object_declaration) @class)
+
+(class_declaration
+  (class_body
+    (function_declaration) @method))
+
+(object_declaration
+  (class_body
+    (function_declaration) @method))
+
+(companion_object
+  (class_body
+    (function_declaration) @method))
+
+(class_declaration
+  (class_body
+    (property_declaration) @class_variable))
+
+(object_declaration
+  (class_body
+    (property_declaration) @class_variable))
+
+(companion_object
+  (class_body
+    (property_declaration) @class_variable))
+
+(source_file
+  (function_declaration) @function)
+
+(source_file
+  (property_declaration) @variable)
@@ -0,0 +1,10 @@
+;; Capture module level routines, constants and variables
+
+(source_file
+  [
+    (proc_declaration)
+    (func_declaration)
+  ] @nim_routine)
+
+(source_file
+  (local_decl) @nim_variable)
@@ -0,0 +1,15 @@
+;; Capture classes, categories and protocols along with their methods and properties,
+;; categories are class interfaces and implementations with a `category` field
+
+[
+  (class_interface)
+  (class_implementation)
+  (protocol_declaration)
+] @objc_class
+
+[
+  (method_declaration)
+  (method_definition)
+] @objc_method
+
+(property_declaration) @objc_property
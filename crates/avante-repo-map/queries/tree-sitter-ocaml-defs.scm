@@ -0,0 +1,15 @@
+;; Capture module-level let bindings, type declarations, modules and module signatures
+
+(compilation_unit
+  (value_definition
+    (let_binding) @let_binding))
+
+(structure
+  (value_definition
+    (let_binding) @let_binding))
+
+(type_binding) @type_binding
+
+(module_binding) @module
+
+(module_type_definition) @module_type
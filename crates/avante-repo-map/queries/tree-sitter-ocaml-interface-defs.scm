@@ -0,0 +1,11 @@
+;; Capture the `val` specifications, type declarations, modules and module signatures of
+;; interface files
+
+(compilation_unit
+  (value_specification) @value_specification)
+
+(type_binding) @type_binding
+
+(module_binding) @module
+
+(module_type_definition) @module_type
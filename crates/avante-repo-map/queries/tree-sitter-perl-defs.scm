@@ -0,0 +1,7 @@
+;; Capture packages, named subs and `our` package variables
+
+(package_statement) @package
+
+(function_definition) @subroutine
+
+(variable_declaration) @variable_declaration
@@ -0,0 +1,10 @@
+;; Capture script parameters, global variables, functions and classes
+
+(program
+  (param_block) @param_block)
+
+(assignment_expression) @ps_assignment
+
+(function_statement) @function_statement
+
+(class_statement) @class_statement
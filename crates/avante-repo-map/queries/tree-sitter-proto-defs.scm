@@ -0,0 +1,7 @@
+;; Capture messages with their fields, enums and services with their rpcs
+
+(message) @message
+
+(enum) @enum
+
+(service) @service
@@ -23,3 +23,24 @@
     )
   )
 )
+;; Decorated classes such as `@dataclass`, whose annotated attributes are their fields
+(module
+  (decorated_definition
+    definition: (class_definition
+      body: (block
+        (expression_statement
+          (assignment) @class_assignment
+        )
+      )
+    )
+  )
+)
+(module
+  (decorated_definition
+    definition: (class_definition
+      body: (block
+        (function_definition) @method
+      )
+    )
+  )
+)
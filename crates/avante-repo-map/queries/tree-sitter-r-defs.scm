@@ -0,0 +1,7 @@
+;; Capture top-level assignments and the S4 `setClass`, `setGeneric` and `setMethod` calls
+
+(program
+  (binary_operator) @binary_operator)
+
+(program
+  (call) @call)
@@ -18,3 +18,4 @@
 )
 (const_item) @variable
 (static_item) @variable
+(use_declaration) @import
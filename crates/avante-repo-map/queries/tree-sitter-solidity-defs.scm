@@ -0,0 +1,16 @@
+;; Capture contracts, interfaces and libraries with their functions, events and state variables
+
+[
+  (contract_declaration)
+  (interface_declaration)
+  (library_declaration)
+] @sol_contract
+
+(contract_body
+  (function_definition) @sol_function)
+
+(contract_body
+  (event_definition) @sol_event)
+
+(contract_body
+  (state_variable_declaration) @sol_state_variable)
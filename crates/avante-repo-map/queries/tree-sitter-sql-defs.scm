@@ -0,0 +1,11 @@
+;; Capture tables with their columns, views, functions and enum types
+
+(create_table) @create_table
+
+(alter_table) @alter_table
+
+(create_type) @create_type
+
+(create_view) @create_view
+
+(create_function) @create_function
@@ -31,3 +31,11 @@
     )
   )
 )
+;; Tagged templates such as styled components are listed even when they aren't exported
+(program
+  (lexical_declaration
+    (variable_declarator
+      value: (call_expression
+        arguments: (template_string))) @styled_component
+  )
+)
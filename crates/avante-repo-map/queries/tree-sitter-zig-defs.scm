@@ -21,3 +21,10 @@
 (source_file (function_declaration) @function)
 
 (source_file (variable_declaration (identifier) @variable))
+
+(source_file
+  (variable_declaration
+    (identifier)
+    (builtin_function
+      (builtin_identifier) @_builtin
+      (#eq? @_builtin "@import")) @import))
@@ -1,47 +1,119 @@
-#![allow(clippy::unnecessary_map_or)]
-
+use ignore::WalkBuilder;
 use mlua::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::cell::RefCell;
-use std::collections::BTreeMap;
-use tree_sitter::{Node, Parser, Query, QueryCursor};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Node, Parser, Query, QueryCursor, Tree};
 use tree_sitter_language::LanguageFn;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Func {
     pub name: String,
     pub params: String,
     pub return_type: String,
     pub accessibility_modifier: Option<String>,
+    pub start_byte: usize,
+    // 1-based lines spanned by the definition, for jumping to or citing it. `Class`, `Enum`,
+    // `Union` and `Variable` carry the same pair.
+    #[serde(default)]
+    pub start_line: usize,
+    #[serde(default)]
+    pub end_line: usize,
+    // Distinct callee names referenced in the body, only collected with `include_calls`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<String>,
+    // Other definitions of the same file mentioned in the body
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    // Methods returning their own type, e.g. the steps of a builder chain
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub returns_self: bool,
+    // Source of short functions, only kept with `small_body_lines`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    // Doc comment or docstring, only collected with `include_docs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
+    // Type parameters of a generic function, e.g. `<U>` of `map<U>(...)`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generics: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Class {
     pub type_name: String,
     pub name: String,
     pub methods: Vec<Func>,
     pub properties: Vec<Variable>,
     pub visibility_modifier: Option<String>,
+    pub start_byte: usize,
+    #[serde(default)]
+    pub start_line: usize,
+    #[serde(default)]
+    pub end_line: usize,
+    // Generic parameters, e.g. `<T>` of `class Box<T>`, with the bounds of a Rust
+    // `impl<T: Clone> Foo<T>` block when there is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generics: Option<String>,
+    // Doc comment or docstring, only collected with `include_docs`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Enum {
     pub name: String,
     pub items: Vec<Variable>,
+    pub start_byte: usize,
+    #[serde(default)]
+    pub start_line: usize,
+    #[serde(default)]
+    pub end_line: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Union {
     pub name: String,
     pub items: Vec<Variable>,
+    pub start_byte: usize,
+    #[serde(default)]
+    pub start_line: usize,
+    #[serde(default)]
+    pub end_line: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Variable {
     pub name: String,
     pub value_type: String,
+    pub start_byte: usize,
+    #[serde(default)]
+    pub start_line: usize,
+    #[serde(default)]
+    pub end_line: usize,
+    // Short literal initializer of a Rust `const` or `static`, e.g. `1024`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    // Attributes of a field, e.g. `#[serde(default)]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Import {
+    pub name: String,
+    pub path: String,
+    pub glob: bool,
+    // Re-exported from a `prelude` module, which usually defines a crate's everyday API
+    pub prelude: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
 pub enum Definition {
     Func(Func),
     Class(Class),
@@ -49,9 +121,19 @@ pub enum Definition {
     Enum(Enum),
     Variable(Variable),
     Union(Union),
-    // TODO: Namespace support
+    Import(Import),
+    // C++, C# and PHP namespaces with the definitions declared inside them
+    Namespace {
+        name: String,
+        children: Vec<Definition>,
+    },
 }
 
+pub use avante_error::Error;
+
+// Prefix of the errors raised to Lua
+const LUA_MODULE: &str = "avante_repo_map";
+
 fn get_ts_language(language: &str) -> Option<LanguageFn> {
     match language {
         "rust" => Some(tree_sitter_rust::LANGUAGE),
@@ -70,6 +152,32 @@ fn get_ts_language(language: &str) -> Option<LanguageFn> {
         "swift" => Some(tree_sitter_swift::LANGUAGE),
         "elixir" => Some(tree_sitter_elixir::LANGUAGE),
         "csharp" => Some(tree_sitter_c_sharp::LANGUAGE),
+        "kotlin" => Some(tree_sitter_kotlin_ng::LANGUAGE),
+        "dart" => Some(tree_sitter_dart::LANGUAGE),
+        "haskell" => Some(tree_sitter_haskell::LANGUAGE),
+        "ocaml" => Some(tree_sitter_ocaml::LANGUAGE_OCAML),
+        "ocaml_interface" => Some(tree_sitter_ocaml::LANGUAGE_OCAML_INTERFACE),
+        "hcl" => Some(tree_sitter_hcl::LANGUAGE),
+        "erlang" => Some(tree_sitter_erlang::LANGUAGE),
+        "bash" => Some(tree_sitter_bash::LANGUAGE),
+        "julia" => Some(tree_sitter_julia::LANGUAGE),
+        "r" => Some(tree_sitter_r::LANGUAGE),
+        "perl" => Some(tree_sitter_perl::LANGUAGE),
+        "objc" => Some(tree_sitter_objc::LANGUAGE),
+        "groovy" => Some(tree_sitter_groovy::LANGUAGE),
+        "sql" => Some(tree_sitter_sequel::LANGUAGE),
+        "proto" => Some(tree_sitter_proto::LANGUAGE),
+        "clojure" => Some(tree_sitter_clojure_orchard::LANGUAGE),
+        "powershell" => Some(tree_sitter_powershell::LANGUAGE),
+        "graphql" => Some(tree_sitter_graphql::LANGUAGE),
+        "fsharp" => Some(tree_sitter_fsharp::LANGUAGE_FSHARP),
+        "vue" => Some(tree_sitter_vue_next::LANGUAGE),
+        "nim" => Some(tree_sitter_nim::LANGUAGE),
+        "svelte" => Some(tree_sitter_svelte_ng::LANGUAGE),
+        "solidity" => Some(tree_sitter_solidity::LANGUAGE),
+        "crystal" => Some(tree_sitter_crystal::LANGUAGE),
+        "gleam" => Some(tree_sitter_gleam::LANGUAGE),
+        "d" => Some(tree_sitter_d::LANGUAGE),
         _ => None,
     }
 }
@@ -90,11 +198,37 @@ const SCALA_QUERY: &str = include_str!("../queries/tree-sitter-scala-defs.scm");
 const SWIFT_QUERY: &str = include_str!("../queries/tree-sitter-swift-defs.scm");
 const ELIXIR_QUERY: &str = include_str!("../queries/tree-sitter-elixir-defs.scm");
 const CSHARP_QUERY: &str = include_str!("../queries/tree-sitter-c-sharp-defs.scm");
-
-fn get_definitions_query(language: &str) -> Result<Query, String> {
+const KOTLIN_QUERY: &str = include_str!("../queries/tree-sitter-kotlin-defs.scm");
+const DART_QUERY: &str = include_str!("../queries/tree-sitter-dart-defs.scm");
+const HASKELL_QUERY: &str = include_str!("../queries/tree-sitter-haskell-defs.scm");
+const OCAML_QUERY: &str = include_str!("../queries/tree-sitter-ocaml-defs.scm");
+const OCAML_INTERFACE_QUERY: &str = include_str!("../queries/tree-sitter-ocaml-interface-defs.scm");
+const HCL_QUERY: &str = include_str!("../queries/tree-sitter-hcl-defs.scm");
+const ERLANG_QUERY: &str = include_str!("../queries/tree-sitter-erlang-defs.scm");
+const BASH_QUERY: &str = include_str!("../queries/tree-sitter-bash-defs.scm");
+const JULIA_QUERY: &str = include_str!("../queries/tree-sitter-julia-defs.scm");
+const R_QUERY: &str = include_str!("../queries/tree-sitter-r-defs.scm");
+const PERL_QUERY: &str = include_str!("../queries/tree-sitter-perl-defs.scm");
+const OBJC_QUERY: &str = include_str!("../queries/tree-sitter-objc-defs.scm");
+const GROOVY_QUERY: &str = include_str!("../queries/tree-sitter-groovy-defs.scm");
+const SQL_QUERY: &str = include_str!("../queries/tree-sitter-sql-defs.scm");
+const PROTO_QUERY: &str = include_str!("../queries/tree-sitter-proto-defs.scm");
+const CLOJURE_QUERY: &str = include_str!("../queries/tree-sitter-clojure-defs.scm");
+const POWERSHELL_QUERY: &str = include_str!("../queries/tree-sitter-powershell-defs.scm");
+const GRAPHQL_QUERY: &str = include_str!("../queries/tree-sitter-graphql-defs.scm");
+const FSHARP_QUERY: &str = include_str!("../queries/tree-sitter-fsharp-defs.scm");
+const NIM_QUERY: &str = include_str!("../queries/tree-sitter-nim-defs.scm");
+const SOLIDITY_QUERY: &str = include_str!("../queries/tree-sitter-solidity-defs.scm");
+const CRYSTAL_QUERY: &str = include_str!("../queries/tree-sitter-crystal-defs.scm");
+const GLEAM_QUERY: &str = include_str!("../queries/tree-sitter-gleam-defs.scm");
+const D_QUERY: &str = include_str!("../queries/tree-sitter-d-defs.scm");
+
+fn get_definitions_query(language: &str) -> Result<Query, Error> {
     let ts_language = get_ts_language(language);
     if ts_language.is_none() {
-        return Err(format!("Unsupported language: {language}"));
+        return Err(Error::Unsupported(format!(
+            "Unsupported language: {language}"
+        )));
     }
     let ts_language = ts_language.unwrap();
     let contents = match language {
@@ -114,19 +248,55 @@ fn get_definitions_query(language: &str) -> Result<Query, String> {
         "swift" => SWIFT_QUERY,
         "elixir" => ELIXIR_QUERY,
         "csharp" => CSHARP_QUERY,
-        _ => return Err(format!("Unsupported language: {language}")),
+        "kotlin" => KOTLIN_QUERY,
+        "dart" => DART_QUERY,
+        "haskell" => HASKELL_QUERY,
+        "ocaml" => OCAML_QUERY,
+        "ocaml_interface" => OCAML_INTERFACE_QUERY,
+        "hcl" => HCL_QUERY,
+        "erlang" => ERLANG_QUERY,
+        "bash" => BASH_QUERY,
+        "julia" => JULIA_QUERY,
+        "r" => R_QUERY,
+        "perl" => PERL_QUERY,
+        "objc" => OBJC_QUERY,
+        "groovy" => GROOVY_QUERY,
+        "sql" => SQL_QUERY,
+        "proto" => PROTO_QUERY,
+        "clojure" => CLOJURE_QUERY,
+        "powershell" => POWERSHELL_QUERY,
+        "graphql" => GRAPHQL_QUERY,
+        "fsharp" => FSHARP_QUERY,
+        "nim" => NIM_QUERY,
+        "solidity" => SOLIDITY_QUERY,
+        "crystal" => CRYSTAL_QUERY,
+        "gleam" => GLEAM_QUERY,
+        "d" => D_QUERY,
+        _ => {
+            return Err(Error::Unsupported(format!(
+                "Unsupported language: {language}"
+            )))
+        }
     };
     let query = Query::new(&ts_language.into(), contents)
-        .unwrap_or_else(|e| panic!("Failed to parse query for {language}: {e}"));
+        .map_err(|e| Error::Parse(format!("Failed to parse query for {language}: {e}")))?;
     Ok(query)
 }
 
+fn new_parser(ts_language: LanguageFn, language: &str) -> Result<Parser, Error> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&ts_language.into())
+        .map_err(|e| Error::Unsupported(format!("Failed to set language for {language}: {e}")))?;
+    Ok(parser)
+}
+
 fn get_closest_ancestor_name(node: &Node, source: &str) -> String {
     let mut parent = node.parent();
     while let Some(parent_node) = parent {
         let name_node = parent_node.child_by_field_name("name");
         if let Some(name_node) = name_node {
-            return get_node_text(&name_node, source.as_bytes()).to_string();
+            return get_node_text(&name_node, source.as_bytes()).clone();
         }
         parent = parent_node.parent();
     }
@@ -170,6 +340,179 @@ fn find_descendant_by_type<'a>(node: &'a Node, child_type: &str) -> Option<Node<
     None
 }
 
+fn find_descendants_by_type<'a>(node: &'a Node, child_type: &str) -> Vec<Node<'a>> {
+    let mut cursor = node.walk();
+    let mut descendants = Vec::new();
+    for i in 0..node.descendant_count() {
+        cursor.goto_descendant(i);
+        let node = cursor.node();
+        if node.kind() == child_type {
+            descendants.push(node);
+        }
+    }
+    descendants
+}
+
+// `#[test]`, `#[tokio::test]` and friends, attributes are siblings of the function they annotate
+fn rust_is_test_function(node: &Node, source: &[u8]) -> bool {
+    let mut prev_sibling = node.prev_named_sibling();
+    while let Some(sibling) = prev_sibling {
+        match sibling.kind() {
+            "attribute_item" => {
+                let text = get_node_text(&sibling, source);
+                let path = text
+                    .trim_start_matches("#[")
+                    .trim_end_matches(']')
+                    .split('(')
+                    .next()
+                    .unwrap_or_default()
+                    .trim();
+                if path == "test" || path.ends_with("::test") {
+                    return true;
+                }
+            }
+            "line_comment" | "block_comment" => {}
+            _ => return false,
+        }
+        prev_sibling = sibling.prev_named_sibling();
+    }
+    false
+}
+
+fn java_is_test_method(node: &Node, source: &[u8]) -> bool {
+    let Some(modifiers) = find_child_by_type(node, "modifiers") else {
+        return false;
+    };
+    modifiers
+        .named_children(&mut modifiers.walk())
+        .filter(|n| n.kind() == "marker_annotation" || n.kind() == "annotation")
+        .filter_map(|n| n.child_by_field_name("name"))
+        .any(|n| {
+            let name = get_node_text(&n, source);
+            name == "Test" || name.ends_with(".Test")
+        })
+}
+
+// Follow pytest's collection rules: `test_*` functions of a module or of a `Test*` class without
+// `__init__`, and tests don't return a value
+fn python_is_test_function(node: &Node, source: &[u8]) -> bool {
+    // pytest collects `test*` functions whatever they return
+    let is_test_name = node
+        .child_by_field_name("name")
+        .is_some_and(|n| get_node_text(&n, source).starts_with("test"));
+    if !is_test_name {
+        return false;
+    }
+    let mut container = node.parent();
+    if container.is_some_and(|n| n.kind() == "decorated_definition") {
+        container = container.and_then(|n| n.parent());
+    }
+    let Some(container) = container else {
+        return false;
+    };
+    if container.kind() == "module" {
+        return true;
+    }
+    let Some(class) = container
+        .parent()
+        .filter(|n| n.kind() == "class_definition")
+    else {
+        return false;
+    };
+    let is_test_class = class
+        .child_by_field_name("name")
+        .is_some_and(|n| get_node_text(&n, source).starts_with("Test"));
+    let has_constructor = container
+        .named_children(&mut container.walk())
+        .filter(|n| n.kind() == "function_definition")
+        .filter_map(|n| n.child_by_field_name("name"))
+        .any(|n| get_node_text(&n, source) == "__init__");
+    is_test_class && !has_constructor
+}
+
+fn test_name_from_string(node: &Node, source: &[u8]) -> String {
+    get_node_text(node, source)
+        .trim_matches(|c| c == '"' || c == '\'' || c == '`')
+        .to_string()
+}
+
+// Nodes that can be test cases, only these are visited when looking for tests
+fn get_test_candidates_query(language: &str) -> Option<&'static str> {
+    match language {
+        "rust" => Some("(function_item) @candidate"),
+        "java" => Some("(method_declaration) @candidate"),
+        "python" => Some("(function_definition) @candidate"),
+        "zig" => Some("(test_declaration) @candidate"),
+        "javascript" | "typescript" => Some(
+            r#"((call_expression function: (identifier) @callee) @candidate
+                (#any-of? @callee "it" "test"))"#,
+        ),
+        _ => None,
+    }
+}
+
+// Test cases are kept apart from the definitions, they are navigated to rather than called
+fn collect_tests(language: &str, root: &Node, source: &[u8]) -> Vec<Func> {
+    let (Some(ts_language), Some(pattern)) = (
+        get_ts_language(language),
+        get_test_candidates_query(language),
+    ) else {
+        return vec![];
+    };
+    let Ok(query) = Query::new(&ts_language.into(), pattern) else {
+        return vec![];
+    };
+    let Some(candidate_index) = query.capture_index_for_name("candidate") else {
+        return vec![];
+    };
+    let mut tests = Vec::new();
+    let mut query_cursor = QueryCursor::new();
+    let mut matches = query_cursor.matches(&query, *root, source);
+    while let Some(m) = matches.next() {
+        let Some(node) = m.nodes_for_capture_index(candidate_index).next() else {
+            continue;
+        };
+        let name = match (language, node.kind()) {
+            ("rust", "function_item") if rust_is_test_function(&node, source) => node
+                .child_by_field_name("name")
+                .map(|n| get_node_text(&n, source)),
+            ("java", "method_declaration") if java_is_test_method(&node, source) => node
+                .child_by_field_name("name")
+                .map(|n| get_node_text(&n, source)),
+            ("python", "function_definition") if python_is_test_function(&node, source) => node
+                .child_by_field_name("name")
+                .map(|n| get_node_text(&n, source)),
+            ("zig", "test_declaration") => node
+                .named_children(&mut node.walk())
+                .find(|n| n.kind() == "string" || n.kind() == "identifier")
+                .map(|n| test_name_from_string(&n, source)),
+            ("javascript" | "typescript", "call_expression") => node
+                .child_by_field_name("arguments")
+                .and_then(|arguments| arguments.named_child(0))
+                .filter(|n| n.kind() == "string" || n.kind() == "template_string")
+                .map(|n| test_name_from_string(&n, source)),
+            _ => None,
+        };
+        if let Some(name) = name {
+            let (start_line, end_line) = node_lines(&node);
+            tests.push(Func {
+                name,
+                params: String::new(),
+                return_type: String::new(),
+                accessibility_modifier: None,
+                start_byte: node.start_byte(),
+                start_line,
+                end_line,
+                calls: vec![],
+                references: vec![],
+                returns_self: false,
+                ..Default::default()
+            });
+        }
+    }
+    tests
+}
+
 fn ruby_method_is_private<'a>(node: &'a Node, source: &'a [u8]) -> bool {
     let mut prev_sibling = node.prev_sibling();
     while let Some(prev_sibling_node) = prev_sibling {
@@ -238,1803 +581,12401 @@ fn zig_find_type_in_parent<'a>(node: &'a Node, source: &'a [u8]) -> Option<Strin
 
 fn csharp_is_primary_constructor(node: &Node) -> bool {
     node.kind() == "parameter_list"
-        && node.parent().map_or(false, |n| {
-            n.kind() == "class_declaration" || n.kind() == "record_declaration"
-        })
+        && node
+            .parent()
+            .is_some_and(|n| n.kind() == "class_declaration" || n.kind() == "record_declaration")
+}
+
+// `where T : class, new()`, one clause per constrained type parameter
+fn csharp_type_constraints(node: &Node, source: &[u8]) -> Option<String> {
+    let mut cursor = node.walk();
+    let clauses: Vec<String> = node
+        .children(&mut cursor)
+        .filter(|n| n.kind() == "type_parameter_constraints_clause")
+        .map(|n| get_params_text(&n, source))
+        .collect();
+    (!clauses.is_empty()).then(|| clauses.join(" "))
 }
 
 fn csharp_find_parent_type_node<'a>(node: &'a Node) -> Option<Node<'a>> {
     find_first_ancestor_by_types(node, &["class_declaration", "record_declaration"])
 }
 
-fn ex_find_parent_module_declaration_name<'a>(node: &'a Node, source: &'a [u8]) -> Option<String> {
-    let mut parent = node.parent();
-    while let Some(parent_node) = parent {
-        if parent_node.kind() == "call" {
-            let text = get_node_text(&parent_node, source);
-            if text.starts_with("defmodule ") {
-                let arguments_node = find_child_by_type(&parent_node, "arguments");
-                if let Some(arguments_node) = arguments_node {
-                    return Some(get_node_text(&arguments_node, source));
-                }
+// Kotlin declarations keep their name as a plain identifier child instead of a field
+fn kotlin_get_name(node: &Node, source: &[u8]) -> Option<String> {
+    let target = find_child_by_type(node, "variable_declaration").unwrap_or(*node);
+    let mut receiver = None;
+    for child in target.children(&mut target.walk()) {
+        match child.kind() {
+            "simple_identifier" | "type_identifier" | "identifier" => {
+                let name = get_node_text(&child, source);
+                // Extension functions are qualified with their receiver, `fun String.shout()`
+                return Some(match receiver {
+                    Some(receiver) => format!("{receiver}.{name}"),
+                    None => name,
+                });
             }
+            kind if kind.ends_with("_type") => receiver = Some(get_node_text(&child, source)),
+            _ => {}
         }
-        parent = parent_node.parent();
     }
     None
 }
 
-fn ruby_find_parent_module_declaration_name<'a>(
-    node: &'a Node,
-    source: &'a [u8],
-) -> Option<String> {
-    let mut path_parts = Vec::new();
-    let mut current = Some(*node);
+fn kotlin_is_private(node: &Node, source: &[u8]) -> bool {
+    find_child_by_type(node, "modifiers")
+        .is_some_and(|n| get_node_text(&n, source).contains("private"))
+}
 
-    while let Some(current_node) = current {
-        if current_node.kind() == "module" || current_node.kind() == "class" {
-            if let Some(name_node) = current_node.child_by_field_name("name") {
-                path_parts.push(get_node_text(&name_node, source));
-            }
-        }
-        current = current_node.parent();
-    }
+// Methods of a companion object belong to the class that encloses it
+fn kotlin_find_parent_class_name(node: &Node, source: &[u8]) -> Option<String> {
+    find_first_ancestor_by_types(node, &["class_declaration", "object_declaration"])
+        .and_then(|n| kotlin_get_name(&n, source))
+}
 
-    if path_parts.is_empty() {
-        None
+fn kotlin_class_kind(node: &Node, source: &[u8]) -> &'static str {
+    if node.kind() == "object_declaration" {
+        return "object";
+    }
+    if find_child_by_type(node, "interface").is_some() {
+        return "interface";
+    }
+    let modifiers = find_child_by_type(node, "modifiers");
+    let has_modifier = |modifier: &str| {
+        modifiers.is_some_and(|n| {
+            n.children(&mut n.walk())
+                .any(|child| get_node_text(&child, source) == modifier)
+        })
+    };
+    if has_modifier("data") {
+        "data class"
+    } else if has_modifier("enum") {
+        "enum class"
     } else {
-        path_parts.reverse();
-        Some(path_parts.join("::"))
+        "class"
     }
 }
 
-fn get_node_text<'a>(node: &'a Node, source: &'a [u8]) -> String {
-    node.utf8_text(source).unwrap_or_default().to_string()
+// The type follows the first `:` in Kotlin properties, parameters and functions, and in OCaml
+// bindings and specifications
+fn type_after_colon(node: &Node, source: &[u8]) -> Option<String> {
+    let target = find_child_by_type(node, "variable_declaration").unwrap_or(*node);
+    let mut cursor = target.walk();
+    let mut children = target.children(&mut cursor);
+    children.find(|child| child.kind() == ":")?;
+    children
+        .find(Node::is_named)
+        .map(|n| get_node_text(&n, source))
 }
 
-fn get_node_type<'a>(node: &'a Node, source: &'a [u8]) -> String {
-    let predefined_type_node = find_descendant_by_type(node, "predefined_type");
-    if let Some(type_node) = predefined_type_node {
-        return type_node.utf8_text(source).unwrap().to_string();
+fn kotlin_variable_type(node: &Node, source: &[u8], options: &ExtractOptions) -> String {
+    if !options.signatures {
+        return String::new();
     }
-    let value_type_node = node.child_by_field_name("type");
-    value_type_node
-        .map(|n| n.utf8_text(source).unwrap().to_string())
-        .unwrap_or_default()
+    type_after_colon(node, source).unwrap_or_default()
 }
 
-fn is_first_letter_uppercase(name: &str) -> bool {
-    if name.is_empty() {
-        return false;
+fn kotlin_function_signature(node: &Node, source: &[u8]) -> (String, String) {
+    let params = find_child_by_type(node, "function_value_parameters")
+        .map_or_else(|| "()".to_string(), |n| get_params_text(&n, source));
+    let return_type = type_after_colon(node, source).unwrap_or_else(|| "Unit".to_string());
+    (params, return_type)
+}
+
+// `data class User(val name: String)` declares properties in its primary constructor
+fn kotlin_constructor_properties(
+    node: &Node,
+    source: &[u8],
+    options: &ExtractOptions,
+) -> Vec<Variable> {
+    let Some(constructor) = find_child_by_type(node, "primary_constructor") else {
+        return vec![];
+    };
+    let mut properties = Vec::new();
+    let mut cursor = constructor.walk();
+    for i in 0..constructor.descendant_count() {
+        cursor.goto_descendant(i);
+        let parameter = cursor.node();
+        if parameter.kind() != "class_parameter" || kotlin_is_private(&parameter, source) {
+            continue;
+        }
+        let is_property = parameter
+            .children(&mut parameter.walk())
+            .any(|child| child.kind() == "val" || child.kind() == "var");
+        if !is_property {
+            continue;
+        }
+        if let Some(name) = kotlin_get_name(&parameter, source) {
+            properties.push(Variable {
+                name,
+                value_type: kotlin_variable_type(&parameter, source, options),
+                start_byte: parameter.start_byte(),
+                value: None,
+                ..Default::default()
+            });
+        }
     }
-    name.chars().next().unwrap().is_uppercase()
+    properties
 }
 
-// Given a language, parse the given source code and return exported definitions
-fn extract_definitions(language: &str, source: &str) -> Result<Vec<Definition>, String> {
-    let ts_language = get_ts_language(language);
+// Dart members starting with an underscore are library private
+fn dart_is_private(name: &str) -> bool {
+    name.rsplit('.').next().is_some_and(|n| n.starts_with('_'))
+}
 
-    if ts_language.is_none() {
-        return Ok(vec![]);
+fn dart_is_constructor(node: &Node) -> bool {
+    matches!(
+        node.kind(),
+        "constructor_signature"
+            | "constant_constructor_signature"
+            | "factory_constructor_signature"
+    )
+}
+
+// Method signatures wrap the actual constructor, function, getter or setter signature
+fn dart_signature_node<'a>(node: &Node<'a>) -> Node<'a> {
+    if node.kind() == "method_signature" {
+        node.named_child(0).unwrap_or(*node)
+    } else {
+        *node
+    }
+}
+
+fn dart_get_name(node: &Node, source: &[u8]) -> Option<String> {
+    let signature = dart_signature_node(node);
+    let mut cursor = signature.walk();
+    if dart_is_constructor(&signature) {
+        // Named constructors keep their qualified name, `Point.origin`
+        let name: String = signature
+            .children(&mut cursor)
+            .take_while(|child| child.kind() != "formal_parameter_list")
+            .filter(|child| child.kind() == "identifier" || child.kind() == ".")
+            .map(|child| get_node_text(&child, source))
+            .collect();
+        return if name.is_empty() { None } else { Some(name) };
     }
+    if signature.kind() == "declaration" {
+        return find_descendant_by_type(&signature, "initialized_identifier")
+            .and_then(|n| find_child_by_type(&n, "identifier").map(|n| get_node_text(&n, source)));
+    }
+    let name_node = signature
+        .children(&mut cursor)
+        .find(|child| child.kind() == "identifier")?;
+    Some(get_node_text(&name_node, source))
+}
 
-    let ts_language = ts_language.unwrap();
+fn dart_find_parent_class_name(node: &Node, source: &[u8]) -> Option<String> {
+    find_first_ancestor_by_types(node, &["class_declaration", "mixin_declaration"])
+        .and_then(|n| dart_get_name(&n, source))
+}
 
-    let mut definitions = Vec::new();
-    let mut parser = Parser::new();
-    parser
-        .set_language(&ts_language.into())
-        .unwrap_or_else(|_| panic!("Failed to set language for {language}"));
-    let tree = parser
-        .parse(source, None)
-        .unwrap_or_else(|| panic!("Failed to parse source code for {language}"));
-    let root_node = tree.root_node();
+// The source text of the named children in `node` that come before the first `stop` child
+fn dart_text_before(node: &Node, stop: &str, source: &[u8]) -> Option<String> {
+    let mut range: Option<(usize, usize)> = None;
+    for child in node.children(&mut node.walk()) {
+        if child.kind() == stop {
+            break;
+        }
+        if !child.is_named() || child.kind().ends_with("_builtin") {
+            continue;
+        }
+        let start = range.map_or(child.start_byte(), |(start, _)| start);
+        range = Some((start, child.end_byte()));
+    }
+    let (start, end) = range?;
+    std::str::from_utf8(&source[start..end])
+        .ok()
+        .map(ToString::to_string)
+}
 
-    let query = get_definitions_query(language)?;
-    let mut query_cursor = QueryCursor::new();
-    let captures = query_cursor.captures(&query, root_node, source.as_bytes());
+fn dart_function_signature(node: &Node, source: &[u8]) -> (String, String) {
+    let signature = dart_signature_node(node);
+    let params = find_descendant_by_type(&signature, "formal_parameter_list")
+        .map_or_else(|| "()".to_string(), |n| get_params_text(&n, source));
+    let return_type = if dart_is_constructor(&signature) {
+        dart_find_parent_class_name(node, source)
+    } else {
+        dart_text_before(&signature, "identifier", source)
+    };
+    (params, return_type.unwrap_or_else(|| "dynamic".to_string()))
+}
 
-    let mut class_def_map: BTreeMap<String, RefCell<Class>> = BTreeMap::new();
-    let mut enum_def_map: BTreeMap<String, RefCell<Enum>> = BTreeMap::new();
-    let mut union_def_map: BTreeMap<String, RefCell<Union>> = BTreeMap::new();
+fn dart_field_type(node: &Node, source: &[u8], options: &ExtractOptions) -> String {
+    if !options.signatures {
+        return String::new();
+    }
+    dart_text_before(node, "initialized_identifier_list", source).unwrap_or_default()
+}
 
-    let ensure_class_def =
-        |language: &str, name: &str, class_def_map: &mut BTreeMap<String, RefCell<Class>>| {
-            let mut type_name = "class";
-            if language == "elixir" {
-                type_name = "module";
+// Split a type on its top-level arrows, `(a -> b) -> [a] -> [b]` has three segments
+fn haskell_type_segments(type_text: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut depth = 0usize;
+    let mut current = String::new();
+    let mut chars = type_text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            '-' if depth == 0 && chars.peek() == Some(&'>') => {
+                chars.next();
+                segments.push(current.trim().to_string());
+                current.clear();
+                continue;
             }
-            class_def_map.entry(name.to_string()).or_insert_with(|| {
-                RefCell::new(Class {
-                    type_name: type_name.to_string(),
-                    name: name.to_string(),
-                    methods: vec![],
-                    properties: vec![],
-                    visibility_modifier: None,
-                })
-            });
-        };
+            _ => {}
+        }
+        current.push(c);
+    }
+    segments.push(current.trim().to_string());
+    segments
+}
 
-    let ensure_module_def = |name: &str, class_def_map: &mut BTreeMap<String, RefCell<Class>>| {
-        class_def_map.entry(name.to_string()).or_insert_with(|| {
-            RefCell::new(Class {
-                name: name.to_string(),
-                type_name: "module".to_string(),
-                methods: vec![],
-                properties: vec![],
-                visibility_modifier: None,
+// `scale :: Double -> Shape -> Shape` takes `(Double, Shape)` and returns the last arrow segment,
+// equations without a signature only have their argument patterns
+fn haskell_function_signature(node: &Node, source: &[u8]) -> (String, String) {
+    if node.kind() == "function" {
+        let params = node
+            .child_by_field_name("patterns")
+            .map(|patterns| {
+                patterns
+                    .named_children(&mut patterns.walk())
+                    .map(|n| get_params_text(&n, source))
+                    .collect::<Vec<_>>()
             })
-        });
+            .unwrap_or_default();
+        return (format!("({})", params.join(", ")), String::new());
+    }
+    let type_text = node
+        .child_by_field_name("type")
+        .map(|n| get_params_text(&n, source))
+        .unwrap_or_default();
+    // Class constraints such as `Num a =>` are dropped
+    let type_text = type_text
+        .rsplit_once("=>")
+        .map_or(type_text.as_str(), |(_, type_text)| type_text);
+    let mut segments = haskell_type_segments(type_text);
+    let return_type = segments.pop().unwrap_or_default();
+    if segments.is_empty() {
+        return (String::new(), return_type);
+    }
+    (format!("({})", segments.join(", ")), return_type)
+}
+
+// Equations are only listed for functions without a type signature, and only once
+fn haskell_is_typed_function(node: &Node, name: &str, source: &[u8]) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
     };
+    parent
+        .named_children(&mut parent.walk())
+        .filter(|n| n.kind() == "signature")
+        .filter_map(|n| n.child_by_field_name("name"))
+        .any(|n| get_node_text(&n, source) == name)
+}
 
-    let ensure_enum_def = |name: &str, enum_def_map: &mut BTreeMap<String, RefCell<Enum>>| {
-        enum_def_map.entry(name.to_string()).or_insert_with(|| {
-            RefCell::new(Enum {
-                name: name.to_string(),
-                items: vec![],
+// Constructors of a `data` or `newtype` declaration, with their fields as the item type
+fn haskell_constructors(node: &Node, source: &[u8]) -> Vec<Variable> {
+    let mut constructors = find_descendants_by_type(node, "data_constructor");
+    constructors.extend(find_descendants_by_type(node, "newtype_constructor"));
+    constructors
+        .iter()
+        .filter_map(|constructor| {
+            let name_node = find_descendant_by_type(constructor, "constructor")?;
+            let fields = std::str::from_utf8(&source[name_node.end_byte()..constructor.end_byte()])
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            Some(Variable {
+                name: get_node_text(&name_node, source),
+                value_type: fields,
+                start_byte: constructor.start_byte(),
+                value: None,
+                ..Default::default()
             })
-        });
+        })
+        .collect()
+}
+
+fn ocaml_declaration_name(node: &Node, source: &[u8]) -> Option<String> {
+    node.child_by_field_name("name")
+        .or_else(|| find_child_by_type(node, "module_name"))
+        .or_else(|| find_child_by_type(node, "module_type_name"))
+        .map(|n| get_node_text(&n, source))
+}
+
+// Nested modules are joined with dots, `module Foo = struct module Bar = ...` gives `Foo.Bar`
+fn ocaml_module_path(node: &Node, source: &[u8]) -> String {
+    let mut names = Vec::new();
+    let mut parent = node.parent();
+    while let Some(parent_node) = parent {
+        if parent_node.kind() == "module_binding" || parent_node.kind() == "module_type_definition"
+        {
+            names.extend(ocaml_declaration_name(&parent_node, source));
+        }
+        parent = parent_node.parent();
+    }
+    names.reverse();
+    names.join(".")
+}
+
+// `val area : shape -> float` is a function, `val name : string` a value
+fn ocaml_value_specification(
+    node: &Node,
+    source: &[u8],
+    options: &ExtractOptions,
+) -> Option<Definition> {
+    let name = get_node_text(&find_child_by_type(node, "value_name")?, source);
+    let value_type = if options.signatures {
+        type_after_colon(node, source).unwrap_or_default()
+    } else {
+        String::new()
     };
+    if type_after_colon(node, source).is_some_and(|value_type| value_type.contains("->")) {
+        Some(Definition::Func(Func {
+            name,
+            params: String::new(),
+            return_type: value_type,
+            accessibility_modifier: None,
+            start_byte: node.start_byte(),
+            calls: vec![],
+            references: vec![],
+            returns_self: false,
+            ..Default::default()
+        }))
+    } else {
+        Some(Definition::Variable(Variable {
+            name,
+            value_type,
+            start_byte: node.start_byte(),
+            value: None,
+            ..Default::default()
+        }))
+    }
+}
 
-    let ensure_union_def = |name: &str, union_def_map: &mut BTreeMap<String, RefCell<Union>>| {
-        union_def_map.entry(name.to_string()).or_insert_with(|| {
-            RefCell::new(Union {
-                name: name.to_string(),
-                items: vec![],
-            })
-        });
+fn ocaml_parameters(node: &Node, source: &[u8]) -> Vec<String> {
+    node.children(&mut node.walk())
+        .filter(|child| child.kind() == "parameter")
+        .map(|child| {
+            let text = get_params_text(&child, source);
+            text.strip_prefix('(')
+                .and_then(|text| text.strip_suffix(')'))
+                .map_or(text.clone(), ToString::to_string)
+        })
+        .collect()
+}
+
+// The labels of `resource "aws_instance" "web" { ... }` without their quotes
+fn hcl_block_labels(node: &Node, source: &[u8]) -> Vec<String> {
+    node.named_children(&mut node.walk())
+        .skip(1)
+        .take_while(|child| child.kind() == "string_lit" || child.kind() == "identifier")
+        .map(|child| get_node_text(&child, source).trim_matches('"').to_string())
+        .collect()
+}
+
+// The attributes set directly in a block, nested blocks such as `lifecycle` are left out
+fn hcl_attributes(node: &Node, source: &[u8]) -> Vec<Variable> {
+    let Some(body) = find_child_by_type(node, "body") else {
+        return vec![];
     };
+    body.named_children(&mut body.walk())
+        .filter(|child| child.kind() == "attribute")
+        .filter_map(|child| {
+            let name = find_child_by_type(&child, "identifier")?;
+            Some(Variable {
+                name: get_node_text(&name, source),
+                value_type: String::new(),
+                start_byte: child.start_byte(),
+                value: None,
+                ..Default::default()
+            })
+        })
+        .collect()
+}
 
-    // Sometimes, multiple queries capture the same node with the same capture name.
-    // We need to ensure that we only add the node to the definition map once.
-    let mut captured_nodes: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+fn hcl_attribute_value(node: &Node, attribute: &str, source: &[u8]) -> Option<String> {
+    let body = find_child_by_type(node, "body")?;
+    body.named_children(&mut body.walk())
+        .filter(|child| child.kind() == "attribute")
+        .find(|child| {
+            find_child_by_type(child, "identifier")
+                .is_some_and(|n| get_node_text(&n, source) == attribute)
+        })
+        .and_then(|child| {
+            find_child_by_type(&child, "expression").map(|n| get_node_text(&n, source))
+        })
+}
 
-    for (m, _) in captures {
-        for capture in m.captures {
-            let capture_name = &query.capture_names()[capture.index as usize];
-            let node = capture.node;
-            let node_text = node.utf8_text(source.as_bytes()).unwrap();
+// Functions are known by their arity, `area/1`, the clauses of a declaration share it
+fn erlang_function_name(node: &Node, source: &[u8]) -> Option<String> {
+    let clause = find_child_by_type(node, "function_clause")?;
+    let name = clause.child_by_field_name("name")?;
+    let arity = clause
+        .child_by_field_name("args")
+        .map_or(0, |args| args.named_child_count());
+    Some(format!("{}/{arity}", get_node_text(&name, source)))
+}
 
-            let node_id = node.id();
-            if captured_nodes
-                .get(*capture_name)
-                .map_or(false, |v| v.contains(&node_id))
-            {
-                continue;
-            }
-            captured_nodes
-                .entry(String::from(*capture_name))
-                .or_default()
-                .push(node_id);
+fn erlang_module_name(root: &Node, source: &[u8]) -> Option<String> {
+    find_descendant_by_type(root, "module_attribute")
+        .and_then(|n| n.child_by_field_name("name"))
+        .map(|n| get_node_text(&n, source))
+}
 
-            let name = match language {
-                "cpp" => {
-                    if *capture_name == "class" {
-                        node.child_by_field_name("name")
-                            .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                            .unwrap_or(node_text)
-                            .to_string()
-                    } else {
-                        let ident = find_descendant_by_type(&node, "field_identifier")
-                            .or_else(|| find_descendant_by_type(&node, "operator_name"))
-                            .or_else(|| find_descendant_by_type(&node, "identifier"))
-                            .map(|n| n.utf8_text(source.as_bytes()).unwrap());
-                        if let Some(ident) = ident {
-                            let scope = node
-                                .child_by_field_name("declarator")
-                                .and_then(|n| n.child_by_field_name("declarator"))
-                                .and_then(|n| n.child_by_field_name("scope"));
-
-                            if let Some(scope_node) = scope {
-                                format!(
-                                    "{}::{}",
-                                    scope_node.utf8_text(source.as_bytes()).unwrap(),
-                                    ident
-                                )
-                            } else {
-                                ident.to_string()
-                            }
-                        } else {
-                            node_text.to_string()
-                        }
-                    }
-                }
-                "scala" => node
-                    .child_by_field_name("name")
-                    .or_else(|| node.child_by_field_name("pattern"))
-                    .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                    .unwrap_or(node_text)
-                    .to_string(),
-                "csharp" => {
-                    let mut identifier = node;
-                    // Handle primary constructors (they are direct children of *_declaration)
-                    if *capture_name == "method" && csharp_is_primary_constructor(&node) {
-                        identifier = node.parent().unwrap_or(node);
-                    } else if *capture_name == "class_variable" {
-                        identifier =
-                            find_descendant_by_type(&node, "variable_declarator").unwrap_or(node);
-                    }
+// Every `name/arity` listed in the `-export([...])` attributes of the file
+fn erlang_exports(root: &Node, source: &[u8]) -> BTreeSet<String> {
+    find_descendants_by_type(root, "export_attribute")
+        .iter()
+        .flat_map(|export| find_descendants_by_type(export, "fa"))
+        .filter_map(|fa| {
+            let name = fa.child_by_field_name("fun")?;
+            // The `arity` node keeps its slash, `/1`
+            let arity = fa
+                .child_by_field_name("arity")?
+                .child_by_field_name("value")?;
+            Some(format!(
+                "{}/{}",
+                get_node_text(&name, source),
+                get_node_text(&arity, source)
+            ))
+        })
+        .collect()
+}
 
-                    identifier
-                        .child_by_field_name("name")
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or(node_text)
-                        .to_string()
-                }
-                "ruby" => {
-                    let name = node
-                        .child_by_field_name("name")
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or(node_text)
-                        .to_string();
-                    if *capture_name == "class" || *capture_name == "module" {
-                        ruby_find_parent_module_declaration_name(&node, source.as_bytes())
-                            .unwrap_or(name)
-                    } else {
-                        name
-                    }
-                }
-                _ => node
-                    .child_by_field_name("name")
-                    .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                    .unwrap_or(node_text)
-                    .to_string(),
+// The call in `function area(r::Float64)::Float64` or in the short form `area(r) = ...`
+fn julia_call_signature<'a>(node: &'a Node) -> Option<Node<'a>> {
+    if node.kind() == "assignment" {
+        return node
+            .named_child(0)
+            .filter(|n| n.kind() == "call_expression");
+    }
+    find_descendant_by_type(node, "call_expression").filter(|call| {
+        find_first_ancestor_by_types(call, &["function_definition"]).map(|n| n.id())
+            == Some(node.id())
+    })
+}
+
+fn julia_own_name(node: &Node, source: &[u8]) -> Option<String> {
+    let text = |n: Node| get_node_text(&n, source);
+    match node.kind() {
+        "module_definition" => node
+            .child_by_field_name("name")
+            .or_else(|| find_child_by_type(node, "identifier"))
+            .map(text),
+        "struct_definition" => match find_child_by_type(node, "type_head") {
+            Some(head) => find_descendant_by_type(&head, "identifier").map(text),
+            None => find_child_by_type(node, "identifier").map(text),
+        },
+        "const_statement" => find_descendant_by_type(node, "identifier").map(text),
+        _ => julia_call_signature(node)
+            .and_then(|call| call.named_child(0))
+            .map(text),
+    }
+}
+
+// Nested modules are joined with dots, `module A ... module B` gives `A.B`
+fn julia_module_path(node: &Node, source: &[u8]) -> String {
+    let mut names = Vec::new();
+    let mut parent = node.parent();
+    while let Some(parent_node) = parent {
+        if parent_node.kind() == "module_definition" {
+            names.extend(julia_own_name(&parent_node, source));
+        }
+        parent = parent_node.parent();
+    }
+    names.reverse();
+    names.join(".")
+}
+
+// Functions of a module are listed when its `export` statements name them
+fn julia_is_exported(node: &Node, name: &str, source: &[u8]) -> bool {
+    let Some(module) = find_ancestor_by_type(node, "module_definition") else {
+        return true;
+    };
+    find_descendants_by_type(&module, "export_statement")
+        .iter()
+        .filter(|export| {
+            find_ancestor_by_type(export, "module_definition").map(|n| n.id()) == Some(module.id())
+        })
+        .flat_map(|export| find_descendants_by_type(export, "identifier"))
+        .any(|identifier| get_node_text(&identifier, source) == name)
+}
+
+// `x::Float64` gives the field name and its type, the name side may itself contain `::` as in
+// `area(p::Point)::Float64`
+fn julia_typed_name(node: &Node, source: &[u8]) -> Option<(String, String)> {
+    match node.kind() {
+        "identifier" => Some((get_node_text(node, source), String::new())),
+        "typed_expression" => {
+            let name = node.named_child(0)?;
+            let value_type = node.named_children(&mut node.walk()).last()?;
+            (name.id() != value_type.id()).then(|| {
+                (
+                    get_node_text(&name, source),
+                    get_node_text(&value_type, source),
+                )
+            })
+        }
+        _ => None,
+    }
+}
+
+fn r_string_value(node: &Node, source: &[u8]) -> Option<String> {
+    (node.kind() == "string").then(|| {
+        get_node_text(node, source)
+            .trim_matches(|c| c == '"' || c == '\'')
+            .to_string()
+    })
+}
+
+// The `(name, value)` pairs of a call's arguments, `name` is empty for positional ones
+fn r_call_arguments<'a>(node: &'a Node) -> Vec<(Option<Node<'a>>, Option<Node<'a>>)> {
+    let Some(arguments) = node.child_by_field_name("arguments") else {
+        return vec![];
+    };
+    let mut cursor = arguments.walk();
+    arguments
+        .named_children(&mut cursor)
+        .filter(|n| n.kind() == "argument")
+        .map(|argument| {
+            (
+                argument.child_by_field_name("name"),
+                argument.child_by_field_name("value"),
+            )
+        })
+        .collect()
+}
+
+fn r_function_params(node: &Node, source: &[u8]) -> String {
+    node.child_by_field_name("parameters")
+        .map(|n| get_params_text(&n, source))
+        .unwrap_or_default()
+}
+
+// `setClass("Person", representation(name = "character"))`, slots may also be given through
+// `slots = c(...)` or, for reference classes, `fields = list(...)`
+fn r_class_slots(node: &Node, source: &[u8]) -> Vec<Variable> {
+    let mut slots = Vec::new();
+    for (index, (name, value)) in r_call_arguments(node).into_iter().enumerate() {
+        let argument_name = name.map(|n| get_node_text(&n, source)).unwrap_or_default();
+        let is_slots = matches!(
+            argument_name.as_str(),
+            "representation" | "slots" | "fields"
+        ) || (argument_name.is_empty() && index == 1);
+        let Some(value) = value.filter(|value| is_slots && value.kind() == "call") else {
+            continue;
+        };
+        for (slot_name, slot_type) in r_call_arguments(&value) {
+            let (name, value_type) = match (slot_name, slot_type) {
+                (Some(name), value_type) => (
+                    get_node_text(&name, source),
+                    value_type
+                        .and_then(|n| r_string_value(&n, source))
+                        .unwrap_or_default(),
+                ),
+                (None, Some(value)) => match r_string_value(&value, source) {
+                    Some(name) => (name, String::new()),
+                    None => continue,
+                },
+                (None, None) => continue,
             };
+            slots.push(Variable {
+                name,
+                value_type,
+                start_byte: value.start_byte(),
+                value: None,
+                ..Default::default()
+            });
+        }
+    }
+    slots
+}
 
-            match *capture_name {
-                "class" => {
-                    if !name.is_empty() {
-                        if language == "go" && !is_first_letter_uppercase(&name) {
-                            continue;
-                        }
-                        ensure_class_def(language, &name, &mut class_def_map);
-                        let visibility_modifier_node =
-                            find_child_by_type(&node, "visibility_modifier");
-                        let visibility_modifier = visibility_modifier_node
-                            .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                            .unwrap_or("");
-                        let class_def = class_def_map.get_mut(&name).unwrap();
-                        class_def.borrow_mut().visibility_modifier =
-                            if visibility_modifier.is_empty() {
-                                None
-                            } else {
-                                Some(visibility_modifier.to_string())
-                            };
-                    }
-                }
-                "module" => {
-                    if !name.is_empty() {
-                        ensure_module_def(&name, &mut class_def_map);
-                    }
+// Package names are absolute in Perl, so the innermost `package Foo::Bar` wins, either a
+// block around the node or the last `package Foo::Bar;` statement before it
+fn perl_package_name(node: &Node, source: &[u8]) -> Option<String> {
+    let package_name =
+        |n: &Node| find_child_by_type(n, "package_name").map(|name| get_node_text(&name, source));
+    let mut current = Some(*node);
+    while let Some(current_node) = current {
+        if current_node.kind() == "package_statement"
+            && current_node.child_by_field_name("body").is_some()
+            && current_node.id() != node.id()
+        {
+            return package_name(&current_node);
+        }
+        let mut sibling = current_node.prev_named_sibling();
+        while let Some(sibling_node) = sibling {
+            if sibling_node.kind() == "package_statement"
+                && sibling_node.child_by_field_name("body").is_none()
+            {
+                return package_name(&sibling_node);
+            }
+            sibling = sibling_node.prev_named_sibling();
+        }
+        current = current_node.parent();
+    }
+    None
+}
+
+// Subs declare their parameters in a signature, or unpack them with `my ($self, $x) = @_;`
+fn perl_sub_params(node: &Node, source: &[u8]) -> String {
+    let mut cursor = node.walk();
+    let signature = node
+        .named_children(&mut cursor)
+        .find(|n| matches!(n.kind(), "function_signature" | "function_prototype"));
+    if let Some(signature) = signature {
+        return get_params_text(&signature, source);
+    }
+    let first_statement = node
+        .child_by_field_name("body")
+        .and_then(|body| body.named_child(0))
+        .map(|statement| get_node_text(&statement, source))
+        .unwrap_or_default();
+    first_statement
+        .trim()
+        .strip_prefix("my")
+        .and_then(|rest| rest.trim_end_matches(';').trim_end().strip_suffix("@_"))
+        .and_then(|rest| rest.trim_end().strip_suffix('='))
+        .map(|params| params.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|params| params.starts_with('('))
+        .unwrap_or_else(|| "()".to_string())
+}
+
+// Splits `(NSString *)name` into `(NSString *)` and `name`
+// The labels of `CREATE TYPE mood AS ENUM ('sad', 'happy')`, unquoted
+fn sql_enum_labels(text: &str) -> Option<Vec<String>> {
+    let lowercase = text.to_ascii_lowercase();
+    let words: Vec<(usize, &str)> = lowercase
+        .split_whitespace()
+        .map(|word| (word.as_ptr() as usize - lowercase.as_ptr() as usize, word))
+        .collect();
+    let enum_start = words
+        .windows(2)
+        .find(|pair| pair[0].1 == "as" && pair[1].1.starts_with("enum"))
+        .map(|pair| pair[1].0 + "enum".len())?;
+    let (labels, _) = take_parenthesized(text[enum_start..].trim_start())?;
+    Some(
+        split_params(&labels[1..labels.len() - 1])
+            .into_iter()
+            .map(|label| label.trim().trim_matches('\'').replace("''", "'"))
+            .filter(|label| !label.is_empty())
+            .collect(),
+    )
+}
+
+fn take_parenthesized(text: &str) -> Option<(&str, &str)> {
+    if !text.starts_with('(') {
+        return None;
+    }
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((&text[..=index], &text[index + 1..]));
                 }
-                "enum_item" => {
-                    let visibility_modifier_node =
-                        find_descendant_by_type(&node, "visibility_modifier");
-                    let visibility_modifier = visibility_modifier_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("");
-                    if language == "rust" && !visibility_modifier.contains("pub") {
-                        continue;
-                    }
-                    if language == "zig"
-                        && !zig_is_variable_declaration_public(&node, source.as_bytes())
-                    {
-                        continue;
-                    }
-                    let mut enum_name = get_closest_ancestor_name(&node, source);
-                    if language == "zig" {
-                        enum_name =
-                            zig_find_parent_variable_declaration_name(&node, source.as_bytes())
-                                .unwrap_or_default();
-                    }
-                    if language == "scala" {
-                        if let Some(enum_node) = find_ancestor_by_type(&node, "enum_definition") {
-                            if let Some(name_node) = enum_node.child_by_field_name("name") {
-                                enum_name =
-                                    name_node.utf8_text(source.as_bytes()).unwrap().to_string();
-                            }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Categories name their base class first, `@interface Person (Formatting)` belongs to `Person`
+fn objc_container_name(node: &Node, source: &[u8]) -> Option<String> {
+    let container = find_first_ancestor_by_types(
+        node,
+        &[
+            "class_interface",
+            "class_implementation",
+            "protocol_declaration",
+        ],
+    )?;
+    container
+        .child_by_field_name("name")
+        .or_else(|| find_child_by_type(&container, "identifier"))
+        .map(|n| get_node_text(&n, source))
+}
+
+// `- (void)setName:(NSString *)name age:(int)age` gives the selector `setName:age:`, the
+// typed arguments `(NSString *name, int age)` and the return type, `id` when it is omitted
+fn objc_method_signature(node: &Node, source: &[u8]) -> (String, String, String) {
+    let text = get_node_text(node, source);
+    let text = text.split('{').next().unwrap_or_default();
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut rest = text
+        .trim_end_matches(';')
+        .trim()
+        .trim_start_matches(['+', '-'])
+        .trim_start();
+    let mut return_type = "id".to_string();
+    if let Some((method_type, after)) = take_parenthesized(rest) {
+        return_type = method_type[1..method_type.len() - 1].trim().to_string();
+        rest = after.trim_start();
+    }
+    let is_identifier_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut selector = String::new();
+    let mut params = Vec::new();
+    while let Some((keyword, after)) = rest.split_once(':') {
+        if keyword.contains(|c: char| !is_identifier_char(c) && !c.is_whitespace()) {
+            break;
+        }
+        let keyword = keyword.trim();
+        let mut after = after.trim_start();
+        let mut param_type = "id";
+        if let Some((parenthesized, remaining)) = take_parenthesized(after) {
+            param_type = parenthesized[1..parenthesized.len() - 1].trim();
+            after = remaining.trim_start();
+        }
+        let name_end = after
+            .find(|c: char| !is_identifier_char(c))
+            .unwrap_or(after.len());
+        selector.push_str(keyword);
+        selector.push(':');
+        let separator = if param_type.ends_with('*') { "" } else { " " };
+        params.push(format!("{param_type}{separator}{}", &after[..name_end]));
+        rest = after[name_end..].trim_start();
+    }
+    if selector.is_empty() {
+        let name_end = rest
+            .find(|c: char| !is_identifier_char(c))
+            .unwrap_or(rest.len());
+        return (rest[..name_end].to_string(), "()".to_string(), return_type);
+    }
+    (selector, format!("({})", params.join(", ")), return_type)
+}
+
+// `@property (nonatomic, copy) NSString *name;` gives `name` typed `NSString *`
+fn objc_property(node: &Node, source: &[u8]) -> Option<Variable> {
+    let text = get_node_text(node, source);
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut rest = text.strip_prefix("@property")?.trim_start();
+    if let Some((_, after)) = take_parenthesized(rest) {
+        rest = after.trim_start();
+    }
+    let rest = rest.trim_end_matches(';').trim_end();
+    let name_start = rest
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |index| index + 1);
+    let name = &rest[name_start..];
+    if name.is_empty() {
+        return None;
+    }
+    Some(Variable {
+        name: name.to_string(),
+        value_type: rest[..name_start].trim().to_string(),
+        start_byte: node.start_byte(),
+        value: None,
+        ..Default::default()
+    })
+}
+
+// The first parameter list outside of attributes, `[CmdletBinding()] param($a)` gives `($a)`
+fn powershell_param_list(text: &str) -> Option<String> {
+    let mut depth = 0usize;
+    for (index, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = depth.saturating_sub(1),
+            '(' if depth == 0 => {
+                let (params, _) = take_parenthesized(&text[index..])?;
+                return Some(
+                    params
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .replace("( ", "(")
+                        .replace(" )", ")"),
+                );
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// `[hashtable]$Global:Config` gives `$Global:Config` typed `hashtable`, other scopes are skipped
+fn powershell_global_variable(text: &str) -> Option<(String, String)> {
+    let dollar = text.find('$')?;
+    let scope = text.get(dollar + 1..dollar + 8)?;
+    if !scope.eq_ignore_ascii_case("global:") {
+        return None;
+    }
+    let unscoped = format!("{}${}", &text[..dollar], &text[dollar + 8..]);
+    let (name, value_type) = powershell_typed_variable(&unscoped)?;
+    Some((format!("${scope}{}", &name[1..]), value_type))
+}
+
+// `[Parameter(Mandatory)] [string[]]$Path = "."` gives `$Path` typed `string[]`, attributes
+// are the bracketed groups with arguments
+fn powershell_typed_variable(text: &str) -> Option<(String, String)> {
+    let declaration = text.split('=').next().unwrap_or_default();
+    let dollar = declaration.rfind('$')?;
+    let name_end = declaration[dollar + 1..]
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(declaration.len(), |index| dollar + 1 + index);
+    let name = declaration[dollar..name_end].to_string();
+    let prefix = declaration[..dollar].trim_end();
+    let mut value_type = String::new();
+    if prefix.ends_with(']') {
+        let mut depth = 0usize;
+        for (index, c) in prefix.char_indices().rev() {
+            match c {
+                ']' => depth += 1,
+                '[' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let inner = &prefix[index + 1..prefix.len() - 1];
+                        if !inner.contains('(') {
+                            value_type = inner.trim().to_string();
                         }
+                        break;
                     }
-                    if !enum_name.is_empty()
-                        && language == "go"
-                        && !is_first_letter_uppercase(&enum_name)
-                    {
-                        continue;
-                    }
-                    ensure_enum_def(&enum_name, &mut enum_def_map);
-                    let enum_def = enum_def_map.get_mut(&enum_name).unwrap();
-                    let enum_type_node = find_descendant_by_type(&node, "type_identifier");
-                    let enum_type = enum_type_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("");
-                    let variable = Variable {
-                        name: name.to_string(),
-                        value_type: enum_type.to_string(),
-                    };
-                    enum_def.borrow_mut().items.push(variable);
                 }
-                "union_item" => {
-                    if language != "zig" {
-                        continue;
-                    }
-                    if !zig_is_variable_declaration_public(&node, source.as_bytes()) {
-                        continue;
+                _ => {}
+            }
+        }
+    }
+    Some((name, value_type))
+}
+
+// `inline private add (x: int) (y: int) : int = ...` gives the name `add`, the parameters
+// `(x: int) (y: int)`, the annotated type `int` and whether the binding is hidden
+fn fsharp_binding(text: &str) -> Option<(String, String, String, bool)> {
+    let mut depth = 0usize;
+    let mut head_end = text.len();
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            '=' if depth == 0 => {
+                head_end = index;
+                break;
+            }
+            _ => {}
+        }
+    }
+    let mut head = text[..head_end].trim();
+    let mut hidden = false;
+    loop {
+        let word = head.split_whitespace().next()?;
+        match word {
+            "let" | "rec" | "inline" | "mutable" | "static" | "member" | "abstract"
+            | "override" | "default" | "public" => {}
+            "private" | "internal" => hidden = true,
+            _ => break,
+        }
+        head = head[word.len()..].trim_start();
+    }
+    let name_end = head
+        .find(|c: char| c.is_whitespace() || matches!(c, '(' | ':' | '<'))
+        .unwrap_or(head.len());
+    // `this.Scale` names the member `Scale`
+    let name = head[..name_end].rsplit('.').next()?.to_string();
+    if name.is_empty() {
+        return None;
+    }
+    let mut rest = head[name_end..].trim();
+    if rest.starts_with('<') {
+        rest = rest.find('>').map_or("", |index| rest[index + 1..].trim());
+    }
+    depth = 0;
+    let mut colon = None;
+    for (index, c) in rest.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ':' if depth == 0 => {
+                colon = Some(index);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let (params, value_type) = match colon {
+        Some(index) => (&rest[..index], rest[index + 1..].trim()),
+        None => (rest, ""),
+    };
+    Some((
+        name,
+        params.split_whitespace().collect::<Vec<_>>().join(" "),
+        value_type.to_string(),
+        hidden,
+    ))
+}
+
+// Modules and namespaces enclosing a node, joined like `Shapes.Geometry`, and whether one of
+// them is `private`/`internal`
+fn fsharp_module_path(node: &Node, source: &[u8]) -> (Option<String>, bool) {
+    let mut names = vec![];
+    let mut hidden = false;
+    let mut current = Some(*node);
+    while let Some(current_node) = current {
+        if matches!(
+            current_node.kind(),
+            "namespace" | "named_module" | "module_defn"
+        ) {
+            let name = current_node
+                .child_by_field_name("name")
+                .or_else(|| find_child_by_type(&current_node, "long_identifier"))
+                .or_else(|| find_child_by_type(&current_node, "identifier"));
+            if let Some(name) = name {
+                names.push(get_node_text(&name, source));
+            }
+            hidden |= find_child_by_type(&current_node, "access_modifier")
+                .is_some_and(|n| get_node_text(&n, source) != "public");
+        }
+        current = current_node.parent();
+    }
+    names.reverse();
+    ((!names.is_empty()).then(|| names.join(".")), hidden)
+}
+
+// `area*` is exported, `` `+`* `` names an operator, gives the name and whether it has the marker
+fn nim_symbol(text: &str) -> Option<(String, bool, &str)> {
+    let text = text.trim_start();
+    let name_end = if let Some(quoted) = text.strip_prefix('`') {
+        quoted.find('`')? + 2
+    } else {
+        text.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(text.len())
+    };
+    if name_end == 0 {
+        return None;
+    }
+    let name = text[..name_end].trim_matches('`').to_string();
+    let rest = text[name_end..].trim_start();
+    match rest.strip_prefix('*') {
+        Some(rest) => Some((name, true, rest)),
+        None => Some((name, false, rest)),
+    }
+}
+
+// `proc area*(s: Shape): float {.inline.} =` gives `area`, the export marker, `(s: Shape)`
+// and `float`
+fn nim_routine_signature(text: &str) -> Option<(String, bool, String, String)> {
+    let text = text.trim_start();
+    let keyword_end = text.find(char::is_whitespace)?;
+    let (name, exported, mut rest) = nim_symbol(&text[keyword_end..])?;
+    // Generic parameters, `proc first*[T](items: seq[T]): T`
+    if rest.starts_with('[') {
+        let mut depth = 0;
+        for (index, c) in rest.char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        rest = &rest[index + 1..];
+                        break;
                     }
-                    let union_name =
-                        zig_find_parent_variable_declaration_name(&node, source.as_bytes())
-                            .unwrap_or_default();
-                    ensure_union_def(&union_name, &mut union_def_map);
-                    let union_def = union_def_map.get_mut(&union_name).unwrap();
-                    let union_type_node = find_descendant_by_type(&node, "type_identifier");
-                    let union_type = union_type_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("");
-                    let variable = Variable {
-                        name: name.to_string(),
-                        value_type: union_type.to_string(),
-                    };
-                    union_def.borrow_mut().items.push(variable);
                 }
-                "method" => {
-                    // TODO: C++: Skip private/protected class/struct methods
-                    let visibility_modifier_node =
-                        find_descendant_by_type(&node, "visibility_modifier");
-                    let visibility_modifier = visibility_modifier_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("");
-                    if language == "swift" {
-                        if visibility_modifier.contains("private") {
-                            continue;
-                        }
-                    }
-                    if language == "java" {
-                        let modifier_node = find_descendant_by_type(&node, "modifiers");
-                        if modifier_node.is_some() {
-                            let modifier_text =
-                                modifier_node.unwrap().utf8_text(source.as_bytes()).unwrap();
-                            if modifier_text.contains("private") {
-                                continue;
-                            }
-                        }
-                    }
-                    if language == "rust" && !visibility_modifier.contains("pub") {
-                        continue;
-                    }
-                    if language == "zig"
-                        && !(zig_is_function_declaration_public(&node, source.as_bytes())
-                            && zig_is_variable_declaration_public(&node, source.as_bytes()))
-                    {
-                        continue;
-                    }
-                    if language == "cpp"
-                        && find_descendant_by_type(&node, "destructor_name").is_some()
-                    {
-                        continue;
-                    }
+                _ => {}
+            }
+        }
+    }
+    let (params, rest) = take_parenthesized(rest.trim_start()).unwrap_or(("()", rest));
+    let return_type = rest
+        .trim_start()
+        .strip_prefix(':')
+        .map(|return_type| {
+            let end = return_type
+                .find("{.")
+                .into_iter()
+                .chain(return_type.find('='))
+                .min()
+                .unwrap_or(return_type.len());
+            return_type[..end].trim().to_string()
+        })
+        .unwrap_or_default();
+    Some((
+        name,
+        exported,
+        params.split_whitespace().collect::<Vec<_>>().join(" "),
+        return_type,
+    ))
+}
+
+// `x*, y: int = 0` gives the exported names `x` and the type `int`
+fn nim_exported_names(text: &str) -> (Vec<String>, String) {
+    let declaration = text.split('=').next().unwrap_or_default();
+    let (names, value_type) = declaration.split_once(':').unwrap_or((declaration, ""));
+    let names = names
+        .split(',')
+        .filter_map(nim_symbol)
+        .filter(|(_, exported, _)| *exported)
+        .map(|(name, _, _)| name)
+        .collect();
+    (names, value_type.trim().to_string())
+}
+
+// The parameter list following the name of a function or event, and its `returns (...)`
+// clause with a single unnamed value unwrapped
+fn solidity_signature(node: &Node, source: &[u8]) -> Option<(String, String)> {
+    let name = node.child_by_field_name("name")?;
+    let after_name = std::str::from_utf8(&source[name.end_byte()..node.end_byte()]).ok()?;
+    let (params, _) = take_parenthesized(after_name.trim_start())?;
+    let return_type = find_child_by_type(node, "return_type_definition")
+        .map(|n| {
+            let returns = get_params_text(&n, source);
+            let returns = returns.trim_start_matches("returns").trim().to_string();
+            let values = returns
+                .strip_prefix('(')
+                .and_then(|values| values.strip_suffix(')'))
+                .map(split_params)
+                .unwrap_or_default();
+            match values.as_slice() {
+                [value] if !value.contains(' ') => value.clone(),
+                _ => returns,
+            }
+        })
+        .unwrap_or_default();
+    Some((
+        params.split_whitespace().collect::<Vec<_>>().join(" "),
+        return_type,
+    ))
+}
+
+// `def area(scale : Float64) : Float64` gives `(scale : Float64)` and `Float64`, methods
+// without a return type annotation are `void` like Ruby's
+fn crystal_method_signature(node: &Node, source: &[u8]) -> (String, String) {
+    let Some(after_name) = node
+        .child_by_field_name("name")
+        .and_then(|name| std::str::from_utf8(&source[name.end_byte()..node.end_byte()]).ok())
+    else {
+        return ("()".to_string(), "void".to_string());
+    };
+    let (params, rest) = take_parenthesized(after_name.trim_start()).unwrap_or(("()", after_name));
+    let return_type = rest
+        .lines()
+        .next()
+        .and_then(|line| line.trim_start().strip_prefix(':'))
+        .map(|return_type| {
+            let return_type = return_type.split(" forall ").next().unwrap_or_default();
+            return_type
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        })
+        .filter(|return_type| !return_type.is_empty())
+        .unwrap_or_else(|| "void".to_string());
+    (
+        params.split_whitespace().collect::<Vec<_>>().join(" "),
+        return_type,
+    )
+}
+
+// `private def` and `protected def` leave the modifier as a bare identifier statement right
+// before the method on the same line
+fn crystal_visibility(node: &Node, source: &[u8]) -> Option<String> {
+    node.prev_named_sibling()
+        .filter(|sibling| {
+            sibling.kind() == "expression_statement"
+                && sibling.end_position().row == node.start_position().row
+        })
+        .map(|sibling| get_node_text(&sibling, source))
+        .filter(|modifier| matches!(modifier.as_str(), "private" | "protected"))
+}
+
+// Attributes and storage classes that may lead a D declaration
+const D_ATTRIBUTES: &[&str] = &[
+    "public",
+    "private",
+    "protected",
+    "package",
+    "export",
+    "static",
+    "final",
+    "override",
+    "abstract",
+    "extern",
+    "pure",
+    "nothrow",
+    "shared",
+    "__gshared",
+    "synchronized",
+    "deprecated",
+    "immutable",
+    "const",
+    "inout",
+    "scope",
+];
+
+// `public static immutable int` gives `["public", "static", "immutable"]` and `int`
+fn d_attributes_and_type(prefix: &str) -> (Vec<String>, String) {
+    let mut attributes = Vec::new();
+    let mut rest = prefix.trim();
+    loop {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let word = &rest[..end];
+        if word.is_empty() || !(word.starts_with('@') || D_ATTRIBUTES.contains(&word)) {
+            break;
+        }
+        attributes.push(word.to_string());
+        rest = rest[end..].trim_start();
+    }
+    (attributes, rest.to_string())
+}
+
+// The text of a declaration up to its body
+fn d_header(text: &str) -> &str {
+    &text[..text.find(['{', ';']).unwrap_or(text.len())]
+}
+
+// `pure T max(T)(T a, T b) if (isNumeric!T)` gives the attributes, `max`, the template and
+// regular parameters `(T)(T a, T b)` and `T`
+fn d_function_signature(text: &str) -> Option<(Vec<String>, String, String, String)> {
+    let header = d_header(text);
+    // The name is the identifier right before the first parameter list, `const(char)[]` and
+    // `Array!(int)` are part of the return type
+    let mut search = 0;
+    let (before, name_start, open) = loop {
+        let open = search + header[search..].find('(')?;
+        let before = header[..open].trim_end();
+        let name_start = before
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+            .last()
+            .map_or(before.len(), |(index, _)| index);
+        let name = &before[name_start..];
+        if !name.is_empty()
+            && !matches!(name, "const" | "immutable" | "shared" | "inout" | "scope")
+            && !before[..name_start].ends_with('!')
+        {
+            break (before, name_start, open);
+        }
+        search = open + 1;
+    };
+    let (attributes, return_type) = d_attributes_and_type(&before[..name_start]);
+    let (first, rest) = take_parenthesized(&header[open..])?;
+    let params = match take_parenthesized(rest.trim_start()) {
+        Some((second, _)) => format!("{first}{second}"),
+        None => first.to_string(),
+    };
+    Some((
+        attributes,
+        before[name_start..].to_string(),
+        params.split_whitespace().collect::<Vec<_>>().join(" "),
+        return_type,
+    ))
+}
+
+// `final class Stack(T) : Container` gives the attributes, `Stack` and its template parameters
+fn d_type_declaration(text: &str) -> Option<(Vec<String>, String, Option<String>)> {
+    let (attributes, rest) = d_attributes_and_type(d_header(text));
+    let rest = ["class", "struct", "interface"]
+        .iter()
+        .find_map(|keyword| rest.strip_prefix(keyword))?
+        .trim_start();
+    let name_end = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    if name_end == 0 {
+        return None;
+    }
+    let generics = take_parenthesized(rest[name_end..].trim_start())
+        .map(|(generics, _)| generics.split_whitespace().collect::<Vec<_>>().join(" "));
+    Some((attributes, rest[..name_end].to_string(), generics))
+}
+
+// `public static immutable int MAX = 10, MIN = 0;` gives the attributes, `int` and the names
+fn d_variable_declaration(text: &str) -> (Vec<String>, String, Vec<String>) {
+    let declarators = split_params(text.trim().trim_end_matches(';'));
+    let declared_name = |declarator: &str| {
+        let target = declarator.split('=').next().unwrap_or_default().trim();
+        let name_start = target
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+            .last()
+            .map_or(target.len(), |(index, _)| index);
+        (
+            target[..name_start].to_string(),
+            target[name_start..].to_string(),
+        )
+    };
+    let Some((first, others)) = declarators.split_first() else {
+        return (vec![], String::new(), vec![]);
+    };
+    let (prefix, name) = declared_name(first);
+    let (attributes, value_type) = d_attributes_and_type(&prefix);
+    let names = std::iter::once(name)
+        .chain(others.iter().map(|declarator| declared_name(declarator).1))
+        .filter(|name| !name.is_empty())
+        .collect();
+    (attributes, value_type, names)
+}
+
+// The forms of a list, leaving out comments and `#_` discarded forms
+fn clojure_forms<'a>(node: &'a Node) -> Vec<Node<'a>> {
+    let mut cursor = node.walk();
+    node.named_children(&mut cursor)
+        .filter(|n| {
+            !matches!(
+                n.kind(),
+                "comment" | "dis_expr" | "meta_lit" | "old_meta_lit"
+            )
+        })
+        .collect()
+}
+
+// Definitions belong to the namespace declared by the last `(ns ...)` form before them
+fn forms_namespace(node: &Node, source: &[u8]) -> Option<String> {
+    let mut sibling = node.prev_named_sibling();
+    while let Some(sibling_node) = sibling {
+        if sibling_node.kind() == "list_lit" {
+            let forms = clojure_forms(&sibling_node);
+            let head = forms.first().and_then(|n| clojure_symbol_name(n, source));
+            if head.as_deref() == Some("ns") {
+                return forms.get(1).and_then(|n| clojure_symbol_name(n, source));
+            }
+        }
+        sibling = sibling_node.prev_named_sibling();
+    }
+    None
+}
+
+// `^:private foo` gives `foo`, the metadata is attached to the symbol
+fn clojure_symbol_name(node: &Node, source: &[u8]) -> Option<String> {
+    if node.kind() != "sym_lit" {
+        return None;
+    }
+    Some(find_child_by_type(node, "sym_name").map_or_else(
+        || get_node_text(node, source),
+        |n| get_node_text(&n, source),
+    ))
+}
+
+fn clojure_is_private(node: &Node, source: &[u8]) -> bool {
+    let mut cursor = node.walk();
+    let is_private = node
+        .children(&mut cursor)
+        .filter(|n| matches!(n.kind(), "meta_lit" | "old_meta_lit"))
+        .any(|n| get_node_text(&n, source).contains(":private"));
+    is_private
+}
+
+// The argument vectors of a function, `[x]` or `[x] [x y]` for multi-arity ones
+fn clojure_arg_vectors(forms: &[Node], source: &[u8]) -> String {
+    if let Some(vector) = forms.iter().find(|n| n.kind() == "vec_lit") {
+        return get_params_text(vector, source);
+    }
+    forms
+        .iter()
+        .filter(|n| n.kind() == "list_lit")
+        .filter_map(|arity| {
+            clojure_forms(arity)
+                .first()
+                .filter(|n| n.kind() == "vec_lit")
+                .map(|n| get_params_text(n, source))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Nested messages and enums are referenced as `Outer.Inner`
+fn proto_name(node: &Node, source: &[u8]) -> Option<String> {
+    let own_name = |n: &Node| {
+        find_child_by_type(n, "message_name")
+            .or_else(|| find_child_by_type(n, "enum_name"))
+            .or_else(|| find_child_by_type(n, "service_name"))
+            .map(|name| get_node_text(&name, source))
+    };
+    let mut names = vec![own_name(node)?];
+    let mut parent = node.parent();
+    while let Some(parent_node) = parent {
+        if parent_node.kind() == "message" {
+            names.extend(own_name(&parent_node));
+        }
+        parent = parent_node.parent();
+    }
+    names.reverse();
+    Some(names.join("."))
+}
+
+// `repeated string tags = 3;` gives `tags` typed `repeated string`, the same goes for
+// `map<string, int32> scores = 4;`
+fn proto_field(node: &Node, source: &[u8]) -> Option<Variable> {
+    let text = get_node_text(node, source);
+    let (declaration, _) = text.split_once('=')?;
+    let declaration = declaration.split_whitespace().collect::<Vec<_>>().join(" ");
+    let (value_type, name) = declaration.rsplit_once(' ')?;
+    Some(Variable {
+        name: name.to_string(),
+        value_type: value_type.to_string(),
+        start_byte: node.start_byte(),
+        value: None,
+        ..Default::default()
+    })
+}
+
+// `rpc GetUser (GetUserRequest) returns (stream User);`
+fn proto_rpc(node: &Node, source: &[u8]) -> Option<(String, String, String)> {
+    let text = get_node_text(node, source);
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let rest = text.strip_prefix("rpc")?.trim_start();
+    let (name, rest) = rest.split_once('(')?;
+    let (request, rest) = rest.split_once(')')?;
+    let (_, response) = rest.split_once("returns")?.1.split_once('(')?;
+    let (response, _) = response.split_once(')')?;
+    Some((
+        name.trim().to_string(),
+        format!("({})", request.trim()),
+        response.trim().to_string(),
+    ))
+}
+
+// The type following `RETURNS` in `CREATE FUNCTION`
+fn sql_return_type(node: &Node, source: &[u8]) -> String {
+    let mut cursor = node.walk();
+    let mut children = node.children(&mut cursor);
+    children
+        .find(|n| n.kind() == "keyword_returns")
+        .and_then(|_| children.find(Node::is_named))
+        .map(|n| get_node_text(&n, source))
+        .unwrap_or_default()
+}
+
+// Styled components and other CSS-in-JS values are tagged templates, `styled.button\`...\``
+// gives the tag `styled.button`
+fn js_template_tag(node: &Node, source: &[u8]) -> Option<String> {
+    if node.kind() != "call_expression"
+        || node.child_by_field_name("arguments")?.kind() != "template_string"
+    {
+        return None;
+    }
+    let tag = node.child_by_field_name("function")?;
+    Some(
+        get_node_text(&tag, source)
+            .split_whitespace()
+            .collect::<String>(),
+    )
+}
+
+// Groovy members are public unless declared otherwise
+fn groovy_is_private(node: &Node, source: &[u8]) -> bool {
+    find_child_by_type(node, "modifiers").is_some_and(|modifiers| {
+        get_node_text(&modifiers, source)
+            .split_whitespace()
+            .any(|modifier| matches!(modifier, "private" | "protected"))
+    })
+}
+
+// `def` leaves the type to be inferred
+fn groovy_type(node: &Node, source: &[u8]) -> String {
+    node.child_by_field_name("type")
+        .map(|n| get_node_text(&n, source))
+        .filter(|text| text != "def")
+        .unwrap_or_default()
+}
+
+// Top-level calls of a build script, `dependencies { ... }` gives `dependencies` while
+// `task hello { ... }` and `tasks.register('hello')` give the name of the task
+fn groovy_script_call_name(node: &Node, source: &[u8]) -> Option<String> {
+    // The name of `tasks.register(...)` is `register`, its receiver comes before it
+    let function = node.child_by_field_name("name")?;
+    let function_name = std::str::from_utf8(&source[node.start_byte()..function.end_byte()])
+        .ok()?
+        .to_string();
+    let arguments = std::str::from_utf8(&source[function.end_byte()..node.end_byte()])
+        .unwrap_or_default()
+        .trim_start()
+        .trim_start_matches('(')
+        .trim_start();
+    if matches!(
+        function_name.as_str(),
+        "task" | "tasks.register" | "tasks.create"
+    ) {
+        let arguments = arguments.trim_start_matches(['\'', '"']);
+        let name_end = arguments
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(arguments.len());
+        return (name_end > 0).then(|| arguments[..name_end].to_string());
+    }
+    // Only configuration blocks, plain statements such as `apply plugin: 'java'` are skipped
+    arguments.contains('{').then_some(function_name)
+}
+
+fn ex_find_parent_module_declaration_name<'a>(node: &'a Node, source: &'a [u8]) -> Option<String> {
+    let mut parent = node.parent();
+    while let Some(parent_node) = parent {
+        if parent_node.kind() == "call" {
+            let text = get_node_text(&parent_node, source);
+            if text.starts_with("defmodule ") {
+                let arguments_node = find_child_by_type(&parent_node, "arguments");
+                if let Some(arguments_node) = arguments_node {
+                    return Some(get_node_text(&arguments_node, source));
+                }
+            }
+        }
+        parent = parent_node.parent();
+    }
+    None
+}
+
+fn ruby_find_parent_module_declaration_name<'a>(
+    node: &'a Node,
+    source: &'a [u8],
+) -> Option<String> {
+    let mut path_parts = Vec::new();
+    let mut current = Some(*node);
+
+    while let Some(current_node) = current {
+        // Crystal's `class_declaration` nests like Ruby's
+        if matches!(
+            current_node.kind(),
+            "module" | "class" | "class_declaration"
+        ) {
+            if let Some(name_node) = current_node.child_by_field_name("name") {
+                path_parts.push(get_node_text(&name_node, source));
+            }
+        }
+        current = current_node.parent();
+    }
+
+    if path_parts.is_empty() {
+        None
+    } else {
+        path_parts.reverse();
+        Some(path_parts.join("::"))
+    }
+}
+
+// Rust-specific function to expand a `use` argument into the imported paths
+fn rust_collect_use_paths(node: &Node, prefix: &str, source: &[u8], imports: &mut Vec<Import>) {
+    let join = |path: &str| {
+        if prefix.is_empty() {
+            path.to_string()
+        } else if path == "self" {
+            prefix.to_string()
+        } else {
+            format!("{prefix}::{path}")
+        }
+    };
+    match node.kind() {
+        "use_wildcard" => {
+            let text = get_node_text(node, source);
+            imports.push(Import {
+                name: "*".to_string(),
+                path: join(text.trim_end_matches('*').trim_end_matches("::")),
+                glob: true,
+                prelude: false,
+            });
+        }
+        "use_as_clause" => {
+            let path = node
+                .child_by_field_name("path")
+                .map(|n| get_node_text(&n, source))
+                .unwrap_or_default();
+            let alias = node
+                .child_by_field_name("alias")
+                .map(|n| get_node_text(&n, source))
+                .unwrap_or_default();
+            imports.push(Import {
+                name: alias,
+                path: join(&path),
+                glob: false,
+                prelude: false,
+            });
+        }
+        "scoped_use_list" => {
+            let path = node
+                .child_by_field_name("path")
+                .map(|n| join(&get_node_text(&n, source)))
+                .unwrap_or_else(|| prefix.to_string());
+            if let Some(list) = node.child_by_field_name("list") {
+                rust_collect_use_paths(&list, &path, source, imports);
+            }
+        }
+        "use_list" => {
+            for child in node.named_children(&mut node.walk()) {
+                rust_collect_use_paths(&child, prefix, source, imports);
+            }
+        }
+        _ => {
+            let path = join(&get_node_text(node, source));
+            let name = path.rsplit("::").next().unwrap_or_default().to_string();
+            imports.push(Import {
+                name,
+                path,
+                glob: false,
+                prelude: false,
+            });
+        }
+    }
+}
+
+fn get_node_text<'a>(node: &'a Node, source: &'a [u8]) -> String {
+    node.utf8_text(source).unwrap_or_default().to_string()
+}
+
+fn get_node_type<'a>(node: &'a Node, source: &'a [u8]) -> String {
+    // TypeScript annotations keep their colon, `: Box<U>`
+    if node.kind() == "type_annotation" {
+        return get_node_text(node, source)
+            .trim_start_matches(':')
+            .trim()
+            .to_string();
+    }
+    let predefined_type_node = find_descendant_by_type(node, "predefined_type");
+    if let Some(type_node) = predefined_type_node {
+        return type_node.utf8_text(source).unwrap_or_default().to_string();
+    }
+    let value_type_node = node.child_by_field_name("type");
+    value_type_node
+        .map(|n| {
+            if n.kind() == "type_annotation" {
+                get_node_type(&n, source)
+            } else {
+                n.utf8_text(source).unwrap_or_default().to_string()
+            }
+        })
+        .unwrap_or_default()
+}
+
+// Parameter lists are rebuilt one parameter at a time, so a list spread over several lines fits
+// on one. Each parameter keeps its source text, including annotations and attributes such as
+// `@RequestParam` or Rust's `#[cfg(..)]`
+fn get_params_text(node: &Node, source: &[u8]) -> String {
+    let children = node.children(&mut node.walk()).collect::<Vec<_>>();
+    let (Some(open), Some(close)) = (children.first(), children.last()) else {
+        return get_node_text(node, source);
+    };
+    if children.len() < 2
+        || open.is_named()
+        || close.is_named()
+        || !matches!(
+            (open.kind(), close.kind()),
+            ("(", ")") | ("<", ">") | ("[", "]")
+        )
+    {
+        return get_node_text(node, source);
+    }
+    let mut params = Vec::new();
+    for group in children[1..children.len() - 1].split(|child| child.kind() == ",") {
+        let mut parts = group
+            .iter()
+            .filter(|child| !child.kind().contains("comment"));
+        let Some(first) = parts.next() else {
+            continue;
+        };
+        let last = parts.next_back().unwrap_or(first);
+        let text =
+            std::str::from_utf8(&source[first.start_byte()..last.end_byte()]).unwrap_or_default();
+        params.push(text.to_string());
+    }
+    format!("{}{}{}", open.kind(), params.join(", "), close.kind())
+}
+
+// Attributes in front of a field, e.g. the `#[serde(default)]` of a Rust struct field
+fn get_field_attributes(node: &Node, source: &[u8], options: &ExtractOptions) -> Vec<String> {
+    let mut attributes = Vec::new();
+    if !options.signatures {
+        return attributes;
+    }
+    let mut sibling = node.prev_named_sibling();
+    while let Some(attribute) = sibling.filter(|n| n.is_extra() || n.kind() == "attribute_item") {
+        if !attribute.is_extra() {
+            attributes.push(get_node_text(&attribute, source));
+        }
+        sibling = attribute.prev_named_sibling();
+    }
+    attributes.reverse();
+    attributes
+}
+
+// Languages whose generic classes and functions keep their `<T>` in the repo map
+const TYPE_PARAMETERS_LANGUAGES: &[&str] = &["rust", "typescript", "java", "csharp", "swift"];
+
+const GENERIC_TYPE_DECLARATIONS: &[&str] = &[
+    "struct_item",
+    "class_declaration",
+    "abstract_class_declaration",
+    "interface_declaration",
+    "record_declaration",
+    "struct_declaration",
+];
+
+fn get_type_parameters(
+    node: &Node,
+    language: &str,
+    source: &[u8],
+    options: &ExtractOptions,
+) -> Option<String> {
+    if !options.signatures || !TYPE_PARAMETERS_LANGUAGES.contains(&language) {
+        return None;
+    }
+    node.child_by_field_name("type_parameters")
+        .or_else(|| find_child_by_type(node, "type_parameters"))
+        .or_else(|| find_child_by_type(node, "type_parameter_list"))
+        .map(|n| get_params_text(&n, source))
+}
+
+// Add the bounds of an inherent `impl` to the type's parameters: `<T>` with `impl<T: Clone>` gives
+// `<T: Clone>`, and a second `impl<T: Default>` gives `<T: Clone + Default>`
+fn merge_impl_bounds(generics: &str, type_parameters: &Node, source: &[u8]) -> String {
+    let inner = generics
+        .strip_prefix('<')
+        .and_then(|rest| rest.strip_suffix('>'))
+        .unwrap_or(generics);
+    let mut params = split_params(inner);
+    let mut cursor = type_parameters.walk();
+    for param in type_parameters.named_children(&mut cursor) {
+        // Older grammars emit `constrained_type_parameter` with `left`, newer ones `type_parameter`
+        // with `name`
+        let (Some(left), Some(bounds)) = (
+            param
+                .child_by_field_name("left")
+                .or_else(|| param.child_by_field_name("name")),
+            param.child_by_field_name("bounds"),
+        ) else {
+            continue;
+        };
+        let name = left.utf8_text(source).unwrap_or_default();
+        let Some(existing) = params
+            .iter_mut()
+            .find(|p| p.split(':').next().map(str::trim) == Some(name))
+        else {
+            continue;
+        };
+        let mut bounds_cursor = bounds.walk();
+        for bound in bounds.named_children(&mut bounds_cursor) {
+            let bound = bound.utf8_text(source).unwrap_or_default();
+            let known = existing
+                .split_once(':')
+                .is_some_and(|(_, known)| known.split('+').any(|b| b.trim() == bound));
+            if !known {
+                existing.push_str(if existing.contains(':') { " + " } else { ": " });
+                existing.push_str(bound);
+            }
+        }
+    }
+    format!("<{}>", params.join(", "))
+}
+
+// Type parameters of the class, struct or interface declaring `node`
+fn get_class_type_parameters(
+    node: &Node,
+    language: &str,
+    source: &[u8],
+    options: &ExtractOptions,
+) -> Option<String> {
+    let declaration = if GENERIC_TYPE_DECLARATIONS.contains(&node.kind()) {
+        Some(*node)
+    } else {
+        find_first_ancestor_by_types(node, GENERIC_TYPE_DECLARATIONS)
+    };
+    declaration.and_then(|n| get_type_parameters(&n, language, source, options))
+}
+
+fn get_variable_type(node: &Node, source: &[u8], options: &ExtractOptions) -> String {
+    if !options.signatures {
+        return String::new();
+    }
+    get_node_type(node, source)
+}
+
+const MAX_CONST_VALUE_CHARS: usize = 32;
+
+// Initializers worth showing next to a constant: short literals such as `1024`, `"v1"` or `-1`,
+// and paths such as `Level::Info`
+fn rust_const_value(node: &Node, source: &[u8]) -> Option<String> {
+    let value = node.child_by_field_name("value")?;
+    let is_literal = |n: &Node| {
+        matches!(
+            n.kind(),
+            "integer_literal"
+                | "float_literal"
+                | "boolean_literal"
+                | "char_literal"
+                | "string_literal"
+                | "raw_string_literal"
+        )
+    };
+    let is_simple = match value.kind() {
+        "identifier" | "scoped_identifier" => true,
+        "unary_expression" => value.named_child(0).is_some_and(|n| is_literal(&n)),
+        _ => is_literal(&value),
+    };
+    let text = get_node_text(&value, source);
+    (is_simple && !text.contains('\n') && text.chars().count() <= MAX_CONST_VALUE_CHARS)
+        .then_some(text)
+}
+
+fn is_first_letter_uppercase(name: &str) -> bool {
+    if name.is_empty() {
+        return false;
+    }
+    name.chars().next().unwrap().is_uppercase()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ExtractOptions {
+    pub include_calls: bool,
+    pub max_calls: usize,
+    // Resolve parameter lists, return types, type parameters, field attributes and constant values,
+    // disabled for name-only listings
+    pub signatures: bool,
+    // Record which other definitions of the file each function refers to, only `get_definitions`
+    // hands them out
+    pub include_references: bool,
+    // Name of the Vue component of the file, usually its file stem, `<script setup>` has no other
+    pub component_name: Option<String>,
+    // Emit arrow functions and lambdas bound to a variable as functions, they are dropped by default
+    pub include_anonymous: bool,
+    // Keep the re-indented source of functions spanning at most this many lines, none when 0
+    pub small_body_lines: usize,
+    // Attach doc comments and docstrings to functions and classes
+    pub include_docs: bool,
+    // Also keep private, unexported and underscore-prefixed members, e.g. for refactoring
+    pub include_private: bool,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        ExtractOptions {
+            include_calls: false,
+            max_calls: 20,
+            signatures: true,
+            include_references: false,
+            component_name: None,
+            include_anonymous: false,
+            small_body_lines: 0,
+            include_docs: false,
+            include_private: false,
+        }
+    }
+}
+
+// Builtins and helpers that only add noise to call lists
+const CALL_DENYLIST: &[&str] = &[
+    // rust
+    "Some",
+    "Ok",
+    "Err",
+    "unwrap",
+    "expect",
+    "clone",
+    "to_string",
+    "into",
+    "iter",
+    "collect",
+    // go
+    "len",
+    "cap",
+    "append",
+    "make",
+    "new",
+    "panic",
+    "Println",
+    "Printf",
+    "Sprintf",
+    "Errorf",
+    // python
+    "print",
+    "str",
+    "int",
+    "float",
+    "bool",
+    "list",
+    "dict",
+    "set",
+    "tuple",
+    "range",
+    "isinstance",
+    "super",
+    // typescript
+    "log",
+    "push",
+    "require",
+    "then",
+    "toString",
+];
+
+fn get_callee_name(function: Node<'_>) -> Option<Node<'_>> {
+    match function.kind() {
+        "identifier" | "field_identifier" | "property_identifier" => Some(function),
+        // rust
+        "field_expression" => function.child_by_field_name("field"),
+        "scoped_identifier" => function.child_by_field_name("name"),
+        "generic_function" => function
+            .child_by_field_name("function")
+            .and_then(get_callee_name),
+        // go
+        "selector_expression" => function.child_by_field_name("field"),
+        // python
+        "attribute" => function.child_by_field_name("attribute"),
+        // typescript
+        "member_expression" => function.child_by_field_name("property"),
+        _ => None,
+    }
+}
+
+// Collect the distinct names called inside a function, method calls are recorded without receiver
+fn collect_calls(
+    node: &Node,
+    language: &str,
+    source: &[u8],
+    options: &ExtractOptions,
+) -> Vec<String> {
+    if !options.include_calls {
+        return vec![];
+    }
+    let call_kind = match language {
+        "rust" | "go" | "typescript" | "javascript" => "call_expression",
+        "python" => "call",
+        _ => return vec![],
+    };
+    let mut calls: Vec<String> = Vec::new();
+    let mut cursor = node.walk();
+    for i in 0..node.descendant_count() {
+        if calls.len() >= options.max_calls {
+            break;
+        }
+        cursor.goto_descendant(i);
+        let call_node = cursor.node();
+        if call_node.kind() != call_kind {
+            continue;
+        }
+        let Some(callee) = call_node
+            .child_by_field_name("function")
+            .and_then(get_callee_name)
+        else {
+            continue;
+        };
+        let callee = get_node_text(&callee, source);
+        if CALL_DENYLIST.contains(&callee.as_str()) || calls.contains(&callee) {
+            continue;
+        }
+        calls.push(callee);
+    }
+    calls
+}
+
+// Distinct identifiers used in a function, narrowed down to known definitions afterwards
+fn collect_identifiers(node: &Node, source: &[u8], options: &ExtractOptions) -> Vec<String> {
+    if !options.include_references {
+        return vec![];
+    }
+    let mut identifiers: Vec<String> = Vec::new();
+    let mut cursor = node.walk();
+    for i in 0..node.descendant_count() {
+        cursor.goto_descendant(i);
+        let identifier_node = cursor.node();
+        if !matches!(
+            identifier_node.kind(),
+            "identifier"
+                | "type_identifier"
+                | "field_identifier"
+                | "property_identifier"
+                | "simple_identifier"
+                | "constant"
+        ) {
+            continue;
+        }
+        let identifier = get_node_text(&identifier_node, source);
+        if !identifiers.contains(&identifier) {
+            identifiers.push(identifier);
+        }
+    }
+    identifiers
+}
+
+// Keep only the references that name another definition of the same file
+fn resolve_references(definitions: &mut [Definition]) {
+    let mut names: BTreeSet<String> = BTreeSet::new();
+    for definition in definitions.iter() {
+        match definition {
+            Definition::Func(func) => {
+                names.insert(func.name.clone());
+            }
+            Definition::Class(class) | Definition::Module(class) => {
+                names.insert(class.name.clone());
+                names.extend(class.methods.iter().map(|method| method.name.clone()));
+            }
+            Definition::Enum(enum_def) => {
+                names.insert(enum_def.name.clone());
+            }
+            Definition::Union(union_def) => {
+                names.insert(union_def.name.clone());
+            }
+            Definition::Variable(variable) => {
+                names.insert(variable.name.clone());
+            }
+            Definition::Import(_) | Definition::Namespace { .. } => {}
+        }
+    }
+    let resolve = |func: &mut Func| {
+        func.references
+            .retain(|reference| *reference != func.name && names.contains(reference));
+    };
+    for definition in definitions.iter_mut() {
+        match definition {
+            Definition::Func(func) => resolve(func),
+            Definition::Class(class) | Definition::Module(class) => {
+                for method in &mut class.methods {
+                    resolve(method);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// `Self`, `&mut Self` or the enclosing type itself, generic arguments aside
+fn is_self_type(return_type: &str, class_name: &str) -> bool {
+    let return_type = return_type
+        .trim_start_matches(['&', '*'])
+        .trim_start_matches("mut ")
+        .trim();
+    let return_type = return_type
+        .split_once('<')
+        .map_or(return_type, |(base, _)| base)
+        .trim();
+    matches!(return_type, "Self" | "this") || return_type == class_name
+}
+
+fn mark_builder_methods(definitions: &mut [Definition]) {
+    for definition in definitions.iter_mut() {
+        if let Definition::Class(class) | Definition::Module(class) = definition {
+            for method in &mut class.methods {
+                method.returns_self = is_self_type(&method.return_type, &class.name);
+            }
+        }
+    }
+}
+
+// Multiple return values are rendered as `(A, B)` whatever the language, so Go's named
+// `(n int, err error)` and Python's `Tuple[int, str]` read like a Rust `(u32, String)`
+fn normalize_tuple_return(node: &Node, return_type: &str, source: &[u8]) -> String {
+    let types = match node.kind() {
+        "parameter_list" => {
+            let mut types = Vec::new();
+            let mut cursor = node.walk();
+            for parameter in node.named_children(&mut cursor) {
+                let Some(parameter_type) = parameter.child_by_field_name("type") else {
+                    continue;
+                };
+                let parameter_type = get_node_text(&parameter_type, source);
+                // `(a, b int)` declares one value per name
+                let names = parameter
+                    .children_by_field_name("name", &mut parameter.walk())
+                    .count();
+                types.extend(std::iter::repeat_n(parameter_type, names.max(1)));
+            }
+            types
+        }
+        "tuple_type" => match return_type
+            .strip_prefix('(')
+            .and_then(|inner| inner.strip_suffix(')'))
+        {
+            Some(inner) => split_params(inner),
+            None => return return_type.to_string(),
+        },
+        _ => {
+            let inner = return_type
+                .trim_start_matches("typing.")
+                .strip_prefix("Tuple[")
+                .or_else(|| return_type.strip_prefix("tuple["))
+                .and_then(|inner| inner.strip_suffix(']'));
+            // `Tuple[int, ...]` is a homogeneous sequence rather than several values
+            match inner {
+                Some(inner) if !inner.contains("...") => split_params(inner),
+                _ => return return_type.to_string(),
+            }
+        }
+    };
+    match types.as_slice() {
+        [] => return_type.to_string(),
+        [single] if node.kind() == "parameter_list" => single.clone(),
+        _ => format!("({})", types.join(", ")),
+    }
+}
+
+// Render the parameter list and return type of a method, the slowest part of extraction
+fn get_method_signature(
+    node: &Node,
+    language: &str,
+    name: &str,
+    source: &[u8],
+) -> (String, String) {
+    if language == "kotlin" {
+        return kotlin_function_signature(node, source);
+    }
+    if language == "dart" {
+        return dart_function_signature(node, source);
+    }
+    let mut params_node = node
+        .child_by_field_name("parameters")
+        .or_else(|| find_descendant_by_type(node, "parameter_list"));
+
+    let zig_function_node = find_ancestor_by_type(node, "function_declaration");
+    if language == "zig" {
+        params_node = zig_function_node
+            .as_ref()
+            .and_then(|n| find_child_by_type(n, "parameters"));
+    }
+    let ex_function_node = find_ancestor_by_type(node, "call");
+    if language == "elixir" {
+        params_node = ex_function_node
+            .as_ref()
+            .and_then(|n| find_child_by_type(n, "arguments"));
+    }
+
+    let params = params_node.map_or_else(|| "()".to_string(), |n| get_params_text(&n, source));
+    let mut return_type_node = match language {
+        "cpp" => node.child_by_field_name("type"),
+        "csharp" => node.child_by_field_name("returns"),
+        _ => node.child_by_field_name("return_type"),
+    };
+    if language == "cpp" {
+        let class_specifier_node = find_ancestor_by_type(node, "class_specifier");
+        let type_identifier_node = class_specifier_node.and_then(|n| n.child_by_field_name("name"));
+
+        if let Some(type_identifier_node) = type_identifier_node {
+            let type_identifier_text = type_identifier_node.utf8_text(source).unwrap_or_default();
+            if name == type_identifier_text {
+                return_type_node = Some(type_identifier_node);
+            }
+        }
+    }
+    if language == "csharp" {
+        let type_specifier_node = csharp_find_parent_type_node(node);
+        let type_identifier_node = type_specifier_node.and_then(|n| n.child_by_field_name("name"));
+
+        if let Some(type_identifier_node) = type_identifier_node {
+            let type_identifier_text = type_identifier_node.utf8_text(source).unwrap_or_default();
+            if name == type_identifier_text {
+                return_type_node = Some(type_identifier_node);
+            }
+        }
+    }
+    if return_type_node.is_none() {
+        return_type_node = node.child_by_field_name("result");
+    }
+    let mut return_type = "void".to_string();
+    if language == "elixir" {
+        return_type = String::new();
+    }
+    if let Some(return_type_node) = return_type_node {
+        return_type = get_node_type(&return_type_node, source);
+        if return_type.is_empty() {
+            return_type = return_type_node
+                .utf8_text(source)
+                .unwrap_or("void")
+                .to_string();
+        }
+        return_type = normalize_tuple_return(&return_type_node, &return_type, source);
+    }
+    (params, return_type)
+}
+
+fn get_function_signature(node: &Node, language: &str, source: &[u8]) -> (String, String) {
+    if language == "haskell" {
+        return haskell_function_signature(node, source);
+    }
+    if language == "kotlin" {
+        return kotlin_function_signature(node, source);
+    }
+    if language == "dart" {
+        return dart_function_signature(node, source);
+    }
+    if language == "bash" {
+        // Shell functions read positional arguments and only return a status
+        return (String::new(), String::new());
+    }
+    if language == "zig" {
+        // Comptime type constructors such as `fn ArrayList(comptime T: type) type`
+        let returns_type = node
+            .child_by_field_name("type")
+            .is_some_and(|n| get_node_text(&n, source) == "type");
+        if returns_type {
+            let params = find_child_by_type(node, "parameters")
+                .map_or_else(|| "()".to_string(), |n| get_node_text(&n, source));
+            return (params, "type".to_string());
+        }
+    }
+    let params_node = node
+        .child_by_field_name("parameters")
+        .or_else(|| find_descendant_by_type(node, "parameter_list"));
+    let params = params_node.map_or_else(|| "()".to_string(), |n| get_params_text(&n, source));
+
+    let mut return_type = "void".to_string();
+    let return_type_node = match language {
+        "cpp" => node.child_by_field_name("type"),
+        _ => node
+            .child_by_field_name("return_type")
+            .or_else(|| node.child_by_field_name("result")),
+    };
+    if let Some(return_type_node) = return_type_node {
+        return_type = get_node_type(&return_type_node, source);
+        if return_type.is_empty() {
+            return_type = return_type_node
+                .utf8_text(source)
+                .unwrap_or("void")
+                .to_string();
+        }
+        return_type = normalize_tuple_return(&return_type_node, &return_type, source);
+    }
+    (params, return_type)
+}
+
+// Given a language, parse the given source code and return exported definitions
+fn extract_definitions(language: &str, source: &str) -> Result<Vec<Definition>, Error> {
+    extract_definitions_with_options(language, source, &ExtractOptions::default())
+}
+
+fn extract_definitions_with_options(
+    language: &str,
+    source: &str,
+    options: &ExtractOptions,
+) -> Result<Vec<Definition>, Error> {
+    extract_definitions_and_tests(language, source, options).map(|(definitions, _)| definitions)
+}
+
+fn extract_tests(language: &str, source: &str) -> Result<Vec<Func>, Error> {
+    extract_definitions_and_tests(language, source, &ExtractOptions::default())
+        .map(|(_, tests)| tests)
+}
+
+// Positions found in an embedded block are relative to it, move them to the enclosing file
+fn shift_definition(definition: &mut Definition, offset: usize, rows: usize) {
+    let shift = |start_byte: &mut usize, start_line: &mut usize, end_line: &mut usize| {
+        *start_byte += offset;
+        *start_line += rows;
+        *end_line += rows;
+    };
+    match definition {
+        Definition::Func(func) => shift(
+            &mut func.start_byte,
+            &mut func.start_line,
+            &mut func.end_line,
+        ),
+        Definition::Class(class_def) | Definition::Module(class_def) => {
+            shift(
+                &mut class_def.start_byte,
+                &mut class_def.start_line,
+                &mut class_def.end_line,
+            );
+            for method in &mut class_def.methods {
+                shift(
+                    &mut method.start_byte,
+                    &mut method.start_line,
+                    &mut method.end_line,
+                );
+            }
+            for property in &mut class_def.properties {
+                shift(
+                    &mut property.start_byte,
+                    &mut property.start_line,
+                    &mut property.end_line,
+                );
+            }
+        }
+        Definition::Enum(enum_def) => {
+            shift(
+                &mut enum_def.start_byte,
+                &mut enum_def.start_line,
+                &mut enum_def.end_line,
+            );
+            for item in &mut enum_def.items {
+                shift(
+                    &mut item.start_byte,
+                    &mut item.start_line,
+                    &mut item.end_line,
+                );
+            }
+        }
+        Definition::Union(union_def) => {
+            shift(
+                &mut union_def.start_byte,
+                &mut union_def.start_line,
+                &mut union_def.end_line,
+            );
+            for item in &mut union_def.items {
+                shift(
+                    &mut item.start_byte,
+                    &mut item.start_line,
+                    &mut item.end_line,
+                );
+            }
+        }
+        Definition::Variable(variable) => shift(
+            &mut variable.start_byte,
+            &mut variable.start_line,
+            &mut variable.end_line,
+        ),
+        Definition::Import(_) => {}
+        Definition::Namespace { children, .. } => {
+            for child in children {
+                shift_definition(child, offset, rows);
+            }
+        }
+    }
+}
+
+// 1-based first and last line of a node, a trailing newline doesn't count as a line of its own
+fn node_lines(node: &Node) -> (usize, usize) {
+    let start = node.start_position();
+    let end = node.end_position();
+    let end_row = if end.column == 0 && end.row > start.row {
+        end.row - 1
+    } else {
+        end.row
+    };
+    (start.row + 1, end_row + 1)
+}
+
+// Captured nodes by start byte, the outermost one wins when several start there
+type Spans<'tree> = BTreeMap<usize, Node<'tree>>;
+
+fn record_span<'tree>(spans: &mut Spans<'tree>, node: Node<'tree>) {
+    let span = spans.entry(node.start_byte()).or_insert(node);
+    if node.end_byte() > span.end_byte() {
+        *span = node;
+    }
+}
+
+// Definitions take the lines of the node their `start_byte` comes from, the ones built from
+// something else than a capture fall back to the line they start on. Containers also extend to
+// their last member, e.g. a Rust struct to the end of its `impl` blocks.
+fn locate_definitions(
+    definitions: &mut [Definition],
+    tests: &mut [Func],
+    spans: &Spans,
+    root: &Node,
+) {
+    let locate = |start_byte: usize, start_line: &mut usize, end_line: &mut usize| {
+        if *start_line != 0 {
+            return;
+        }
+        (*start_line, *end_line) = spans.get(&start_byte).map_or_else(
+            || {
+                let row = root
+                    .descendant_for_byte_range(start_byte, start_byte)
+                    .map_or(0, |n| n.start_position().row);
+                (row + 1, row + 1)
+            },
+            node_lines,
+        );
+    };
+    let locate_items = |items: &mut [Variable]| {
+        items.iter_mut().fold(0, |last, item| {
+            locate(item.start_byte, &mut item.start_line, &mut item.end_line);
+            last.max(item.end_line)
+        })
+    };
+    for test in tests.iter_mut() {
+        locate(test.start_byte, &mut test.start_line, &mut test.end_line);
+    }
+    for definition in definitions {
+        match definition {
+            Definition::Func(func) => {
+                locate(func.start_byte, &mut func.start_line, &mut func.end_line);
+            }
+            Definition::Class(class_def) | Definition::Module(class_def) => {
+                locate(
+                    class_def.start_byte,
+                    &mut class_def.start_line,
+                    &mut class_def.end_line,
+                );
+                let mut last = locate_items(&mut class_def.properties);
+                for method in &mut class_def.methods {
+                    locate(
+                        method.start_byte,
+                        &mut method.start_line,
+                        &mut method.end_line,
+                    );
+                    last = last.max(method.end_line);
+                }
+                class_def.end_line = class_def.end_line.max(last);
+            }
+            Definition::Enum(enum_def) => {
+                locate(
+                    enum_def.start_byte,
+                    &mut enum_def.start_line,
+                    &mut enum_def.end_line,
+                );
+                let last = locate_items(&mut enum_def.items);
+                enum_def.end_line = enum_def.end_line.max(last);
+            }
+            Definition::Union(union_def) => {
+                locate(
+                    union_def.start_byte,
+                    &mut union_def.start_line,
+                    &mut union_def.end_line,
+                );
+                let last = locate_items(&mut union_def.items);
+                union_def.end_line = union_def.end_line.max(last);
+            }
+            Definition::Variable(variable) => {
+                locate(
+                    variable.start_byte,
+                    &mut variable.start_line,
+                    &mut variable.end_line,
+                );
+            }
+            // Namespaces are only formed once the definitions are located
+            Definition::Import(_) | Definition::Namespace { .. } => {}
+        }
+    }
+}
+
+// Leading tabs count as four spaces, then the indentation shared by all non-blank lines is
+// removed so that a body taken out of a nested block starts at column 0
+fn normalize_indentation(text: &str) -> String {
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            let content = line.trim_start_matches([' ', '\t']);
+            let indent: usize = line[..line.len() - content.len()]
+                .chars()
+                .map(|c| if c == '\t' { 4 } else { 1 })
+                .sum();
+            format!("{}{}", " ".repeat(indent), content.trim_end())
+        })
+        .collect();
+    let common = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| line.get(common..).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn attach_small_bodies(definitions: &mut [Definition], source: &str, max_lines: usize) {
+    let lines: Vec<&str> = source.lines().collect();
+    let attach = |func: &mut Func| {
+        if func.start_line == 0 || func.end_line - func.start_line >= max_lines {
+            return;
+        }
+        if let Some(body) = lines.get(func.start_line - 1..func.end_line) {
+            func.body = Some(normalize_indentation(&body.join("\n")));
+        }
+    };
+    for definition in definitions {
+        match definition {
+            Definition::Func(func) => attach(func),
+            Definition::Class(class) | Definition::Module(class) => {
+                class.methods.iter_mut().for_each(attach);
+            }
+            _ => {}
+        }
+    }
+}
+
+// Only `///` and `/** */` document an item in Rust, `/** */` in the C family, any comment right
+// above a definition elsewhere
+fn is_doc_comment(language: &str, text: &str) -> bool {
+    match language {
+        "rust" => text.starts_with("///") || text.starts_with("/**"),
+        "java" | "javascript" | "typescript" | "kotlin" | "php" | "csharp" | "scala" | "dart"
+        | "swift" | "c" | "cpp" => text.starts_with("/**") || text.starts_with("///"),
+        _ => true,
+    }
+}
+
+// The docstring opening a Python body, or the comments right above the definition, skipping
+// attributes, annotations and decorators in between
+fn doc_comment(node: &Node, language: &str, source: &[u8]) -> Option<String> {
+    let mut lines: Vec<String> = Vec::new();
+    if language == "python" {
+        let docstring = node
+            .child_by_field_name("body")
+            .and_then(|body| body.named_child(0))
+            .filter(|statement| statement.kind() == "expression_statement")
+            .and_then(|statement| statement.named_child(0))
+            .filter(|string| string.kind() == "string")?;
+        let text = get_node_text(&docstring, source);
+        let text = text.trim_matches(|c| c == '"' || c == '\'');
+        lines.extend(text.lines().map(|line| line.trim().to_string()));
+    } else {
+        // `export function` is documented above the `export`
+        let node = node
+            .parent()
+            .filter(|parent| parent.kind() == "export_statement")
+            .unwrap_or(*node);
+        let mut blocks = Vec::new();
+        let mut row = node.start_position().row;
+        let mut sibling = node.prev_sibling();
+        while let Some(previous) = sibling {
+            let text = get_node_text(&previous, source);
+            // Line comments may include their trailing newline
+            let end_row = previous.end_position().row - usize::from(text.ends_with('\n'));
+            if end_row + 1 < row {
+                break;
+            }
+            if previous.kind().contains("comment") {
+                if !is_doc_comment(language, &text) {
+                    break;
+                }
+                blocks.push(strip_comment_markers(&text));
+            } else if !matches!(
+                previous.kind(),
+                "attribute_item" | "annotation" | "marker_annotation" | "decorator"
+            ) {
+                break;
+            }
+            row = previous.start_position().row;
+            sibling = previous.prev_sibling();
+        }
+        lines.extend(blocks.into_iter().rev().flatten());
+    }
+    let doc = lines.join("\n").trim().to_string();
+    (!doc.is_empty()).then_some(doc)
+}
+
+fn attach_docs(definitions: &mut [Definition], language: &str, source: &[u8], spans: &Spans) {
+    let doc_at = |start_byte: usize| {
+        spans
+            .get(&start_byte)
+            .and_then(|node| doc_comment(node, language, source))
+    };
+    for definition in definitions {
+        match definition {
+            Definition::Func(func) => func.doc = doc_at(func.start_byte),
+            Definition::Class(class) | Definition::Module(class) => {
+                // Python classes are known from their members, the class itself isn't captured
+                class.doc = spans.get(&class.start_byte).and_then(|node| {
+                    let node = find_ancestor_by_type(node, "class_definition")
+                        .filter(|_| language == "python")
+                        .unwrap_or(*node);
+                    doc_comment(&node, language, source)
+                });
+                for method in &mut class.methods {
+                    method.doc = doc_at(method.start_byte);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// `defineEmits<Emits>()` gives `defineEmits<Emits>`, the macros of `<script setup>`
+fn vue_macro_call(node: &Node, source: &[u8]) -> Option<String> {
+    if node.kind() != "call_expression" {
+        return None;
+    }
+    let function = node.child_by_field_name("function")?;
+    let function_name = get_node_text(&function, source);
+    // `withDefaults(defineProps<Props>(), {...})` wraps the props macro
+    if function_name == "withDefaults" {
+        let arguments = node.child_by_field_name("arguments")?;
+        let mut cursor = arguments.walk();
+        let first = arguments.named_children(&mut cursor).next()?;
+        return vue_macro_call(&first, source);
+    }
+    if !matches!(function_name.as_str(), "defineProps" | "defineEmits") {
+        return None;
+    }
+    let type_arguments = node
+        .child_by_field_name("type_arguments")
+        .map(|n| get_node_text(&n, source))
+        .unwrap_or_default();
+    Some(format!("{function_name}{type_arguments}"))
+}
+
+// The options object of `export default {...}` or `export default defineComponent({...})`
+fn vue_component_options<'a>(program: &'a Node) -> Option<Node<'a>> {
+    let mut cursor = program.walk();
+    let export = program
+        .children(&mut cursor)
+        .filter(|n| n.kind() == "export_statement")
+        .find(|n| find_child_by_type(n, "default").is_some())?;
+    let value = export.child_by_field_name("value")?;
+    match value.kind() {
+        "object" => Some(value),
+        "call_expression" => {
+            let arguments = value.child_by_field_name("arguments")?;
+            let mut arguments_cursor = arguments.walk();
+            let object = arguments
+                .named_children(&mut arguments_cursor)
+                .find(|n| n.kind() == "object");
+            object
+        }
+        _ => None,
+    }
+}
+
+fn vue_object_key(node: &Node, source: &[u8]) -> Option<String> {
+    let key = node
+        .child_by_field_name("key")
+        .or_else(|| node.child_by_field_name("name"))?;
+    Some(
+        get_node_text(&key, source)
+            .trim_matches(|c| c == '"' || c == '\'')
+            .to_string(),
+    )
+}
+
+// `props: ['title']` or `props: { title: String }`, also the runtime form of `defineProps`
+fn vue_props(value: &Node, source: &[u8], extract_options: &ExtractOptions) -> Vec<Variable> {
+    let mut props = Vec::new();
+    let mut cursor = value.walk();
+    for prop in value.named_children(&mut cursor) {
+        let prop_name = match prop.kind() {
+            "string" => Some(
+                get_node_text(&prop, source)
+                    .trim_matches(|c| c == '"' || c == '\'')
+                    .to_string(),
+            ),
+            "pair" | "method_definition" => vue_object_key(&prop, source),
+            _ => None,
+        };
+        let Some(prop_name) = prop_name else {
+            continue;
+        };
+        let prop_type = prop
+            .child_by_field_name("value")
+            .filter(|n| n.kind() == "identifier" && extract_options.signatures)
+            .map(|n| get_node_text(&n, source))
+            .unwrap_or_default();
+        props.push(Variable {
+            name: prop_name,
+            value_type: prop_type,
+            start_byte: prop.start_byte(),
+            value: None,
+            ..Default::default()
+        });
+    }
+    props
+}
+
+// `defineProps<{ title: string }>()`, a named type is looked up among the declarations of the script
+fn vue_type_props(
+    type_node: &Node,
+    program: &Node,
+    source: &[u8],
+    extract_options: &ExtractOptions,
+) -> Vec<Variable> {
+    match type_node.kind() {
+        "object_type" | "interface_body" => {
+            let mut props = Vec::new();
+            let mut cursor = type_node.walk();
+            for member in type_node.named_children(&mut cursor) {
+                if member.kind() != "property_signature" {
+                    continue;
+                }
+                let Some(name) = member.child_by_field_name("name") else {
+                    continue;
+                };
+                let prop_type = member
+                    .child_by_field_name("type")
+                    .filter(|_| extract_options.signatures)
+                    .map(|n| {
+                        get_node_text(&n, source)
+                            .trim_start_matches(':')
+                            .trim()
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+                props.push(Variable {
+                    name: get_node_text(&name, source),
+                    value_type: prop_type,
+                    start_byte: member.start_byte(),
+                    value: None,
+                    ..Default::default()
+                });
+            }
+            props
+        }
+        "type_identifier" => {
+            let type_name = get_node_text(type_node, source);
+            let mut cursor = program.walk();
+            let declarations: Vec<Node> = program
+                .named_children(&mut cursor)
+                .map(|statement| {
+                    if statement.kind() == "export_statement" {
+                        statement
+                            .child_by_field_name("declaration")
+                            .unwrap_or(statement)
+                    } else {
+                        statement
+                    }
+                })
+                .collect();
+            for declaration in declarations {
+                let body = match declaration.kind() {
+                    "interface_declaration" => declaration.child_by_field_name("body"),
+                    "type_alias_declaration" => declaration.child_by_field_name("value"),
+                    _ => None,
+                };
+                let Some(body) = body else {
+                    continue;
+                };
+                let is_named = declaration
+                    .child_by_field_name("name")
+                    .is_some_and(|name| get_node_text(&name, source) == type_name);
+                if is_named && body.kind() != "type_identifier" {
+                    return vue_type_props(&body, program, source, extract_options);
+                }
+            }
+            vec![]
+        }
+        _ => vec![],
+    }
+}
+
+// The props of a `defineProps(...)` call of `<script setup>`, `None` for any other expression
+fn vue_define_props(
+    node: &Node,
+    program: &Node,
+    source: &[u8],
+    extract_options: &ExtractOptions,
+) -> Option<Vec<Variable>> {
+    if node.kind() != "call_expression" {
+        return None;
+    }
+    let function = node.child_by_field_name("function")?;
+    let arguments = node.child_by_field_name("arguments")?;
+    let mut cursor = arguments.walk();
+    let first_argument = arguments.named_children(&mut cursor).next();
+    match get_node_text(&function, source).as_str() {
+        "withDefaults" => vue_define_props(&first_argument?, program, source, extract_options),
+        "defineProps" => {
+            if let Some(type_arguments) = node.child_by_field_name("type_arguments") {
+                let mut type_cursor = type_arguments.walk();
+                let props = type_arguments
+                    .named_children(&mut type_cursor)
+                    .next()
+                    .map(|type_node| vue_type_props(&type_node, program, source, extract_options))
+                    .unwrap_or_default();
+                return Some(props);
+            }
+            Some(
+                first_argument
+                    .map(|argument| vue_props(&argument, source, extract_options))
+                    .unwrap_or_default(),
+            )
+        }
+        _ => None,
+    }
+}
+
+// The component declared by the options API, its props, computed values and methods
+fn vue_component(options_object: &Node, source: &[u8], extract_options: &ExtractOptions) -> Class {
+    let mut component = Class {
+        type_name: "component".to_string(),
+        name: extract_options
+            .component_name
+            .clone()
+            .unwrap_or_else(|| "default".to_string()),
+        methods: vec![],
+        properties: vec![],
+        visibility_modifier: None,
+        start_byte: options_object.start_byte(),
+        generics: None,
+        ..Default::default()
+    };
+    let mut cursor = options_object.walk();
+    for option in options_object.named_children(&mut cursor) {
+        if option.kind() != "pair" {
+            continue;
+        }
+        let (Some(key), Some(value)) = (
+            vue_object_key(&option, source),
+            option.child_by_field_name("value"),
+        ) else {
+            continue;
+        };
+        match key.as_str() {
+            "name" if value.kind() == "string" => {
+                component.name = get_node_text(&value, source)
+                    .trim_matches(|c| c == '"' || c == '\'' || c == '`')
+                    .to_string();
+            }
+            "props" | "computed" => {
+                component
+                    .properties
+                    .extend(vue_props(&value, source, extract_options));
+            }
+            "methods" => {
+                let mut value_cursor = value.walk();
+                for method in value.named_children(&mut value_cursor) {
+                    let function = match method.kind() {
+                        "method_definition" => Some(method),
+                        "pair" => method.child_by_field_name("value").filter(|n| {
+                            matches!(n.kind(), "function_expression" | "arrow_function")
+                        }),
+                        _ => None,
+                    };
+                    let (Some(function), Some(method_name)) =
+                        (function, vue_object_key(&method, source))
+                    else {
+                        continue;
+                    };
+                    let params = function
+                        .child_by_field_name("parameters")
+                        .or_else(|| function.child_by_field_name("parameter"))
+                        .filter(|_| extract_options.signatures)
+                        .map(|n| get_params_text(&n, source))
+                        .unwrap_or_default();
+                    component.methods.push(Func {
+                        name: method_name,
+                        params,
+                        return_type: String::new(),
+                        accessibility_modifier: None,
+                        start_byte: method.start_byte(),
+                        calls: vec![],
+                        references: vec![],
+                        returns_self: false,
+                        ..Default::default()
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    component
+}
+
+// A `<script>` block goes through the JavaScript or TypeScript extraction, then what the
+// component exposes without exporting it is added on top: Vue component options and `<script
+// setup>` macros, Svelte props and functions
+fn extract_component_script(
+    component_language: &str,
+    script_language: &str,
+    script: &str,
+    options: &ExtractOptions,
+) -> Result<(Vec<Definition>, Vec<Func>), Error> {
+    let Some(ts_language) = get_ts_language(script_language) else {
+        return Ok((vec![], vec![]));
+    };
+    let mut parser = new_parser(ts_language, script_language)?;
+    let tree = parser.parse(script, None).ok_or_else(|| {
+        Error::Parse(format!(
+            "Failed to parse the script of a {component_language} component"
+        ))
+    })?;
+    let (mut definitions, tests) =
+        extract_definitions_from_tree(script_language, script, &tree, options)?;
+    let program = tree.root_node();
+
+    if component_language == "svelte" {
+        // Exported `let`s are the props of the component, its functions are methods
+        let mut component = Class {
+            type_name: "component".to_string(),
+            name: options
+                .component_name
+                .clone()
+                .unwrap_or_else(|| "default".to_string()),
+            methods: vec![],
+            properties: vec![],
+            visibility_modifier: None,
+            start_byte: 0,
+            generics: None,
+            ..Default::default()
+        };
+        let mut rest = Vec::new();
+        for definition in definitions {
+            match definition {
+                Definition::Variable(variable) => component.properties.push(variable),
+                Definition::Func(func) => component.methods.push(func),
+                definition => rest.push(definition),
+            }
+        }
+        let mut cursor = program.walk();
+        for statement in program.named_children(&mut cursor) {
+            // Svelte 5 props, `let { name, count = 0 }: Props = $props()`
+            if statement.kind() == "lexical_declaration" {
+                let mut declarator_cursor = statement.walk();
+                for declarator in statement.named_children(&mut declarator_cursor) {
+                    let (Some(pattern), Some(value)) = (
+                        declarator.child_by_field_name("name"),
+                        declarator.child_by_field_name("value"),
+                    ) else {
+                        continue;
+                    };
+                    let is_props_rune = value.kind() == "call_expression"
+                        && value
+                            .child_by_field_name("function")
+                            .is_some_and(|n| get_node_text(&n, script.as_bytes()) == "$props");
+                    if !is_props_rune || pattern.kind() != "object_pattern" {
+                        continue;
+                    }
+                    let mut pattern_cursor = pattern.walk();
+                    for prop in pattern.named_children(&mut pattern_cursor) {
+                        let prop_name = match prop.kind() {
+                            "shorthand_property_identifier_pattern" => Some(prop),
+                            "object_assignment_pattern" => prop.child_by_field_name("left"),
+                            "pair_pattern" => prop.child_by_field_name("key"),
+                            _ => None,
+                        };
+                        let Some(prop_name) = prop_name else {
+                            continue;
+                        };
+                        component.properties.push(Variable {
+                            name: get_node_text(&prop_name, script.as_bytes()),
+                            value_type: String::new(),
+                            start_byte: prop.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+            // `function greet() {}` and `const reset = () => {}`
+            let functions = match statement.kind() {
+                "function_declaration" => statement
+                    .child_by_field_name("name")
+                    .map(|name| (name, statement))
+                    .into_iter()
+                    .collect(),
+                "lexical_declaration" => {
+                    let mut declarator_cursor = statement.walk();
+                    let functions: Vec<_> = statement
+                        .named_children(&mut declarator_cursor)
+                        .filter_map(|declarator| {
+                            let value = declarator.child_by_field_name("value")?;
+                            if !matches!(value.kind(), "arrow_function" | "function_expression") {
+                                return None;
+                            }
+                            Some((declarator.child_by_field_name("name")?, value))
+                        })
+                        .collect();
+                    functions
+                }
+                _ => vec![],
+            };
+            for (name, function) in functions {
+                let (params, return_type) = if options.signatures {
+                    get_function_signature(&function, script_language, script.as_bytes())
+                } else {
+                    (String::new(), String::new())
+                };
+                component.methods.push(Func {
+                    name: get_node_text(&name, script.as_bytes()),
+                    params,
+                    return_type,
+                    accessibility_modifier: None,
+                    start_byte: statement.start_byte(),
+                    calls: collect_calls(&function, script_language, script.as_bytes(), options),
+                    references: collect_identifiers(&function, script.as_bytes(), options),
+                    returns_self: false,
+                    ..Default::default()
+                });
+            }
+        }
+        if component.methods.is_empty() && component.properties.is_empty() {
+            return Ok((rest, tests));
+        }
+        let mut definitions: Vec<Definition> = std::iter::once(Definition::Class(component))
+            .chain(rest)
+            .collect();
+        locate_definitions(&mut definitions, &mut [], &Spans::new(), &program);
+        return Ok((definitions, tests));
+    }
+
+    if let Some(options_object) = vue_component_options(&program) {
+        definitions.push(Definition::Class(vue_component(
+            &options_object,
+            script.as_bytes(),
+            options,
+        )));
+    }
+
+    // The props of `<script setup>` belong to a component named after the file
+    let mut setup_props = Vec::new();
+    let mut setup_start = None;
+    let mut cursor = program.walk();
+    for statement in program.named_children(&mut cursor) {
+        match statement.kind() {
+            // `const props = defineProps<Props>()`, `const emit = defineEmits(['change'])`
+            "lexical_declaration" | "variable_declaration" => {
+                let mut declarator_cursor = statement.walk();
+                for declarator in statement.named_children(&mut declarator_cursor) {
+                    let (Some(name), Some(value)) = (
+                        declarator.child_by_field_name("name"),
+                        declarator.child_by_field_name("value"),
+                    ) else {
+                        continue;
+                    };
+                    if let Some(props) =
+                        vue_define_props(&value, &program, script.as_bytes(), options)
+                    {
+                        setup_props.extend(props);
+                        setup_start.get_or_insert(statement.start_byte());
+                        continue;
+                    }
+                    let Some(macro_call) = vue_macro_call(&value, script.as_bytes()) else {
+                        continue;
+                    };
+                    definitions.push(Definition::Variable(Variable {
+                        name: get_node_text(&name, script.as_bytes()),
+                        value_type: if options.signatures {
+                            macro_call
+                        } else {
+                            String::new()
+                        },
+                        start_byte: statement.start_byte(),
+                        value: None,
+                        ..Default::default()
+                    }));
+                }
+            }
+            // A bare `defineEmits(['change'])` is listed as `emits`
+            "expression_statement" => {
+                let Some(call) = statement.named_child(0) else {
+                    continue;
+                };
+                if let Some(props) = vue_define_props(&call, &program, script.as_bytes(), options) {
+                    setup_props.extend(props);
+                    setup_start.get_or_insert(statement.start_byte());
+                    continue;
+                }
+                let Some(macro_call) = vue_macro_call(&call, script.as_bytes()) else {
+                    continue;
+                };
+                definitions.push(Definition::Variable(Variable {
+                    name: "emits".to_string(),
+                    value_type: if options.signatures {
+                        macro_call
+                    } else {
+                        String::new()
+                    },
+                    start_byte: statement.start_byte(),
+                    value: None,
+                    ..Default::default()
+                }));
+            }
+            _ => {}
+        }
+    }
+    if let Some(start_byte) = setup_start {
+        definitions.push(Definition::Class(Class {
+            type_name: "component".to_string(),
+            name: options
+                .component_name
+                .clone()
+                .unwrap_or_else(|| "default".to_string()),
+            methods: vec![],
+            properties: setup_props,
+            visibility_modifier: None,
+            start_byte,
+            generics: None,
+            ..Default::default()
+        }));
+    }
+    locate_definitions(&mut definitions, &mut [], &Spans::new(), &program);
+    Ok((definitions, tests))
+}
+
+// The scripts of one file describe the same component, e.g. the Vue options API and `<script
+// setup>`, it leads the definitions so that the rest reads as its script or module exports
+fn merge_components(definitions: Vec<Definition>) -> Vec<Definition> {
+    let mut component: Option<Class> = None;
+    let mut rest = Vec::new();
+    for definition in definitions {
+        match definition {
+            Definition::Class(class_def) if class_def.type_name == "component" => {
+                match component.as_mut() {
+                    None => component = Some(class_def),
+                    Some(component) => {
+                        component.methods.extend(class_def.methods);
+                        component.properties.extend(class_def.properties);
+                    }
+                }
+            }
+            definition => rest.push(definition),
+        }
+    }
+    component
+        .map(Definition::Class)
+        .into_iter()
+        .chain(rest)
+        .collect()
+}
+
+// Attributes of a `<script>` start tag by name, valueless ones like `setup` map to ""
+fn script_attributes(start_tag: &Node, source: &[u8]) -> BTreeMap<String, String> {
+    let mut attributes = BTreeMap::new();
+    let mut cursor = start_tag.walk();
+    for attribute in start_tag.named_children(&mut cursor) {
+        if attribute.kind() != "attribute" {
+            continue;
+        }
+        let Some(name) = find_child_by_type(&attribute, "attribute_name") else {
+            continue;
+        };
+        let value = match find_child_by_type(&attribute, "quoted_attribute_value") {
+            Some(quoted) => find_child_by_type(&quoted, "attribute_value")
+                .map(|value| get_node_text(&value, source)),
+            None => find_child_by_type(&attribute, "attribute_value")
+                .map(|value| get_node_text(&value, source)),
+        }
+        .unwrap_or_default();
+        attributes.insert(get_node_text(&name, source), value);
+    }
+    attributes
+}
+
+// Vue and Svelte components are only parsed for their `<script>` blocks, a component without
+// one has no definitions
+fn extract_component_definitions(
+    language: &str,
+    source: &str,
+    options: &ExtractOptions,
+) -> Result<(Vec<Definition>, Vec<Func>), Error> {
+    let Some(ts_language) = get_ts_language(language) else {
+        return Ok((vec![], vec![]));
+    };
+    let mut parser = new_parser(ts_language, language)?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or_else(|| Error::Parse(format!("Failed to parse source code for {language}")))?;
+    let root_node = tree.root_node();
+
+    let mut definitions = Vec::new();
+    let mut tests = Vec::new();
+    for script in find_descendants_by_type(&root_node, "script_element") {
+        let Some(raw_text) = find_child_by_type(&script, "raw_text") else {
+            continue;
+        };
+        let attributes = find_child_by_type(&script, "start_tag")
+            .map(|start_tag| script_attributes(&start_tag, source.as_bytes()))
+            .unwrap_or_default();
+        // `<script lang="ts">` and `<script setup lang="tsx">` are TypeScript
+        let script_language = match attributes.get("lang").map(String::as_str) {
+            Some("ts" | "tsx") => "typescript",
+            _ => "javascript",
+        };
+        // `<script context="module">`, or `<script module>` since Svelte 5, holds plain exports
+        // shared by every instance of the component
+        let is_module_script = language == "svelte"
+            && (attributes
+                .get("context")
+                .is_some_and(|context| context == "module")
+                || attributes.contains_key("module"));
+        let offset = raw_text.start_byte();
+        let script = &source[offset..raw_text.end_byte()];
+        let (script_definitions, script_tests) = if is_module_script {
+            extract_definitions_and_tests(script_language, script, options)?
+        } else {
+            extract_component_script(language, script_language, script, options)?
+        };
+        let rows = raw_text.start_position().row;
+        for mut definition in script_definitions {
+            shift_definition(&mut definition, offset, rows);
+            definitions.push(definition);
+        }
+        tests.extend(script_tests.into_iter().map(|mut test| {
+            test.start_byte += offset;
+            test.start_line += rows;
+            test.end_line += rows;
+            test
+        }));
+    }
+    Ok((merge_components(definitions), tests))
+}
+
+// Containers under construction by name
+type ClassDefMap = BTreeMap<String, RefCell<Class>>;
+type EnumDefMap = BTreeMap<String, RefCell<Enum>>;
+
+fn extract_definitions_and_tests(
+    language: &str,
+    source: &str,
+    options: &ExtractOptions,
+) -> Result<(Vec<Definition>, Vec<Func>), Error> {
+    if matches!(language, "vue" | "svelte") {
+        return extract_component_definitions(language, source, options);
+    }
+
+    let Some(ts_language) = get_ts_language(language) else {
+        return Ok((vec![], vec![]));
+    };
+    let mut parser = new_parser(ts_language, language)?;
+    let tree = parser
+        .parse(source, None)
+        .ok_or_else(|| Error::Parse(format!("Failed to parse source code for {language}")))?;
+    extract_definitions_from_tree(language, source, &tree, options)
+}
+
+// The extraction itself, for callers that keep using the tree of `source` afterwards
+fn extract_definitions_from_tree(
+    language: &str,
+    source: &str,
+    tree: &Tree,
+    options: &ExtractOptions,
+) -> Result<(Vec<Definition>, Vec<Func>), Error> {
+    let mut definitions = Vec::new();
+    let root_node = tree.root_node();
+
+    let query = get_definitions_query(language)?;
+    let mut query_cursor = QueryCursor::new();
+    let mut captures = query_cursor.captures(&query, root_node, source.as_bytes());
+    // Interface files only differ from implementations in their grammar
+    let language = if language == "ocaml_interface" {
+        "ocaml"
+    } else {
+        language
+    };
+
+    let mut class_def_map: ClassDefMap = BTreeMap::new();
+    let mut enum_def_map: EnumDefMap = BTreeMap::new();
+    let mut union_def_map: BTreeMap<String, RefCell<Union>> = BTreeMap::new();
+
+    // Containers keep the earliest position they were seen at, members may be captured first
+    let ensure_class_def =
+        |language: &str,
+         name: &str,
+         start_byte: usize,
+         class_def_map: &mut BTreeMap<String, RefCell<Class>>| {
+            let mut type_name = "class";
+            if language == "elixir" {
+                type_name = "module";
+            }
+            let class_def = class_def_map.entry(name.to_string()).or_insert_with(|| {
+                RefCell::new(Class {
+                    type_name: type_name.to_string(),
+                    name: name.to_string(),
+                    methods: vec![],
+                    properties: vec![],
+                    visibility_modifier: None,
+                    start_byte,
+                    generics: None,
+                    ..Default::default()
+                })
+            });
+            let mut class_def = class_def.borrow_mut();
+            class_def.start_byte = class_def.start_byte.min(start_byte);
+        };
+
+    let ensure_module_def =
+        |name: &str, start_byte: usize, class_def_map: &mut BTreeMap<String, RefCell<Class>>| {
+            let module_def = class_def_map.entry(name.to_string()).or_insert_with(|| {
+                RefCell::new(Class {
+                    name: name.to_string(),
+                    type_name: "module".to_string(),
+                    methods: vec![],
+                    properties: vec![],
+                    visibility_modifier: None,
+                    start_byte,
+                    generics: None,
+                    ..Default::default()
+                })
+            });
+            let mut module_def = module_def.borrow_mut();
+            module_def.start_byte = module_def.start_byte.min(start_byte);
+        };
+
+    let ensure_enum_def =
+        |name: &str, start_byte: usize, enum_def_map: &mut BTreeMap<String, RefCell<Enum>>| {
+            enum_def_map.entry(name.to_string()).or_insert_with(|| {
+                RefCell::new(Enum {
+                    name: name.to_string(),
+                    items: vec![],
+                    start_byte,
+                    ..Default::default()
+                })
+            });
+        };
+
+    let ensure_union_def =
+        |name: &str, start_byte: usize, union_def_map: &mut BTreeMap<String, RefCell<Union>>| {
+            union_def_map.entry(name.to_string()).or_insert_with(|| {
+                RefCell::new(Union {
+                    name: name.to_string(),
+                    items: vec![],
+                    start_byte,
+                    ..Default::default()
+                })
+            });
+        };
+
+    // Erlang functions belong to the module of the file and are only listed when exported
+    let (erlang_module, erlang_exported) = if language == "erlang" {
+        (
+            erlang_module_name(&root_node, source.as_bytes()).unwrap_or_default(),
+            erlang_exports(&root_node, source.as_bytes()),
+        )
+    } else {
+        (String::new(), BTreeSet::new())
+    };
+
+    // Sometimes, multiple queries capture the same node with the same capture name.
+    // We need to ensure that we only add the node to the definition map once.
+    let mut captured_nodes: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    let mut spans = Spans::new();
+
+    // Private, unexported and underscore-prefixed members are skipped unless `include_private`
+    let public_only = !options.include_private;
+
+    // Classes and enums are kept per namespace so that same-named ones of different namespaces
+    // don't merge, the maps of the other namespaces wait here while a capture uses its own
+    let namespaces = collect_namespaces(language, &root_node, source.as_bytes());
+    let mut current_namespace = String::new();
+    let mut namespaced_defs: BTreeMap<String, (ClassDefMap, EnumDefMap)> = BTreeMap::new();
+
+    while let Some((m, _)) = captures.next() {
+        for capture in m.captures {
+            let capture_name = &query.capture_names()[capture.index as usize];
+            let node = capture.node;
+            if !namespaces.is_empty() {
+                let namespace = namespace_path(&namespaces, node.start_byte());
+                if namespace != current_namespace {
+                    let (class_defs, enum_defs) =
+                        namespaced_defs.remove(&namespace).unwrap_or_default();
+                    namespaced_defs.insert(
+                        std::mem::replace(&mut current_namespace, namespace),
+                        (
+                            std::mem::replace(&mut class_def_map, class_defs),
+                            std::mem::replace(&mut enum_def_map, enum_defs),
+                        ),
+                    );
+                }
+            }
+            let node_text = node.utf8_text(source.as_bytes()).unwrap_or_default();
+
+            let node_id = node.id();
+            if captured_nodes
+                .get(*capture_name)
+                .is_some_and(|v| v.contains(&node_id))
+            {
+                continue;
+            }
+            captured_nodes
+                .entry(String::from(*capture_name))
+                .or_default()
+                .push(node_id);
+            record_span(&mut spans, node);
+
+            let name = match language {
+                "cpp" => {
+                    if *capture_name == "class" {
+                        node.child_by_field_name("name")
+                            .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                            .unwrap_or(node_text)
+                            .to_string()
+                    } else {
+                        let ident = find_descendant_by_type(&node, "field_identifier")
+                            .or_else(|| find_descendant_by_type(&node, "operator_name"))
+                            .or_else(|| find_descendant_by_type(&node, "identifier"))
+                            .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default());
+                        if let Some(ident) = ident {
+                            // `Outer::Inner::Widget::draw` nests a qualified name per scope
+                            let mut scopes = Vec::new();
+                            let mut qualified = node
+                                .child_by_field_name("declarator")
+                                .and_then(|n| n.child_by_field_name("declarator"));
+                            while let Some(qualified_node) = qualified {
+                                let Some(scope_node) = qualified_node.child_by_field_name("scope")
+                                else {
+                                    break;
+                                };
+                                scopes.push(
+                                    scope_node.utf8_text(source.as_bytes()).unwrap_or_default(),
+                                );
+                                qualified = qualified_node.child_by_field_name("name");
+                            }
+
+                            if scopes.is_empty() {
+                                ident.to_string()
+                            } else {
+                                format!("{}::{ident}", scopes.join("::"))
+                            }
+                        } else {
+                            node_text.to_string()
+                        }
+                    }
+                }
+                "scala" => node
+                    .child_by_field_name("name")
+                    .or_else(|| node.child_by_field_name("pattern"))
+                    .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                    .unwrap_or(node_text)
+                    .to_string(),
+                "csharp" => {
+                    let mut identifier = node;
+                    // Handle primary constructors (they are direct children of *_declaration)
+                    if *capture_name == "method" && csharp_is_primary_constructor(&node) {
+                        identifier = node.parent().unwrap_or(node);
+                    } else if *capture_name == "class_variable" {
+                        identifier =
+                            find_descendant_by_type(&node, "variable_declarator").unwrap_or(node);
+                    }
+
+                    identifier
+                        .child_by_field_name("name")
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or(node_text)
+                        .to_string()
+                }
+                "kotlin" => kotlin_get_name(&node, source.as_bytes()).unwrap_or_default(),
+                // Fields are named by their declarator, `String greeting = "Hello"`
+                "groovy" if node.kind() == "field_declaration" => node
+                    .child_by_field_name("declarator")
+                    .and_then(|n| n.child_by_field_name("name"))
+                    .map(|n| get_node_text(&n, source.as_bytes()))
+                    .unwrap_or_default(),
+                "dart" => dart_get_name(&node, source.as_bytes()).unwrap_or_default(),
+                "erlang" => {
+                    if node.kind() == "fun_decl" {
+                        erlang_function_name(&node, source.as_bytes()).unwrap_or_default()
+                    } else {
+                        node.child_by_field_name("name")
+                            .map(|n| get_node_text(&n, source.as_bytes()))
+                            .unwrap_or_default()
+                    }
+                }
+                "hcl" => {
+                    // Resources and data sources are referenced as `aws_instance.web`, everything
+                    // else by its label
+                    let block_type = find_child_by_type(&node, "identifier")
+                        .map(|n| get_node_text(&n, source.as_bytes()))
+                        .unwrap_or_default();
+                    let labels = hcl_block_labels(&node, source.as_bytes());
+                    if block_type == "resource" || block_type == "data" {
+                        labels.join(".")
+                    } else {
+                        labels.first().cloned().unwrap_or_default()
+                    }
+                }
+                "julia" => {
+                    let name = julia_own_name(&node, source.as_bytes()).unwrap_or_default();
+                    let module_path = julia_module_path(&node, source.as_bytes());
+                    if name.is_empty() || module_path.is_empty() {
+                        name
+                    } else {
+                        format!("{module_path}.{name}")
+                    }
+                }
+                "ocaml" => {
+                    let name = ocaml_declaration_name(&node, source.as_bytes()).unwrap_or_default();
+                    let module_path = ocaml_module_path(&node, source.as_bytes());
+                    if name.is_empty() || module_path.is_empty() {
+                        name
+                    } else {
+                        format!("{module_path}.{name}")
+                    }
+                }
+                "proto" => proto_name(&node, source.as_bytes()).unwrap_or_default(),
+                "graphql" => find_child_by_type(&node, "name")
+                    .map(|n| get_node_text(&n, source.as_bytes()))
+                    .unwrap_or_default(),
+                // Names keep their schema, `public.orders`
+                "sql" => find_child_by_type(&node, "object_reference")
+                    .map(|n| get_node_text(&n, source.as_bytes()))
+                    .unwrap_or_default(),
+                "objc" => node
+                    .child_by_field_name("name")
+                    .or_else(|| find_child_by_type(&node, "identifier"))
+                    .map(|n| get_node_text(&n, source.as_bytes()))
+                    .unwrap_or_default(),
+                "perl" => node
+                    .child_by_field_name("name")
+                    .map(|n| get_node_text(&n, source.as_bytes()))
+                    .unwrap_or_default(),
+                "ruby" => {
+                    let name = node
+                        .child_by_field_name("name")
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or(node_text)
+                        .to_string();
+                    if *capture_name == "class" || *capture_name == "module" {
+                        ruby_find_parent_module_declaration_name(&node, source.as_bytes())
+                            .unwrap_or(name)
+                    } else {
+                        name
+                    }
+                }
+                _ => node
+                    .child_by_field_name("name")
+                    .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                    .unwrap_or(node_text)
+                    .to_string(),
+            };
+
+            match *capture_name {
+                "class" if !name.is_empty() => {
+                    if public_only && language == "go" && !is_first_letter_uppercase(&name) {
+                        continue;
+                    }
+                    if public_only
+                        && language == "kotlin"
+                        && kotlin_is_private(&node, source.as_bytes())
+                    {
+                        continue;
+                    }
+                    if public_only && language == "dart" && dart_is_private(&name) {
+                        continue;
+                    }
+                    ensure_class_def(language, &name, node.start_byte(), &mut class_def_map);
+                    let visibility_modifier_node = find_child_by_type(&node, "visibility_modifier");
+                    let visibility_modifier = visibility_modifier_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+                    let class_def = class_def_map.get_mut(&name).unwrap();
+                    if class_def.borrow().generics.is_none() {
+                        class_def.borrow_mut().generics =
+                            get_class_type_parameters(&node, language, source.as_bytes(), options);
+                    }
+                    if language == "kotlin" {
+                        let mut class_def = class_def.borrow_mut();
+                        class_def.type_name =
+                            kotlin_class_kind(&node, source.as_bytes()).to_string();
+                        class_def.properties.extend(kotlin_constructor_properties(
+                            &node,
+                            source.as_bytes(),
+                            options,
+                        ));
+                    }
+                    if language == "dart" && node.kind() == "mixin_declaration" {
+                        class_def.borrow_mut().type_name = "mixin".to_string();
+                    }
+                    if language == "haskell" {
+                        // Type class methods are the signatures in the `where` block
+                        for signature in find_descendants_by_type(&node, "signature") {
+                            let (params, return_type) = if options.signatures {
+                                haskell_function_signature(&signature, source.as_bytes())
+                            } else {
+                                (String::new(), String::new())
+                            };
+                            let method_name = signature
+                                .child_by_field_name("name")
+                                .map(|n| get_node_text(&n, source.as_bytes()))
+                                .unwrap_or_default();
+                            class_def.borrow_mut().methods.push(Func {
+                                name: method_name,
+                                params,
+                                return_type,
+                                accessibility_modifier: None,
+                                start_byte: signature.start_byte(),
+                                calls: vec![],
+                                references: collect_identifiers(
+                                    &signature,
+                                    source.as_bytes(),
+                                    options,
+                                ),
+                                returns_self: false,
+                                ..Default::default()
+                            });
+                        }
+                    }
+                    class_def.borrow_mut().visibility_modifier = if visibility_modifier.is_empty() {
+                        None
+                    } else {
+                        Some(visibility_modifier.to_string())
+                    };
+                }
+                "module" => {
+                    // Only `struct ... end` modules have members, skip aliases like `module L = List`
+                    if language == "ocaml" && find_child_by_type(&node, "structure").is_none() {
+                        continue;
+                    }
+                    if !name.is_empty() {
+                        ensure_module_def(&name, node.start_byte(), &mut class_def_map);
+                    }
+                }
+                "sol_contract" => {
+                    if language != "solidity" {
+                        continue;
+                    }
+                    let Some(contract_name) = node
+                        .child_by_field_name("name")
+                        .map(|n| get_node_text(&n, source.as_bytes()))
+                    else {
+                        continue;
+                    };
+                    ensure_class_def(
+                        language,
+                        &contract_name,
+                        node.start_byte(),
+                        &mut class_def_map,
+                    );
+                    let mut class_def = class_def_map.get_mut(&contract_name).unwrap().borrow_mut();
+                    class_def.type_name = node.kind().trim_end_matches("_declaration").to_string();
+                }
+                // Only the external interface of a contract is listed, `private` and `internal`
+                // members are left out
+                "sol_function" | "sol_event" | "sol_state_variable" => {
+                    if language != "solidity" {
+                        continue;
+                    }
+                    let Some(contract_name) = find_first_ancestor_by_types(
+                        &node,
+                        &[
+                            "contract_declaration",
+                            "interface_declaration",
+                            "library_declaration",
+                        ],
+                    )
+                    .and_then(|n| n.child_by_field_name("name"))
+                    .map(|n| get_node_text(&n, source.as_bytes())) else {
+                        continue;
+                    };
+                    let Some(member_name) = node
+                        .child_by_field_name("name")
+                        .map(|n| get_node_text(&n, source.as_bytes()))
+                    else {
+                        continue;
+                    };
+                    let visibility = find_child_by_type(&node, "visibility")
+                        .map(|n| get_node_text(&n, source.as_bytes()));
+                    if public_only && matches!(visibility.as_deref(), Some("private" | "internal"))
+                    {
+                        continue;
+                    }
+                    ensure_class_def(
+                        language,
+                        &contract_name,
+                        node.start_byte(),
+                        &mut class_def_map,
+                    );
+                    let mut class_def = class_def_map.get_mut(&contract_name).unwrap().borrow_mut();
+                    if *capture_name == "sol_state_variable" {
+                        // State variables default to `internal`
+                        if visibility.as_deref() != Some("public") {
+                            continue;
+                        }
+                        class_def.properties.push(Variable {
+                            name: member_name,
+                            value_type: node
+                                .child_by_field_name("type")
+                                .filter(|_| options.signatures)
+                                .map(|n| get_node_text(&n, source.as_bytes()))
+                                .unwrap_or_default(),
+                            start_byte: node.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        });
+                        continue;
+                    }
+                    let (params, return_type) = solidity_signature(&node, source.as_bytes())
+                        .filter(|_| options.signatures)
+                        .unwrap_or_default();
+                    let accessibility_modifier = if *capture_name == "sol_event" {
+                        Some("event".to_string())
+                    } else {
+                        let modifiers = visibility
+                            .into_iter()
+                            .chain(
+                                find_child_by_type(&node, "state_mutability")
+                                    .map(|n| get_node_text(&n, source.as_bytes())),
+                            )
+                            .collect::<Vec<_>>();
+                        (!modifiers.is_empty()).then(|| modifiers.join(" "))
+                    };
+                    class_def.methods.push(Func {
+                        name: member_name,
+                        params,
+                        return_type,
+                        accessibility_modifier,
+                        start_byte: node.start_byte(),
+                        calls: collect_calls(&node, language, source.as_bytes(), options),
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        ..Default::default()
+                    });
+                }
+                // Like Java, `private` members are left out
+                "d_type" => {
+                    if language != "d"
+                        || find_ancestor_by_type(&node, "function_declaration").is_some()
+                    {
+                        continue;
+                    }
+                    let Some((attributes, type_name, generics)) =
+                        d_type_declaration(&get_node_text(&node, source.as_bytes()))
+                    else {
+                        continue;
+                    };
+                    if public_only && attributes.iter().any(|a| a == "private") {
+                        continue;
+                    }
+                    ensure_class_def(language, &type_name, node.start_byte(), &mut class_def_map);
+                    let mut class_def = class_def_map[&type_name].borrow_mut();
+                    class_def.type_name = node.kind().trim_end_matches("_declaration").to_string();
+                    class_def.generics = generics;
+                }
+                "d_enum" => {
+                    if language != "d"
+                        || find_ancestor_by_type(&node, "function_declaration").is_some()
+                    {
+                        continue;
+                    }
+                    let text = get_node_text(&node, source.as_bytes());
+                    let (attributes, rest) = d_attributes_and_type(&text);
+                    if public_only && attributes.iter().any(|a| a == "private") {
+                        continue;
+                    }
+                    let rest = rest.strip_prefix("enum").unwrap_or(&rest).trim_start();
+                    let name_end = rest
+                        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                        .unwrap_or(rest.len());
+                    let enum_name = &rest[..name_end];
+                    let (Some(open), Some(close)) = (rest.find('{'), rest.rfind('}')) else {
+                        // A manifest constant, `enum size = 10;`
+                        if !enum_name.is_empty() {
+                            definitions.push(Definition::Variable(Variable {
+                                name: enum_name.to_string(),
+                                value_type: String::new(),
+                                start_byte: node.start_byte(),
+                                value: None,
+                                ..Default::default()
+                            }));
+                        }
+                        continue;
+                    };
+                    if enum_name.is_empty() {
+                        continue;
+                    }
+                    ensure_enum_def(enum_name, node.start_byte(), &mut enum_def_map);
+                    let mut enum_def = enum_def_map[enum_name].borrow_mut();
+                    for member in split_params(&rest[open + 1..close]) {
+                        let member_name = member.split('=').next().unwrap_or_default().trim();
+                        if member_name.is_empty() {
+                            continue;
+                        }
+                        enum_def.items.push(Variable {
+                            name: member_name.to_string(),
+                            value_type: String::new(),
+                            start_byte: node.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        });
+                    }
+                }
+                // Members belong to the closest class, struct or interface, functions outside of
+                // them are free functions
+                "d_function" | "d_variable" => {
+                    if language != "d"
+                        || find_ancestor_by_type(&node, "function_declaration").is_some()
+                    {
+                        continue;
+                    }
+                    let container = find_first_ancestor_by_types(
+                        &node,
+                        &[
+                            "class_declaration",
+                            "struct_declaration",
+                            "interface_declaration",
+                        ],
+                    )
+                    .and_then(|n| d_type_declaration(&get_node_text(&n, source.as_bytes())));
+                    if public_only
+                        && container.as_ref().is_some_and(|(attributes, _, _)| {
+                            attributes.iter().any(|a| a == "private")
+                        })
+                    {
+                        continue;
+                    }
+                    let container_name = container.map(|(_, container_name, _)| container_name);
+                    let text = get_node_text(&node, source.as_bytes());
+                    if *capture_name == "d_variable" {
+                        let (attributes, value_type, names) = d_variable_declaration(&text);
+                        if public_only && attributes.iter().any(|a| a == "private") {
+                            continue;
+                        }
+                        // Only constants are part of a module's interface
+                        if container_name.is_none()
+                            && !attributes.iter().any(|a| a == "immutable" || a == "const")
+                        {
+                            continue;
+                        }
+                        let value_type = if options.signatures {
+                            value_type
+                        } else {
+                            String::new()
+                        };
+                        for variable_name in names {
+                            let variable = Variable {
+                                name: variable_name,
+                                value_type: value_type.clone(),
+                                start_byte: node.start_byte(),
+                                value: None,
+                                ..Default::default()
+                            };
+                            match &container_name {
+                                Some(container_name) => {
+                                    ensure_class_def(
+                                        language,
+                                        container_name,
+                                        node.start_byte(),
+                                        &mut class_def_map,
+                                    );
+                                    class_def_map[container_name]
+                                        .borrow_mut()
+                                        .properties
+                                        .push(variable);
+                                }
+                                None => definitions.push(Definition::Variable(variable)),
+                            }
+                        }
+                        continue;
+                    }
+                    let Some((attributes, function_name, params, return_type)) =
+                        d_function_signature(&text)
+                    else {
+                        continue;
+                    };
+                    if public_only && attributes.iter().any(|a| a == "private") {
+                        continue;
+                    }
+                    let (params, return_type) = if options.signatures {
+                        (params, return_type)
+                    } else {
+                        (String::new(), String::new())
+                    };
+                    let func = Func {
+                        name: function_name,
+                        params,
+                        return_type,
+                        accessibility_modifier: attributes
+                            .iter()
+                            .any(|a| a == "static")
+                            .then(|| "static".to_string()),
+                        start_byte: node.start_byte(),
+                        calls: collect_calls(&node, language, source.as_bytes(), options),
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        ..Default::default()
+                    };
+                    match container_name {
+                        Some(container_name) => {
+                            ensure_class_def(
+                                language,
+                                &container_name,
+                                node.start_byte(),
+                                &mut class_def_map,
+                            );
+                            class_def_map[&container_name]
+                                .borrow_mut()
+                                .methods
+                                .push(func);
+                        }
+                        None => definitions.push(Definition::Func(func)),
+                    }
+                }
+                // Like Rust, only `pub` items are part of the module's interface
+                "gl_function" | "gl_type" | "gl_constant" => {
+                    if language != "gleam"
+                        || (public_only
+                            && find_child_by_type(&node, "visibility_modifier").is_none())
+                    {
+                        continue;
+                    }
+                    let text_of = |n: Node| {
+                        get_node_text(&n, source.as_bytes())
+                            .split_whitespace()
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    };
+                    match *capture_name {
+                        "gl_function" => {
+                            let (params, return_type) = if options.signatures {
+                                (
+                                    node.child_by_field_name("parameters")
+                                        .map_or_else(|| "()".to_string(), text_of),
+                                    node.child_by_field_name("return_type")
+                                        .map(text_of)
+                                        .unwrap_or_default(),
+                                )
+                            } else {
+                                (String::new(), String::new())
+                            };
+                            definitions.push(Definition::Func(Func {
+                                name: name.clone(),
+                                params,
+                                return_type,
+                                accessibility_modifier: None,
+                                start_byte: node.start_byte(),
+                                calls: collect_calls(&node, language, source.as_bytes(), options),
+                                references: collect_identifiers(&node, source.as_bytes(), options),
+                                returns_self: false,
+                                ..Default::default()
+                            }));
+                        }
+                        "gl_constant" => {
+                            definitions.push(Definition::Variable(Variable {
+                                name: name.clone(),
+                                value_type: node
+                                    .child_by_field_name("type")
+                                    .filter(|_| options.signatures)
+                                    .map(text_of)
+                                    .unwrap_or_default(),
+                                start_byte: node.start_byte(),
+                                value: None,
+                                ..Default::default()
+                            }));
+                        }
+                        _ => {
+                            // `Box(a)` is named `Box`
+                            let Some(type_name) = find_child_by_type(&node, "type_name")
+                                .map(text_of)
+                                .and_then(|n| n.split('(').next().map(|n| n.trim().to_string()))
+                            else {
+                                continue;
+                            };
+                            // The constructors of an opaque type are private to its module
+                            if find_child_by_type(&node, "opacity_modifier").is_some() {
+                                ensure_class_def(
+                                    language,
+                                    &type_name,
+                                    node.start_byte(),
+                                    &mut class_def_map,
+                                );
+                                class_def_map[&type_name].borrow_mut().type_name =
+                                    "opaque type".to_string();
+                                continue;
+                            }
+                            ensure_enum_def(&type_name, node.start_byte(), &mut enum_def_map);
+                            let mut enum_def = enum_def_map[&type_name].borrow_mut();
+                            for constructor in find_descendants_by_type(&node, "data_constructor") {
+                                let Some(constructor_name) = constructor
+                                    .child_by_field_name("name")
+                                    .map(|n| get_node_text(&n, source.as_bytes()))
+                                else {
+                                    continue;
+                                };
+                                enum_def.items.push(Variable {
+                                    name: constructor_name,
+                                    value_type: constructor
+                                        .child_by_field_name("arguments")
+                                        .filter(|_| options.signatures)
+                                        .map(text_of)
+                                        .unwrap_or_default(),
+                                    start_byte: constructor.start_byte(),
+                                    value: None,
+                                    ..Default::default()
+                                });
+                            }
+                        }
+                    }
+                }
+                "cr_type" => {
+                    if language != "crystal" {
+                        continue;
+                    }
+                    let Some(path) =
+                        ruby_find_parent_module_declaration_name(&node, source.as_bytes())
+                    else {
+                        continue;
+                    };
+                    ensure_class_def(language, &path, node.start_byte(), &mut class_def_map);
+                }
+                // Methods outside of any type are top-level functions, private ones are kept and
+                // marked like Ruby's
+                "cr_method" => {
+                    if language != "crystal"
+                        || find_ancestor_by_type(&node, "method_definition").is_some()
+                    {
+                        continue;
+                    }
+                    let (params, return_type) = if options.signatures {
+                        crystal_method_signature(&node, source.as_bytes())
+                    } else {
+                        (String::new(), String::new())
+                    };
+                    let func = Func {
+                        name: name.clone(),
+                        params,
+                        return_type,
+                        accessibility_modifier: crystal_visibility(&node, source.as_bytes()),
+                        start_byte: node.start_byte(),
+                        calls: collect_calls(&node, language, source.as_bytes(), options),
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        ..Default::default()
+                    };
+                    match ruby_find_parent_module_declaration_name(&node, source.as_bytes()) {
+                        Some(path) => {
+                            ensure_class_def(
+                                language,
+                                &path,
+                                node.start_byte(),
+                                &mut class_def_map,
+                            );
+                            class_def_map[&path].borrow_mut().methods.push(func);
+                        }
+                        None => definitions.push(Definition::Func(func)),
+                    }
+                }
+                "cr_constant" => {
+                    if language != "crystal"
+                        || find_ancestor_by_type(&node, "method_definition").is_some()
+                    {
+                        continue;
+                    }
+                    // Only uppercase assignments are constants, the rest are locals
+                    let Some(constant_name) = node
+                        .child_by_field_name("name")
+                        .map(|n| get_node_text(&n, source.as_bytes()))
+                        .filter(|name| name.starts_with(|c: char| c.is_ascii_uppercase()))
+                    else {
+                        continue;
+                    };
+                    let variable = Variable {
+                        name: constant_name,
+                        value_type: String::new(),
+                        start_byte: node.start_byte(),
+                        value: None,
+                        ..Default::default()
+                    };
+                    match ruby_find_parent_module_declaration_name(&node, source.as_bytes()) {
+                        Some(path) => {
+                            ensure_class_def(
+                                language,
+                                &path,
+                                node.start_byte(),
+                                &mut class_def_map,
+                            );
+                            class_def_map[&path].borrow_mut().properties.push(variable);
+                        }
+                        None => definitions.push(Definition::Variable(variable)),
+                    }
+                }
+                // Like Go's capitalization, only symbols with the `*` marker are exported
+                "nim_routine" => {
+                    if language != "nim" {
+                        continue;
+                    }
+                    let Some((routine_name, true, params, return_type)) =
+                        nim_routine_signature(node_text)
+                    else {
+                        continue;
+                    };
+                    definitions.push(Definition::Func(Func {
+                        name: routine_name,
+                        params: if options.signatures {
+                            params
+                        } else {
+                            String::new()
+                        },
+                        return_type: if options.signatures {
+                            return_type
+                        } else {
+                            String::new()
+                        },
+                        accessibility_modifier: None,
+                        start_byte: node.start_byte(),
+                        calls: collect_calls(&node, language, source.as_bytes(), options),
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        ..Default::default()
+                    }));
+                }
+                "nim_variable" => {
+                    if language != "nim" {
+                        continue;
+                    }
+                    // `const Version* = "1.0"`, the names follow the `const`, `var` or `let`
+                    let declaration = node_text
+                        .trim_start()
+                        .split_once(char::is_whitespace)
+                        .map_or("", |(_, declaration)| declaration);
+                    let (variable_names, value_type) = nim_exported_names(declaration);
+                    for variable_name in variable_names {
+                        definitions.push(Definition::Variable(Variable {
+                            name: variable_name,
+                            value_type: if options.signatures {
+                                value_type.clone()
+                            } else {
+                                String::new()
+                            },
+                            start_byte: node.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        }));
+                    }
+                }
+                "fsharp_module" => {
+                    if language != "fsharp" {
+                        continue;
+                    }
+                    let (Some(path), false) = fsharp_module_path(&node, source.as_bytes()) else {
+                        continue;
+                    };
+                    ensure_module_def(&path, node.start_byte(), &mut class_def_map);
+                }
+                "fsharp_let" => {
+                    if language != "fsharp" {
+                        continue;
+                    }
+                    let Some((binding_name, params, value_type, false)) = fsharp_binding(node_text)
+                    else {
+                        continue;
+                    };
+                    let (module_path, module_hidden) = fsharp_module_path(&node, source.as_bytes());
+                    if module_hidden || binding_name == "_" {
+                        continue;
+                    }
+                    let value_type = if options.signatures {
+                        value_type
+                    } else {
+                        String::new()
+                    };
+                    // `let pi = 3.14` is a value, `let area shape = ...` and `let f () = ...` are
+                    // functions
+                    let member = if params.is_empty() {
+                        Err(Variable {
+                            name: binding_name,
+                            value_type,
+                            start_byte: node.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        })
+                    } else {
+                        Ok(Func {
+                            name: binding_name,
+                            params: if options.signatures {
+                                params
+                            } else {
+                                String::new()
+                            },
+                            return_type: value_type,
+                            accessibility_modifier: None,
+                            start_byte: node.start_byte(),
+                            calls: collect_calls(&node, language, source.as_bytes(), options),
+                            references: collect_identifiers(&node, source.as_bytes(), options),
+                            returns_self: false,
+                            ..Default::default()
+                        })
+                    };
+                    let Some(module_path) = module_path else {
+                        definitions.push(match member {
+                            Ok(func) => Definition::Func(func),
+                            Err(variable) => Definition::Variable(variable),
+                        });
+                        continue;
+                    };
+                    ensure_module_def(&module_path, node.start_byte(), &mut class_def_map);
+                    let mut module_def = class_def_map.get_mut(&module_path).unwrap().borrow_mut();
+                    match member {
+                        Ok(func) => module_def.methods.push(func),
+                        Err(variable) => module_def.properties.push(variable),
+                    }
+                }
+                "fsharp_type" => {
+                    if language != "fsharp" {
+                        continue;
+                    }
+                    let (module_path, module_hidden) = fsharp_module_path(&node, source.as_bytes());
+                    if module_hidden {
+                        continue;
+                    }
+                    // `type A = ... and B = ...` defines several types at once
+                    let mut cursor = node.walk();
+                    for defn in node.named_children(&mut cursor) {
+                        let Some(type_name_node) = find_child_by_type(&defn, "type_name") else {
+                            continue;
+                        };
+                        let type_name_text = get_node_text(&type_name_node, source.as_bytes());
+                        let mut type_name_text = type_name_text.trim();
+                        if public_only
+                            && (type_name_text.starts_with("private")
+                                || type_name_text.starts_with("internal"))
+                        {
+                            continue;
+                        }
+                        type_name_text = type_name_text.trim_start_matches("public").trim_start();
+                        let own_name = type_name_text
+                            .split(|c: char| c.is_whitespace() || matches!(c, '<' | '('))
+                            .next()
+                            .unwrap_or_default();
+                        if own_name.is_empty() {
+                            continue;
+                        }
+                        let type_name = match &module_path {
+                            Some(module_path) => format!("{module_path}.{own_name}"),
+                            None => own_name.to_string(),
+                        };
+                        if defn.kind() == "union_type_defn" {
+                            ensure_union_def(&type_name, defn.start_byte(), &mut union_def_map);
+                            let mut union_def =
+                                union_def_map.get_mut(&type_name).unwrap().borrow_mut();
+                            for case in find_descendants_by_type(&defn, "union_type_case") {
+                                let case_text = get_node_text(&case, source.as_bytes());
+                                let case_text = case_text.trim().trim_start_matches('|').trim();
+                                let (case_name, fields) =
+                                    case_text.split_once(" of ").unwrap_or((case_text, ""));
+                                union_def.items.push(Variable {
+                                    name: case_name.trim().to_string(),
+                                    value_type: if options.signatures {
+                                        fields.trim().to_string()
+                                    } else {
+                                        String::new()
+                                    },
+                                    start_byte: case.start_byte(),
+                                    value: None,
+                                    ..Default::default()
+                                });
+                            }
+                            continue;
+                        }
+                        let members = find_descendants_by_type(&defn, "member_defn");
+                        let class_type_name = match defn.kind() {
+                            "record_type_defn" => "record",
+                            "interface_type_defn" => "interface",
+                            "anon_type_defn" => "class",
+                            _ if !members.is_empty() => "type",
+                            _ => continue,
+                        };
+                        ensure_class_def(
+                            language,
+                            &type_name,
+                            defn.start_byte(),
+                            &mut class_def_map,
+                        );
+                        let mut class_def = class_def_map.get_mut(&type_name).unwrap().borrow_mut();
+                        class_def.type_name = class_type_name.to_string();
+                        for field in find_descendants_by_type(&defn, "record_field") {
+                            let field_text = get_node_text(&field, source.as_bytes());
+                            let (field_name, field_type) =
+                                field_text.split_once(':').unwrap_or((&field_text, ""));
+                            let field_name = field_name.trim().trim_start_matches("mutable ");
+                            class_def.properties.push(Variable {
+                                name: field_name.trim().to_string(),
+                                value_type: if options.signatures {
+                                    field_type.trim().to_string()
+                                } else {
+                                    String::new()
+                                },
+                                start_byte: field.start_byte(),
+                                value: None,
+                                ..Default::default()
+                            });
+                        }
+                        for member in members {
+                            let member_text = get_node_text(&member, source.as_bytes());
+                            let Some((member_name, params, value_type, false)) =
+                                fsharp_binding(&member_text)
+                            else {
+                                continue;
+                            };
+                            let value_type = if options.signatures {
+                                value_type
+                            } else {
+                                String::new()
+                            };
+                            if params.is_empty() {
+                                class_def.properties.push(Variable {
+                                    name: member_name,
+                                    value_type,
+                                    start_byte: member.start_byte(),
+                                    value: None,
+                                    ..Default::default()
+                                });
+                                continue;
+                            }
+                            class_def.methods.push(Func {
+                                name: member_name,
+                                params: if options.signatures {
+                                    params
+                                } else {
+                                    String::new()
+                                },
+                                return_type: value_type,
+                                accessibility_modifier: None,
+                                start_byte: member.start_byte(),
+                                calls: vec![],
+                                references: vec![],
+                                returns_self: false,
+                                ..Default::default()
+                            });
+                        }
+                    }
+                }
+                // `union SearchResult = User | Post`
+                "graphql_union" => {
+                    if language != "graphql" || name.is_empty() {
+                        continue;
+                    }
+                    ensure_union_def(&name, node.start_byte(), &mut union_def_map);
+                    let mut union_def = union_def_map.get_mut(&name).unwrap().borrow_mut();
+                    for member in find_descendants_by_type(&node, "named_type") {
+                        union_def.items.push(Variable {
+                            name: get_node_text(&member, source.as_bytes()),
+                            value_type: String::new(),
+                            start_byte: member.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        });
+                    }
+                }
+                "graphql_type" => {
+                    if language != "graphql" || name.is_empty() {
+                        continue;
+                    }
+                    ensure_class_def(language, &name, node.start_byte(), &mut class_def_map);
+                    let mut class_def = class_def_map.get_mut(&name).unwrap().borrow_mut();
+                    class_def.type_name = match node.kind() {
+                        "interface_type_definition" => "interface",
+                        "input_object_type_definition" => "input",
+                        _ => "type",
+                    }
+                    .to_string();
+                    let Some(fields) = find_child_by_type(&node, "fields_definition")
+                        .or_else(|| find_child_by_type(&node, "input_fields_definition"))
+                    else {
+                        continue;
+                    };
+                    let mut cursor = fields.walk();
+                    for field in fields.named_children(&mut cursor) {
+                        let Some(field_name) = find_child_by_type(&field, "name")
+                            .map(|n| get_node_text(&n, source.as_bytes()))
+                        else {
+                            continue;
+                        };
+                        let field_type = if options.signatures {
+                            find_child_by_type(&field, "type")
+                                .map(|n| get_node_text(&n, source.as_bytes()))
+                                .unwrap_or_default()
+                        } else {
+                            String::new()
+                        };
+                        // Fields taking arguments are resolvers, `user(id: ID!): User`
+                        match find_child_by_type(&field, "arguments_definition") {
+                            Some(arguments) => class_def.methods.push(Func {
+                                name: field_name,
+                                params: if options.signatures {
+                                    get_params_text(&arguments, source.as_bytes())
+                                } else {
+                                    String::new()
+                                },
+                                return_type: field_type,
+                                accessibility_modifier: None,
+                                start_byte: field.start_byte(),
+                                calls: vec![],
+                                references: vec![],
+                                returns_self: false,
+                                ..Default::default()
+                            }),
+                            None => class_def.properties.push(Variable {
+                                name: field_name,
+                                value_type: field_type,
+                                start_byte: field.start_byte(),
+                                value: None,
+                                ..Default::default()
+                            }),
+                        }
+                    }
+                }
+                "param_block" => {
+                    if language != "powershell" {
+                        continue;
+                    }
+                    let params = powershell_param_list(&get_node_text(&node, source.as_bytes()))
+                        .unwrap_or_default();
+                    let params = params
+                        .strip_prefix('(')
+                        .and_then(|params| params.strip_suffix(')'))
+                        .unwrap_or_default();
+                    for param in split_params(params) {
+                        let Some((param_name, value_type)) = powershell_typed_variable(&param)
+                        else {
+                            continue;
+                        };
+                        definitions.push(Definition::Variable(Variable {
+                            name: param_name,
+                            value_type: if options.signatures {
+                                value_type
+                            } else {
+                                String::new()
+                            },
+                            start_byte: node.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        }));
+                    }
+                }
+                "ps_assignment" => {
+                    if language != "powershell" {
+                        continue;
+                    }
+                    // Script-level assignments only, functions mostly set globals as a side effect
+                    if find_ancestor_by_type(&node, "function_statement").is_some()
+                        || find_ancestor_by_type(&node, "class_statement").is_some()
+                    {
+                        continue;
+                    }
+                    let Some(target) = node.named_child(0) else {
+                        continue;
+                    };
+                    let Some((variable_name, value_type)) =
+                        powershell_global_variable(&get_node_text(&target, source.as_bytes()))
+                    else {
+                        continue;
+                    };
+                    definitions.push(Definition::Variable(Variable {
+                        name: variable_name,
+                        value_type: if options.signatures {
+                            value_type
+                        } else {
+                            String::new()
+                        },
+                        start_byte: node.start_byte(),
+                        value: None,
+                        ..Default::default()
+                    }));
+                }
+                "function_statement" => {
+                    if language != "powershell" {
+                        continue;
+                    }
+                    if find_ancestor_by_type(&node, "function_statement").is_some() {
+                        continue;
+                    }
+                    let Some(function_name) = find_child_by_type(&node, "function_name")
+                        .map(|n| get_node_text(&n, source.as_bytes()))
+                    else {
+                        continue;
+                    };
+                    // `function Add([int]$a) {}` or a `param(...)` block at the top of the body
+                    let param_text = |n: Node| get_node_text(&n, source.as_bytes());
+                    let params = find_child_by_type(&node, "function_parameter_declaration")
+                        .map(param_text)
+                        .or_else(|| {
+                            find_child_by_type(&node, "script_block")
+                                .and_then(|n| find_child_by_type(&n, "param_block").map(param_text))
+                        })
+                        .and_then(|text| powershell_param_list(&text))
+                        .unwrap_or_else(|| "()".to_string());
+                    definitions.push(Definition::Func(Func {
+                        name: function_name,
+                        params: if options.signatures {
+                            params
+                        } else {
+                            String::new()
+                        },
+                        return_type: String::new(),
+                        accessibility_modifier: None,
+                        start_byte: node.start_byte(),
+                        calls: collect_calls(&node, language, source.as_bytes(), options),
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        ..Default::default()
+                    }));
+                }
+                "class_statement" => {
+                    if language != "powershell" {
+                        continue;
+                    }
+                    let Some(class_name) = find_child_by_type(&node, "simple_name")
+                        .map(|n| get_node_text(&n, source.as_bytes()))
+                    else {
+                        continue;
+                    };
+                    ensure_class_def(language, &class_name, node.start_byte(), &mut class_def_map);
+                    let mut class_def = class_def_map.get_mut(&class_name).unwrap().borrow_mut();
+                    let mut cursor = node.walk();
+                    for member in node.named_children(&mut cursor) {
+                        // `hidden` members are left out of `Get-Member`, treat them as private
+                        let member_text = get_node_text(&member, source.as_bytes());
+                        if member_text
+                            .split_whitespace()
+                            .any(|word| word.eq_ignore_ascii_case("hidden"))
+                        {
+                            continue;
+                        }
+                        match member.kind() {
+                            "class_property_definition" => {
+                                let Some((property_name, value_type)) =
+                                    powershell_typed_variable(&member_text)
+                                else {
+                                    continue;
+                                };
+                                class_def.properties.push(Variable {
+                                    name: property_name.trim_start_matches('$').to_string(),
+                                    value_type: if options.signatures {
+                                        value_type
+                                    } else {
+                                        String::new()
+                                    },
+                                    start_byte: member.start_byte(),
+                                    value: None,
+                                    ..Default::default()
+                                });
+                            }
+                            "class_method_definition" => {
+                                let Some(method_name) = find_child_by_type(&member, "simple_name")
+                                else {
+                                    continue;
+                                };
+                                let params = source
+                                    .get(method_name.end_byte()..member.end_byte())
+                                    .and_then(powershell_param_list)
+                                    .unwrap_or_else(|| "()".to_string());
+                                let return_type = find_child_by_type(&member, "type_literal")
+                                    .map(|n| {
+                                        get_node_text(&n, source.as_bytes())
+                                            .trim_start_matches('[')
+                                            .trim_end_matches(']')
+                                            .to_string()
+                                    })
+                                    .unwrap_or_default();
+                                let (params, return_type) = if options.signatures {
+                                    (params, return_type)
+                                } else {
+                                    (String::new(), String::new())
+                                };
+                                class_def.methods.push(Func {
+                                    name: get_node_text(&method_name, source.as_bytes()),
+                                    params,
+                                    return_type,
+                                    accessibility_modifier: None,
+                                    start_byte: member.start_byte(),
+                                    calls: collect_calls(
+                                        &member,
+                                        language,
+                                        source.as_bytes(),
+                                        options,
+                                    ),
+                                    references: collect_identifiers(
+                                        &member,
+                                        source.as_bytes(),
+                                        options,
+                                    ),
+                                    returns_self: false,
+                                    ..Default::default()
+                                });
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                "form" => {
+                    if language != "clojure" {
+                        continue;
+                    }
+                    let forms = clojure_forms(&node);
+                    let (Some(head), Some(name_node)) = (forms.first(), forms.get(1)) else {
+                        continue;
+                    };
+                    let Some(head) = clojure_symbol_name(head, source.as_bytes()) else {
+                        continue;
+                    };
+                    let Some(form_name) = clojure_symbol_name(name_node, source.as_bytes()) else {
+                        continue;
+                    };
+                    // Like non-`pub` Rust items, `defn-` and `^:private` vars stay out of the map
+                    if public_only
+                        && (head == "defn-" || clojure_is_private(name_node, source.as_bytes()))
+                    {
+                        continue;
+                    }
+                    let namespace = forms_namespace(&node, source.as_bytes());
+                    let rest = &forms[2..];
+                    match head.as_str() {
+                        "ns" => {
+                            ensure_module_def(&form_name, node.start_byte(), &mut class_def_map);
+                        }
+                        "defn" | "defmacro" | "def" => {
+                            let member = if head == "def" {
+                                Err(Variable {
+                                    name: form_name,
+                                    value_type: String::new(),
+                                    start_byte: node.start_byte(),
+                                    value: None,
+                                    ..Default::default()
+                                })
+                            } else {
+                                Ok(Func {
+                                    name: form_name,
+                                    params: if options.signatures {
+                                        clojure_arg_vectors(rest, source.as_bytes())
+                                    } else {
+                                        String::new()
+                                    },
+                                    return_type: String::new(),
+                                    accessibility_modifier: None,
+                                    start_byte: node.start_byte(),
+                                    calls: collect_calls(
+                                        &node,
+                                        language,
+                                        source.as_bytes(),
+                                        options,
+                                    ),
+                                    references: collect_identifiers(
+                                        &node,
+                                        source.as_bytes(),
+                                        options,
+                                    ),
+                                    returns_self: false,
+                                    ..Default::default()
+                                })
+                            };
+                            let Some(namespace) = namespace else {
+                                definitions.push(match member {
+                                    Ok(func) => Definition::Func(func),
+                                    Err(variable) => Definition::Variable(variable),
+                                });
+                                continue;
+                            };
+                            ensure_module_def(&namespace, node.start_byte(), &mut class_def_map);
+                            let mut module_def =
+                                class_def_map.get_mut(&namespace).unwrap().borrow_mut();
+                            match member {
+                                Ok(func) => module_def.methods.push(func),
+                                Err(variable) => module_def.properties.push(variable),
+                            }
+                        }
+                        "defprotocol" | "defrecord" => {
+                            ensure_class_def(
+                                language,
+                                &form_name,
+                                node.start_byte(),
+                                &mut class_def_map,
+                            );
+                            let mut class_def =
+                                class_def_map.get_mut(&form_name).unwrap().borrow_mut();
+                            class_def.type_name = head.trim_start_matches("def").to_string();
+                            if head == "defrecord" {
+                                let fields = rest.iter().find(|n| n.kind() == "vec_lit");
+                                for field in fields.map(clojure_forms).unwrap_or_default() {
+                                    let Some(field_name) =
+                                        clojure_symbol_name(&field, source.as_bytes())
+                                    else {
+                                        continue;
+                                    };
+                                    class_def.properties.push(Variable {
+                                        name: field_name,
+                                        value_type: String::new(),
+                                        start_byte: field.start_byte(),
+                                        value: None,
+                                        ..Default::default()
+                                    });
+                                }
+                                continue;
+                            }
+                            // `(perimeter [this] "doc")` declares a protocol method
+                            for signature in rest.iter().filter(|n| n.kind() == "list_lit") {
+                                let signature_forms = clojure_forms(signature);
+                                let Some(method_name) = signature_forms
+                                    .first()
+                                    .and_then(|n| clojure_symbol_name(n, source.as_bytes()))
+                                else {
+                                    continue;
+                                };
+                                class_def.methods.push(Func {
+                                    name: method_name,
+                                    params: if options.signatures {
+                                        clojure_arg_vectors(
+                                            &signature_forms[1..],
+                                            source.as_bytes(),
+                                        )
+                                    } else {
+                                        String::new()
+                                    },
+                                    return_type: String::new(),
+                                    accessibility_modifier: None,
+                                    start_byte: signature.start_byte(),
+                                    calls: vec![],
+                                    references: vec![],
+                                    returns_self: false,
+                                    ..Default::default()
+                                });
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                "message" => {
+                    if language != "proto" || name.is_empty() {
+                        continue;
+                    }
+                    ensure_class_def(language, &name, node.start_byte(), &mut class_def_map);
+                    let mut class_def = class_def_map.get_mut(&name).unwrap().borrow_mut();
+                    class_def.type_name = "message".to_string();
+                    let Some(body) = find_child_by_type(&node, "message_body") else {
+                        continue;
+                    };
+                    // Fields of a `oneof` belong to the message as well
+                    let mut fields = Vec::new();
+                    for child in body.named_children(&mut body.walk()) {
+                        match child.kind() {
+                            "field" | "map_field" => fields.push(child),
+                            "oneof" => fields.extend(
+                                child
+                                    .named_children(&mut child.walk())
+                                    .filter(|n| n.kind() == "oneof_field"),
+                            ),
+                            _ => {}
+                        }
+                    }
+                    for field in fields {
+                        let Some(mut variable) = proto_field(&field, source.as_bytes()) else {
+                            continue;
+                        };
+                        if !options.signatures {
+                            variable.value_type = String::new();
+                        }
+                        class_def.properties.push(variable);
+                    }
+                }
+                "service" => {
+                    if language != "proto" || name.is_empty() {
+                        continue;
+                    }
+                    ensure_class_def(language, &name, node.start_byte(), &mut class_def_map);
+                    let mut class_def = class_def_map.get_mut(&name).unwrap().borrow_mut();
+                    class_def.type_name = "service".to_string();
+                    for rpc in find_descendants_by_type(&node, "rpc") {
+                        let Some((rpc_name, request, response)) =
+                            proto_rpc(&rpc, source.as_bytes())
+                        else {
+                            continue;
+                        };
+                        let (params, return_type) = if options.signatures {
+                            (request, response)
+                        } else {
+                            (String::new(), String::new())
+                        };
+                        class_def.methods.push(Func {
+                            name: rpc_name,
+                            params,
+                            return_type,
+                            accessibility_modifier: None,
+                            start_byte: rpc.start_byte(),
+                            calls: vec![],
+                            references: vec![],
+                            returns_self: false,
+                            ..Default::default()
+                        });
+                    }
+                }
+                "create_table" | "create_view" => {
+                    if language != "sql" || name.is_empty() {
+                        continue;
+                    }
+                    ensure_class_def(language, &name, node.start_byte(), &mut class_def_map);
+                    let mut class_def = class_def_map.get_mut(&name).unwrap().borrow_mut();
+                    class_def.type_name = if *capture_name == "create_table" {
+                        "table".to_string()
+                    } else {
+                        "view".to_string()
+                    };
+                    let Some(columns) = find_child_by_type(&node, "column_definitions") else {
+                        continue;
+                    };
+                    let mut cursor = columns.walk();
+                    for column in columns
+                        .named_children(&mut cursor)
+                        .filter(|n| n.kind() == "column_definition")
+                    {
+                        let Some(column_name) = column.child_by_field_name("name") else {
+                            continue;
+                        };
+                        class_def.properties.push(Variable {
+                            name: get_node_text(&column_name, source.as_bytes()),
+                            value_type: if options.signatures {
+                                column
+                                    .child_by_field_name("type")
+                                    .map(|n| get_node_text(&n, source.as_bytes()))
+                                    .unwrap_or_default()
+                            } else {
+                                String::new()
+                            },
+                            start_byte: column.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        });
+                    }
+                }
+                // `ALTER TABLE orders ADD COLUMN status TEXT` extends a table created in the file
+                "alter_table" => {
+                    if language != "sql" || name.is_empty() {
+                        continue;
+                    }
+                    let Some(class_def) = class_def_map.get(&name) else {
+                        continue;
+                    };
+                    let mut class_def = class_def.borrow_mut();
+                    for add_column in find_descendants_by_type(&node, "add_column") {
+                        let Some(column) = find_child_by_type(&add_column, "column_definition")
+                        else {
+                            continue;
+                        };
+                        let Some(column_name) = column.child_by_field_name("name") else {
+                            continue;
+                        };
+                        class_def.properties.push(Variable {
+                            name: get_node_text(&column_name, source.as_bytes()),
+                            value_type: if options.signatures {
+                                column
+                                    .child_by_field_name("type")
+                                    .map(|n| get_node_text(&n, source.as_bytes()))
+                                    .unwrap_or_default()
+                            } else {
+                                String::new()
+                            },
+                            start_byte: column.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        });
+                    }
+                }
+                // `CREATE TYPE status AS ENUM ('active', 'banned')`, other types are skipped
+                "create_type" => {
+                    if language != "sql" || name.is_empty() {
+                        continue;
+                    }
+                    let Some(items) = sql_enum_labels(&get_node_text(&node, source.as_bytes()))
+                    else {
+                        continue;
+                    };
+                    ensure_enum_def(&name, node.start_byte(), &mut enum_def_map);
+                    let enum_def = enum_def_map.get_mut(&name).unwrap();
+                    enum_def
+                        .borrow_mut()
+                        .items
+                        .extend(items.into_iter().map(|label| Variable {
+                            name: label,
+                            value_type: String::new(),
+                            start_byte: node.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        }));
+                }
+                "create_function" => {
+                    if language != "sql" || name.is_empty() {
+                        continue;
+                    }
+                    let (params, return_type) = if options.signatures {
+                        (
+                            find_child_by_type(&node, "function_arguments").map_or_else(
+                                || "()".to_string(),
+                                |n| get_params_text(&n, source.as_bytes()),
+                            ),
+                            sql_return_type(&node, source.as_bytes()),
+                        )
+                    } else {
+                        (String::new(), String::new())
+                    };
+                    definitions.push(Definition::Func(Func {
+                        name,
+                        params,
+                        return_type,
+                        accessibility_modifier: None,
+                        start_byte: node.start_byte(),
+                        calls: vec![],
+                        references: vec![],
+                        returns_self: false,
+                        ..Default::default()
+                    }));
+                }
+                "groovy_class" => {
+                    if language != "groovy" || name.is_empty() {
+                        continue;
+                    }
+                    if public_only && groovy_is_private(&node, source.as_bytes()) {
+                        continue;
+                    }
+                    ensure_class_def(language, &name, node.start_byte(), &mut class_def_map);
+                }
+                "groovy_function" => {
+                    if language != "groovy" || name.is_empty() {
+                        continue;
+                    }
+                    if public_only && groovy_is_private(&node, source.as_bytes()) {
+                        continue;
+                    }
+                    let (params, return_type) = if options.signatures {
+                        (
+                            node.child_by_field_name("parameters").map_or_else(
+                                || "()".to_string(),
+                                |n| get_params_text(&n, source.as_bytes()),
+                            ),
+                            groovy_type(&node, source.as_bytes()),
+                        )
+                    } else {
+                        (String::new(), String::new())
+                    };
+                    let func = Func {
+                        name,
+                        params,
+                        return_type,
+                        accessibility_modifier: None,
+                        start_byte: node.start_byte(),
+                        calls: collect_calls(&node, language, source.as_bytes(), options),
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        ..Default::default()
+                    };
+                    let parent = find_first_ancestor_by_types(
+                        &node,
+                        &["class_declaration", "function_definition", "closure"],
+                    );
+                    match parent {
+                        Some(class_node) if class_node.kind() == "class_declaration" => {
+                            let Some(class_name) = class_node
+                                .child_by_field_name("name")
+                                .map(|n| get_node_text(&n, source.as_bytes()))
+                            else {
+                                continue;
+                            };
+                            if public_only && groovy_is_private(&class_node, source.as_bytes()) {
+                                continue;
+                            }
+                            ensure_class_def(
+                                language,
+                                &class_name,
+                                class_node.start_byte(),
+                                &mut class_def_map,
+                            );
+                            let class_def = class_def_map.get_mut(&class_name).unwrap();
+                            class_def.borrow_mut().methods.push(func);
+                        }
+                        // Helpers nested in a function or a closure are local
+                        Some(_) => {}
+                        None => definitions.push(Definition::Func(func)),
+                    }
+                }
+                "groovy_field" => {
+                    if language != "groovy" || name.is_empty() {
+                        continue;
+                    }
+                    if public_only && groovy_is_private(&node, source.as_bytes()) {
+                        continue;
+                    }
+                    let Some(class_node) = find_ancestor_by_type(&node, "class_declaration") else {
+                        continue;
+                    };
+                    let Some(class_name) = class_node
+                        .child_by_field_name("name")
+                        .map(|n| get_node_text(&n, source.as_bytes()))
+                    else {
+                        continue;
+                    };
+                    if public_only && groovy_is_private(&class_node, source.as_bytes()) {
+                        continue;
+                    }
+                    ensure_class_def(
+                        language,
+                        &class_name,
+                        class_node.start_byte(),
+                        &mut class_def_map,
+                    );
+                    let class_def = class_def_map.get_mut(&class_name).unwrap();
+                    class_def.borrow_mut().properties.push(Variable {
+                        name,
+                        value_type: if options.signatures {
+                            groovy_type(&node, source.as_bytes())
+                        } else {
+                            String::new()
+                        },
+                        start_byte: node.start_byte(),
+                        value: None,
+                        ..Default::default()
+                    });
+                }
+                "groovy_call" => {
+                    if language != "groovy" {
+                        continue;
+                    }
+                    let Some(call_name) = groovy_script_call_name(&node, source.as_bytes()) else {
+                        continue;
+                    };
+                    definitions.push(Definition::Func(Func {
+                        name: call_name,
+                        params: String::new(),
+                        return_type: String::new(),
+                        accessibility_modifier: None,
+                        start_byte: node.start_byte(),
+                        calls: collect_calls(&node, language, source.as_bytes(), options),
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        ..Default::default()
+                    }));
+                }
+                "objc_class" => {
+                    if language != "objc" || name.is_empty() {
+                        continue;
+                    }
+                    ensure_class_def(language, &name, node.start_byte(), &mut class_def_map);
+                    if node.kind() == "protocol_declaration" {
+                        class_def_map.get_mut(&name).unwrap().borrow_mut().type_name =
+                            "protocol".to_string();
+                    }
+                }
+                "objc_method" => {
+                    if language != "objc" {
+                        continue;
+                    }
+                    let Some(class_name) = objc_container_name(&node, source.as_bytes()) else {
+                        continue;
+                    };
+                    let (selector, params, return_type) =
+                        objc_method_signature(&node, source.as_bytes());
+                    let accessibility_modifier = get_node_text(&node, source.as_bytes())
+                        .starts_with('+')
+                        .then(|| "class".to_string());
+                    ensure_class_def(language, &class_name, node.start_byte(), &mut class_def_map);
+                    let mut class_def = class_def_map.get_mut(&class_name).unwrap().borrow_mut();
+                    // Methods declared in the `@interface` are implemented again later on
+                    if class_def.methods.iter().any(|method| {
+                        method.name == selector
+                            && method.accessibility_modifier == accessibility_modifier
+                    }) {
+                        continue;
+                    }
+                    let (params, return_type) = if options.signatures {
+                        (params, return_type)
+                    } else {
+                        (String::new(), String::new())
+                    };
+                    class_def.methods.push(Func {
+                        name: selector,
+                        params,
+                        return_type,
+                        accessibility_modifier,
+                        start_byte: node.start_byte(),
+                        calls: collect_calls(&node, language, source.as_bytes(), options),
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        ..Default::default()
+                    });
+                }
+                "objc_property" => {
+                    if language != "objc" {
+                        continue;
+                    }
+                    let Some(class_name) = objc_container_name(&node, source.as_bytes()) else {
+                        continue;
+                    };
+                    let Some(mut property) = objc_property(&node, source.as_bytes()) else {
+                        continue;
+                    };
+                    if !options.signatures {
+                        property.value_type = String::new();
+                    }
+                    ensure_class_def(language, &class_name, node.start_byte(), &mut class_def_map);
+                    let class_def = class_def_map.get_mut(&class_name).unwrap();
+                    class_def.borrow_mut().properties.push(property);
+                }
+                "package" => {
+                    if language != "perl" {
+                        continue;
+                    }
+                    let Some(package_name) = find_child_by_type(&node, "package_name")
+                        .map(|n| get_node_text(&n, source.as_bytes()))
+                    else {
+                        continue;
+                    };
+                    ensure_module_def(&package_name, node.start_byte(), &mut class_def_map);
+                }
+                "subroutine" => {
+                    // Subs starting with an underscore are private by convention
+                    if language != "perl"
+                        || name.is_empty()
+                        || (public_only && name.starts_with('_'))
+                    {
+                        continue;
+                    }
+                    if find_ancestor_by_type(&node, "function_definition").is_some() {
+                        continue;
+                    }
+                    let func = Func {
+                        name,
+                        params: if options.signatures {
+                            perl_sub_params(&node, source.as_bytes())
+                        } else {
+                            String::new()
+                        },
+                        return_type: String::new(),
+                        accessibility_modifier: None,
+                        start_byte: node.start_byte(),
+                        calls: collect_calls(&node, language, source.as_bytes(), options),
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        ..Default::default()
+                    };
+                    match perl_package_name(&node, source.as_bytes()) {
+                        Some(package_name) => {
+                            ensure_module_def(&package_name, node.start_byte(), &mut class_def_map);
+                            let module_def = class_def_map.get_mut(&package_name).unwrap();
+                            module_def.borrow_mut().methods.push(func);
+                        }
+                        None => definitions.push(Definition::Func(func)),
+                    }
+                }
+                "variable_declaration" => {
+                    // Only package variables, `my` and `state` are lexically scoped
+                    if language != "perl"
+                        || !get_node_text(&node, source.as_bytes()).starts_with("our")
+                        || find_ancestor_by_type(&node, "function_definition").is_some()
+                    {
+                        continue;
+                    }
+                    let mut variables: Vec<Node> =
+                        ["scalar_variable", "array_variable", "hash_variable"]
+                            .iter()
+                            .flat_map(|kind| find_descendants_by_type(&node, kind))
+                            .collect();
+                    variables.sort_by_key(Node::start_byte);
+                    let package_name = perl_package_name(&node, source.as_bytes());
+                    for variable in variables {
+                        let variable = Variable {
+                            name: get_node_text(&variable, source.as_bytes()),
+                            value_type: String::new(),
+                            start_byte: variable.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        };
+                        match &package_name {
+                            Some(package_name) => {
+                                ensure_module_def(
+                                    package_name,
+                                    node.start_byte(),
+                                    &mut class_def_map,
+                                );
+                                let module_def = class_def_map.get_mut(package_name).unwrap();
+                                module_def.borrow_mut().properties.push(variable);
+                            }
+                            None => definitions.push(Definition::Variable(variable)),
+                        }
+                    }
+                }
+                "module_type" => {
+                    if language != "ocaml" || name.is_empty() {
+                        continue;
+                    }
+                    ensure_class_def(language, &name, node.start_byte(), &mut class_def_map);
+                    let mut class_def = class_def_map.get_mut(&name).unwrap().borrow_mut();
+                    class_def.type_name = "module type".to_string();
+                    let Some(signature) = find_child_by_type(&node, "signature") else {
+                        continue;
+                    };
+                    for specification in signature.children(&mut signature.walk()) {
+                        if specification.kind() != "value_specification" {
+                            continue;
+                        }
+                        match ocaml_value_specification(&specification, source.as_bytes(), options)
+                        {
+                            Some(Definition::Func(func)) => class_def.methods.push(func),
+                            Some(Definition::Variable(variable)) => {
+                                class_def.properties.push(variable);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                "value_specification" => {
+                    if language != "ocaml" {
+                        continue;
+                    }
+                    definitions.extend(ocaml_value_specification(
+                        &node,
+                        source.as_bytes(),
+                        options,
+                    ));
+                }
+                "type_binding" => {
+                    if language != "ocaml" || name.is_empty() {
+                        continue;
+                    }
+                    if let Some(variants) = find_descendant_by_type(&node, "variant_declaration") {
+                        ensure_enum_def(&name, node.start_byte(), &mut enum_def_map);
+                        let enum_def = enum_def_map.get_mut(&name).unwrap();
+                        for constructor in variants.children(&mut variants.walk()) {
+                            if constructor.kind() != "constructor_declaration" {
+                                continue;
+                            }
+                            let Some(constructor_name) =
+                                find_child_by_type(&constructor, "constructor_name")
+                            else {
+                                continue;
+                            };
+                            let arguments =
+                                source[constructor_name.end_byte()..constructor.end_byte()].trim();
+                            let arguments = arguments.strip_prefix("of").unwrap_or(arguments);
+                            enum_def.borrow_mut().items.push(Variable {
+                                name: get_node_text(&constructor_name, source.as_bytes()),
+                                value_type: arguments
+                                    .split_whitespace()
+                                    .collect::<Vec<_>>()
+                                    .join(" "),
+                                start_byte: constructor.start_byte(),
+                                value: None,
+                                ..Default::default()
+                            });
+                        }
+                    } else if let Some(record) =
+                        find_descendant_by_type(&node, "record_declaration")
+                    {
+                        ensure_class_def(language, &name, node.start_byte(), &mut class_def_map);
+                        let mut class_def = class_def_map.get_mut(&name).unwrap().borrow_mut();
+                        class_def.type_name = "record".to_string();
+                        for field in record.children(&mut record.walk()) {
+                            if field.kind() != "field_declaration" {
+                                continue;
+                            }
+                            let Some(field_name) = find_child_by_type(&field, "field_name") else {
+                                continue;
+                            };
+                            class_def.properties.push(Variable {
+                                name: get_node_text(&field_name, source.as_bytes()),
+                                value_type: if options.signatures {
+                                    type_after_colon(&field, source.as_bytes()).unwrap_or_default()
+                                } else {
+                                    String::new()
+                                },
+                                start_byte: field.start_byte(),
+                                value: None,
+                                ..Default::default()
+                            });
+                        }
+                    }
+                }
+                "fun_decl" => {
+                    if name.is_empty() || (public_only && !erlang_exported.contains(&name)) {
+                        continue;
+                    }
+                    // Each clause of `area(...) -> ...; area(_) -> 0.` is its own declaration,
+                    // only the first one is kept
+                    let continues_previous = node.prev_named_sibling().is_some_and(|previous| {
+                        previous.kind() == "fun_decl"
+                            && erlang_function_name(&previous, source.as_bytes()).as_ref()
+                                == Some(&name)
+                            && get_node_text(&previous, source.as_bytes())
+                                .trim_end()
+                                .ends_with(';')
+                    });
+                    if continues_previous {
+                        continue;
+                    }
+                    let Some(clause) = find_child_by_type(&node, "function_clause") else {
+                        continue;
+                    };
+                    let func = Func {
+                        name,
+                        params: if options.signatures {
+                            clause
+                                .child_by_field_name("args")
+                                .map(|n| get_params_text(&n, source.as_bytes()))
+                                .unwrap_or_default()
+                        } else {
+                            String::new()
+                        },
+                        return_type: String::new(),
+                        accessibility_modifier: None,
+                        start_byte: node.start_byte(),
+                        calls: collect_calls(&node, language, source.as_bytes(), options),
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        ..Default::default()
+                    };
+                    if erlang_module.is_empty() {
+                        definitions.push(Definition::Func(func));
+                    } else {
+                        ensure_module_def(&erlang_module, node.start_byte(), &mut class_def_map);
+                        let class_def = class_def_map.get_mut(&erlang_module).unwrap();
+                        class_def.borrow_mut().methods.push(func);
+                    }
+                }
+                "record" => {
+                    if language != "erlang" || name.is_empty() {
+                        continue;
+                    }
+                    // `-record(point, {x = 0, y :: integer()}).` lists its fields with their types
+                    ensure_class_def(language, &name, node.start_byte(), &mut class_def_map);
+                    let mut class_def = class_def_map.get_mut(&name).unwrap().borrow_mut();
+                    class_def.type_name = "record".to_string();
+                    for field in find_descendants_by_type(&node, "record_field") {
+                        let Some(field_name) = field.child_by_field_name("name") else {
+                            continue;
+                        };
+                        class_def.properties.push(Variable {
+                            name: get_node_text(&field_name, source.as_bytes()),
+                            value_type: field
+                                .child_by_field_name("ty")
+                                .filter(|_| options.signatures)
+                                .map(|n| {
+                                    get_node_text(&n, source.as_bytes())
+                                        .trim_start_matches("::")
+                                        .trim()
+                                        .to_string()
+                                })
+                                .unwrap_or_default(),
+                            start_byte: field.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        });
+                    }
+                }
+                "struct_definition" => {
+                    if language != "julia" || name.is_empty() {
+                        continue;
+                    }
+                    ensure_class_def(language, &name, node.start_byte(), &mut class_def_map);
+                    let mut class_def = class_def_map.get_mut(&name).unwrap().borrow_mut();
+                    class_def.type_name =
+                        if get_node_text(&node, source.as_bytes()).starts_with("mutable") {
+                            "mutable struct".to_string()
+                        } else {
+                            "struct".to_string()
+                        };
+                    // Without a `type_head` the first child is the name of the struct
+                    let skip = usize::from(find_child_by_type(&node, "type_head").is_none());
+                    for field in node.named_children(&mut node.walk()).skip(skip) {
+                        let Some((field_name, value_type)) =
+                            julia_typed_name(&field, source.as_bytes())
+                        else {
+                            continue;
+                        };
+                        class_def.properties.push(Variable {
+                            name: field_name,
+                            value_type: if options.signatures {
+                                value_type
+                            } else {
+                                String::new()
+                            },
+                            start_byte: field.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        });
+                    }
+                }
+                "function_definition" => {
+                    if language != "julia" {
+                        continue;
+                    }
+                    // Closures assigned inside a function body aren't definitions
+                    if find_ancestor_by_type(&node, "function_definition").is_some() {
+                        continue;
+                    }
+                    let Some(call) = julia_call_signature(&node) else {
+                        continue;
+                    };
+                    let function_name = call
+                        .named_child(0)
+                        .map(|n| get_node_text(&n, source.as_bytes()))
+                        .unwrap_or_default();
+                    if function_name.is_empty()
+                        || (public_only
+                            && !julia_is_exported(&node, &function_name, source.as_bytes()))
+                    {
+                        continue;
+                    }
+                    // `function f(x)::Int`, the call is wrapped in a typed expression
+                    let return_type = call
+                        .parent()
+                        .filter(|n| n.kind() == "typed_expression")
+                        .and_then(|n| julia_typed_name(&n, source.as_bytes()))
+                        .map(|(_, return_type)| return_type)
+                        .unwrap_or_default();
+                    // Multiple dispatch, every method is listed with its own parameters
+                    let func = Func {
+                        name: function_name,
+                        params: if options.signatures {
+                            find_child_by_type(&call, "argument_list")
+                                .map(|n| get_params_text(&n, source.as_bytes()))
+                                .unwrap_or_default()
+                        } else {
+                            String::new()
+                        },
+                        return_type: if options.signatures {
+                            return_type
+                        } else {
+                            String::new()
+                        },
+                        accessibility_modifier: None,
+                        start_byte: node.start_byte(),
+                        calls: collect_calls(&node, language, source.as_bytes(), options),
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        ..Default::default()
+                    };
+                    let module_path = julia_module_path(&node, source.as_bytes());
+                    if module_path.is_empty() {
+                        definitions.push(Definition::Func(func));
+                    } else {
+                        ensure_module_def(&module_path, node.start_byte(), &mut class_def_map);
+                        let class_def = class_def_map.get_mut(&module_path).unwrap();
+                        class_def.borrow_mut().methods.push(func);
+                    }
+                }
+                "const_statement" => {
+                    if language != "julia" {
+                        continue;
+                    }
+                    let Some(target) = find_descendant_by_type(&node, "assignment")
+                        .and_then(|assignment| assignment.named_child(0))
+                    else {
+                        continue;
+                    };
+                    let Some((const_name, value_type)) =
+                        julia_typed_name(&target, source.as_bytes())
+                    else {
+                        continue;
+                    };
+                    let variable = Variable {
+                        name: const_name,
+                        value_type: if options.signatures {
+                            value_type
+                        } else {
+                            String::new()
+                        },
+                        start_byte: node.start_byte(),
+                        value: None,
+                        ..Default::default()
+                    };
+                    let module_path = julia_module_path(&node, source.as_bytes());
+                    if module_path.is_empty() {
+                        definitions.push(Definition::Variable(variable));
+                    } else {
+                        ensure_module_def(&module_path, node.start_byte(), &mut class_def_map);
+                        let class_def = class_def_map.get_mut(&module_path).unwrap();
+                        class_def.borrow_mut().properties.push(variable);
+                    }
+                }
+                "binary_operator" => {
+                    if language != "r" {
+                        continue;
+                    }
+                    let operator = node
+                        .child_by_field_name("operator")
+                        .map(|n| get_node_text(&n, source.as_bytes()))
+                        .unwrap_or_default();
+                    // `value -> name` assigns to the right-hand side
+                    let (target, value) = match operator.as_str() {
+                        "<-" | "<<-" | "=" => (
+                            node.child_by_field_name("lhs"),
+                            node.child_by_field_name("rhs"),
+                        ),
+                        "->" | "->>" => (
+                            node.child_by_field_name("rhs"),
+                            node.child_by_field_name("lhs"),
+                        ),
+                        _ => continue,
+                    };
+                    let Some(target) = target.filter(|n| n.kind() == "identifier") else {
+                        continue;
+                    };
+                    let target_name = get_node_text(&target, source.as_bytes());
+                    // Dot-prefixed names are private by convention
+                    if target_name.starts_with('.') {
+                        continue;
+                    }
+                    match value.filter(|n| n.kind() == "function_definition") {
+                        Some(function) => definitions.push(Definition::Func(Func {
+                            name: target_name,
+                            params: if options.signatures {
+                                r_function_params(&function, source.as_bytes())
+                            } else {
+                                String::new()
+                            },
+                            return_type: String::new(),
+                            accessibility_modifier: None,
+                            start_byte: node.start_byte(),
+                            calls: collect_calls(&function, language, source.as_bytes(), options),
+                            references: collect_identifiers(&function, source.as_bytes(), options),
+                            returns_self: false,
+                            ..Default::default()
+                        })),
+                        None => definitions.push(Definition::Variable(Variable {
+                            name: target_name,
+                            value_type: String::new(),
+                            start_byte: node.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        })),
+                    }
+                }
+                "call" => {
+                    if language != "r" {
+                        continue;
+                    }
+                    let callee = node
+                        .child_by_field_name("function")
+                        .map(|n| get_node_text(&n, source.as_bytes()))
+                        .unwrap_or_default();
+                    let arguments = r_call_arguments(&node);
+                    let Some(first) = arguments
+                        .first()
+                        .and_then(|(_, value)| value.as_ref())
+                        .and_then(|value| r_string_value(value, source.as_bytes()))
+                    else {
+                        continue;
+                    };
+                    let function = arguments
+                        .iter()
+                        .filter_map(|(_, value)| value.as_ref())
+                        .find(|value| value.kind() == "function_definition");
+                    let func = function.map(|function| Func {
+                        name: first.clone(),
+                        params: if options.signatures {
+                            r_function_params(function, source.as_bytes())
+                        } else {
+                            String::new()
+                        },
+                        return_type: String::new(),
+                        accessibility_modifier: None,
+                        start_byte: node.start_byte(),
+                        calls: collect_calls(function, language, source.as_bytes(), options),
+                        references: collect_identifiers(function, source.as_bytes(), options),
+                        returns_self: false,
+                        ..Default::default()
+                    });
+                    match callee.as_str() {
+                        "setClass" | "setRefClass" => {
+                            ensure_class_def(
+                                language,
+                                &first,
+                                node.start_byte(),
+                                &mut class_def_map,
+                            );
+                            let class_def = class_def_map.get_mut(&first).unwrap();
+                            class_def
+                                .borrow_mut()
+                                .properties
+                                .extend(r_class_slots(&node, source.as_bytes()));
+                        }
+                        "setGeneric" => definitions.extend(func.map(Definition::Func)),
+                        // `setMethod("greet", "Person", function(obj) ...)` belongs to `Person`
+                        "setMethod" => {
+                            let Some(class_name) = arguments
+                                .get(1)
+                                .and_then(|(_, value)| value.as_ref())
+                                .and_then(|value| r_string_value(value, source.as_bytes()))
+                            else {
+                                continue;
+                            };
+                            let Some(func) = func else {
+                                continue;
+                            };
+                            ensure_class_def(
+                                language,
+                                &class_name,
+                                node.start_byte(),
+                                &mut class_def_map,
+                            );
+                            let class_def = class_def_map.get_mut(&class_name).unwrap();
+                            class_def.borrow_mut().methods.push(func);
+                        }
+                        _ => {}
+                    }
+                }
+                "declaration" => {
+                    if language != "bash" {
+                        continue;
+                    }
+                    // Only `export FOO=bar` and `declare -x FOO` are visible to other scripts
+                    let text = get_node_text(&node, source.as_bytes());
+                    let mut words = text.split_whitespace();
+                    let is_exported = match words.next() {
+                        Some("export") => true,
+                        Some("declare" | "typeset") => words
+                            .take_while(|word| word.starts_with('-'))
+                            .any(|word| word.contains('x')),
+                        _ => false,
+                    };
+                    if public_only && !is_exported {
+                        continue;
+                    }
+                    for child in node.named_children(&mut node.walk()) {
+                        let name_node = match child.kind() {
+                            "variable_assignment" => child.child_by_field_name("name"),
+                            "variable_name" => Some(child),
+                            _ => None,
+                        };
+                        if let Some(name_node) = name_node {
+                            definitions.push(Definition::Variable(Variable {
+                                name: get_node_text(&name_node, source.as_bytes()),
+                                value_type: String::new(),
+                                start_byte: child.start_byte(),
+                                value: None,
+                                ..Default::default()
+                            }));
+                        }
+                    }
+                }
+                "block" => {
+                    if language != "hcl" || name.is_empty() {
+                        continue;
+                    }
+                    let block_type = find_child_by_type(&node, "identifier")
+                        .map(|n| get_node_text(&n, source.as_bytes()))
+                        .unwrap_or_default();
+                    match block_type.as_str() {
+                        "resource" | "module" | "data" => {
+                            ensure_class_def(
+                                language,
+                                &name,
+                                node.start_byte(),
+                                &mut class_def_map,
+                            );
+                            let mut class_def = class_def_map.get_mut(&name).unwrap().borrow_mut();
+                            class_def.type_name = block_type;
+                            class_def
+                                .properties
+                                .extend(hcl_attributes(&node, source.as_bytes()));
+                        }
+                        // Named the way the rest of the configuration refers to them
+                        "variable" => definitions.push(Definition::Variable(Variable {
+                            name: format!("var.{name}"),
+                            value_type: if options.signatures {
+                                hcl_attribute_value(&node, "type", source.as_bytes())
+                                    .unwrap_or_default()
+                            } else {
+                                String::new()
+                            },
+                            start_byte: node.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        })),
+                        "output" => definitions.push(Definition::Variable(Variable {
+                            name: format!("output.{name}"),
+                            value_type: String::new(),
+                            start_byte: node.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        })),
+                        _ => {}
+                    }
+                }
+                "let_binding" => {
+                    if language != "ocaml" {
+                        continue;
+                    }
+                    // Only plain names, `let () = ...` and destructuring patterns are skipped
+                    let Some(pattern) = node
+                        .child_by_field_name("pattern")
+                        .filter(|n| n.kind() == "value_name")
+                    else {
+                        continue;
+                    };
+                    let name = get_node_text(&pattern, source.as_bytes());
+                    let module_path = ocaml_module_path(&node, source.as_bytes());
+                    let parameters = ocaml_parameters(&node, source.as_bytes());
+                    let value_type = if options.signatures {
+                        type_after_colon(&node, source.as_bytes()).unwrap_or_default()
+                    } else {
+                        String::new()
+                    };
+                    if !module_path.is_empty() {
+                        ensure_module_def(&module_path, node.start_byte(), &mut class_def_map);
+                    }
+                    if parameters.is_empty() {
+                        let variable = Variable {
+                            name,
+                            value_type,
+                            start_byte: node.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        };
+                        if module_path.is_empty() {
+                            definitions.push(Definition::Variable(variable));
+                        } else {
+                            let class_def = class_def_map.get_mut(&module_path).unwrap();
+                            class_def.borrow_mut().properties.push(variable);
+                        }
+                        continue;
+                    }
+                    let func = Func {
+                        name,
+                        params: if options.signatures {
+                            format!("({})", parameters.join(", "))
+                        } else {
+                            String::new()
+                        },
+                        return_type: value_type,
+                        accessibility_modifier: None,
+                        start_byte: node.start_byte(),
+                        calls: collect_calls(&node, language, source.as_bytes(), options),
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        ..Default::default()
+                    };
+                    if module_path.is_empty() {
+                        definitions.push(Definition::Func(func));
+                    } else {
+                        let class_def = class_def_map.get_mut(&module_path).unwrap();
+                        class_def.borrow_mut().methods.push(func);
+                    }
+                }
+                "enum" => {
+                    if name.is_empty() {
+                        continue;
+                    }
+                    let items = match language {
+                        "haskell" => haskell_constructors(&node, source.as_bytes()),
+                        "graphql" => find_descendants_by_type(&node, "enum_value")
+                            .iter()
+                            .map(|value| Variable {
+                                name: get_node_text(value, source.as_bytes()),
+                                value_type: String::new(),
+                                start_byte: value.start_byte(),
+                                value: None,
+                                ..Default::default()
+                            })
+                            .collect(),
+                        "proto" => find_descendants_by_type(&node, "enum_field")
+                            .iter()
+                            .filter_map(|field| {
+                                let name_node = find_child_by_type(field, "identifier")?;
+                                Some(Variable {
+                                    name: get_node_text(&name_node, source.as_bytes()),
+                                    value_type: String::new(),
+                                    start_byte: field.start_byte(),
+                                    value: None,
+                                    ..Default::default()
+                                })
+                            })
+                            .collect(),
+                        "dart" if !public_only || !dart_is_private(&name) => {
+                            find_descendants_by_type(&node, "enum_constant")
+                                .iter()
+                                .filter_map(|constant| {
+                                    let name_node = find_child_by_type(constant, "identifier")?;
+                                    Some(Variable {
+                                        name: get_node_text(&name_node, source.as_bytes()),
+                                        value_type: String::new(),
+                                        start_byte: constant.start_byte(),
+                                        value: None,
+                                        ..Default::default()
+                                    })
+                                })
+                                .collect()
+                        }
+                        _ => continue,
+                    };
+                    ensure_enum_def(&name, node.start_byte(), &mut enum_def_map);
+                    let enum_def = enum_def_map.get_mut(&name).unwrap();
+                    enum_def.borrow_mut().items.extend(items);
+                }
+                "enum_item" => {
+                    let visibility_modifier_node =
+                        find_descendant_by_type(&node, "visibility_modifier");
+                    let visibility_modifier = visibility_modifier_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+                    if public_only && language == "rust" && !visibility_modifier.contains("pub") {
+                        continue;
+                    }
+                    if language == "zig"
+                        && !zig_is_variable_declaration_public(&node, source.as_bytes())
+                    {
+                        continue;
+                    }
+                    let mut enum_name = get_closest_ancestor_name(&node, source);
+                    if language == "zig" {
+                        enum_name =
+                            zig_find_parent_variable_declaration_name(&node, source.as_bytes())
+                                .unwrap_or_default();
+                    }
+                    if language == "scala" {
+                        if let Some(enum_node) = find_ancestor_by_type(&node, "enum_definition") {
+                            if let Some(name_node) = enum_node.child_by_field_name("name") {
+                                enum_name = name_node
+                                    .utf8_text(source.as_bytes())
+                                    .unwrap_or_default()
+                                    .to_string();
+                            }
+                        }
+                    }
+                    if !enum_name.is_empty()
+                        && language == "go"
+                        && !is_first_letter_uppercase(&enum_name)
+                    {
+                        continue;
+                    }
+                    ensure_enum_def(&enum_name, node.start_byte(), &mut enum_def_map);
+                    let enum_def = enum_def_map.get_mut(&enum_name).unwrap();
+                    let enum_type_node = find_descendant_by_type(&node, "type_identifier");
+                    let enum_type = enum_type_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+                    let variable = Variable {
+                        name: name.clone(),
+                        value_type: enum_type.to_string(),
+                        start_byte: node.start_byte(),
+                        value: None,
+                        ..Default::default()
+                    };
+                    enum_def.borrow_mut().items.push(variable);
+                }
+                "union_item" => {
+                    if language != "zig" {
+                        continue;
+                    }
+                    if !zig_is_variable_declaration_public(&node, source.as_bytes()) {
+                        continue;
+                    }
+                    let union_name =
+                        zig_find_parent_variable_declaration_name(&node, source.as_bytes())
+                            .unwrap_or_default();
+                    ensure_union_def(&union_name, node.start_byte(), &mut union_def_map);
+                    let union_def = union_def_map.get_mut(&union_name).unwrap();
+                    let union_type_node = find_descendant_by_type(&node, "type_identifier");
+                    let union_type = union_type_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+                    let variable = Variable {
+                        name: name.clone(),
+                        value_type: union_type.to_string(),
+                        start_byte: node.start_byte(),
+                        value: None,
+                        ..Default::default()
+                    };
+                    union_def.borrow_mut().items.push(variable);
+                }
+                "method" => {
+                    // TODO: C++: Skip private/protected class/struct methods
+                    let visibility_modifier_node =
+                        find_descendant_by_type(&node, "visibility_modifier");
+                    let visibility_modifier = visibility_modifier_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+                    if language == "swift" {
+                        if public_only && visibility_modifier.contains("private") {
+                            continue;
+                        }
+                    }
+                    if language == "java" {
+                        if let Some(modifier_node) = find_descendant_by_type(&node, "modifiers") {
+                            let modifier_text = modifier_node
+                                .utf8_text(source.as_bytes())
+                                .unwrap_or_default();
+                            if public_only && modifier_text.contains("private") {
+                                continue;
+                            }
+                        }
+                    }
+                    if public_only && language == "rust" && !visibility_modifier.contains("pub") {
+                        continue;
+                    }
+                    if language == "zig"
+                        && !(zig_is_function_declaration_public(&node, source.as_bytes())
+                            && zig_is_variable_declaration_public(&node, source.as_bytes()))
+                    {
+                        continue;
+                    }
+                    if language == "cpp"
+                        && find_descendant_by_type(&node, "destructor_name").is_some()
+                    {
+                        continue;
+                    }
+                    if public_only
+                        && language == "kotlin"
+                        && kotlin_is_private(&node, source.as_bytes())
+                    {
+                        continue;
+                    }
+                    if public_only && language == "dart" && dart_is_private(&name) {
+                        continue;
+                    }
+
+                    if public_only
+                        && !name.is_empty()
+                        && language == "go"
+                        && !is_first_letter_uppercase(&name)
+                    {
+                        continue;
+                    }
+
+                    if language == "csharp" {
+                        let csharp_visibility = find_descendant_by_type(&node, "modifier");
+                        if csharp_visibility.is_none() && !csharp_is_primary_constructor(&node) {
+                            continue;
+                        }
+                        if let Some(csharp_visibility) = csharp_visibility {
+                            let csharp_visibility_text = csharp_visibility
+                                .utf8_text(source.as_bytes())
+                                .unwrap_or_default();
+                            if public_only && csharp_visibility_text == "private" {
+                                continue;
+                            }
+                        }
+                    }
+
+                    let (params, mut return_type) = if options.signatures {
+                        get_method_signature(&node, language, &name, source.as_bytes())
+                    } else {
+                        (String::new(), String::new())
+                    };
+                    if language == "csharp" && options.signatures {
+                        if let Some(constraints) = csharp_type_constraints(&node, source.as_bytes())
+                        {
+                            return_type = format!("{return_type} {constraints}");
+                        }
+                    }
+
+                    let impl_item_node = find_ancestor_by_type(&node, "impl_item");
+                    let receiver_node = node.child_by_field_name("receiver");
+                    let class_name = if language == "zig" {
+                        zig_find_parent_variable_declaration_name(&node, source.as_bytes())
+                            .unwrap_or_default()
+                    } else if language == "elixir" {
+                        ex_find_parent_module_declaration_name(&node, source.as_bytes())
+                            .unwrap_or_default()
+                    } else if language == "cpp" {
+                        find_ancestor_by_type(&node, "class_specifier")
+                            .or_else(|| find_ancestor_by_type(&node, "struct_specifier"))
+                            .and_then(|n| n.child_by_field_name("name"))
+                            .and_then(|n| n.utf8_text(source.as_bytes()).ok())
+                            .unwrap_or("")
+                            .to_string()
+                    } else if language == "csharp" {
+                        csharp_find_parent_type_node(&node)
+                            .and_then(|n| n.child_by_field_name("name"))
+                            .and_then(|n| n.utf8_text(source.as_bytes()).ok())
+                            .unwrap_or("")
+                            .to_string()
+                    } else if language == "ruby" {
+                        ruby_find_parent_module_declaration_name(&node, source.as_bytes())
+                            .unwrap_or_default()
+                    } else if language == "kotlin" {
+                        kotlin_find_parent_class_name(&node, source.as_bytes()).unwrap_or_default()
+                    } else if language == "dart" {
+                        dart_find_parent_class_name(&node, source.as_bytes()).unwrap_or_default()
+                    } else if let Some(impl_item) = impl_item_node {
+                        // `impl<T> Foo<T>` adds its methods to `Foo`, the generics are kept apart
+                        let impl_type_node = impl_item.child_by_field_name("type").map(|n| {
+                            if n.kind() == "generic_type" {
+                                n.child_by_field_name("type").unwrap_or(n)
+                            } else {
+                                n
+                            }
+                        });
+                        impl_type_node
+                            .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                            .unwrap_or("")
+                            .to_string()
+                    } else if let Some(receiver) = receiver_node {
+                        let type_identifier_node =
+                            find_descendant_by_type(&receiver, "type_identifier");
+                        type_identifier_node
+                            .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                            .unwrap_or("")
+                            .to_string()
+                    } else {
+                        get_closest_ancestor_name(&node, source).clone()
+                    };
+
+                    if public_only && language == "go" && !is_first_letter_uppercase(&class_name) {
+                        continue;
+                    }
+
+                    ensure_class_def(language, &class_name, node.start_byte(), &mut class_def_map);
+                    let class_def = class_def_map.get_mut(&class_name).unwrap();
+                    // Only inherent impls speak for the type, `impl<T: Debug> Display for Foo<T>`
+                    // constrains the trait implementation alone
+                    if let Some(type_parameters) = impl_item_node
+                        .filter(|n| options.signatures && n.child_by_field_name("trait").is_none())
+                        .and_then(|n| n.child_by_field_name("type_parameters"))
+                    {
+                        let mut class_def = class_def.borrow_mut();
+                        class_def.generics = Some(match class_def.generics.as_deref() {
+                            Some(generics) => {
+                                merge_impl_bounds(generics, &type_parameters, source.as_bytes())
+                            }
+                            None => get_params_text(&type_parameters, source.as_bytes()),
+                        });
+                    } else if class_def.borrow().generics.is_none() {
+                        class_def.borrow_mut().generics =
+                            get_class_type_parameters(&node, language, source.as_bytes(), options);
+                    }
+
+                    let accessibility_modifier_node =
+                        find_descendant_by_type(&node, "accessibility_modifier");
+                    let accessibility_modifier = if language == "ruby" {
+                        if ruby_method_is_private(&node, source.as_bytes()) {
+                            "private"
+                        } else {
+                            ""
+                        }
+                    } else {
+                        accessibility_modifier_node
+                            .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                            .unwrap_or("")
+                    };
+
+                    let calls = collect_calls(&node, language, source.as_bytes(), options);
+                    let func = Func {
+                        name: name.clone(),
+                        params,
+                        return_type,
+                        accessibility_modifier: if accessibility_modifier.is_empty() {
+                            None
+                        } else {
+                            Some(accessibility_modifier.to_string())
+                        },
+                        start_byte: node.start_byte(),
+                        calls,
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        generics: get_type_parameters(&node, language, source.as_bytes(), options),
+                        ..Default::default()
+                    };
+                    class_def.borrow_mut().methods.push(func);
+                }
+                "class_assignment" => {
+                    let visibility_modifier_node =
+                        find_descendant_by_type(&node, "visibility_modifier");
+                    let visibility_modifier = visibility_modifier_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+                    if language == "swift" || language == "java" {
+                        if public_only && visibility_modifier.contains("private") {
+                            continue;
+                        }
+                    }
+                    if language == "java" {
+                        if let Some(modifier_node) = find_descendant_by_type(&node, "modifiers") {
+                            let modifier_text = modifier_node
+                                .utf8_text(source.as_bytes())
+                                .unwrap_or_default();
+                            if public_only && modifier_text.contains("private") {
+                                continue;
+                            }
+                        }
+                    }
+                    if public_only && language == "rust" && !visibility_modifier.contains("pub") {
+                        continue;
+                    }
+                    let left_node = node.child_by_field_name("left");
+                    let left = left_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+                    let value_type = get_variable_type(&node, source.as_bytes(), options);
+                    let mut class_name = get_closest_ancestor_name(&node, source);
+                    if !class_name.is_empty() {
+                        if language == "ruby" {
+                            if let Some(namespaced_name) =
+                                ruby_find_parent_module_declaration_name(&node, source.as_bytes())
+                            {
+                                class_name = namespaced_name;
+                            }
+                        } else if public_only
+                            && language == "go"
+                            && !is_first_letter_uppercase(&class_name)
+                        {
+                            continue;
+                        }
+                    }
+                    if class_name.is_empty() {
+                        continue;
+                    }
+                    ensure_class_def(language, &class_name, node.start_byte(), &mut class_def_map);
+                    let class_def = class_def_map.get_mut(&class_name).unwrap();
+                    let variable = Variable {
+                        name: left.to_string(),
+                        value_type: value_type.clone(),
+                        start_byte: node.start_byte(),
+                        value: None,
+                        ..Default::default()
+                    };
+                    class_def.borrow_mut().properties.push(variable);
+                }
+                "class_variable" => {
+                    // TODO: C++: Skip private/protected class/struct variables
+                    let visibility_modifier_node =
+                        find_descendant_by_type(&node, "visibility_modifier");
+                    let visibility_modifier = visibility_modifier_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+                    if public_only && language == "rust" && !visibility_modifier.contains("pub") {
+                        continue;
+                    }
+
+                    if language == "swift" || language == "java" {
+                        if public_only && visibility_modifier.contains("private") {
+                            continue;
+                        }
+                    }
+
+                    if language == "java" {
+                        if let Some(modifier_node) = find_descendant_by_type(&node, "modifiers") {
+                            let modifier_text = modifier_node
+                                .utf8_text(source.as_bytes())
+                                .unwrap_or_default();
+                            if public_only && modifier_text.contains("private") {
+                                continue;
+                            }
+                        }
+                    }
+
+                    if public_only
+                        && language == "kotlin"
+                        && kotlin_is_private(&node, source.as_bytes())
+                    {
+                        continue;
+                    }
+
+                    let mut value_type = get_variable_type(&node, source.as_bytes(), options);
+                    if language == "kotlin" {
+                        value_type = kotlin_variable_type(&node, source.as_bytes(), options);
+                    }
+                    if language == "dart" {
+                        if public_only && dart_is_private(&name) {
+                            continue;
+                        }
+                        value_type = dart_field_type(&node, source.as_bytes(), options);
+                    }
+
+                    if language == "zig" {
+                        // when top level class is not public, skip
+                        if !zig_is_variable_declaration_public(&node, source.as_bytes()) {
+                            continue;
+                        }
+                    }
+
+                    let mut class_name = get_closest_ancestor_name(&node, source);
+                    if language == "cpp" {
+                        class_name = find_ancestor_by_type(&node, "class_specifier")
+                            .or_else(|| find_ancestor_by_type(&node, "struct_specifier"))
+                            .and_then(|n| n.child_by_field_name("name"))
+                            .and_then(|n| n.utf8_text(source.as_bytes()).ok())
+                            .unwrap_or("")
+                            .to_string();
+                    }
+                    if language == "kotlin" {
+                        class_name = kotlin_find_parent_class_name(&node, source.as_bytes())
+                            .unwrap_or_default();
+                    }
+                    if language == "dart" {
+                        class_name = dart_find_parent_class_name(&node, source.as_bytes())
+                            .unwrap_or_default();
+                    }
+
+                    if language == "csharp" {
+                        let csharp_visibility = find_descendant_by_type(&node, "modifier");
+                        if csharp_visibility.is_none() {
+                            continue;
+                        }
+                        let csharp_visibility_text = csharp_visibility
+                            .unwrap()
+                            .utf8_text(source.as_bytes())
+                            .unwrap_or_default();
+                        if public_only && csharp_visibility_text == "private" {
+                            continue;
+                        }
+                    }
+
+                    if language == "zig" {
+                        class_name =
+                            zig_find_parent_variable_declaration_name(&node, source.as_bytes())
+                                .unwrap_or_default();
+                    }
+                    if !class_name.is_empty()
+                        && language == "go"
+                        && public_only
+                        && !is_first_letter_uppercase(&class_name)
+                    {
+                        continue;
+                    }
+                    if class_name.is_empty() {
+                        continue;
+                    }
+                    if public_only
+                        && !name.is_empty()
+                        && language == "go"
+                        && !is_first_letter_uppercase(&name)
+                    {
+                        continue;
+                    }
+                    ensure_class_def(language, &class_name, node.start_byte(), &mut class_def_map);
+                    let class_def = class_def_map.get_mut(&class_name).unwrap();
+                    if class_def.borrow().generics.is_none() {
+                        class_def.borrow_mut().generics =
+                            get_class_type_parameters(&node, language, source.as_bytes(), options);
+                    }
+                    let variable = Variable {
+                        name: name.clone(),
+                        value_type: value_type.clone(),
+                        start_byte: node.start_byte(),
+                        value: None,
+                        attributes: get_field_attributes(&node, source.as_bytes(), options),
+                        ..Default::default()
+                    };
+                    class_def.borrow_mut().properties.push(variable);
+                }
+                "function" | "arrow_function" => {
+                    let visibility_modifier_node =
+                        find_descendant_by_type(&node, "visibility_modifier");
+                    let visibility_modifier = visibility_modifier_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+
+                    if language == "swift" || language == "java" {
+                        if public_only && visibility_modifier.contains("private") {
+                            continue;
+                        }
+
+                        if node.parent().is_some() {
+                            continue;
+                        }
+                    }
+
+                    if language == "java" {
+                        if let Some(modifier_node) = find_descendant_by_type(&node, "modifiers") {
+                            let modifier_text = modifier_node
+                                .utf8_text(source.as_bytes())
+                                .unwrap_or_default();
+                            if public_only && modifier_text.contains("private") {
+                                continue;
+                            }
+                        }
+                    }
+
+                    if public_only && language == "rust" && !visibility_modifier.contains("pub") {
+                        continue;
+                    }
+
+                    if public_only
+                        && language == "kotlin"
+                        && kotlin_is_private(&node, source.as_bytes())
+                    {
+                        continue;
+                    }
+
+                    if public_only && language == "dart" && dart_is_private(&name) {
+                        continue;
+                    }
+
+                    if language == "haskell"
+                        && node.kind() == "function"
+                        && (haskell_is_typed_function(&node, &name, source.as_bytes())
+                            || definitions.iter().any(|definition| {
+                                matches!(definition, Definition::Func(func) if func.name == name)
+                            }))
+                    {
+                        continue;
+                    }
+
+                    if language == "zig" {
+                        let variable_declaration_text =
+                            node.utf8_text(source.as_bytes()).unwrap_or("");
+                        if public_only && !variable_declaration_text.contains("pub") {
+                            continue;
+                        }
+                    }
+
+                    if public_only
+                        && !name.is_empty()
+                        && language == "go"
+                        && !is_first_letter_uppercase(&name)
+                    {
+                        continue;
+                    }
+                    let impl_item_node = find_ancestor_by_type(&node, "impl_item");
+                    if impl_item_node.is_some() {
+                        continue;
+                    }
+                    let class_specifier_node = find_ancestor_by_type(&node, "class_specifier");
+                    if class_specifier_node.is_some() {
+                        continue;
+                    }
+                    let struct_specifier_node = find_ancestor_by_type(&node, "struct_specifier");
+                    if struct_specifier_node.is_some() {
+                        continue;
+                    }
+                    // Dart's captured signature sits in its own `function_declaration`
+                    let outer_node = if language == "dart" {
+                        node.parent().unwrap_or(node)
+                    } else {
+                        node
+                    };
+                    let function_node = find_ancestor_by_type(&outer_node, "function_declaration")
+                        .or_else(|| find_ancestor_by_type(&outer_node, "function_definition"));
+                    if function_node.is_some() {
+                        continue;
+                    }
+                    let (params, return_type) = if options.signatures {
+                        get_function_signature(&node, language, source.as_bytes())
+                    } else {
+                        (String::new(), String::new())
+                    };
+
+                    let accessibility_modifier_node =
+                        find_descendant_by_type(&node, "accessibility_modifier");
+                    let accessibility_modifier = accessibility_modifier_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+
+                    let calls = collect_calls(&node, language, source.as_bytes(), options);
+                    let func = Func {
+                        name: name.clone(),
+                        params,
+                        return_type,
+                        accessibility_modifier: if accessibility_modifier.is_empty() {
+                            None
+                        } else {
+                            Some(accessibility_modifier.to_string())
+                        },
+                        start_byte: node.start_byte(),
+                        calls,
+                        references: collect_identifiers(&node, source.as_bytes(), options),
+                        returns_self: false,
+                        generics: get_type_parameters(&node, language, source.as_bytes(), options),
+                        ..Default::default()
+                    };
+                    definitions.push(Definition::Func(func));
+                }
+                "assignment" => {
+                    let visibility_modifier_node =
+                        find_descendant_by_type(&node, "visibility_modifier");
+                    let visibility_modifier = visibility_modifier_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+                    if language == "swift" || language == "java" {
+                        if public_only && visibility_modifier.contains("private") {
+                            continue;
+                        }
+                    }
+                    if language == "java" {
+                        if let Some(modifier_node) = find_descendant_by_type(&node, "modifiers") {
+                            let modifier_text = modifier_node
+                                .utf8_text(source.as_bytes())
+                                .unwrap_or_default();
+                            if public_only && modifier_text.contains("private") {
+                                continue;
+                            }
+                        }
+                    }
+                    if public_only && language == "rust" && !visibility_modifier.contains("pub") {
+                        continue;
+                    }
+                    let impl_item_node = find_ancestor_by_type(&node, "impl_item")
+                        .or_else(|| find_ancestor_by_type(&node, "class_declaration"))
+                        .or_else(|| find_ancestor_by_type(&node, "class_definition"));
+                    if impl_item_node.is_some() {
+                        continue;
+                    }
+                    let function_node = find_ancestor_by_type(&node, "function_declaration")
+                        .or_else(|| find_ancestor_by_type(&node, "function_definition"));
+                    if function_node.is_some() {
+                        continue;
+                    }
+                    let left_node = node.child_by_field_name("left");
+                    let left = left_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+                    if public_only
+                        && !left.is_empty()
+                        && language == "go"
+                        && !is_first_letter_uppercase(left)
+                    {
+                        continue;
+                    }
+
+                    if let Some(lambda) = node
+                        .child_by_field_name("right")
+                        .filter(|n| n.kind() == "lambda")
+                    {
+                        if !options.include_anonymous {
+                            continue;
+                        }
+                        let params = lambda.child_by_field_name("parameters").map_or_else(
+                            || "()".to_string(),
+                            |n| format!("({})", get_params_text(&n, source.as_bytes())),
+                        );
+                        definitions.push(Definition::Func(Func {
+                            name: left.to_string(),
+                            params,
+                            return_type: "void".to_string(),
+                            accessibility_modifier: None,
+                            start_byte: node.start_byte(),
+                            calls: collect_calls(&node, language, source.as_bytes(), options),
+                            references: collect_identifiers(&node, source.as_bytes(), options),
+                            returns_self: false,
+                            ..Default::default()
+                        }));
+                        continue;
+                    }
+
+                    let value_type = get_variable_type(&node, source.as_bytes(), options);
+                    let variable = Variable {
+                        name: left.to_string(),
+                        value_type: value_type.clone(),
+                        start_byte: node.start_byte(),
+                        value: None,
+                        ..Default::default()
+                    };
+                    definitions.push(Definition::Variable(variable));
+                }
+                "variable" => {
+                    let visibility_modifier_node =
+                        find_descendant_by_type(&node, "visibility_modifier");
+                    let visibility_modifier = visibility_modifier_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+
+                    if language == "swift" {
+                        if public_only && visibility_modifier.contains("private") {
+                            continue;
+                        }
+                    }
+
+                    if language == "java" {
+                        if let Some(modifier_node) = find_descendant_by_type(&node, "modifiers") {
+                            let modifier_text = modifier_node
+                                .utf8_text(source.as_bytes())
+                                .unwrap_or_default();
+                            if public_only && modifier_text.contains("private") {
+                                continue;
+                            }
+                        }
+                    }
+
+                    if public_only && language == "rust" && !visibility_modifier.contains("pub") {
+                        continue;
+                    }
+
+                    if language == "zig"
+                        && !zig_is_variable_declaration_public(&node, source.as_bytes())
+                    {
+                        continue;
+                    }
+
+                    if public_only
+                        && language == "kotlin"
+                        && kotlin_is_private(&node, source.as_bytes())
+                    {
+                        continue;
+                    }
+
+                    let impl_item_node = find_ancestor_by_type(&node, "impl_item")
+                        .or_else(|| find_ancestor_by_type(&node, "class_declaration"))
+                        .or_else(|| find_ancestor_by_type(&node, "class_definition"));
+                    if impl_item_node.is_some() {
+                        continue;
+                    }
+                    let function_node = find_ancestor_by_type(&node, "function_declaration")
+                        .or_else(|| find_ancestor_by_type(&node, "function_definition"));
+                    if function_node.is_some() {
+                        continue;
+                    }
+                    let value_node = node.child_by_field_name("value");
+                    if let Some(value) = value_node {
+                        if value.kind() == "arrow_function" {
+                            if !options.include_anonymous {
+                                continue;
+                            }
+                            let params_node = value.child_by_field_name("parameters");
+                            let params = params_node.map_or_else(
+                                || "()".to_string(),
+                                |n| get_params_text(&n, source.as_bytes()),
+                            );
+                            let return_type = value.child_by_field_name("return_type").map_or_else(
+                                || "void".to_string(),
+                                |n| get_node_type(&n, source.as_bytes()),
+                            );
+                            let func = Func {
+                                name: name.clone(),
+                                params,
+                                return_type,
+                                accessibility_modifier: None,
+                                start_byte: node.start_byte(),
+                                calls: collect_calls(&node, language, source.as_bytes(), options),
+                                references: collect_identifiers(&node, source.as_bytes(), options),
+                                returns_self: false,
+                                ..Default::default()
+                            };
+                            definitions.push(Definition::Func(func));
+                            continue;
+                        }
+                    }
+                    if let Some(tag) =
+                        value_node.and_then(|n| js_template_tag(&n, source.as_bytes()))
+                    {
+                        definitions.push(Definition::Variable(Variable {
+                            name: name.clone(),
+                            value_type: tag,
+                            start_byte: node.start_byte(),
+                            value: None,
+                            ..Default::default()
+                        }));
+                        continue;
+                    }
+
+                    let mut value_type = get_variable_type(&node, source.as_bytes(), options);
+                    if language == "kotlin" {
+                        value_type = kotlin_variable_type(&node, source.as_bytes(), options);
+                    }
+                    if language == "zig" {
+                        if let Some(zig_type) = zig_find_type_in_parent(&node, source.as_bytes()) {
+                            value_type = zig_type;
+                        } else {
+                            continue;
+                        }
+                    }
+                    if public_only
+                        && !name.is_empty()
+                        && language == "go"
+                        && !is_first_letter_uppercase(&name)
+                    {
+                        continue;
+                    }
+                    let variable = Variable {
+                        name: name.clone(),
+                        value_type: value_type.clone(),
+                        start_byte: node.start_byte(),
+                        value: if language == "rust" && options.signatures {
+                            rust_const_value(&node, source.as_bytes())
+                        } else {
+                            None
+                        },
+                        ..Default::default()
+                    };
+                    definitions.push(Definition::Variable(variable));
+                }
+                "styled_component" => {
+                    if !matches!(language, "javascript" | "typescript") || name.is_empty() {
+                        continue;
+                    }
+                    let Some(tag) = node
+                        .child_by_field_name("value")
+                        .and_then(|n| js_template_tag(&n, source.as_bytes()))
+                    else {
+                        continue;
+                    };
+                    definitions.push(Definition::Variable(Variable {
+                        name,
+                        value_type: tag,
+                        start_byte: node.start_byte(),
+                        value: None,
+                        ..Default::default()
+                    }));
+                }
+                "import" if language == "zig" => {
+                    // `const std = @import("std");`, kept whether public or not
+                    let name = zig_find_parent_variable_declaration_name(&node, source.as_bytes())
+                        .unwrap_or_default();
+                    let path = find_descendant_by_type(&node, "string_content")
+                        .map(|n| get_node_text(&n, source.as_bytes()))
+                        .unwrap_or_default();
+                    if name.is_empty() || path.is_empty() {
+                        continue;
+                    }
+                    definitions.push(Definition::Import(Import {
+                        name,
+                        path,
+                        glob: false,
+                        prelude: false,
+                    }));
+                }
+                "import" if language == "rust" => {
+                    let visibility_modifier_node = find_child_by_type(&node, "visibility_modifier");
+                    let visibility_modifier = visibility_modifier_node
+                        .map(|n| n.utf8_text(source.as_bytes()).unwrap_or_default())
+                        .unwrap_or("");
+                    if !visibility_modifier.contains("pub") {
+                        continue;
+                    }
+                    if find_ancestor_by_type(&node, "function_item").is_some() {
+                        continue;
+                    }
+                    let Some(argument) = node.child_by_field_name("argument") else {
+                        continue;
+                    };
+                    let in_prelude = find_ancestor_by_type(&node, "mod_item")
+                        .and_then(|n| n.child_by_field_name("name"))
+                        .is_some_and(|n| get_node_text(&n, source.as_bytes()) == "prelude");
+                    let mut imports = Vec::new();
+                    rust_collect_use_paths(&argument, "", source.as_bytes(), &mut imports);
+                    for mut import in imports {
+                        import.prelude = in_prelude;
+                        definitions.push(Definition::Import(import));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    namespaced_defs.insert(current_namespace, (class_def_map, enum_def_map));
+    let (class_def_maps, enum_def_maps): (Vec<_>, Vec<_>) = namespaced_defs.into_values().unzip();
+    for (_, def) in class_def_maps.into_iter().flatten() {
+        let class_def = def.into_inner();
+        if matches!(language, "ocaml" | "julia" | "perl" | "clojure" | "fsharp")
+            && class_def.type_name == "module"
+        {
+            definitions.push(Definition::Module(class_def));
+        } else if language == "rust" {
+            let is_public = class_def
+                .visibility_modifier
+                .as_ref()
+                .is_some_and(|visibility_modifier| visibility_modifier.contains("pub"));
+            if is_public || !public_only {
+                definitions.push(Definition::Class(class_def));
+            }
+        } else {
+            definitions.push(Definition::Class(class_def));
+        }
+    }
+
+    for (_, def) in enum_def_maps.into_iter().flatten() {
+        definitions.push(Definition::Enum(def.into_inner()));
+    }
+    for (_, def) in union_def_map {
+        definitions.push(Definition::Union(def.into_inner()));
+    }
+
+    let mut tests = collect_tests(language, &root_node, source.as_bytes());
+    let is_test = |func: &Func| tests.iter().any(|test| test.start_byte == func.start_byte);
+    definitions.retain(|definition| !matches!(definition, Definition::Func(func) if is_test(func)));
+    for definition in &mut definitions {
+        if let Definition::Class(class) | Definition::Module(class) = definition {
+            class.methods.retain(|method| !is_test(method));
+        }
+    }
+    resolve_references(&mut definitions);
+    mark_builder_methods(&mut definitions);
+    locate_definitions(&mut definitions, &mut tests, &spans, &root_node);
+    if options.small_body_lines > 0 {
+        attach_small_bodies(&mut definitions, source, options.small_body_lines);
+    }
+    if options.include_docs {
+        attach_docs(&mut definitions, language, source.as_bytes(), &spans);
+    }
+    if language == "cpp" {
+        merge_out_of_line_members(&mut definitions, &namespaces);
+    }
+    let definitions = nest_definitions(definitions, &namespaces);
+
+    Ok((definitions, tests))
+}
+
+// Where a definition starts, `None` for imports which keep no position
+fn definition_start_byte(definition: &Definition) -> Option<usize> {
+    match definition {
+        Definition::Func(func) => Some(func.start_byte),
+        Definition::Class(class) | Definition::Module(class) => Some(class.start_byte),
+        Definition::Enum(enum_def) => Some(enum_def.start_byte),
+        Definition::Union(union_def) => Some(union_def.start_byte),
+        Definition::Variable(variable) => Some(variable.start_byte),
+        Definition::Import(_) => None,
+        Definition::Namespace { children, .. } => {
+            children.iter().filter_map(definition_start_byte).min()
+        }
+    }
+}
+
+// Named namespaces as `(name, start_byte, end_byte)`, outer ones before the ones they contain.
+// A namespace without a body, e.g. C#'s `namespace Foo;`, runs until the next one or the end of
+// its parent.
+fn collect_namespaces(language: &str, root: &Node, source: &[u8]) -> Vec<(String, usize, usize)> {
+    let kinds: &[&str] = match language {
+        "cpp" | "php" => &["namespace_definition"],
+        "csharp" => &["namespace_declaration", "file_scoped_namespace_declaration"],
+        _ => return vec![],
+    };
+    let mut namespaces = Vec::new();
+    for kind in kinds {
+        for node in find_descendants_by_type(root, kind) {
+            // C++ anonymous namespaces keep their definitions at the top level
+            let Some(name_node) = node.child_by_field_name("name") else {
+                continue;
+            };
+            let end_byte = if node.child_by_field_name("body").is_some() {
+                node.end_byte()
+            } else {
+                let mut next = node.next_named_sibling();
+                while let Some(sibling) = next.filter(|n| !kinds.contains(&n.kind())) {
+                    next = sibling.next_named_sibling();
+                }
+                next.map(|n| n.start_byte()).unwrap_or_else(|| {
+                    node.parent()
+                        .map_or(node.end_byte(), |parent| parent.end_byte())
+                })
+            };
+            namespaces.push((
+                get_node_text(&name_node, source),
+                node.start_byte(),
+                end_byte,
+            ));
+        }
+    }
+    namespaces.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| b.2.cmp(&a.2)));
+    namespaces
+}
+
+// Names of the namespaces around a byte, outermost first
+fn namespace_path(namespaces: &[(String, usize, usize)], byte: usize) -> String {
+    namespaces
+        .iter()
+        .filter(|(_, start, end)| (*start..*end).contains(&byte))
+        .map(|(name, _, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+// C++ members defined out of line, `bool Shape::area() const { ... }`, belong to the class found
+// from their namespace outwards. They are dropped when the class already declares them and moved
+// into it otherwise, members of classes declared elsewhere are kept as they are.
+fn merge_out_of_line_members(
+    definitions: &mut Vec<Definition>,
+    namespaces: &[(String, usize, usize)],
+) {
+    let qualify = |scope: &str, name: &str| {
+        if scope.is_empty() {
+            name.to_string()
+        } else {
+            format!("{scope}::{name}")
+        }
+    };
+    let class_path = |definition: &Definition| match definition {
+        Definition::Class(class) => Some(qualify(
+            &namespace_path(namespaces, class.start_byte),
+            &class.name,
+        )),
+        _ => None,
+    };
+    let class_paths: Vec<String> = definitions.iter().filter_map(class_path).collect();
+    let mut members = Vec::new();
+    for (index, definition) in definitions.iter().enumerate() {
+        let (name, start_byte) = match definition {
+            Definition::Func(func) => (&func.name, func.start_byte),
+            Definition::Variable(variable) => (&variable.name, variable.start_byte),
+            _ => continue,
+        };
+        let Some((qualifier, member)) = name.rsplit_once("::") else {
+            continue;
+        };
+        // `Box<T>::get` names the class without its template arguments
+        let mut depth = 0;
+        let qualifier: String = qualifier
+            .chars()
+            .filter(|c| match c {
+                '<' => {
+                    depth += 1;
+                    false
+                }
+                '>' => {
+                    depth -= 1;
+                    false
+                }
+                _ => depth == 0,
+            })
+            .collect();
+        let namespace = namespace_path(namespaces, start_byte);
+        let mut scope = namespace.as_str();
+        let target = loop {
+            let candidate = qualify(scope, &qualifier);
+            if class_paths.contains(&candidate) {
+                break Some(candidate);
+            }
+            if scope.is_empty() {
+                break None;
+            }
+            scope = scope.rsplit_once("::").map_or("", |(outer, _)| outer);
+        };
+        if let Some(target) = target {
+            members.push((index, target, member.to_string()));
+        }
+    }
+    for (index, target, member) in members.into_iter().rev() {
+        let mut definition = definitions.remove(index);
+        let Some(Definition::Class(class)) = definitions
+            .iter_mut()
+            .find(|definition| class_path(definition).as_ref() == Some(&target))
+        else {
+            continue;
+        };
+        match &mut definition {
+            Definition::Func(func) if !class.methods.iter().any(|method| method.name == member) => {
+                func.name = member;
+                class.methods.push(std::mem::take(func));
+            }
+            Definition::Variable(variable)
+                if !class
+                    .properties
+                    .iter()
+                    .any(|property| property.name == member) =>
+            {
+                variable.name = member;
+                class.properties.push(std::mem::take(variable));
+            }
+            _ => {}
+        }
+    }
+}
+
+// Moves the definitions starting inside a namespace into it, the namespace takes the place of its
+// first definition
+fn nest_definitions(
+    mut definitions: Vec<Definition>,
+    namespaces: &[(String, usize, usize)],
+) -> Vec<Definition> {
+    if namespaces.is_empty() {
+        return definitions;
+    }
+    // Namespaces are placed where their first member was, which only holds in source order
+    definitions.sort_by_key(definition_start_byte);
+    let mut rest = namespaces;
+    while let Some(((name, start, end), tail)) = rest.split_first() {
+        let (inner, after) = tail.split_at(
+            tail.iter()
+                .take_while(|(_, inner_start, _)| inner_start < end)
+                .count(),
+        );
+        rest = after;
+        let inside = |definition: &Definition| {
+            definition_start_byte(definition).is_some_and(|byte| (*start..*end).contains(&byte))
+        };
+        let Some(position) = definitions.iter().position(inside) else {
+            continue;
+        };
+        let (children, others): (Vec<_>, Vec<_>) = definitions.into_iter().partition(inside);
+        definitions = others;
+        definitions.insert(
+            position,
+            Definition::Namespace {
+                name: name.clone(),
+                children: nest_definitions(children, inner),
+            },
+        );
+    }
+    definitions
+}
+
+fn stringify_function(func: &Func) -> String {
+    let params = if func.params.is_empty() {
+        "()"
+    } else {
+        &func.params
+    };
+    stringify_function_signature(func, params)
+}
+
+// Everything around the parameter list, shared by the one-line and the wrapped form
+fn stringify_function_signature(func: &Func, params: &str) -> String {
+    let mut res = format!(
+        "func {}{}{params}",
+        func.name,
+        func.generics.as_deref().unwrap_or_default()
+    );
+    if !func.return_type.is_empty() {
+        res = format!("{res} -> {}", func.return_type);
+    }
+    if let Some(modifier) = &func.accessibility_modifier {
+        res = format!("{modifier} {res}");
+    }
+    format!("{res};")
+}
+
+fn stringify_variable(variable: &Variable, options: &StringifyOptions) -> String {
+    let mut res = format!("var {}", variable.name);
+    for attribute in variable.attributes.iter().rev() {
+        res = format!("{attribute} {res}");
+    }
+    if !variable.value_type.is_empty() {
+        res = format!("{res}:{}", variable.value_type);
+    }
+    if let Some(value) = variable.value.as_ref().filter(|_| options.const_values) {
+        res = format!("{res} = {value}");
+    }
+    format!("{res};")
+}
+
+fn stringify_enum_item(item: &Variable) -> String {
+    let mut res = item.name.clone();
+    if !item.value_type.is_empty() {
+        res = format!("{res}:{}", item.value_type);
+    }
+    format!("{res};")
+}
+
+fn stringify_union_item(item: &Variable) -> String {
+    let mut res = item.name.clone();
+    if !item.value_type.is_empty() {
+        res = format!("{res}:{}", item.value_type);
+    }
+    format!("{res};")
+}
+
+fn stringify_import(import: &Import) -> String {
+    let mut res = if import.glob {
+        format!("use {}::*", import.path)
+    } else if import.path.rsplit("::").next() == Some(import.name.as_str()) {
+        format!("use {}", import.path)
+    } else {
+        format!("use {} as {}", import.path, import.name)
+    };
+    if import.prelude {
+        res = format!("prelude {res}");
+    }
+    format!("{res};")
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemberOrder {
+    // Methods first, then properties
+    #[default]
+    Kind,
+    // Members in the order they are declared in the source
+    Source,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StringifyFormat {
+    // Everything on a single line, the cheapest in tokens
+    #[default]
+    Compact,
+    // One definition per line with indented members, for people to read
+    Pretty,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct StringifyOptions {
+    pub member_order: MemberOrder,
+    pub format: StringifyFormat,
+    // Pretty output wraps parameter lists of longer lines, one parameter per line
+    pub max_line_width: usize,
+    // Only emit these kinds of definitions, e.g. `["func"]` or `["class", "enum"]`, all when empty
+    pub kinds: Vec<String>,
+    // Show short literal initializers of constants, `var MAX:usize = 1024;`
+    pub const_values: bool,
+    // Passed on to the extraction, see `ExtractOptions::component_name`
+    pub component_name: Option<String>,
+    // Prepend the first line of the doc of functions and classes as a comment
+    pub docs: bool,
+    // Passed on to the extraction, see `ExtractOptions::include_private`
+    pub include_private: bool,
+    // Passed on to the extraction, see `ExtractOptions::include_anonymous`
+    pub include_anonymous: bool,
+    // Mark each definition with the lines it spans, e.g. `/* L12-15 */` in the compact format
+    pub with_locations: bool,
+}
+
+impl Default for StringifyOptions {
+    fn default() -> Self {
+        StringifyOptions {
+            member_order: MemberOrder::default(),
+            format: StringifyFormat::default(),
+            max_line_width: 100,
+            kinds: vec![],
+            const_values: false,
+            component_name: None,
+            docs: false,
+            include_private: false,
+            include_anonymous: false,
+            with_locations: false,
+        }
+    }
+}
+
+// Split a parameter list on its top-level commas, `->` and `=>` don't close a bracket
+fn split_params(params: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut current = String::new();
+    let mut previous = ' ';
+    for c in params.chars() {
+        match c {
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            '>' if previous != '-' && previous != '=' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                previous = c;
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+        previous = c;
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+    parts
+}
+
+// The first line of a doc as a comment above the definition, inline in the compact format
+fn stringify_doc(doc: Option<&String>, indent: &str, options: &StringifyOptions) -> String {
+    let Some(first_line) = doc
+        .filter(|_| options.docs)
+        .and_then(|doc| doc.lines().next())
+    else {
+        return String::new();
+    };
+    match options.format {
+        StringifyFormat::Compact => format!("/* {first_line} */"),
+        StringifyFormat::Pretty => format!("// {first_line}\n{indent}"),
+    }
+}
+
+// The lines a definition spans, placed like a doc and left out when they aren't known
+fn stringify_location(
+    start_line: usize,
+    end_line: usize,
+    indent: &str,
+    options: &StringifyOptions,
+) -> String {
+    if !options.with_locations || start_line == 0 {
+        return String::new();
+    }
+    let lines = if end_line > start_line {
+        format!("L{start_line}-{end_line}")
+    } else {
+        format!("L{start_line}")
+    };
+    match options.format {
+        StringifyFormat::Compact => format!("/* {lines} */"),
+        StringifyFormat::Pretty => format!("// {lines}\n{indent}"),
+    }
+}
+
+fn stringify_function_with_options(
+    func: &Func,
+    indent: &str,
+    options: &StringifyOptions,
+) -> String {
+    let location = stringify_location(func.start_line, func.end_line, indent, options);
+    let doc = stringify_doc(func.doc.as_ref(), indent, options);
+    format!(
+        "{location}{doc}{}",
+        stringify_function_wrapped(func, indent, options)
+    )
+}
+
+fn stringify_variable_with_options(
+    variable: &Variable,
+    indent: &str,
+    options: &StringifyOptions,
+) -> String {
+    let location = stringify_location(variable.start_line, variable.end_line, indent, options);
+    format!("{location}{}", stringify_variable(variable, options))
+}
+
+fn stringify_function_wrapped(func: &Func, indent: &str, options: &StringifyOptions) -> String {
+    let res = stringify_function(func);
+    if options.format != StringifyFormat::Pretty
+        || indent.len() + res.chars().count() <= options.max_line_width
+    {
+        return res;
+    }
+    let Some(params) = func
+        .params
+        .strip_prefix('(')
+        .and_then(|params| params.strip_suffix(')'))
+    else {
+        return res;
+    };
+    let params = split_params(params);
+    if params.is_empty() {
+        return res;
+    }
+    let params = params
+        .iter()
+        .map(|param| format!("{indent}  {param},\n"))
+        .collect::<Vec<_>>()
+        .concat();
+    stringify_function_signature(func, &format!("(\n{params}{indent})"))
+}
+
+fn stringify_container(header: &str, items: &[String], options: &StringifyOptions) -> String {
+    match options.format {
+        StringifyFormat::Compact => format!("{header}{{{}}};", items.concat()),
+        StringifyFormat::Pretty => {
+            let mut res = format!("{header} {{\n");
+            for item in items {
+                res = format!("{res}  {item}\n");
+            }
+            format!("{res}}};")
+        }
+    }
+}
+
+fn stringify_class(class: &Class, options: &StringifyOptions) -> String {
+    let mut members = class
+        .methods
+        .iter()
+        .map(|method| {
+            (
+                method.start_byte,
+                stringify_function_with_options(method, "  ", options),
+            )
+        })
+        .chain(class.properties.iter().map(|property| {
+            (
+                property.start_byte,
+                stringify_variable_with_options(property, "  ", options),
+            )
+        }))
+        .collect::<Vec<_>>();
+    if options.member_order == MemberOrder::Source {
+        members.sort_by_key(|(start_byte, _)| *start_byte);
+    }
+    let members: Vec<String> = members.into_iter().map(|(_, member)| member).collect();
+    let header = format!(
+        "{} {}{}",
+        class.type_name,
+        class.name,
+        class.generics.as_deref().unwrap_or_default()
+    );
+    let location = stringify_location(class.start_line, class.end_line, "", options);
+    let doc = stringify_doc(class.doc.as_ref(), "", options);
+    format!(
+        "{location}{doc}{}",
+        stringify_container(&header, &members, options)
+    )
+}
+
+fn stringify_enum(enum_def: &Enum, options: &StringifyOptions) -> String {
+    let items: Vec<String> = enum_def.items.iter().map(stringify_enum_item).collect();
+    let location = stringify_location(enum_def.start_line, enum_def.end_line, "", options);
+    format!(
+        "{location}{}",
+        stringify_container(&format!("enum {}", enum_def.name), &items, options)
+    )
+}
+
+fn stringify_union(union_def: &Union, options: &StringifyOptions) -> String {
+    let items: Vec<String> = union_def.items.iter().map(stringify_union_item).collect();
+    let location = stringify_location(union_def.start_line, union_def.end_line, "", options);
+    format!(
+        "{location}{}",
+        stringify_container(&format!("union {}", union_def.name), &items, options)
+    )
+}
+
+// The names accepted by `StringifyOptions::kinds`
+fn definition_kind(definition: &Definition) -> &'static str {
+    match definition {
+        Definition::Class(class) if class.type_name == "module" => "module",
+        Definition::Class(_) => "class",
+        Definition::Module(_) => "module",
+        Definition::Enum(_) => "enum",
+        Definition::Union(_) => "union",
+        Definition::Func(_) => "func",
+        Definition::Variable(_) => "variable",
+        Definition::Import(_) => "import",
+        Definition::Namespace { .. } => "namespace",
+    }
+}
+
+fn stringify_definitions(definitions: &[Definition]) -> String {
+    stringify_definitions_with_options(definitions, &StringifyOptions::default())
+}
+
+fn stringify_definition(definition: &Definition, options: &StringifyOptions) -> String {
+    match definition {
+        Definition::Class(class) | Definition::Module(class) => stringify_class(class, options),
+        Definition::Enum(enum_def) => stringify_enum(enum_def, options),
+        Definition::Union(union_def) => stringify_union(union_def, options),
+        Definition::Func(func) => stringify_function_with_options(func, "", options),
+        Definition::Import(import) => stringify_import(import),
+        Definition::Variable(variable) => stringify_variable_with_options(variable, "", options),
+        Definition::Namespace { name, children } => stringify_namespace(name, children, options),
+    }
+}
+
+// Nested definitions are indented one more level in the pretty format
+fn stringify_namespace(name: &str, children: &[Definition], options: &StringifyOptions) -> String {
+    let items: Vec<String> = children
+        .iter()
+        .filter(|child| is_kind_selected(child, options))
+        .map(|child| match options.format {
+            StringifyFormat::Compact => stringify_definition(child, options),
+            StringifyFormat::Pretty => stringify_definition(child, options).replace('\n', "\n  "),
+        })
+        .collect();
+    stringify_container(&format!("namespace {name}"), &items, options)
+}
+
+// A namespace is kept when any of its definitions is
+fn is_kind_selected(definition: &Definition, options: &StringifyOptions) -> bool {
+    if let Definition::Namespace { children, .. } = definition {
+        return options.kinds.iter().any(|kind| kind == "namespace")
+            || children
+                .iter()
+                .any(|child| is_kind_selected(child, options));
+    }
+    options.kinds.is_empty()
+        || options
+            .kinds
+            .iter()
+            .any(|kind| kind == definition_kind(definition))
+}
+
+fn stringify_definitions_with_options(
+    definitions: &[Definition],
+    options: &StringifyOptions,
+) -> String {
+    let separator = match options.format {
+        StringifyFormat::Compact => "",
+        StringifyFormat::Pretty => "\n\n",
+    };
+    definitions
+        .iter()
+        .filter(|definition| is_kind_selected(definition, options))
+        .map(|definition| stringify_definition(definition, options))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+pub fn get_definitions_string(
+    language: &str,
+    source: &str,
+    options: &StringifyOptions,
+) -> Result<String, Error> {
+    let extract_options = ExtractOptions {
+        component_name: options.component_name.clone(),
+        include_docs: options.docs,
+        include_private: options.include_private,
+        include_anonymous: options.include_anonymous,
+        ..ExtractOptions::default()
+    };
+    let definitions = extract_definitions_with_options(language, source, &extract_options)?;
+    let stringified = stringify_definitions_with_options(&definitions, options);
+    Ok(stringified)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RangedDefinition {
+    pub name: String,
+    pub kind: &'static str,
+    // Same text as `stringify_definitions` gives for this definition
+    pub text: String,
+    // 1-based and inclusive, 0 for imports which keep no position
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+fn definition_name_and_lines(definition: &Definition) -> (&str, usize, usize) {
+    match definition {
+        Definition::Class(class) | Definition::Module(class) => {
+            (class.name.as_str(), class.start_line, class.end_line)
+        }
+        Definition::Enum(enum_def) => (
+            enum_def.name.as_str(),
+            enum_def.start_line,
+            enum_def.end_line,
+        ),
+        Definition::Union(union_def) => (
+            union_def.name.as_str(),
+            union_def.start_line,
+            union_def.end_line,
+        ),
+        Definition::Func(func) => (func.name.as_str(), func.start_line, func.end_line),
+        Definition::Variable(variable) => (
+            variable.name.as_str(),
+            variable.start_line,
+            variable.end_line,
+        ),
+        Definition::Import(import) => (import.name.as_str(), 0, 0),
+        // From the first line of its first definition to the last line of its last one
+        Definition::Namespace { name, children } => {
+            let (start_line, end_line) = children
+                .iter()
+                .map(definition_name_and_lines)
+                .filter(|(_, start_line, _)| *start_line != 0)
+                .fold((0, 0), |(start, end), (_, start_line, end_line)| {
+                    let start = if start == 0 {
+                        start_line
+                    } else {
+                        start.min(start_line)
+                    };
+                    (start, end.max(end_line))
+                });
+            (name.as_str(), start_line, end_line)
+        }
+    }
+}
+
+// One entry per definition with its stringified text and where it is in the source, so that
+// callers can link each symbol of a repo map to its location
+pub fn get_definitions_with_ranges(
+    language: &str,
+    source: &str,
+    options: &StringifyOptions,
+) -> Result<Vec<RangedDefinition>, Error> {
+    let extract_options = ExtractOptions {
+        component_name: options.component_name.clone(),
+        include_docs: options.docs,
+        include_private: options.include_private,
+        include_anonymous: options.include_anonymous,
+        ..ExtractOptions::default()
+    };
+    let definitions = extract_definitions_with_options(language, source, &extract_options)?;
+    Ok(definitions
+        .iter()
+        .filter(|definition| is_kind_selected(definition, options))
+        .map(|definition| {
+            let (name, start_line, end_line) = definition_name_and_lines(definition);
+            RangedDefinition {
+                name: name.to_string(),
+                kind: definition_kind(definition),
+                text: stringify_definition(definition, options),
+                start_line,
+                end_line,
+            }
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportEntry {
+    pub name: String,
+    pub kind: &'static str,
+    pub line: usize,
+}
+
+// Flatten definitions into qualified names, members are prefixed with their container name and
+// definitions in a namespace with the namespace name
+fn flatten_exports(definitions: &[Definition], source: &str) -> Vec<ExportEntry> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let line_of = |start_byte: usize| line_starts.partition_point(|&start| start <= start_byte);
+    let mut exports = Vec::new();
+    let mut push = |prefix: &str, name: &str, kind: &'static str, start_byte: usize| {
+        exports.push(ExportEntry {
+            name: format!("{prefix}{name}"),
+            kind,
+            line: line_of(start_byte),
+        });
+    };
+    let mut pending = vec![(String::new(), definitions)];
+    while let Some((prefix, definitions)) = pending.pop() {
+        for definition in definitions {
+            match definition {
+                Definition::Class(class) | Definition::Module(class) => {
+                    let kind = if class.type_name == "module" {
+                        "module"
+                    } else {
+                        "class"
+                    };
+                    push(&prefix, &class.name, kind, class.start_byte);
+                    for method in &class.methods {
+                        let name = format!("{}.{}", class.name, method.name);
+                        push(&prefix, &name, "method", method.start_byte);
+                    }
+                    for property in &class.properties {
+                        let name = format!("{}.{}", class.name, property.name);
+                        push(&prefix, &name, "property", property.start_byte);
+                    }
+                }
+                Definition::Enum(enum_def) => {
+                    push(&prefix, &enum_def.name, "enum", enum_def.start_byte);
+                }
+                Definition::Union(union_def) => {
+                    push(&prefix, &union_def.name, "union", union_def.start_byte);
+                }
+                Definition::Func(func) => push(&prefix, &func.name, "func", func.start_byte),
+                Definition::Variable(variable) => {
+                    push(&prefix, &variable.name, "variable", variable.start_byte);
+                }
+                // Re-exports have no declaration site of their own in this file
+                Definition::Import(_) => {}
+                Definition::Namespace { name, children } => {
+                    if let Some(start_byte) = definition_start_byte(definition) {
+                        push(&prefix, name, "namespace", start_byte);
+                    }
+                    pending.push((format!("{prefix}{name}."), children.as_slice()));
+                }
+            }
+        }
+    }
+    exports.sort_by(|a, b| a.line.cmp(&b.line).then_with(|| a.name.cmp(&b.name)));
+    exports
+}
+
+// Qualified names of the public symbols with their kind and line, from an extraction that skips
+// all signature work, flattened
+pub fn list_exports(language: &str, source: &str) -> Result<Vec<ExportEntry>, Error> {
+    let options = ExtractOptions {
+        signatures: false,
+        ..ExtractOptions::default()
+    };
+    let definitions = extract_definitions_with_options(language, source, &options)?;
+    Ok(flatten_exports(&definitions, source))
+}
+
+// Definitions encoded as MessagePack with named fields, the same structure as `get_definitions`
+// but much cheaper to hand over to Lua than a deep table for large files
+pub fn definitions_to_msgpack(language: &str, source: &str) -> Result<Vec<u8>, Error> {
+    let definitions = extract_definitions(language, source)?;
+    rmp_serde::to_vec_named(&definitions)
+        .map_err(|e| Error::Parse(format!("Failed to encode definitions for {language}: {e}")))
+}
+
+// A definition in the `definitions_to_json` schema, every kind has the same shape: members of
+// classes are `method` and `property` children, the items of enums and unions `item` children
+// and the definitions of a namespace children of their own kind
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct JsonDefinition {
+    pub kind: &'static str,
+    pub name: String,
+    // `struct`, `interface`, `trait`... of a class
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generics: Option<String>,
+    // Module path of an import
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub glob: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub prelude: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub returns_self: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    pub start_byte: usize,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub children: Vec<JsonDefinition>,
+}
+
+fn func_to_json(func: &Func, kind: &'static str) -> JsonDefinition {
+    JsonDefinition {
+        kind,
+        name: func.name.clone(),
+        params: Some(func.params.clone()),
+        return_type: Some(func.return_type.clone()),
+        visibility: func.accessibility_modifier.clone(),
+        calls: func.calls.clone(),
+        references: func.references.clone(),
+        returns_self: func.returns_self,
+        generics: func.generics.clone(),
+        body: func.body.clone(),
+        start_byte: func.start_byte,
+        start_line: func.start_line,
+        end_line: func.end_line,
+        ..Default::default()
+    }
+}
+
+fn variable_to_json(variable: &Variable, kind: &'static str) -> JsonDefinition {
+    JsonDefinition {
+        kind,
+        name: variable.name.clone(),
+        value_type: Some(variable.value_type.clone()),
+        value: variable.value.clone(),
+        attributes: variable.attributes.clone(),
+        start_byte: variable.start_byte,
+        start_line: variable.start_line,
+        end_line: variable.end_line,
+        ..Default::default()
+    }
+}
+
+fn definition_to_json(definition: &Definition) -> JsonDefinition {
+    let kind = definition_kind(definition);
+    match definition {
+        Definition::Class(class) | Definition::Module(class) => JsonDefinition {
+            kind,
+            name: class.name.clone(),
+            type_name: Some(class.type_name.clone()),
+            visibility: class.visibility_modifier.clone(),
+            generics: class.generics.clone(),
+            start_byte: class.start_byte,
+            start_line: class.start_line,
+            end_line: class.end_line,
+            children: class
+                .methods
+                .iter()
+                .map(|method| func_to_json(method, "method"))
+                .chain(
+                    class
+                        .properties
+                        .iter()
+                        .map(|property| variable_to_json(property, "property")),
+                )
+                .collect(),
+            ..Default::default()
+        },
+        Definition::Enum(Enum {
+            name,
+            items,
+            start_byte,
+            start_line,
+            end_line,
+        })
+        | Definition::Union(Union {
+            name,
+            items,
+            start_byte,
+            start_line,
+            end_line,
+        }) => JsonDefinition {
+            kind,
+            name: name.clone(),
+            start_byte: *start_byte,
+            start_line: *start_line,
+            end_line: *end_line,
+            children: items
+                .iter()
+                .map(|item| variable_to_json(item, "item"))
+                .collect(),
+            ..Default::default()
+        },
+        Definition::Func(func) => func_to_json(func, kind),
+        Definition::Variable(variable) => variable_to_json(variable, kind),
+        Definition::Import(import) => JsonDefinition {
+            kind,
+            name: import.name.clone(),
+            path: Some(import.path.clone()),
+            glob: import.glob,
+            prelude: import.prelude,
+            ..Default::default()
+        },
+        Definition::Namespace { name, children } => {
+            let (_, start_line, end_line) = definition_name_and_lines(definition);
+            JsonDefinition {
+                kind,
+                name: name.clone(),
+                start_byte: definition_start_byte(definition).unwrap_or_default(),
+                start_line,
+                end_line,
+                children: children.iter().map(definition_to_json).collect(),
+                ..Default::default()
+            }
+        }
+    }
+}
+
+// Definitions as a JSON array of `JsonDefinition`, for callers that render the repo map their
+// own way instead of parsing the `stringify_definitions` format
+pub fn definitions_to_json(language: &str, source: &str) -> Result<String, Error> {
+    let definitions = extract_definitions(language, source)?;
+    let definitions: Vec<JsonDefinition> = definitions.iter().map(definition_to_json).collect();
+    serde_json::to_string(&definitions)
+        .map_err(|e| Error::Parse(format!("Failed to encode definitions for {language}: {e}")))
+}
+
+// Number of top-level definitions per kind, e.g. `{ func = 12, class = 3 }`, members of a class
+// or module count towards their container while the definitions of a namespace count on their own
+pub fn definition_stats(
+    language: &str,
+    source: &str,
+) -> Result<BTreeMap<&'static str, usize>, Error> {
+    let options = ExtractOptions {
+        signatures: false,
+        ..ExtractOptions::default()
+    };
+    let definitions = extract_definitions_with_options(language, source, &options)?;
+    let mut stats = BTreeMap::new();
+    let mut pending: Vec<&[Definition]> = vec![definitions.as_slice()];
+    while let Some(definitions) = pending.pop() {
+        for definition in definitions {
+            *stats.entry(definition_kind(definition)).or_insert(0) += 1;
+            if let Definition::Namespace { children, .. } = definition {
+                pending.push(children);
+            }
+        }
+    }
+    Ok(stats)
+}
+
+// FNV-1a hash of each definition's signature, keyed by name with members as `Class.method` and
+// definitions in a namespace as `Namespace.Class`. Bodies are left out, so only a changed name,
+// parameter, return type or modifier changes the hash
+pub fn signature_hashes(language: &str, source: &str) -> Result<BTreeMap<String, String>, Error> {
+    let definitions = extract_definitions(language, source)?;
+    // Overloads share a name, so their signatures are hashed together
+    let mut signatures: BTreeMap<String, String> = BTreeMap::new();
+    let mut add = |prefix: &str, name: &str, signature: String| {
+        signatures
+            .entry(format!("{prefix}{name}"))
+            .or_default()
+            .push_str(&signature);
+    };
+    let mut pending = vec![(String::new(), definitions.as_slice())];
+    while let Some((prefix, definitions)) = pending.pop() {
+        for definition in definitions {
+            match definition {
+                Definition::Func(func) => add(&prefix, &func.name, stringify_function(func)),
+                Definition::Variable(variable) => add(
+                    &prefix,
+                    &variable.name,
+                    format!("var {}:{};", variable.name, variable.value_type),
+                ),
+                Definition::Class(class) | Definition::Module(class) => {
+                    add(
+                        &prefix,
+                        &class.name,
+                        format!(
+                            "{}{} {}{};",
+                            class
+                                .visibility_modifier
+                                .as_ref()
+                                .map(|modifier| format!("{modifier} "))
+                                .unwrap_or_default(),
+                            class.type_name,
+                            class.name,
+                            class.generics.as_deref().unwrap_or_default()
+                        ),
+                    );
+                    for method in &class.methods {
+                        add(
+                            &prefix,
+                            &format!("{}.{}", class.name, method.name),
+                            stringify_function(method),
+                        );
+                    }
+                    for property in &class.properties {
+                        add(
+                            &prefix,
+                            &format!("{}.{}", class.name, property.name),
+                            format!("var {}:{};", property.name, property.value_type),
+                        );
+                    }
+                }
+                Definition::Enum(enum_def) => add(
+                    &prefix,
+                    &enum_def.name,
+                    stringify_enum(enum_def, &StringifyOptions::default()),
+                ),
+                Definition::Union(union_def) => add(
+                    &prefix,
+                    &union_def.name,
+                    stringify_union(union_def, &StringifyOptions::default()),
+                ),
+                Definition::Import(_) => {}
+                Definition::Namespace { name, children } => {
+                    pending.push((format!("{prefix}{name}."), children.as_slice()));
+                }
+            }
+        }
+    }
+    Ok(signatures
+        .into_iter()
+        .map(|(name, signature)| (name, format!("{:016x}", fnv1a_hash(signature.as_bytes()))))
+        .collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Chunk {
+    // 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    pub text: String,
+    pub tokens: usize,
+}
+
+// Rough count used when the caller has no tokenizer at hand
+fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+// Line ranges that should stay together: each top-level node with its leading comments
+fn definition_segments(language: &str, source: &str, line_count: usize) -> Vec<(usize, usize)> {
+    let mut starts = vec![0];
+    if let Some(ts_language) = get_ts_language(language) {
+        let mut parser = Parser::new();
+        if parser.set_language(&ts_language.into()).is_ok() {
+            if let Some(tree) = parser.parse(source, None) {
+                let root_node = tree.root_node();
+                let mut cursor = root_node.walk();
+                let mut previous: Option<Node> = None;
+                for child in root_node.named_children(&mut cursor) {
+                    let attached_to_comment = previous.is_some_and(|p| {
+                        p.kind().contains("comment")
+                            && p.end_position().row + 1 >= child.start_position().row
+                    });
+                    if !attached_to_comment {
+                        starts.push(child.start_position().row);
+                    }
+                    previous = Some(child);
+                }
+            }
+        }
+    }
+    starts.dedup();
+    let mut segments = Vec::new();
+    for (i, start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(line_count);
+        if *start < end {
+            segments.push((*start, end));
+        }
+    }
+    segments
+}
+
+// Cut a line into the longest pieces within `max_tokens`, backing off to whitespace when there is
+// some. A piece is at least one character, so a counter that never fits still makes progress.
+fn split_long_line<F>(
+    line: &str,
+    max_tokens: usize,
+    count_tokens: &mut F,
+) -> Result<Vec<(String, usize)>, Error>
+where
+    F: FnMut(&str) -> Result<usize, Error>,
+{
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while !rest.is_empty() {
+        let tokens = count_tokens(rest)?;
+        if tokens <= max_tokens {
+            pieces.push((rest.to_string(), tokens));
+            break;
+        }
+        let ends: Vec<usize> = rest
+            .char_indices()
+            .skip(1)
+            .map(|(index, _)| index)
+            .chain([rest.len()])
+            .collect();
+        // Binary search for the last end whose prefix fits
+        let (mut low, mut high) = (0, ends.len() - 1);
+        while low < high {
+            let middle = (low + high).div_ceil(2);
+            if count_tokens(&rest[..ends[middle]])? <= max_tokens {
+                low = middle;
+            } else {
+                high = middle - 1;
+            }
+        }
+        let mut end = ends[low];
+        if let Some((index, c)) = rest[..end]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .filter(|(index, _)| *index > 0)
+        {
+            end = index + c.len_utf8();
+        }
+        pieces.push((rest[..end].to_string(), count_tokens(&rest[..end])?));
+        rest = &rest[end..];
+    }
+    Ok(pieces)
+}
+
+// Group whole top-level definitions into chunks of at most `max_tokens`, definitions that do not
+// fit on their own are split at line boundaries and single lines that do not fit within them
+pub fn chunk_by_definitions<F>(
+    language: &str,
+    source: &str,
+    max_tokens: usize,
+    mut count_tokens: F,
+) -> Result<Vec<Chunk>, Error>
+where
+    F: FnMut(&str) -> Result<usize, String>,
+{
+    if max_tokens == 0 {
+        return Err(Error::InvalidArgument(
+            "max_tokens must be greater than 0".to_string(),
+        ));
+    }
+    let mut count_tokens = |text: &str| {
+        count_tokens(text).map_err(|e| Error::InvalidArgument(format!("count_tokens failed: {e}")))
+    };
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let mut chunks: Vec<Chunk> = Vec::new();
+    let mut current: Option<Chunk> = None;
+    let push_lines = |current: &mut Option<Chunk>,
+                      chunks: &mut Vec<Chunk>,
+                      start: usize,
+                      end: usize,
+                      tokens: usize| {
+        let text: String = lines[start..end].concat();
+        match current {
+            Some(chunk) if chunk.tokens + tokens <= max_tokens => {
+                chunk.text.push_str(&text);
+                chunk.end_line = end;
+                chunk.tokens += tokens;
+            }
+            _ => {
+                chunks.extend(current.take());
+                *current = Some(Chunk {
+                    start_line: start + 1,
+                    end_line: end,
+                    text,
+                    tokens,
+                });
+            }
+        }
+    };
+    for (start, end) in definition_segments(language, source, lines.len()) {
+        let tokens = count_tokens(&lines[start..end].concat())?;
+        if tokens <= max_tokens {
+            push_lines(&mut current, &mut chunks, start, end, tokens);
+            continue;
+        }
+        // Oversized definition, start a fresh chunk and fill it line by line
+        chunks.extend(current.take());
+        for (line, text) in lines.iter().enumerate().take(end).skip(start) {
+            let tokens = count_tokens(text)?;
+            if tokens <= max_tokens {
+                push_lines(&mut current, &mut chunks, line, line + 1, tokens);
+                continue;
+            }
+            // A single line over the limit, e.g. minified code, gets chunks of its own
+            chunks.extend(current.take());
+            for (text, tokens) in split_long_line(text, max_tokens, &mut count_tokens)? {
+                chunks.push(Chunk {
+                    start_line: line + 1,
+                    end_line: line + 1,
+                    text,
+                    tokens,
+                });
+            }
+        }
+        chunks.extend(current.take());
+    }
+    chunks.extend(current);
+    Ok(chunks)
+}
+
+fn get_language_from_extension(path: &Path) -> Option<&'static str> {
+    // Jenkins pipelines are Groovy scripts named `Jenkinsfile` or `<name>.Jenkinsfile`
+    let file_name = path.file_name()?.to_str()?;
+    if file_name == "Jenkinsfile" || file_name.ends_with(".Jenkinsfile") {
+        return Some("groovy");
+    }
+    let extension = path.extension()?.to_str()?;
+    match extension {
+        "rs" => Some("rust"),
+        "py" => Some("python"),
+        "php" => Some("php"),
+        "java" => Some("java"),
+        "js" | "jsx" | "mjs" | "cjs" => Some("javascript"),
+        "ts" | "tsx" | "mts" | "cts" => Some("typescript"),
+        "go" => Some("go"),
+        "c" | "h" => Some("c"),
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => Some("cpp"),
+        "lua" => Some("lua"),
+        "rb" => Some("ruby"),
+        "zig" => Some("zig"),
+        "scala" | "sc" => Some("scala"),
+        "swift" => Some("swift"),
+        "ex" | "exs" => Some("elixir"),
+        "cs" => Some("csharp"),
+        "kt" | "kts" => Some("kotlin"),
+        "dart" => Some("dart"),
+        "hs" => Some("haskell"),
+        "ml" => Some("ocaml"),
+        "mli" => Some("ocaml_interface"),
+        "tf" | "hcl" => Some("hcl"),
+        "erl" | "hrl" => Some("erlang"),
+        "sh" | "bash" => Some("bash"),
+        "jl" => Some("julia"),
+        "r" | "R" => Some("r"),
+        "pl" | "pm" => Some("perl"),
+        "m" => Some("objc"),
+        "groovy" | "gvy" | "gradle" => Some("groovy"),
+        "sql" => Some("sql"),
+        "proto" => Some("proto"),
+        "clj" | "cljs" | "cljc" => Some("clojure"),
+        "ps1" | "psm1" => Some("powershell"),
+        "graphql" | "gql" => Some("graphql"),
+        "fs" | "fsx" => Some("fsharp"),
+        "vue" => Some("vue"),
+        "nim" => Some("nim"),
+        "svelte" => Some("svelte"),
+        "sol" => Some("solidity"),
+        "cr" => Some("crystal"),
+        "gleam" => Some("gleam"),
+        "d" | "di" => Some("d"),
+        _ => None,
+    }
+}
+
+fn guess_language_from_shebang(first_line: &str) -> Option<&'static str> {
+    let interpreter = first_line.strip_prefix("#!")?;
+    let interpreter = interpreter
+        .split_whitespace()
+        .find(|part| !part.ends_with("/env") && !part.starts_with('-'))?;
+    let interpreter = interpreter.rsplit('/').next().unwrap_or(interpreter);
+    let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    match interpreter {
+        "python" => Some("python"),
+        "node" | "deno" | "bun" => Some("javascript"),
+        "ts-node" | "tsx" => Some("typescript"),
+        "ruby" => Some("ruby"),
+        "php" => Some("php"),
+        "lua" | "luajit" => Some("lua"),
+        "elixir" => Some("elixir"),
+        "swift" => Some("swift"),
+        "scala" => Some("scala"),
+        _ => None,
+    }
+}
+
+// Lightweight heuristics for sources without a file name, e.g. pasted code in a scratch buffer
+pub fn guess_language(source: &str) -> Option<&'static str> {
+    let source = source.trim_start_matches('\u{feff}').trim_start();
+    let first_line = source.lines().next().unwrap_or_default();
+    if first_line.starts_with("#!") {
+        return guess_language_from_shebang(first_line);
+    }
+    // Strong markers beat line counting
+    if source.starts_with("<?php") {
+        return Some("php");
+    }
+    if source.lines().any(|line| line.starts_with("package main")) {
+        return Some("go");
+    }
+    if source.contains("fn main()") && !source.contains("func main()") {
+        return Some("rust");
+    }
+
+    // Each signal is a line prefix; the language with the most matching lines wins
+    let signals: &[(&'static str, &[&str])] = &[
+        ("go", &["package ", "func ", "import (", "type "]),
+        (
+            "rust",
+            &[
+                "fn ",
+                "pub fn ",
+                "use std::",
+                "use crate::",
+                "impl ",
+                "pub struct ",
+                "mod ",
+                "let mut ",
+            ],
+        ),
+        (
+            "python",
+            &[
+                "def ",
+                "import ",
+                "from ",
+                "class ",
+                "if __name__ ==",
+                "elif ",
+                "async def ",
+            ],
+        ),
+        (
+            "java",
+            &[
+                "public class ",
+                "package ",
+                "import java.",
+                "private ",
+                "public static void main",
+            ],
+        ),
+        (
+            "csharp",
+            &[
+                "using System",
+                "namespace ",
+                "public class ",
+                "private ",
+                "public static void Main",
+            ],
+        ),
+        (
+            "c",
+            &["#include <", "int main(", "static ", "typedef ", "#define "],
+        ),
+        (
+            "cpp",
+            &[
+                "#include <",
+                "std::",
+                "namespace ",
+                "template <",
+                "class ",
+                "using namespace ",
+            ],
+        ),
+        (
+            "javascript",
+            &[
+                "const ",
+                "let ",
+                "function ",
+                "module.exports",
+                "export default ",
+                "import ",
+            ],
+        ),
+        (
+            "typescript",
+            &[
+                "interface ",
+                "export interface ",
+                "type ",
+                "export type ",
+                "const ",
+                "import ",
+            ],
+        ),
+        ("lua", &["local ", "function ", "return M", "end"]),
+        (
+            "ruby",
+            &[
+                "require '",
+                "require \"",
+                "def ",
+                "end",
+                "class ",
+                "module ",
+            ],
+        ),
+        (
+            "elixir",
+            &["defmodule ", "def ", "defp ", "end", "use ", "alias "],
+        ),
+        (
+            "zig",
+            &["const std = @import(", "pub fn ", "const ", "test \""],
+        ),
+        (
+            "swift",
+            &[
+                "import Foundation",
+                "import UIKit",
+                "import SwiftUI",
+                "func ",
+                "struct ",
+                "var ",
+                "let ",
+            ],
+        ),
+        (
+            "scala",
+            &[
+                "object ",
+                "case class ",
+                "def ",
+                "val ",
+                "trait ",
+                "package ",
+            ],
+        ),
+    ];
+    let mut best: Option<(&'static str, usize)> = None;
+    for (language, prefixes) in signals {
+        let score = source
+            .lines()
+            .map(str::trim_start)
+            .filter(|line| prefixes.iter().any(|prefix| line.starts_with(prefix)))
+            .count();
+        let typed = *language == "typescript" && has_type_annotations(source);
+        let wins = match best {
+            None => score > 0,
+            // TypeScript shares most line prefixes with JavaScript, annotations break the tie
+            Some(("javascript", best_score)) if typed => score >= best_score,
+            Some((_, best_score)) => score > best_score,
+        };
+        if wins {
+            best = Some((*language, score));
+        }
+    }
+    best.map(|(language, _)| language)
+}
+
+fn has_type_annotations(source: &str) -> bool {
+    [
+        "string", "number", "boolean", "any", "void", "unknown", "never",
+    ]
+    .iter()
+    .any(|ty| source.contains(&format!(": {ty}")))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathStyle {
+    Absolute,
+    #[default]
+    Relative,
+    Basename,
+    // Replaces the whole path with a stable hash so no directory or file names leak
+    Redacted,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MapOptions {
+    pub path_style: PathStyle,
+    // Attach the leading comment or docstring of each file as `module_doc`
+    pub include_module_doc: bool,
+    pub module_doc_max_chars: usize,
+    // Attach the SHA-256 digest of each file's content as `checksum`, prefixed with `sha256:`
+    pub include_checksum: bool,
+}
+
+impl Default for MapOptions {
+    fn default() -> Self {
+        MapOptions {
+            path_style: PathStyle::default(),
+            include_module_doc: false,
+            module_doc_max_chars: 400,
+            include_checksum: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMap {
+    pub path: String,
+    pub lang: String,
+    pub defs: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub module_doc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    // Why the definitions of the file could not be extracted, `defs` is empty then
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn strip_comment_markers(comment: &str) -> Vec<String> {
+    comment
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = ["//!", "///", "//", "/**", "/*", "--[[", "--", "#"]
+                .iter()
+                .find_map(|marker| line.strip_prefix(marker))
+                .unwrap_or(line);
+            let line = line
+                .strip_suffix("*/")
+                .or_else(|| line.strip_suffix("]]"))
+                .unwrap_or(line);
+            let line = line.trim();
+            line.strip_prefix('*').unwrap_or(line).trim().to_string()
+        })
+        .collect()
+}
+
+// The first comment block of a file, or a Python module docstring, capped to `max_chars`
+fn extract_module_doc(language: &str, source: &str, max_chars: usize) -> Option<String> {
+    let ts_language = get_ts_language(language)?;
+    let mut parser = Parser::new();
+    parser.set_language(&ts_language.into()).ok()?;
+    let tree = parser.parse(source, None)?;
+    let root_node = tree.root_node();
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut last_comment_row = None;
+    let mut cursor = root_node.walk();
+    for child in root_node.children(&mut cursor) {
+        let text = get_node_text(&child, source.as_bytes());
+        if child.kind().contains("comment") {
+            if text.starts_with("#!") {
+                continue;
+            }
+            // A blank line ends the block
+            if last_comment_row.is_some_and(|row| child.start_position().row > row + 1) {
+                break;
+            }
+            lines.extend(strip_comment_markers(&text));
+            // Line comments may include their trailing newline
+            let end_row = child.end_position().row - usize::from(text.ends_with('\n'));
+            last_comment_row = Some(end_row);
+            continue;
+        }
+        if lines.is_empty()
+            && language == "python"
+            && child.kind() == "expression_statement"
+            && child.named_child(0).is_some_and(|n| n.kind() == "string")
+        {
+            let docstring = text.trim_matches(|c| c == '"' || c == '\'');
+            lines.extend(docstring.lines().map(|line| line.trim().to_string()));
+        }
+        break;
+    }
+
+    let doc = lines.join("\n").trim().to_string();
+    if doc.is_empty() {
+        return None;
+    }
+    if doc.chars().count() <= max_chars {
+        return Some(doc);
+    }
+    let truncated: String = doc.chars().take(max_chars).collect();
+    Some(format!("{}...", truncated.trim_end()))
+}
+
+fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+fn format_path(path: &Path, root: &Path, path_style: PathStyle) -> String {
+    let relative_path = path.strip_prefix(root).unwrap_or(path);
+    match path_style {
+        PathStyle::Absolute => path.to_string_lossy().to_string(),
+        PathStyle::Relative => relative_path.to_string_lossy().to_string(),
+        PathStyle::Basename => path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        PathStyle::Redacted => {
+            let hash = fnv1a_hash(relative_path.to_string_lossy().as_bytes());
+            match path.extension() {
+                Some(extension) => format!("redacted-{hash:016x}.{}", extension.to_string_lossy()),
+                None => format!("redacted-{hash:016x}"),
+            }
+        }
+    }
+}
+
+// Supported source files under `root` in path order. The walk honours `.gitignore` and `.ignore`
+// files, skips hidden entries, `node_modules` and `target`, and never follows symlinks, so a link
+// cycle can't trap it. Unreadable entries are left out.
+fn collect_source_files(root: &Path) -> Vec<PathBuf> {
+    WalkBuilder::new(root)
+        .follow_links(false)
+        .require_git(false)
+        .sort_by_file_name(Ord::cmp)
+        .filter_entry(|entry| {
+            !matches!(entry.file_name().to_str(), Some("node_modules" | "target"))
+        })
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+        })
+        .map(ignore::DirEntry::into_path)
+        .filter(|path| get_language_from_extension(path).is_some())
+        .collect()
+}
+
+fn map_file(file: &Path, root: &Path, options: &MapOptions) -> Result<Option<FileMap>, Error> {
+    let Some(language) = get_language_from_extension(file) else {
+        return Ok(None);
+    };
+    let Ok(source) = std::fs::read_to_string(file) else {
+        return Ok(None);
+    };
+    let extract_options = ExtractOptions {
+        component_name: file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string()),
+        ..ExtractOptions::default()
+    };
+    let definitions = extract_definitions_with_options(language, &source, &extract_options)?;
+    let defs = stringify_definitions(&definitions);
+    if defs.is_empty() {
+        return Ok(None);
+    }
+    let module_doc = if options.include_module_doc {
+        extract_module_doc(language, &source, options.module_doc_max_chars)
+    } else {
+        None
+    };
+    let checksum = options
+        .include_checksum
+        .then(|| format!("sha256:{:x}", Sha256::digest(source.as_bytes())));
+    Ok(Some(FileMap {
+        path: format_path(file, root, options.path_style),
+        lang: language.to_string(),
+        defs,
+        module_doc,
+        checksum,
+        error: None,
+    }))
+}
+
+// `map_file`, with a failed extraction recorded in the entry of the file instead of failing the
+// whole map
+fn map_file_or_error(file: &Path, root: &Path, options: &MapOptions) -> Option<FileMap> {
+    map_file(file, root, options).unwrap_or_else(|error| {
+        Some(FileMap {
+            path: format_path(file, root, options.path_style),
+            lang: get_language_from_extension(file)
+                .unwrap_or_default()
+                .to_string(),
+            defs: String::new(),
+            module_doc: None,
+            checksum: None,
+            error: Some(error.to_string()),
+        })
+    })
+}
+
+// Walk the given directory and return the stringified definitions of every supported file
+pub fn map_repository(root: &Path, options: &MapOptions) -> Result<Vec<FileMap>, Error> {
+    let root = std::fs::canonicalize(root)
+        .map_err(|e| Error::Io(format!("Failed to resolve {}: {e}", root.display())))?;
+    Ok(collect_source_files(&root)
+        .iter()
+        .filter_map(|file| map_file_or_error(file, &root, options))
+        .collect())
+}
+
+const MAX_PROJECT_SESSIONS: usize = 8;
+const MAX_SESSION_FILES: usize = 50_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectMap {
+    pub files: Vec<FileMap>,
+    // Files that are still dirty because the re-parse budget ran out
+    pub stale: Vec<String>,
+    pub reparsed: usize,
+}
+
+// Keeps the extracted map of a project so that only invalidated files are parsed again
+pub struct ProjectSession {
+    root: PathBuf,
+    options: MapOptions,
+    files: BTreeMap<PathBuf, Option<FileMap>>,
+    dirty: BTreeSet<PathBuf>,
+    parse_count: usize,
+}
+
+impl ProjectSession {
+    pub fn open(root: &Path, options: MapOptions) -> Result<Self, Error> {
+        let root = std::fs::canonicalize(root)
+            .map_err(|e| Error::Io(format!("Failed to resolve {}: {e}", root.display())))?;
+        let files = collect_source_files(&root);
+        if files.len() > MAX_SESSION_FILES {
+            return Err(Error::LimitExceeded(format!(
+                "Project {} has more than {MAX_SESSION_FILES} source files",
+                root.display()
+            )));
+        }
+        let mut session = ProjectSession {
+            root,
+            options,
+            files: BTreeMap::new(),
+            dirty: files.into_iter().collect(),
+            parse_count: 0,
+        };
+        session.refresh(None);
+        Ok(session)
+    }
+
+    pub fn invalidate(&mut self, paths: &[String]) {
+        for path in paths {
+            let path = Path::new(path);
+            let path = if path.is_absolute() {
+                path.to_path_buf()
+            } else {
+                self.root.join(path)
+            };
+            let path = std::fs::canonicalize(&path).unwrap_or(path);
+            if path.starts_with(&self.root) && get_language_from_extension(&path).is_some() {
+                self.dirty.insert(path);
+            }
+        }
+    }
+
+    // Re-extract at most `budget` dirty files, reusing the cached results for everything else. A
+    // file that fails to extract keeps its error in its entry and doesn't stop the others.
+    pub fn refresh(&mut self, budget: Option<usize>) -> usize {
+        let budget = budget.unwrap_or(usize::MAX);
+        let paths = self.dirty.iter().take(budget).cloned().collect::<Vec<_>>();
+        for path in &paths {
+            self.dirty.remove(path);
+            if !path.is_file() {
+                self.files.remove(path);
+                continue;
+            }
+            if !self.files.contains_key(path) && self.files.len() >= MAX_SESSION_FILES {
+                continue;
+            }
+            self.parse_count += 1;
+            let file_map = map_file_or_error(path, &self.root, &self.options);
+            self.files.insert(path.clone(), file_map);
+        }
+        paths.len()
+    }
+
+    pub fn map(&mut self, budget: Option<usize>) -> ProjectMap {
+        let reparsed = self.refresh(budget);
+        ProjectMap {
+            files: self.files.values().flatten().cloned().collect(),
+            stale: self
+                .dirty
+                .iter()
+                .map(|path| format_path(path, &self.root, self.options.path_style))
+                .collect(),
+            reparsed,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    next_session_id: u64,
+    sessions: BTreeMap<u64, ProjectSession>,
+}
+
+impl State {
+    fn open_session(&mut self, root: &Path, options: MapOptions) -> Result<u64, Error> {
+        if self.sessions.len() >= MAX_PROJECT_SESSIONS {
+            return Err(Error::LimitExceeded(format!(
+                "Too many open project sessions (max {MAX_PROJECT_SESSIONS}), close one first"
+            )));
+        }
+        let session = ProjectSession::open(root, options)?;
+        self.next_session_id += 1;
+        self.sessions.insert(self.next_session_id, session);
+        Ok(self.next_session_id)
+    }
+
+    fn session(&mut self, session_id: u64) -> Result<&mut ProjectSession, Error> {
+        self.sessions
+            .get_mut(&session_id)
+            .ok_or_else(|| Error::NotFound(format!("Project session {session_id} is not open")))
+    }
+}
+
+#[mlua::lua_module]
+fn avante_repo_map(lua: &Lua) -> LuaResult<LuaTable> {
+    let exports = lua.create_table()?;
+    exports.set(
+        "stringify_definitions",
+        lua.create_function(
+            move |lua, (language, source, options): (String, String, Option<LuaValue>)| {
+                let options: StringifyOptions = match options {
+                    Some(options) => lua.from_value(options)?,
+                    None => StringifyOptions::default(),
+                };
+                get_definitions_string(language.as_str(), source.as_str(), &options)
+                    .map_err(|e| e.into_lua(LUA_MODULE, "stringify_definitions"))
+            },
+        )?,
+    )?;
+    exports.set(
+        "stringify_definitions_with_ranges",
+        lua.create_function(
+            move |lua, (language, source, options): (String, String, Option<LuaValue>)| {
+                let options: StringifyOptions = match options {
+                    Some(options) => lua.from_value(options)?,
+                    None => StringifyOptions::default(),
+                };
+                let definitions =
+                    get_definitions_with_ranges(language.as_str(), source.as_str(), &options)
+                        .map_err(|e| e.into_lua(LUA_MODULE, "stringify_definitions_with_ranges"))?;
+                lua.to_value(&definitions)
+            },
+        )?,
+    )?;
+    let state = Arc::new(Mutex::new(State::default()));
+    let open_state = Arc::clone(&state);
+    exports.set(
+        "project_open",
+        lua.create_function(move |lua, (root, options): (String, Option<LuaValue>)| {
+            let options: MapOptions = match options {
+                Some(options) => lua.from_value(options)?,
+                None => MapOptions::default(),
+            };
+            let mut state = open_state.lock().unwrap();
+            state
+                .open_session(Path::new(&root), options)
+                .map_err(|e| e.into_lua(LUA_MODULE, "project_open"))
+        })?,
+    )?;
+    let invalidate_state = Arc::clone(&state);
+    exports.set(
+        "project_invalidate",
+        lua.create_function(move |_, (session_id, paths): (u64, Vec<String>)| {
+            let mut state = invalidate_state.lock().unwrap();
+            state
+                .session(session_id)
+                .map_err(|e| e.into_lua(LUA_MODULE, "project_invalidate"))?
+                .invalidate(&paths);
+            Ok(())
+        })?,
+    )?;
+    let map_state = Arc::clone(&state);
+    exports.set(
+        "project_map",
+        lua.create_function(move |lua, (session_id, budget): (u64, Option<usize>)| {
+            let mut state = map_state.lock().unwrap();
+            let project_map = state
+                .session(session_id)
+                .map(|session| session.map(budget))
+                .map_err(|e| e.into_lua(LUA_MODULE, "project_map"))?;
+            lua.to_value(&project_map)
+        })?,
+    )?;
+    exports.set(
+        "project_close",
+        lua.create_function(move |_, session_id: u64| {
+            let mut state = state.lock().unwrap();
+            Ok(state.sessions.remove(&session_id).is_some())
+        })?,
+    )?;
+    exports.set(
+        "get_definitions",
+        lua.create_function(
+            move |lua, (language, source, options): (String, String, Option<LuaValue>)| {
+                let options: ExtractOptions = match options {
+                    Some(options) => lua.from_value(options)?,
+                    None => ExtractOptions::default(),
+                };
+                let definitions = extract_definitions_with_options(&language, &source, &options)
+                    .map_err(|e| e.into_lua(LUA_MODULE, "get_definitions"))?;
+                lua.to_value(&definitions)
+            },
+        )?,
+    )?;
+    exports.set(
+        "get_tests",
+        lua.create_function(move |lua, (language, source): (String, String)| {
+            let tests = extract_tests(&language, &source)
+                .map_err(|e| e.into_lua(LUA_MODULE, "get_tests"))?;
+            lua.to_value(&tests)
+        })?,
+    )?;
+    exports.set(
+        "list_exports",
+        lua.create_function(move |lua, (language, source): (String, String)| {
+            let exports = list_exports(&language, &source)
+                .map_err(|e| e.into_lua(LUA_MODULE, "list_exports"))?;
+            lua.to_value(&exports)
+        })?,
+    )?;
+    exports.set(
+        "definition_stats",
+        lua.create_function(move |lua, (language, source): (String, String)| {
+            let stats = definition_stats(&language, &source)
+                .map_err(|e| e.into_lua(LUA_MODULE, "definition_stats"))?;
+            lua.to_value(&stats)
+        })?,
+    )?;
+    exports.set(
+        "signature_hashes",
+        lua.create_function(move |lua, (language, source): (String, String)| {
+            let hashes = signature_hashes(&language, &source)
+                .map_err(|e| e.into_lua(LUA_MODULE, "signature_hashes"))?;
+            lua.to_value(&hashes)
+        })?,
+    )?;
+    exports.set(
+        "definitions_to_msgpack",
+        lua.create_function(move |lua, (language, source): (String, String)| {
+            let bytes = definitions_to_msgpack(&language, &source)
+                .map_err(|e| e.into_lua(LUA_MODULE, "definitions_to_msgpack"))?;
+            lua.create_string(&bytes)
+        })?,
+    )?;
+    exports.set(
+        "definitions_to_json",
+        lua.create_function(move |_, (language, source): (String, String)| {
+            definitions_to_json(&language, &source)
+                .map_err(|e| e.into_lua(LUA_MODULE, "definitions_to_json"))
+        })?,
+    )?;
+    exports.set(
+        "chunk_by_definitions",
+        lua.create_function(
+            move |lua,
+                  (language, source, max_tokens, count_tokens): (
+                String,
+                String,
+                usize,
+                Option<LuaFunction>,
+            )| {
+                let chunks =
+                    chunk_by_definitions(
+                        &language,
+                        &source,
+                        max_tokens,
+                        |text| match &count_tokens {
+                            Some(count_tokens) => {
+                                count_tokens.call(text).map_err(|e| e.to_string())
+                            }
+                            None => Ok(estimate_tokens(text)),
+                        },
+                    )
+                    .map_err(|e| e.into_lua(LUA_MODULE, "chunk_by_definitions"))?;
+                lua.to_value(&chunks)
+            },
+        )?,
+    )?;
+    exports.set(
+        "guess_language",
+        lua.create_function(move |_, source: String| Ok(guess_language(&source)))?,
+    )?;
+    exports.set(
+        "map_repository",
+        lua.create_function(move |lua, (root, options): (String, Option<LuaValue>)| {
+            let options: MapOptions = match options {
+                Some(options) => lua.from_value(options)?,
+                None => MapOptions::default(),
+            };
+            let file_maps = map_repository(Path::new(&root), &options)
+                .map_err(|e| e.into_lua(LUA_MODULE, "map_repository"))?;
+            lua.to_value(&file_maps)
+        })?,
+    )?;
+    Ok(exports)
+}
+
+#[cfg(test)]
+#[allow(clippy::print_stdout)]
+mod tests {
+    use super::*;
+
+    const RUST_SOURCE: &str = r"
+        // This is a test comment
+        pub const TEST_CONST: u32 = 1;
+        pub static TEST_STATIC: u32 = 2;
+        const INNER_TEST_CONST: u32 = 3;
+        static INNER_TEST_STATIC: u32 = 4;
+        pub(crate) struct TestStruct {
+            pub test_field: String,
+            inner_test_field: String,
+        }
+        impl TestStruct {
+            pub fn test_method(&self, a: u32, b: u32) -> u32 {
+                a + b
+            }
+            fn inner_test_method(&self, a: u32, b: u32) -> u32 {
+                a + b
+            }
+        }
+        struct InnerTestStruct {
+            pub test_field: String,
+            inner_test_field: String,
+        }
+        impl InnerTestStruct {
+            pub fn test_method(&self, a: u32, b: u32) -> u32 {
+                a + b
+            }
+            fn inner_test_method(&self, a: u32, b: u32) -> u32 {
+                a + b
+            }
+        }
+        pub enum TestEnum {
+            TestEnumField1,
+            TestEnumField2,
+        }
+        enum InnerTestEnum {
+            InnerTestEnumField1,
+            InnerTestEnumField2,
+        }
+        pub fn test_fn(a: u32, b: u32) -> u32 {
+            let inner_var_in_func = 1;
+            struct InnerStructInFunc {
+                c: u32,
+            }
+            a + b + c
+        }
+        fn inner_test_fn(a: u32, b: u32) -> u32 {
+            a + b
+        }
+        ";
+
+    #[test]
+    fn test_rust() {
+        let source = RUST_SOURCE;
+        let definitions = extract_definitions("rust", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var TEST_CONST:u32;var TEST_STATIC:u32;func test_fn(a: u32, b: u32) -> u32;class TestStruct{func test_method(&self, a: u32, b: u32) -> u32;var test_field:String;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_with_locations() {
+        let definitions = extract_definitions("rust", RUST_SOURCE).unwrap();
+        let lines: Vec<_> = definitions.iter().map(definition_name_and_lines).collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("TEST_CONST", 3, 3),
+                ("TEST_STATIC", 4, 4),
+                ("test_fn", 39, 45),
+                ("TestStruct", 7, 14),
+            ]
+        );
+
+        let options = StringifyOptions {
+            with_locations: true,
+            ..StringifyOptions::default()
+        };
+        let stringified = stringify_definitions_with_options(&definitions, &options);
+        println!("{stringified}");
+        let expected = "/* L3 */var TEST_CONST:u32;/* L4 */var TEST_STATIC:u32;/* L39-45 */func test_fn(a: u32, b: u32) -> u32;/* L7-14 */class TestStruct{/* L12-14 */func test_method(&self, a: u32, b: u32) -> u32;/* L8 */var test_field:String;};";
+        assert_eq!(stringified, expected);
+
+        let options = StringifyOptions {
+            format: StringifyFormat::Pretty,
+            kinds: vec!["class".to_string()],
+            ..options
+        };
+        let pretty = stringify_definitions_with_options(&definitions, &options);
+        let expected = "// L7-14
+class TestStruct {
+  // L12-14
+  func test_method(&self, a: u32, b: u32) -> u32;
+  // L8
+  var test_field:String;
+};";
+        assert_eq!(pretty, expected);
+    }
+
+    #[test]
+    fn test_include_private() {
+        let source = r"
+        pub struct Config {
+            pub name: String,
+            secret: String,
+        }
+        impl Config {
+            pub fn name(&self) -> &str {
+                &self.name
+            }
+            fn validate(&self) -> bool {
+                true
+            }
+        }
+        fn helper(a: u32) -> u32 {
+            a
+        }
+        ";
+        let definitions = extract_definitions("rust", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        assert_eq!(
+            stringified,
+            "class Config{func name(&self) -> str;var name:String;};"
+        );
+
+        let options = StringifyOptions {
+            include_private: true,
+            ..StringifyOptions::default()
+        };
+        let stringified = get_definitions_string("rust", source, &options).unwrap();
+        println!("{stringified}");
+        let expected = "func helper(a: u32) -> u32;class Config{func name(&self) -> str;func validate(&self) -> bool;var name:String;var secret:String;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_stringify_pretty() {
+        let definitions = extract_definitions("rust", RUST_SOURCE).unwrap();
+        let compact = stringify_definitions_with_options(
+            &definitions,
+            &StringifyOptions {
+                format: StringifyFormat::Compact,
+                ..Default::default()
+            },
+        );
+        assert_eq!(compact, stringify_definitions(&definitions));
+
+        let options = StringifyOptions {
+            format: StringifyFormat::Pretty,
+            ..Default::default()
+        };
+        let pretty = stringify_definitions_with_options(&definitions, &options);
+        println!("{pretty}");
+        let expected = "var TEST_CONST:u32;
+
+var TEST_STATIC:u32;
+
+func test_fn(a: u32, b: u32) -> u32;
+
+class TestStruct {
+  func test_method(&self, a: u32, b: u32) -> u32;
+  var test_field:String;
+};";
+        assert_eq!(pretty, expected);
+    }
+
+    #[test]
+    fn test_stringify_kinds() {
+        let source = r"
+        pub const LIMIT: usize = 10;
+
+        pub fn run(limit: usize) -> bool {
+            true
+        }
+
+        pub struct Runner {
+            pub name: String,
+        }
+
+        impl Runner {
+            pub fn start(&self) {}
+        }
+        ";
+        let definitions = extract_definitions("rust", source).unwrap();
+        let options = StringifyOptions {
+            kinds: vec!["func".to_string()],
+            ..Default::default()
+        };
+        let stringified = stringify_definitions_with_options(&definitions, &options);
+        assert_eq!(stringified, "func run(limit: usize) -> bool;");
+
+        let options = StringifyOptions {
+            kinds: vec!["class".to_string(), "variable".to_string()],
+            ..Default::default()
+        };
+        let stringified = stringify_definitions_with_options(&definitions, &options);
+        assert_eq!(
+            stringified,
+            "var LIMIT:usize;class Runner{func start(&self) -> void;var name:String;};"
+        );
+    }
+
+    #[test]
+    fn test_python_dataclass() {
+        let source = r#"
+from dataclasses import dataclass, field
+from typing import NamedTuple
+
+
+@dataclass(frozen=True)
+class User:
+    name: str
+    age: int = 0
+    tags: list[str] = field(default_factory=list)
+
+    def greeting(self) -> str:
+        return f"Hello {self.name}"
+
+
+class Point(NamedTuple):
+    x: float
+    y: float
+"#;
+
+        let definitions = extract_definitions("python", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "class Point{var x:float;var y:float;};class User{func greeting(self) -> str;var name:str;var age:int;var tags:list[str];};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_rust_const_values() {
+        let source = r#"
+        pub const MAX: usize = 1024;
+        pub const OFFSET: i32 = -1;
+        pub static NAME: &str = "repo-map";
+        pub const LEVEL: Level = Level::Info;
+        pub const TABLE: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+        pub const GREETING: &str = "a string literal that is far too long to show";
+        "#;
+        let definitions = extract_definitions("rust", source).unwrap();
+        assert_eq!(
+            stringify_definitions(&definitions),
+            "var MAX:usize;var OFFSET:i32;var NAME:&str;var LEVEL:Level;var TABLE:[u8; 8];var GREETING:&str;"
+        );
+
+        let options = StringifyOptions {
+            const_values: true,
+            ..Default::default()
+        };
+        let stringified = stringify_definitions_with_options(&definitions, &options);
+        assert_eq!(
+            stringified,
+            "var MAX:usize = 1024;var OFFSET:i32 = -1;var NAME:&str = \"repo-map\";var LEVEL:Level = Level::Info;var TABLE:[u8; 8];var GREETING:&str;"
+        );
+    }
+
+    #[test]
+    fn test_stringify_max_line_width() {
+        let source = r"
+        pub fn connect(address: std::net::SocketAddr, timeout: std::time::Duration, handler: Box<dyn Fn(Request) -> Response>) -> Result<Connection, Error> {
+            todo!()
+        }
+
+        pub struct Server;
+
+        impl Server {
+            pub fn serve(&self, routes: HashMap<String, Handler>, middleware: Vec<Middleware>) -> Result<(), Error> {
+                todo!()
+            }
+        }
+        ";
+        let definitions = extract_definitions("rust", source).unwrap();
+        let options = StringifyOptions {
+            format: StringifyFormat::Pretty,
+            max_line_width: 80,
+            ..Default::default()
+        };
+        let pretty = stringify_definitions_with_options(&definitions, &options);
+        println!("{pretty}");
+        let expected = "func connect(
+  address: std::net::SocketAddr,
+  timeout: std::time::Duration,
+  handler: Box<dyn Fn(Request) -> Response>,
+) -> Result;
+
+class Server {
+  func serve(
+    &self,
+    routes: HashMap<String, Handler>,
+    middleware: Vec<Middleware>,
+  ) -> Result;
+};";
+        assert_eq!(pretty, expected);
+
+        // Lines that fit are left alone, and compact output never wraps
+        let options = StringifyOptions {
+            format: StringifyFormat::Pretty,
+            max_line_width: 200,
+            ..Default::default()
+        };
+        let pretty = stringify_definitions_with_options(&definitions, &options);
+        assert_eq!(pretty.lines().count(), 5);
+        let compact = stringify_definitions_with_options(
+            &definitions,
+            &StringifyOptions {
+                max_line_width: 10,
+                ..Default::default()
+            },
+        );
+        assert_eq!(compact, stringify_definitions(&definitions));
+    }
+
+    #[test]
+    fn test_member_order_source() {
+        let source = r"
+        class MyClass:
+            def first(self):
+                pass
+
+            field = 1
+
+            def second(self):
+                pass
+        ";
+        let definitions = extract_definitions("python", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        let expected =
+            "class MyClass{func first(self) -> void;func second(self) -> void;var field;};";
+        assert_eq!(stringified, expected);
+
+        let options = StringifyOptions {
+            member_order: MemberOrder::Source,
+            ..Default::default()
+        };
+        let stringified = stringify_definitions_with_options(&definitions, &options);
+        println!("{stringified}");
+        let expected =
+            "class MyClass{func first(self) -> void;var field;func second(self) -> void;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_builder() {
+        let source = r"
+        pub struct RequestBuilder {
+            url: String,
+        }
+
+        impl RequestBuilder {
+            pub fn header(&mut self, name: &str) -> &mut Self {
+                self
+            }
+            pub fn build(&self) -> Request {
+                Request {}
+            }
+        }
+        ";
+
+        let definitions = extract_definitions("rust", source).unwrap();
+        let class = definitions
+            .iter()
+            .find_map(|definition| match definition {
+                Definition::Class(class) if class.name == "RequestBuilder" => Some(class),
+                _ => None,
+            })
+            .unwrap();
+        let returns_self = class
+            .methods
+            .iter()
+            .map(|method| (method.name.as_str(), method.returns_self))
+            .collect::<Vec<_>>();
+        assert_eq!(returns_self, vec![("header", true), ("build", false)]);
+    }
+
+    #[test]
+    fn test_rust_impl_generics() {
+        let source = r"
+        pub struct Foo<T> {
+            pub value: T,
+        }
+
+        impl<T: Clone> Foo<T> {
+            pub fn dup(&self) -> T {
+                self.value.clone()
+            }
+        }
+
+        impl<T: fmt::Debug> fmt::Display for Foo<T> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.value.fmt(f)
+            }
+        }
+
+        impl<T: Default + Clone> Foo<T> {
+            pub fn reset(&mut self) {
+                self.value = T::default();
+            }
+        }
+        ";
+
+        let definitions = extract_definitions("rust", source).unwrap();
+        let foo = definitions
+            .iter()
+            .find_map(|definition| match definition {
+                Definition::Class(class) if class.name == "Foo" => Some(class),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(foo.generics.as_deref(), Some("<T: Clone + Default>"));
+        let methods = foo
+            .methods
+            .iter()
+            .map(|method| method.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(methods, vec!["dup", "reset"]);
+
+        let stringified = stringify_definitions(&definitions);
+        assert_eq!(
+            stringified,
+            "class Foo<T: Clone + Default>{func dup(&self) -> T;func reset(&mut self) -> void;var value:T;};"
+        );
+    }
+
+    #[test]
+    fn test_type_parameters() {
+        let source = r"
+        pub fn wrap<T: Clone>(value: T) -> Vec<T> {
+            vec![value]
+        }
+
+        pub struct Pair<A, B> {
+            pub first: A,
+            pub second: B,
+        }
+        ";
+        let definitions = extract_definitions("rust", source).unwrap();
+        assert_eq!(
+            stringify_definitions(&definitions),
+            "func wrap<T: Clone>(value: T) -> Vec;class Pair<A, B>{var first:A;var second:B;};"
+        );
+
+        let source = r"
+        export class Box<T> {
+            value: T;
+            map<U>(f: (value: T) => U): Box<U> {
+                return new Box(f(this.value));
+            }
+        }
+        export function identity<T>(value: T): T {
+            return value;
+        }
+        ";
+        let definitions = extract_definitions("typescript", source).unwrap();
+        assert_eq!(
+            stringify_definitions(&definitions),
+            "func identity<T>(value: T) -> T;class Box<T>{func map<U>(f: (value: T) => U) -> Box<U>;var value:T;};"
+        );
+
+        let source = r"
+        public class Box<T> {
+            public <U> Box<U> map(Function<T, U> mapper) {
+                return null;
+            }
+        }
+        ";
+        let definitions = extract_definitions("java", source).unwrap();
+        assert_eq!(
+            stringify_definitions(&definitions),
+            "class Box<T>{func map<U>(Function<T, U> mapper) -> void;};"
+        );
+    }
+
+    #[test]
+    fn test_rust_tests_section() {
+        let source = r"
+        pub fn add(a: u32, b: u32) -> u32 {
+            a + b
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            #[test]
+            fn it_works() {
+                assert_eq!(add(2, 2), 4);
+            }
+
+            #[tokio::test]
+            // Runs on the async runtime
+            pub async fn it_works_async() {}
+
+            pub fn helper() {}
+        }
+        ";
+
+        let (definitions, tests) =
+            extract_definitions_and_tests("rust", source, &ExtractOptions::default()).unwrap();
+        let names: Vec<&str> = tests.iter().map(|test| test.name.as_str()).collect();
+        assert_eq!(names, vec!["it_works", "it_works_async"]);
+        let stringified = stringify_definitions(&definitions);
+        assert!(!stringified.contains("it_works"));
+        assert_eq!(
+            stringified,
+            "func add(a: u32, b: u32) -> u32;func helper() -> void;"
+        );
+    }
+
+    #[test]
+    fn test_extract_tests() {
+        let python = r"
+def test_addition():
+    assert 1 + 1 == 2
+
+def test_sum() -> int:
+    return 3
+
+def testSubtraction():
+    assert 2 - 1 == 1
+
+def helper():
+    return 1
+
+class TestMath:
+    @pytest.mark.slow
+    def test_division(self):
+        assert 4 / 2 == 2
+";
+        let names = |tests: Vec<Func>| tests.into_iter().map(|test| test.name).collect::<Vec<_>>();
+        assert_eq!(
+            names(extract_tests("python", python).unwrap()),
+            vec![
+                "test_addition",
+                "test_sum",
+                "testSubtraction",
+                "test_division"
+            ]
+        );
+        let definitions = extract_definitions("python", python).unwrap();
+        assert_eq!(
+            stringify_definitions(&definitions),
+            "func helper() -> void;"
+        );
+
+        let javascript = r"
+describe('math', () => {
+  it('adds numbers', () => {});
+  test(`subtracts numbers`, () => {});
+  run('not a test');
+});
+";
+        assert_eq!(
+            names(extract_tests("javascript", javascript).unwrap()),
+            vec!["adds numbers", "subtracts numbers"]
+        );
+
+        let java = r"
+public class MathTest {
+    @Test
+    public void addsNumbers() {}
+
+    public void setUp() {}
+}
+";
+        assert_eq!(
+            names(extract_tests("java", java).unwrap()),
+            vec!["addsNumbers"]
+        );
+        let definitions = extract_definitions("java", java).unwrap();
+        assert_eq!(
+            stringify_definitions(&definitions),
+            "class MathTest{func setUp() -> void;};"
+        );
+    }
+
+    #[test]
+    fn test_rust_glob_reexport() {
+        let source = r"
+        use std::io;
+        pub use crate::errors::*;
+        pub use crate::config::{Config, self};
+        pub use crate::client::Client as ApiClient;
+        pub mod prelude {
+            pub use crate::types::*;
+        }
+        ";
+        let definitions = extract_definitions("rust", source).unwrap();
+        let glob_reexport = definitions.iter().find_map(|d| match d {
+            Definition::Import(import) if import.path == "crate::errors" => Some(import),
+            _ => None,
+        });
+        let glob_reexport = glob_reexport.unwrap();
+        assert!(glob_reexport.glob);
+        assert!(!glob_reexport.prelude);
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "use crate::errors::*;use crate::config::Config;use crate::config;use crate::client::Client as ApiClient;prelude use crate::types::*;";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_rust_calls() {
+        let source = r#"
+        pub fn a() -> u32 {
+            b() + helper::c()
+        }
+        pub fn b() -> u32 {
+            let v = Some(1);
+            v.map(|x| x).unwrap_or(0)
+        }
+        pub struct Foo {}
+        impl Foo {
+            pub fn run(&self) {
+                self.step();
+                a();
+                println!("done");
+            }
+        }
+        "#;
+        let options = ExtractOptions {
+            include_calls: true,
+            ..Default::default()
+        };
+        let definitions = extract_definitions_with_options("rust", source, &options).unwrap();
+        let mut calls = BTreeMap::new();
+        for definition in &definitions {
+            match definition {
+                Definition::Func(func) => {
+                    calls.insert(func.name.clone(), func.calls.clone());
+                }
+                Definition::Class(class) => {
+                    for method in &class.methods {
+                        calls.insert(method.name.clone(), method.calls.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(calls["a"], vec!["b", "c"]);
+        assert_eq!(calls["b"], vec!["unwrap_or", "map"]);
+        assert_eq!(calls["run"], vec!["step", "a"]);
+
+        let options = ExtractOptions {
+            include_calls: true,
+            max_calls: 1,
+            ..Default::default()
+        };
+        let definitions = extract_definitions_with_options("rust", source, &options).unwrap();
+        let Some(Definition::Func(a)) = definitions.first() else {
+            panic!("expected a function");
+        };
+        assert_eq!(a.calls, vec!["b"]);
+
+        let definitions = extract_definitions("rust", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        assert!(!stringified.contains("calls"));
+    }
+
+    #[test]
+    fn test_list_exports_skips_signature_work() {
+        use std::fmt::Write;
+        let mut source = String::new();
+        for i in 0..400 {
+            write!(
+                source,
+                "pub struct Item{i}<T: Clone> {{
+    #[serde(default)]
+    pub value: T,
+}}
+impl<T: Clone + Default> Item{i}<T> {{
+    pub fn get_{i}<U: Into<T>>(&self, a: u32, b: Vec<String>) -> Option<T> {{
+        None
+    }}
+}}
+pub const LIMIT_{i}: u32 = {i};
+"
+            )
+            .unwrap();
+        }
+        // Alternating the two and keeping the fastest run of each spreads the load of tests running
+        // alongside over both sides of the comparison
+        let time = |run: &dyn Fn()| {
+            let started = std::time::Instant::now();
+            run();
+            started.elapsed()
+        };
+        let (mut full, mut exports) = (std::time::Duration::MAX, std::time::Duration::MAX);
+        for _ in 0..7 {
+            full = full.min(time(&|| {
+                flatten_exports(&extract_definitions("rust", &source).unwrap(), &source);
+            }));
+            exports = exports.min(time(&|| {
+                list_exports("rust", &source).unwrap();
+            }));
+        }
+        assert!(
+            exports < full,
+            "list_exports {exports:?}, full extraction {full:?}"
+        );
+    }
+
+    #[test]
+    fn test_list_exports() {
+        let fixtures = [
+            (
+                "rust",
+                r"pub const TEST_CONST: u32 = 1;
+pub struct TestStruct {
+    pub test_field: String,
+}
+impl TestStruct {
+    pub fn test_method(&self, a: u32) -> u32 {
+        a
+    }
+}
+pub fn test_fn(a: u32, b: u32) -> u32 {
+    a + b
+}
+fn inner_test_fn() {}
+",
+            ),
+            (
+                "python",
+                r"class TestClass:
+    def __init__(self, a: int):
+        self.a = a
+
+    def test_method(self, b: int) -> int:
+        return self.a + b
+
+def test_fn(a: int, b: int) -> int:
+    return a + b
+
+TEST_VAR: int = 1
+",
+            ),
+            (
+                "go",
+                r"package main
+
+type TestStruct struct {
+	TestField string
+}
+
+func (t *TestStruct) TestMethod(a int) int {
+	return a
+}
+
+func TestFn(a int, b int) int {
+	return a + b
+}
+",
+            ),
+        ];
+        for (language, source) in fixtures {
+            let exports = list_exports(language, source).unwrap();
+            let full = flatten_exports(&extract_definitions(language, source).unwrap(), source);
+            let names: BTreeSet<_> = exports.iter().map(|e| e.name.clone()).collect();
+            let full_names: BTreeSet<_> = full.iter().map(|e| e.name.clone()).collect();
+            assert_eq!(names, full_names, "{language}");
+        }
+
+        let exports = list_exports("rust", fixtures[0].1).unwrap();
+        let entries: Vec<_> = exports
+            .iter()
+            .map(|e| (e.name.as_str(), e.kind, e.line))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ("TEST_CONST", "variable", 1),
+                ("TestStruct", "class", 2),
+                ("TestStruct.test_field", "property", 3),
+                ("TestStruct.test_method", "method", 6),
+                ("test_fn", "func", 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_definition_stats() {
+        let stats = definition_stats("rust", RUST_SOURCE).unwrap();
+        assert_eq!(
+            stats.into_iter().collect::<Vec<_>>(),
+            vec![("class", 1), ("func", 1), ("variable", 2)]
+        );
+        assert!(definition_stats("python", "").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_definitions_table() {
+        // `get_definitions` hands Lua the serialized definitions, rebuilding the compact string
+        // from that table must give what `stringify_definitions` gives for the same definitions
+        let definitions = extract_definitions("rust", RUST_SOURCE).unwrap();
+        let table = serde_json::to_value(&definitions).unwrap();
+        let field = |value: &serde_json::Value, key: &str| value[key].as_str().unwrap().to_string();
+        let func_text = |func: &serde_json::Value| {
+            format!(
+                "func {}{} -> {};",
+                field(func, "name"),
+                field(func, "params"),
+                field(func, "return_type")
+            )
+        };
+        let variable_text = |variable: &serde_json::Value| {
+            format!(
+                "var {}:{};",
+                field(variable, "name"),
+                field(variable, "value_type")
+            )
+        };
+        let rebuilt: String = table
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|definition| match definition["kind"].as_str().unwrap() {
+                "func" => func_text(definition),
+                "variable" => variable_text(definition),
+                "class" => {
+                    let methods: String = definition["methods"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(func_text)
+                        .collect();
+                    let properties: String = definition["properties"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(variable_text)
+                        .collect();
+                    format!(
+                        "{} {}{{{methods}{properties}}};",
+                        field(definition, "type_name"),
+                        field(definition, "name")
+                    )
+                }
+                kind => panic!("unexpected kind {kind}"),
+            })
+            .collect();
+        assert_eq!(rebuilt, stringify_definitions(&definitions));
+
+        let test_struct = table
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|definition| definition["name"] == "TestStruct")
+            .unwrap();
+        assert_eq!(test_struct["visibility_modifier"], "pub(crate)");
+    }
+
+    #[test]
+    fn test_definitions_to_msgpack() {
+        let definitions = extract_definitions("rust", RUST_SOURCE).unwrap();
+        let bytes = definitions_to_msgpack("rust", RUST_SOURCE).unwrap();
+        let decoded: Vec<Definition> = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(
+            stringify_definitions(&decoded),
+            stringify_definitions(&definitions)
+        );
+        for (original, decoded) in definitions.iter().zip(&decoded) {
+            assert_eq!(definition_kind(original), definition_kind(decoded));
+            assert_eq!(format!("{original:?}"), format!("{decoded:?}"));
+        }
+        assert!(definitions_to_msgpack("unknown", "").is_ok());
+    }
+
+    #[test]
+    fn test_small_bodies() {
+        let source = "
+            pub fn add(a: u32, b: u32) -> u32 {
+            \tlet c = a + b;
+                c
+            }
+            pub fn long(a: u32) -> u32 {
+                let b = a + 1;
+                let c = b + 1;
+                let d = c + 1;
+                d
+            }
+";
+        let options = ExtractOptions {
+            small_body_lines: 4,
+            ..Default::default()
+        };
+        let definitions = extract_definitions_with_options("rust", source, &options).unwrap();
+        let bodies: Vec<_> = definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                Definition::Func(func) => Some((func.name.as_str(), func.body.as_deref())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            bodies,
+            vec![
+                (
+                    "add",
+                    Some("pub fn add(a: u32, b: u32) -> u32 {\n    let c = a + b;\n    c\n}")
+                ),
+                ("long", None),
+            ]
+        );
+
+        let definitions = extract_definitions("rust", source).unwrap();
+        assert!(definitions.iter().all(
+            |definition| !matches!(definition, Definition::Func(func) if func.body.is_some())
+        ));
+    }
+
+    #[test]
+    fn test_signature_hashes() {
+        let source = r"
+        pub struct Counter {
+            pub count: u32,
+        }
+
+        impl Counter {
+            pub fn increment(&mut self, by: u32) -> u32 {
+                self.count += by;
+                self.count
+            }
+        }
+        ";
+        let hashes = signature_hashes("rust", source).unwrap();
+        assert!(hashes.contains_key("Counter"));
+        assert!(hashes.contains_key("Counter.count"));
+
+        let changed_body = source.replace("self.count += by;", "self.count += by * 2;");
+        let changed_body_hashes = signature_hashes("rust", &changed_body).unwrap();
+        assert_eq!(hashes, changed_body_hashes);
+
+        let changed_return = source.replace(") -> u32 {", ") -> u64 {");
+        let changed_return_hashes = signature_hashes("rust", &changed_return).unwrap();
+        assert_ne!(
+            hashes["Counter.increment"],
+            changed_return_hashes["Counter.increment"]
+        );
+        assert_eq!(
+            hashes["Counter.count"],
+            changed_return_hashes["Counter.count"]
+        );
+    }
+
+    #[test]
+    fn test_definitions_to_json() {
+        let json = definitions_to_json("rust", RUST_SOURCE).unwrap();
+        let definitions: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        let test_fn = definitions
+            .iter()
+            .find(|definition| definition["name"] == "test_fn")
+            .unwrap();
+        assert_eq!(test_fn["kind"], "func");
+        assert_eq!(test_fn["params"], "(a: u32, b: u32)");
+        assert_eq!(test_fn["return_type"], "u32");
+        assert_eq!(test_fn["children"], serde_json::json!([]));
+        let test_struct = definitions
+            .iter()
+            .find(|definition| definition["name"] == "TestStruct")
+            .unwrap();
+        assert_eq!(test_struct["kind"], "class");
+        assert_eq!(test_struct["visibility"], "pub(crate)");
+        let children = test_struct["children"].as_array().unwrap();
+        assert!(children
+            .iter()
+            .any(|child| child["kind"] == "method" && child["name"] == "test_method"));
+        assert!(children
+            .iter()
+            .any(|child| child["kind"] == "property" && child["name"] == "test_field"));
+        assert_eq!(definitions_to_json("unknown", "").unwrap(), "[]");
+    }
+
+    #[test]
+    fn test_docs() {
+        let source = r"
+/// Adds two numbers.
+///
+/// Wraps on overflow.
+#[inline]
+pub fn add(a: u32, b: u32) -> u32 {
+    a.wrapping_add(b)
+}
+
+// Not a doc comment
+pub fn sub(a: u32, b: u32) -> u32 {
+    a - b
+}
+
+/// A point in space.
+#[derive(Debug)]
+pub struct Point {
+    pub x: f64,
+}
+
+impl Point {
+    /// Distance to the origin.
+    pub fn norm(&self) -> f64 {
+        self.x.abs()
+    }
+}
+";
+        let options = ExtractOptions {
+            include_docs: true,
+            ..Default::default()
+        };
+        let definitions = extract_definitions_with_options("rust", source, &options).unwrap();
+        let mut docs = BTreeMap::new();
+        for definition in &definitions {
+            match definition {
+                Definition::Func(func) => {
+                    docs.insert(func.name.clone(), func.doc.clone());
+                }
+                Definition::Class(class) => {
+                    docs.insert(class.name.clone(), class.doc.clone());
+                    for method in &class.methods {
+                        docs.insert(method.name.clone(), method.doc.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(
+            docs["add"].as_deref(),
+            Some("Adds two numbers.\n\nWraps on overflow.")
+        );
+        assert_eq!(docs["sub"], None);
+        assert_eq!(docs["Point"].as_deref(), Some("A point in space."));
+        assert_eq!(docs["norm"].as_deref(), Some("Distance to the origin."));
+
+        let options = StringifyOptions {
+            docs: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            get_definitions_string("rust", source, &options).unwrap(),
+            "/* Adds two numbers. */func add(a: u32, b: u32) -> u32;func sub(a: u32, b: u32) -> u32;/* A point in space. */class Point{/* Distance to the origin. */func norm(&self) -> f64;var x:f64;};"
+        );
+        let stringified = get_definitions_string("rust", source, &StringifyOptions::default());
+        assert!(!stringified.unwrap().contains("/*"));
+
+        let source = r#"
+class Greeter:
+    """Says hello.
+
+    More details."""
+
+    def greet(self, name: str) -> str:
+        """Return a greeting."""
+        return "hi " + name
+
+def main():
+    # Not a docstring
+    pass
+"#;
+        let options = StringifyOptions {
+            docs: true,
+            format: StringifyFormat::Pretty,
+            ..Default::default()
+        };
+        assert_eq!(
+            get_definitions_string("python", source, &options).unwrap(),
+            "func main() -> void;\n\n// Says hello.\nclass Greeter {\n  // Return a greeting.\n  func greet(self, name: str) -> str;\n};"
+        );
+    }
+
+    #[test]
+    fn test_definitions_with_ranges() {
+        let source = r"pub struct Foo {
+    pub a: u32,
+}
+
+impl Foo {
+    pub fn run(&self) -> u32 {
+        self.a
+    }
+}
+
+pub fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+";
+        let options = StringifyOptions::default();
+        let ranged = get_definitions_with_ranges("rust", source, &options).unwrap();
+        let lines: Vec<_> = ranged
+            .iter()
+            .map(|definition| {
+                (
+                    definition.name.as_str(),
+                    definition.start_line,
+                    definition.end_line,
+                )
+            })
+            .collect();
+        assert_eq!(lines, vec![("add", 11, 13), ("Foo", 1, 8)]);
+        let text: String = ranged
+            .iter()
+            .map(|definition| definition.text.as_str())
+            .collect();
+        assert_eq!(
+            text,
+            get_definitions_string("rust", source, &options).unwrap()
+        );
+
+        let definitions = extract_definitions("rust", source).unwrap();
+        let Some(Definition::Class(foo)) = definitions.last() else {
+            panic!("expected a class");
+        };
+        assert_eq!(
+            (foo.properties[0].start_line, foo.properties[0].end_line),
+            (2, 2)
+        );
+        assert_eq!((foo.methods[0].start_line, foo.methods[0].end_line), (6, 8));
+    }
+
+    #[test]
+    fn test_rust_references() {
+        let source = r"
+        pub const LIMIT: u32 = 10;
+        pub fn a(x: u32) -> u32 {
+            b(x).min(LIMIT)
+        }
+        pub fn b(x: u32) -> u32 {
+            let b = x + 1;
+            b
+        }
+        pub struct Foo {}
+        impl Foo {
+            pub fn run(&self) -> u32 {
+                self.step() + a(1)
+            }
+            pub fn step(&self) -> u32 {
+                0
+            }
+        }
+        ";
+        let options = ExtractOptions {
+            include_references: true,
+            ..ExtractOptions::default()
+        };
+        let definitions = extract_definitions_with_options("rust", source, &options).unwrap();
+        let mut references = BTreeMap::new();
+        for definition in &definitions {
+            match definition {
+                Definition::Func(func) => {
+                    references.insert(func.name.clone(), func.references.clone());
+                }
+                Definition::Class(class) => {
+                    for method in &class.methods {
+                        references.insert(method.name.clone(), method.references.clone());
+                    }
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(references["a"], vec!["b", "LIMIT"]);
+        assert!(references["b"].is_empty());
+        assert_eq!(references["run"], vec!["step", "a"]);
+        assert!(references["step"].is_empty());
+    }
+
+    #[test]
+    fn test_chunk_by_definitions() {
+        let source = r"// Adds numbers
+pub fn add(a: u32, b: u32) -> u32 {
+    a + b
+}
+
+pub fn sub(a: u32, b: u32) -> u32 {
+    a - b
+}
+
+pub fn long(a: u32) -> u32 {
+    let b = a + 1;
+    let c = b + 2;
+    let d = c + 3;
+    let e = d + 4;
+    e
+}
+
+pub fn mul(a: u32, b: u32) -> u32 {
+    a * b
+}
+";
+        let count_words = |text: &str| Ok(text.split_whitespace().count());
+        let max_tokens = 25;
+        let chunks = chunk_by_definitions("rust", source, max_tokens, count_words).unwrap();
+        for chunk in &chunks {
+            assert!(chunk.tokens <= max_tokens, "{chunk:?}");
+            assert_eq!(chunk.tokens, chunk.text.split_whitespace().count());
+        }
+        let joined: String = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(joined, source);
+
+        for name in ["add", "sub", "mul"] {
+            let start = source.find(&format!("pub fn {name}")).unwrap();
+            let end = start + source[start..].find("\n}\n").unwrap() + 3;
+            let definition = &source[start..end];
+            assert!(
+                chunks.iter().any(|chunk| chunk.text.contains(definition)),
+                "{name} was split"
+            );
+        }
+        let comment_chunk = chunks
+            .iter()
+            .find(|chunk| chunk.text.contains("// Adds numbers"))
+            .unwrap();
+        assert!(comment_chunk.text.contains("pub fn add"));
+
+        // `long` alone exceeds the limit and is cut at line boundaries
+        let long_chunks: Vec<_> = chunks
+            .iter()
+            .filter(|chunk| chunk.text.contains("let "))
+            .collect();
+        assert!(long_chunks.len() > 1);
+        assert_eq!(chunks[0].start_line, 1);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].end_line + 1, pair[1].start_line);
+        }
+
+        assert!(chunk_by_definitions("rust", source, 0, count_words).is_err());
+    }
+
+    #[test]
+    fn test_chunk_by_definitions_long_line() {
+        let values = (1..=30).map(|n| n.to_string()).collect::<Vec<_>>();
+        let source = format!(
+            "pub fn one() -> u32 {{\n    1\n}}\n\nconst DATA: [u32; 30] = [{}];\n",
+            values.join(", ")
+        );
+        let count_words = |text: &str| Ok(text.split_whitespace().count());
+        let max_tokens = 8;
+        let chunks = chunk_by_definitions("rust", &source, max_tokens, count_words).unwrap();
+        for chunk in &chunks {
+            assert!(chunk.tokens <= max_tokens, "{chunk:?}");
+            assert_eq!(chunk.tokens, chunk.text.split_whitespace().count());
+        }
+        let joined: String = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(joined, source);
+
+        let line_chunks: Vec<_> = chunks
+            .iter()
+            .filter(|chunk| chunk.start_line == 5)
+            .collect();
+        assert!(line_chunks.len() > 1, "{chunks:?}");
+        assert!(line_chunks.iter().all(|chunk| chunk.end_line == 5));
+        // Cut between values, not inside them
+        for chunk in &line_chunks[..line_chunks.len() - 1] {
+            assert!(chunk.text.ends_with(' '), "{chunk:?}");
+        }
+
+        // Even a counter that finds every character over the limit terminates
+        let chunks =
+            chunk_by_definitions("rust", "ab\n", 1, |text: &str| Ok(text.len() * 2)).unwrap();
+        let texts: Vec<_> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+        assert_eq!(texts, vec!["a", "b", "\n"]);
+    }
+
+    #[test]
+    fn test_zig() {
+        let source = r"
+          // This is a test comment
+          pub const TEST_CONST: u32 = 1;
+          pub var TEST_VAR: u32 = 2;
+          const INNER_TEST_CONST: u32 = 3;
+          var INNER_TEST_VAR: u32 = 4;
+          pub const TestStruct = struct {
+              test_field: []const u8,
+              test_field2: u64,
+
+              pub fn test_method(_: *TestStruct, a: u32, b: u32) u32 {
+                  return a + b;
+              }
+
+              fn inner_test_method(_: *TestStruct, a: u32, b: u32) u32 {
+                  return a + b;
+              }
+          };
+          const InnerTestStruct = struct {
+              test_field: []const u8,
+              test_field2: u64,
+
+              pub fn test_method(_: *InnerTestStruct, a: u32, b: u32) u32 {
+                  return a + b;
+              }
+
+              fn inner_test_method(_: *InnerTestStruct, a: u32, b: u32) u32 {
+                  return a + b;
+              }
+          };
+          pub const TestEnum = enum {
+              TestEnumField1,
+              TestEnumField2,
+          };
+          const InnerTestEnum = enum {
+              InnerTestEnumField1,
+              InnerTestEnumField2,
+          };
+
+          pub const TestUnion = union {
+              TestUnionField1: u32,
+              TestUnionField2: u64,
+          };
+
+          const InnerTestUnion = union {
+              InnerTestUnionField1: u32,
+              InnerTestUnionField2: u64,
+          };
+
+          pub fn test_fn(a: u32, b: u32) u32 {
+              const inner_var_in_func = 1;
+              const InnerStructInFunc = struct {
+                  c: u32,
+              };
+              _ = InnerStructInFunc;
+              return a + b + inner_var_in_func;
+          }
+          fn inner_test_fn(a: u32, b: u32) u32 {
+              return a + b;
+          }
+        ";
+
+        let definitions = extract_definitions("zig", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var TEST_CONST:u32;var TEST_VAR:u32;func test_fn() -> void;class TestStruct{func test_method(_: *TestStruct, a: u32, b: u32) -> void;var test_field:[]const u8;var test_field2:u64;};enum TestEnum{TestEnumField1;TestEnumField2;};union TestUnion{TestUnionField1;TestUnionField2;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_zig_import() {
+        let source = r#"
+          const std = @import("std");
+          pub const utils = @import("utils.zig");
+          const InnerConst: u32 = 1;
+
+          pub fn ArrayList(comptime T: type) type {
+              return struct {
+                  items: []T,
+              };
+          }
+        "#;
+
+        let definitions = extract_definitions("zig", source).unwrap();
+        let imports: Vec<_> = definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                Definition::Import(import) => Some((import.name.as_str(), import.path.as_str())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(imports, vec![("std", "std"), ("utils", "utils.zig")]);
+
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "use std;use utils.zig as utils;func ArrayList(comptime T: type) -> type;";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_go() {
+        let source = r#"
+        // This is a test comment
+        package main
+        import "fmt"
+        const TestConst string = "test"
+        const innerTestConst string = "test"
+        var TestVar string
+        var innerTestVar string
+        type TestStruct struct {
+            TestField string
+            innerTestField string
+        }
+        func (t *TestStruct) TestMethod(a int, b int) (int, error) {
+            var InnerVarInFunc int = 1
+            type InnerStructInFunc struct {
+                C int
+            }
+            return a + b, nil
+        }
+        func (t *TestStruct) innerTestMethod(a int, b int) (int, error) {
+            return a + b, nil
+        }
+        type innerTestStruct struct {
+            innerTestField string
+        }
+        func (t *innerTestStruct) testMethod(a int, b int) (int, error) {
+            return a + b, nil
+        }
+        func (t *innerTestStruct) innerTestMethod(a int, b int) (int, error) {
+            return a + b, nil
+        }
+        func TestFunc(a int, b int) (int, error) {
+            return a + b, nil
+        }
+        func innerTestFunc(a int, b int) (int, error) {
+            return a + b, nil
+        }
+        "#;
+        let definitions = extract_definitions("go", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var TestConst:string;var TestVar:string;func TestFunc(a int, b int) -> (int, error);class TestStruct{func TestMethod(a int, b int) -> (int, error);var TestField:string;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_tuple_returns() {
+        let returns = |language: &str, source: &str| {
+            let definitions = extract_definitions(language, source).unwrap();
+            definitions
+                .iter()
+                .filter_map(|definition| match definition {
+                    Definition::Func(func) => Some(func.return_type.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let go = r#"
+        package main
+
+        func Parse(s string) (int, error) {
+            return 0, nil
+        }
+
+        func Split(s string) (head, tail string, err error) {
+            return "", "", nil
+        }
+
+        func Single(s string) (n int) {
+            return 0
+        }
+        "#;
+        assert_eq!(
+            returns("go", go),
+            vec!["(int, error)", "(string, string, error)", "int"]
+        );
+
+        let rust = r"
+        pub fn parse(s: &str) -> (u32, String) {
+            (0, s.to_string())
+        }
+        ";
+        assert_eq!(returns("rust", rust), vec!["(u32, String)"]);
+
+        let python = r"
+from typing import Tuple
+
+def parse(s: str) -> Tuple[int, str]:
+    return 0, s
+
+def pair(s: str) -> tuple[int,str]:
+    return 0, s
+
+def many(s: str) -> Tuple[int, ...]:
+    return (0,)
+";
+        assert_eq!(
+            returns("python", python),
+            vec!["(int, str)", "(int, str)", "Tuple[int, ...]"]
+        );
+    }
+
+    #[test]
+    fn test_styled_components() {
+        let source = r#"
+        import styled, { css } from "styled-components";
+
+        const Btn = styled.button`
+          color: ${(props) => props.color};
+        `;
+
+        const Title = styled(Heading)`
+          font-size: 2em;
+        `;
+
+        const padding = 4;
+
+        export const Card = styled.div`
+          padding: ${padding}px;
+        `;
+        "#;
+
+        for language in ["javascript", "typescript"] {
+            let definitions = extract_definitions(language, source).unwrap();
+            let stringified = stringify_definitions(&definitions);
+            println!("{stringified}");
+            let expected = "var Btn:styled.button;var Title:styled(Heading);var Card:styled.div;";
+            assert_eq!(stringified, expected);
+        }
+    }
+
+    #[test]
+    fn test_python() {
+        let source = r#"
+        # This is a test comment
+        test_var: str = "test"
+        class TestClass:
+            def __init__(self, a, b):
+                self.a = a
+                self.b = b
+            def test_method(self, a: int, b: int) -> int:
+                inner_var_in_method: int = 1
+                return a + b
+        def test_func(a: int, b: int) -> int:
+            inner_var_in_func: str = "test"
+            class InnerClassInFunc:
+                def __init__(self, a, b):
+                    self.a = a
+                    self.b = b
+                def test_method(self, a: int, b: int) -> int:
+                    return a + b
+            def inne_func_in_func(a: int, b: int) -> int:
+                return a + b
+            return a + b
+        "#;
+        let definitions = extract_definitions("python", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        // `test_func` is a pytest test, so it is reported with the tests instead
+        let expected = "var test_var:str;class TestClass{func __init__(self, a, b) -> void;func test_method(self, a: int, b: int) -> int;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_typescript() {
+        let source = r#"
+        // This is a test comment
+        export const testVar: string = "test";
+        const innerTestVar: string = "test";
+        export class TestClass {
+            a: number;
+            b: number;
+            constructor(a: number, b: number) {
+                this.a = a;
+                this.b = b;
+            }
+            testMethod(a: number, b: number): number {
+                const innerConstInMethod: number = 1;
+                function innerFuncInMethod(a: number, b: number): number {
+                    return a + b;
+                }
+                return a + b;
+            }
+        }
+        class InnerTestClass {
+            a: number;
+            b: number;
+        }
+        export function testFunc(a: number, b: number) {
+            const innerConstInFunc: number = 1;
+            function innerFuncInFunc(a: number, b: number): number {
+                return a + b;
+            }
+            return a + b;
+        }
+        export const testFunc2 = (a: number, b: number) => {
+            return a + b;
+        }
+        export const testFunc3 = (a: number, b: number): number => {
+            return a + b;
+        }
+        function innerTestFunc(a: number, b: number) {
+            return a + b;
+        }
+        "#;
+        let options = ExtractOptions {
+            include_anonymous: true,
+            ..Default::default()
+        };
+        let definitions = extract_definitions_with_options("typescript", source, &options).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var testVar:string;func testFunc(a: number, b: number) -> void;func testFunc2(a: number, b: number) -> void;func testFunc3(a: number, b: number) -> number;class TestClass{func constructor(a: number, b: number) -> void;func testMethod(a: number, b: number) -> number;var a:number;var b:number;};"
+;
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_javascript() {
+        let source = r#"
+        // This is a test comment
+        export const testVar = "test";
+        const innerTestVar = "test";
+        export class TestClass {
+            constructor(a, b) {
+                this.a = a;
+                this.b = b;
+            }
+            testMethod(a, b) {
+                const innerConstInMethod = 1;
+                function innerFuncInMethod(a, b) {
+                    return a + b;
+                }
+                return a + b;
+            }
+        }
+        class InnerTestClass {
+            constructor(a, b) {
+                this.a = a;
+                this.b = b;
+            }
+        }
+        export const testFunc = function(a, b) {
+            const innerConstInFunc = 1;
+            function innerFuncInFunc(a, b) {
+                return a + b;
+            }
+            return a + b;
+        }
+        export const testFunc2 = (a, b) => {
+            return a + b;
+        }
+        export const testFunc3 = (a, b) => a + b;
+        function innerTestFunc(a, b) {
+            return a + b;
+        }
+        "#;
+        let options = ExtractOptions {
+            include_anonymous: true,
+            ..Default::default()
+        };
+        let definitions = extract_definitions_with_options("javascript", source, &options).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var testVar;var testFunc;func testFunc2(a, b) -> void;func testFunc3(a, b) -> void;class TestClass{func constructor(a, b) -> void;func testMethod(a, b) -> void;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_javascript_include_anonymous() {
+        let source = r"
+        export const f = () => {};
+        export function g(a) {
+            return a;
+        }
+        ";
+        let definitions = extract_definitions("javascript", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        assert_eq!(stringified, "func g(a) -> void;");
+
+        let options = ExtractOptions {
+            include_anonymous: true,
+            ..Default::default()
+        };
+        let definitions = extract_definitions_with_options("javascript", source, &options).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        assert_eq!(stringified, "func f() -> void;func g(a) -> void;");
+
+        let options = StringifyOptions {
+            include_anonymous: true,
+            ..Default::default()
+        };
+        let stringified = get_definitions_string("javascript", source, &options).unwrap();
+        assert_eq!(stringified, "func f() -> void;func g(a) -> void;");
+    }
+
+    #[test]
+    fn test_ruby() {
+        let source = r#"
+        # This is a test comment
+        test_var = "test"
+        def test_func(a, b)
+            inner_var_in_func = "test"
+            class InnerClassInFunc
+                attr_accessor :a, :b
+                def initialize(a, b)
+                    @a = a
+                    @b = b
+                end
+                def test_method(a, b)
+                    return a + b
+                end
+            end
+            return a + b
+        end
+        class TestClass
+            attr_accessor :a, :b
+            def initialize(a, b)
+                @a = a
+                @b = b
+            end
+            def test_method(a, b)
+                inner_var_in_method = 1
+                def inner_func_in_method(a, b)
+                    return a + b
+                end
+                return a + b
+            end
+        end
+        "#;
+        let definitions = extract_definitions("ruby", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        // FIXME:
+        let expected = "var test_var;func test_func(a, b) -> void;class InnerClassInFunc{func initialize(a, b) -> void;func test_method(a, b) -> void;};class TestClass{func initialize(a, b) -> void;func test_method(a, b) -> void;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_ruby2() {
+        let source = r"
+        # frozen_string_literal: true
+
+        require('jwt')
+
+        top_level_var = 1
+
+        def top_level_func
+          inner_var_in_func = 2
+        end
+
+        module A
+          module B
+            @module_var = :foo
+
+            def module_method
+              @module_var
+            end
+
+            class C < Base
+              TEST_CONST = 1
+              @class_var = :bar
+              attr_accessor :a, :b
+
+              def initialize(a, b)
+                @a = a
+                @b = b
+                super
+              end
+
+              def bar
+                inner_var_in_method = 1
+                true
+              end
+
+              private
+
+              def baz(request, params)
+                auth_header = request.headers['Authorization']
+                parts = auth_header.try(:split, /\s+/)
+                JWT.decode(parts.last)
+              end
+            end
+          end
+        end
+        ";
+        let definitions = extract_definitions("ruby", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var top_level_var;func top_level_func() -> void;module A{};module A::B{func module_method() -> void;var @module_var;};class A::B::C{func initialize(a, b) -> void;func bar() -> void;private func baz(request, params) -> void;var TEST_CONST;var @class_var;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_lua() {
+        let source = r#"
+        -- This is a test comment
+        local test_var = "test"
+        function test_func(a, b)
+            local inner_var_in_func = 1
+            function inner_func_in_func(a, b)
+                return a + b
+            end
+            return a + b
+        end
+        "#;
+        let definitions = extract_definitions("lua", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var test_var;func test_func(a, b) -> void;";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_c() {
+        let source = r"
+        #include <stdio.h>
+
+        int test_var = 2;
+        extern int extern_test_var;
+
+        int TestFunc(bool b) { return b ? 42 : -1; }
+        extern void ExternTestFunc();
+
+        struct Foo {
+            int a;
+            int b;
+        };
+
+        typedef int my_int;
+        ";
+        let definitions = extract_definitions("c", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var extern int extern_test_var;:int;var extern void ExternTestFunc();:void;class Foo{var int a;:int;var int b;:int;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_cpp() {
+        let source = r"
+        // This is a test comment
+        #include <iostream>
+
+        namespace {
+        constexpr int TEST_CONSTEXPR = 1;
+        const int TEST_CONST = 1;
+        }; // namespace
+
+        int test_var = 2;
+
+        int TestFunc(bool b) { return b ? 42 : -1; }
+
+        template <typename T> class TestClass {
+        public:
+          TestClass();
+          TestClass(T a, T b);
+          ~TestClass();
+          bool operator==(const TestClass &other);
+          T testMethod(T x, T y) { return x + y; }
+          T c;
+
+        private:
+          void privateMethod();
+          T a = 0;
+          T b;
+        };
+
+        struct TestStruct {
+        public:
+          TestStruct(int a, int b);
+          ~TestStruct();
+          bool operator==(const TestStruct &other);
+          int testMethod(int x, int y) { return x + y; }
+          static int c;
+
+        private:
+          int a = 0;
+          int b;
+        };
+
+        bool TestStruct::operator==(const TestStruct &other) { return true; }
+
+        int TestStruct::c = 0;
+
+        int testFunction(int a, int b) { return a + b; }
+
+        namespace TestNamespace {
+        class InnerClass {
+        public:
+          bool innerMethod(int a) const;
+        };
+        bool InnerClass::innerMethod(int a) const { return doSomething(a * 2); }
+        } // namespace TestNamespace
+
+        namespace OtherNamespace {
+        class InnerClass {
+        public:
+          void otherMethod();
+        };
+        } // namespace OtherNamespace
+
+        enum TestEnum { ENUM_VALUE_1, ENUM_VALUE_2 };
+        ";
+        let definitions = extract_definitions("cpp", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var TEST_CONSTEXPR:int;var TEST_CONST:int;var test_var:int;func TestFunc(bool b) -> int;class TestClass{func TestClass() -> TestClass;func operator==(const TestClass &other) -> bool;func testMethod(T x, T y) -> T;func privateMethod() -> void;func TestClass(T a, T b) -> TestClass;var c:T;var a:T;var b:T;};class TestStruct{func TestStruct(int a, int b) -> void;func operator==(const TestStruct &other) -> bool;func testMethod(int x, int y) -> int;var c:int;var a:int;var b:int;};func testFunction(int a, int b) -> int;namespace TestNamespace{class InnerClass{func innerMethod(int a) -> bool;};};namespace OtherNamespace{class InnerClass{func otherMethod() -> void;};};enum TestEnum{ENUM_VALUE_1;ENUM_VALUE_2;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let source = r"
+        namespace Outer {
+        namespace Inner {
+        class Widget {
+        public:
+          void draw();
+        };
+        } // namespace Inner
+
+        int helper(int a) { return a; }
+        } // namespace Outer
+
+        int main() { return 0; }
+
+        void Outer::Inner::Widget::draw() {}
+        ";
+        let definitions = extract_definitions("cpp", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "namespace Outer{namespace Inner{class Widget{func draw() -> void;};};func helper(int a) -> int;};func main() -> int;";
+        assert_eq!(stringified, expected);
+
+        // Same-named classes of different namespaces keep their own members
+        let source = r"
+      namespace Shop.Orders
+      {
+        public class Client
+        {
+          public int Total() => 0;
+        }
+      }
+
+      namespace Shop.Billing
+      {
+        public class Client
+        {
+          public void Charge(int amount) {}
+        }
+      }
+
+      public class Program {}
+      ";
+        let definitions = extract_definitions("csharp", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "namespace Shop.Orders{class Client{func Total() -> int;};};namespace Shop.Billing{class Client{func Charge(int amount) -> void;};};class Program{};";
+        assert_eq!(stringified, expected);
+
+        let source = r"
+        <?php
+        namespace App\Models;
+
+        class User {
+            public function name() {
+            }
+        }
+        ";
+        let definitions = extract_definitions("php", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = r"namespace App\Models{class User{func name() -> void;};};";
+        assert_eq!(stringified, expected);
+
+        let exports: Vec<String> = list_exports("php", source)
+            .unwrap()
+            .into_iter()
+            .map(|export| export.name)
+            .collect();
+        assert_eq!(
+            exports,
+            vec![r"App\Models", r"App\Models.User", r"App\Models.User.name"]
+        );
+    }
+
+    #[test]
+    fn test_scala() {
+        let source = r#"
+        object Main {
+          def main(args: Array[String]): Unit = {
+            println("Hello, World!")
+          }
+        }
+
+        class TestClass {
+          val testVal: String = "test"
+          var testVar = 42
+
+          def testMethod(a: Int, b: Int): Int = {
+            a + b
+          }
+        }
+
+        // braceless syntax is also supported
+        trait TestTrait:
+          def abstractMethod(x: Int): Int
+          def concreteMethod(y: Int): Int = y * 2
+
+        case class TestCaseClass(name: String, age: Int)
+
+        enum TestEnum {
+          case First, Second, Third
+        }
+
+        val foo: TestClass = ???
+        "#;
+
+        let definitions = extract_definitions("scala", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var foo:TestClass;class Main{func main(args: Array[String]) -> Unit;};class TestCaseClass{};class TestClass{func testMethod(a: Int, b: Int) -> Int;var testVal:String;var testVar;};class TestTrait{func abstractMethod(x: Int) -> Int;func concreteMethod(y: Int) -> Int;};enum TestEnum{First;Second;Third;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_elixir() {
+        let source = r#"
+        defmodule TestModule do
+          @moduledoc """
+          This is a test module
+          """
+
+          @test_const "test"
+          @other_const 123
+
+          def test_func(a, b) do
+            a + b
+          end
+
+          defp private_func(x) do
+            x * 2
+          end
+
+          defmacro test_macro(expr) do
+            quote do
+              unquote(expr)
+            end
+          end
+        end
+
+        defmodule AnotherModule do
+          def another_func() do
+            :ok
+          end
+        end
+        "#;
+        let definitions = extract_definitions("elixir", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected =
+            "module AnotherModule{func another_func();};module TestModule{func test_func(a, b);};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_csharp() {
+        let source = r"
+      using System;
+
+      namespace TestNamespace;
+
+      public class TestClass(TestDependency m)
+      {
+
+        private int PrivateTestProperty { get; set; }
+
+        private int _privateTestField;
+
+        public int TestProperty { get; set; }
+
+        public string TestField;
+
+        public TestClass()
+        {
+          TestProperty = 0;
+        }
+
+
+        public void TestMethod(int a, int b)
+        {
+          var innerVarInMethod = 1;
+          return a + b;
+        }
+
+        public int TestMethod(int a, int b, int c) => a + b + c;
+
+        private void PrivateMethod()
+        {
+          return;
+        }
+
+        public class MyInnerClass(InnerClassDependency m) {}
+
+        public record MyInnerRecord(int a);
+      }
+
+      public record TestRecord(int a, int b);
+
+      public enum TestEnum { Value1, Value2 }
+      ";
+
+        let definitions = extract_definitions("csharp", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "namespace TestNamespace{class TestClass{func TestClass(TestDependency m) -> TestClass;func TestClass() -> TestClass;func TestMethod(int a, int b) -> void;func TestMethod(int a, int b, int c) -> int;var TestProperty:int;var TestField:string;};class MyInnerClass{func MyInnerClass(InnerClassDependency m) -> MyInnerClass;};class MyInnerRecord{func MyInnerRecord(int a) -> MyInnerRecord;};class TestRecord{func TestRecord(int a, int b) -> TestRecord;};enum TestEnum{Value1;Value2;};};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_csharp_modifiers() {
+        let source = r"
+      public class Parser
+      {
+        public bool TryParse(string text, out int result)
+        {
+          result = 0;
+          return true;
+        }
+
+        public T Create<T>(ref int count, in double scale, params string[] names)
+            where T : class, new()
+        {
+          return new T();
+        }
+
+        public void Swap<TKey, TValue>(TKey key, TValue value) where TKey : notnull where TValue : struct {}
+      }
+      ";
+
+        let definitions = extract_definitions("csharp", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "class Parser{func TryParse(string text, out int result) -> bool;func Create<T>(ref int count, in double scale, params string[] names) -> T where T : class, new();func Swap<TKey, TValue>(TKey key, TValue value) -> void where TKey : notnull where TValue : struct;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_swift() {
+        let source = r#"
+            import Foundation
+
+            private var myVariable = 0
+            public var myPublicVariable = 0
+
+            struct MyStruct {
+              public var myPublicVariable = 0
+              private var myPrivateVariable = 0
+
+              func myPublicMethod(with parameter: Int) -> {
+              }
+
+              private func myPrivateMethod(with parameter: Int) -> {
+              }
+            }
+
+            class MyClass {
+                public var myPublicVariable = 0
+                private var myPrivateVariable = 0
+
+                init(myParameter: Int, myOtherParameter: Int) {
+                }
+
+                func myPublicMethod(with parameter: Int) -> {
+                }
+
+                private func myPrivateMethod(with parameter: Int) -> {
+                }
+
+                func myMethod() {
+                    print("Hello, world!")
+                }
+            }
+        "#;
+
+        let definitions = extract_definitions("swift", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var myPublicVariable;class MyClass{func init() -> void;func myPublicMethod() -> void;func myMethod() -> void;var myPublicVariable;};class MyStruct{func myPublicMethod() -> void;var myPublicVariable;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_php() {
+        let source = r#"
+        <?php
+        class MyClass {
+            public $myPublicVariable = 0;
+            private $myPrivateVariable = 0;
+
+            public function myPublicMethod($parameter) {
+            }
+
+            private function myPrivateMethod($parameter) {
+            }
+
+            function myMethod() {
+                echo "Hello, world!";
+            }
+        }
+        ?>
+        "#;
+
+        let definitions = extract_definitions("php", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "class MyClass{func myPublicMethod($parameter) -> void;func myPrivateMethod($parameter) -> void;func myMethod() -> void;var public $myPublicVariable = 0;;var private $myPrivateVariable = 0;;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_java() {
+        let source = r#"
+        public class MyClass {
+            public void myPublicMethod(String parameter) {
+                System.out.println("Hello, world!");
+            }
+
+            private void myPrivateMethod(String parameter) {
+                System.out.println("Hello, world!");
+            }
+
+            void myMethod() {
+                System.out.println("Hello, world!");
+            }
+        }
+        "#;
+
+        let definitions = extract_definitions("java", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected =
+            "class MyClass{func myPublicMethod(String parameter) -> void;func myMethod() -> void;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_java_param_annotations() {
+        let source = r#"
+        public class SearchController {
+            public void search(@RequestParam String q,
+                                       @RequestParam(defaultValue = "a  b") int limit) {
+                service.search(q, limit);
+            }
+        }
+        "#;
+
+        let definitions = extract_definitions("java", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "class SearchController{func search(@RequestParam String q, @RequestParam(defaultValue = \"a  b\") int limit) -> void;};";
+        assert_eq!(stringified, expected);
+    }
 
-                    if !name.is_empty() && language == "go" && !is_first_letter_uppercase(&name) {
-                        continue;
-                    }
+    #[test]
+    fn test_rust_attributes() {
+        let source = r"
+        pub struct Config {
+            #[serde(default)]
+            // Seconds
+            pub timeout: u64,
+            pub name: String,
+        }
+        pub fn run(#[allow(unused)] verbose: bool,
+                   config: Config) {}
+        ";
 
-                    if language == "csharp" {
-                        let csharp_visibility = find_descendant_by_type(&node, "modifier");
-                        if csharp_visibility.is_none() && !csharp_is_primary_constructor(&node) {
-                            continue;
-                        }
-                        if csharp_visibility.is_some() {
-                            let csharp_visibility_text = csharp_visibility
-                                .unwrap()
-                                .utf8_text(source.as_bytes())
-                                .unwrap();
-                            if csharp_visibility_text == "private" {
-                                continue;
-                            }
-                        }
-                    }
+        let definitions = extract_definitions("rust", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "func run(#[allow(unused)] verbose: bool, config: Config) -> void;class Config{#[serde(default)] var timeout:u64;var name:String;};";
+        assert_eq!(stringified, expected);
+    }
 
-                    let mut params_node = node
-                        .child_by_field_name("parameters")
-                        .or_else(|| find_descendant_by_type(&node, "parameter_list"));
+    #[test]
+    fn test_lua_error_prefix() {
+        let mut state = State::default();
+        let error = state.session(42).err().unwrap();
+        assert!(matches!(error, Error::NotFound(_)));
+        assert_eq!(
+            error.into_lua(LUA_MODULE, "project_map").to_string(),
+            LuaError::RuntimeError(
+                "avante_repo_map: project_map: Project session 42 is not open".to_string()
+            )
+            .to_string()
+        );
+
+        let error = get_definitions_query("brainfuck").err().unwrap();
+        assert!(matches!(error, Error::Unsupported(_)));
+        let error = chunk_by_definitions("rust", "", 0, |_| Ok(0)).unwrap_err();
+        assert!(matches!(error, Error::InvalidArgument(_)));
+        let error = chunk_by_definitions("rust", "fn a() {}\n", 10, |_| Err("boom".to_string()))
+            .unwrap_err()
+            .into_lua(LUA_MODULE, "chunk_by_definitions");
+        assert!(error
+            .to_string()
+            .contains("avante_repo_map: chunk_by_definitions: count_tokens failed: boom"));
+    }
 
-                    let zig_function_node = find_ancestor_by_type(&node, "function_declaration");
-                    if language == "zig" {
-                        params_node = zig_function_node
-                            .as_ref()
-                            .and_then(|n| find_child_by_type(n, "parameters"));
-                    }
-                    let ex_function_node = find_ancestor_by_type(&node, "call");
-                    if language == "elixir" {
-                        params_node = ex_function_node
-                            .as_ref()
-                            .and_then(|n| find_child_by_type(n, "arguments"));
-                    }
+    #[test]
+    fn test_kotlin() {
+        let source = r#"
+        package com.example
 
-                    let params = params_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("()");
-                    let mut return_type_node = match language {
-                        "cpp" => node.child_by_field_name("type"),
-                        "csharp" => node.child_by_field_name("returns"),
-                        _ => node.child_by_field_name("return_type"),
-                    };
-                    if language == "cpp" {
-                        let class_specifier_node = find_ancestor_by_type(&node, "class_specifier");
-                        let type_identifier_node =
-                            class_specifier_node.and_then(|n| n.child_by_field_name("name"));
+        data class User(val name: String, private val age: Int)
 
-                        if let Some(type_identifier_node) = type_identifier_node {
-                            let type_identifier_text =
-                                type_identifier_node.utf8_text(source.as_bytes()).unwrap();
-                            if name == type_identifier_text {
-                                return_type_node = Some(type_identifier_node);
-                            }
-                        }
-                    }
-                    if language == "csharp" {
-                        let type_specifier_node = csharp_find_parent_type_node(&node);
-                        let type_identifier_node =
-                            type_specifier_node.and_then(|n| n.child_by_field_name("name"));
+        class Greeter(private val prefix: String) {
+            val greeting: String = "hi"
+            private val secret: Int = 1
 
-                        if let Some(type_identifier_node) = type_identifier_node {
-                            let type_identifier_text =
-                                type_identifier_node.utf8_text(source.as_bytes()).unwrap();
-                            if name == type_identifier_text {
-                                return_type_node = Some(type_identifier_node);
-                            }
-                        }
-                    }
-                    if return_type_node.is_none() {
-                        return_type_node = node.child_by_field_name("result");
-                    }
-                    let mut return_type = "void".to_string();
-                    if language == "elixir" {
-                        return_type = String::new();
-                    }
-                    if return_type_node.is_some() {
-                        return_type = get_node_type(&return_type_node.unwrap(), source.as_bytes());
-                        if return_type.is_empty() {
-                            return_type = return_type_node
-                                .unwrap()
-                                .utf8_text(source.as_bytes())
-                                .unwrap_or("void")
-                                .to_string();
-                        }
-                    }
+            fun greet(user: User): String {
+                return prefix + user.name
+            }
 
-                    let impl_item_node = find_ancestor_by_type(&node, "impl_item");
-                    let receiver_node = node.child_by_field_name("receiver");
-                    let class_name = if language == "zig" {
-                        zig_find_parent_variable_declaration_name(&node, source.as_bytes())
-                            .unwrap_or_default()
-                    } else if language == "elixir" {
-                        ex_find_parent_module_declaration_name(&node, source.as_bytes())
-                            .unwrap_or_default()
-                    } else if language == "cpp" {
-                        find_ancestor_by_type(&node, "class_specifier")
-                            .or_else(|| find_ancestor_by_type(&node, "struct_specifier"))
-                            .and_then(|n| n.child_by_field_name("name"))
-                            .and_then(|n| n.utf8_text(source.as_bytes()).ok())
-                            .unwrap_or("")
-                            .to_string()
-                    } else if language == "csharp" {
-                        csharp_find_parent_type_node(&node)
-                            .and_then(|n| n.child_by_field_name("name"))
-                            .and_then(|n| n.utf8_text(source.as_bytes()).ok())
-                            .unwrap_or("")
-                            .to_string()
-                    } else if language == "ruby" {
-                        ruby_find_parent_module_declaration_name(&node, source.as_bytes())
-                            .unwrap_or_default()
-                    } else if let Some(impl_item) = impl_item_node {
-                        let impl_type_node = impl_item.child_by_field_name("type");
-                        impl_type_node
-                            .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                            .unwrap_or("")
-                            .to_string()
-                    } else if let Some(receiver) = receiver_node {
-                        let type_identifier_node =
-                            find_descendant_by_type(&receiver, "type_identifier");
-                        type_identifier_node
-                            .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                            .unwrap_or("")
-                            .to_string()
-                    } else {
-                        get_closest_ancestor_name(&node, source).to_string()
-                    };
+            private fun helper() {}
 
-                    if language == "go" && !is_first_letter_uppercase(&class_name) {
-                        continue;
-                    }
+            companion object {
+                fun create(): Greeter = Greeter("Hello")
+            }
+        }
 
-                    ensure_class_def(language, &class_name, &mut class_def_map);
-                    let class_def = class_def_map.get_mut(&class_name).unwrap();
+        object Registry {
+            fun register(name: String) {}
+        }
 
-                    let accessibility_modifier_node =
-                        find_descendant_by_type(&node, "accessibility_modifier");
-                    let accessibility_modifier = if language == "ruby" {
-                        if ruby_method_is_private(&node, source.as_bytes()) {
-                            "private"
-                        } else {
-                            ""
-                        }
-                    } else {
-                        accessibility_modifier_node
-                            .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                            .unwrap_or("")
-                    };
+        fun String.shout(): String = this.uppercase()
 
-                    let func = Func {
-                        name: name.to_string(),
-                        params: params.to_string(),
-                        return_type: return_type.to_string(),
-                        accessibility_modifier: if accessibility_modifier.is_empty() {
-                            None
-                        } else {
-                            Some(accessibility_modifier.to_string())
-                        },
-                    };
-                    class_def.borrow_mut().methods.push(func);
-                }
-                "class_assignment" => {
-                    let visibility_modifier_node =
-                        find_descendant_by_type(&node, "visibility_modifier");
-                    let visibility_modifier = visibility_modifier_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("");
-                    if language == "swift" || language == "java" {
-                        if visibility_modifier.contains("private") {
-                            continue;
-                        }
-                    }
-                    if language == "java" {
-                        let modifier_node = find_descendant_by_type(&node, "modifiers");
-                        if modifier_node.is_some() {
-                            let modifier_text =
-                                modifier_node.unwrap().utf8_text(source.as_bytes()).unwrap();
-                            if modifier_text.contains("private") {
-                                continue;
-                            }
-                        }
-                    }
-                    if language == "rust" && !visibility_modifier.contains("pub") {
-                        continue;
-                    }
-                    let left_node = node.child_by_field_name("left");
-                    let left = left_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("");
-                    let value_type = get_node_type(&node, source.as_bytes());
-                    let mut class_name = get_closest_ancestor_name(&node, source);
-                    if !class_name.is_empty() {
-                        if language == "ruby" {
-                            if let Some(namespaced_name) =
-                                ruby_find_parent_module_declaration_name(&node, source.as_bytes())
-                            {
-                                class_name = namespaced_name;
-                            }
-                        } else if language == "go" && !is_first_letter_uppercase(&class_name) {
-                            continue;
-                        }
-                    }
-                    if class_name.is_empty() {
-                        continue;
-                    }
-                    ensure_class_def(language, &class_name, &mut class_def_map);
-                    let class_def = class_def_map.get_mut(&class_name).unwrap();
-                    let variable = Variable {
-                        name: left.to_string(),
-                        value_type: value_type.to_string(),
-                    };
-                    class_def.borrow_mut().properties.push(variable);
-                }
-                "class_variable" => {
-                    // TODO: C++: Skip private/protected class/struct variables
-                    let visibility_modifier_node =
-                        find_descendant_by_type(&node, "visibility_modifier");
-                    let visibility_modifier = visibility_modifier_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("");
-                    if language == "rust" && !visibility_modifier.contains("pub") {
-                        continue;
-                    }
+        private fun hidden() {}
 
-                    if language == "swift" || language == "java" {
-                        if visibility_modifier.contains("private") {
-                            continue;
-                        }
-                    }
+        val VERSION: String = "1.0"
+        "#;
 
-                    if language == "java" {
-                        let modifier_node = find_descendant_by_type(&node, "modifiers");
-                        if modifier_node.is_some() {
-                            let modifier_text =
-                                modifier_node.unwrap().utf8_text(source.as_bytes()).unwrap();
-                            if modifier_text.contains("private") {
-                                continue;
-                            }
-                        }
-                    }
+        let definitions = extract_definitions("kotlin", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "func String.shout() -> String;var VERSION:String;class Greeter{func greet(user: User) -> String;func create() -> Greeter;var greeting:String;};object Registry{func register(name: String) -> Unit;};data class User{var name:String;};";
+        assert_eq!(stringified, expected);
+    }
 
-                    let value_type = get_node_type(&node, source.as_bytes());
+    #[test]
+    fn test_dart() {
+        let source = r"
+        mixin Walker {
+          void walk(int steps) {}
+        }
 
-                    if language == "zig" {
-                        // when top level class is not public, skip
-                        if !zig_is_variable_declaration_public(&node, source.as_bytes()) {
-                            continue;
-                        }
-                    }
+        class Animal with Walker {
+          final String name;
+          int _age = 0;
 
-                    let mut class_name = get_closest_ancestor_name(&node, source);
-                    if language == "cpp" {
-                        class_name = find_ancestor_by_type(&node, "class_specifier")
-                            .or_else(|| find_ancestor_by_type(&node, "struct_specifier"))
-                            .and_then(|n| n.child_by_field_name("name"))
-                            .and_then(|n| n.utf8_text(source.as_bytes()).ok())
-                            .unwrap_or("")
-                            .to_string();
-                    }
+          Animal(this.name);
+          Animal.named(String name) : this(name);
+          Animal._internal() : name = 'x';
 
-                    if language == "csharp" {
-                        let csharp_visibility = find_descendant_by_type(&node, "modifier");
-                        if csharp_visibility.is_none() {
-                            continue;
-                        }
-                        let csharp_visibility_text = csharp_visibility
-                            .unwrap()
-                            .utf8_text(source.as_bytes())
-                            .unwrap();
-                        if csharp_visibility_text == "private" {
-                            continue;
-                        }
-                    }
+          String speak(String sound) {
+            return '$name says $sound';
+          }
 
-                    if language == "zig" {
-                        class_name =
-                            zig_find_parent_variable_declaration_name(&node, source.as_bytes())
-                                .unwrap_or_default();
-                    }
-                    if !class_name.is_empty()
-                        && language == "go"
-                        && !is_first_letter_uppercase(&class_name)
-                    {
-                        continue;
-                    }
-                    if class_name.is_empty() {
-                        continue;
-                    }
-                    if !name.is_empty() && language == "go" && !is_first_letter_uppercase(&name) {
-                        continue;
-                    }
-                    ensure_class_def(language, &class_name, &mut class_def_map);
-                    let class_def = class_def_map.get_mut(&class_name).unwrap();
-                    let variable = Variable {
-                        name: name.to_string(),
-                        value_type: value_type.to_string(),
-                    };
-                    class_def.borrow_mut().properties.push(variable);
-                }
-                "function" | "arrow_function" => {
-                    let visibility_modifier_node =
-                        find_descendant_by_type(&node, "visibility_modifier");
-                    let visibility_modifier = visibility_modifier_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("");
+          void _secret() {}
+        }
+
+        class _Hidden {}
+
+        String greet(String name) {
+          return 'Hello $name';
+        }
+
+        void _helper() {}
+        ";
+
+        let definitions = extract_definitions("dart", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "func greet(String name) -> String;class Animal{func Animal(this.name) -> Animal;func Animal.named(String name) -> Animal;func speak(String sound) -> String;var name:String;};mixin Walker{func walk(int steps) -> void;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_dart_widget() {
+        let source = r"
+        enum Status { idle, loading, done }
+
+        enum _Phase { start, end }
+
+        class CounterWidget extends StatelessWidget {
+          final int count;
+          final String _label = 'count';
+
+          const CounterWidget({Key? key, required this.count}) : super(key: key);
+          CounterWidget.zero({Key? key}) : this(key: key, count: 0);
+
+          factory CounterWidget.fromJson(Map<String, dynamic> json) {
+            return CounterWidget(count: json['count']);
+          }
+
+          @override
+          Widget build(BuildContext context) {
+            return Text('$_label: $count');
+          }
+        }
+        ";
+
+        let definitions = extract_definitions("dart", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "class CounterWidget{func CounterWidget({Key? key, required this.count}) -> CounterWidget;func CounterWidget.zero({Key? key}) -> CounterWidget;func CounterWidget.fromJson(Map<String, dynamic> json) -> CounterWidget;func build(BuildContext context) -> Widget;var count:int;};enum Status{idle;loading;done;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_haskell() {
+        let source = r"
+module Shapes (Shape (..), Name (..), Describable (..), area, scale) where
+
+data Shape
+  = Circle Double
+  | Rect { width :: Double, height :: Double }
+
+newtype Name = Name String
+
+class Describable a where
+  describe :: a -> String
+  label :: a -> Name
+
+area :: Shape -> Double
+area (Circle r) = pi * r * r
+area (Rect w h) = w * h
+
+scale :: Double -> Shape -> Shape
+scale k (Circle r) = Circle (k * r)
+scale _ s = s
 
-                    if language == "swift" || language == "java" {
-                        if visibility_modifier.contains("private") {
-                            continue;
-                        }
+double :: Num a => a -> a
+double x = x * 2
 
-                        if node.parent().is_some() {
-                            continue;
-                        }
-                    }
+origin :: Shape
+origin = Circle 0
 
-                    if language == "java" {
-                        let modifier_node = find_descendant_by_type(&node, "modifiers");
-                        if modifier_node.is_some() {
-                            let modifier_text =
-                                modifier_node.unwrap().utf8_text(source.as_bytes()).unwrap();
-                            if modifier_text.contains("private") {
-                                continue;
-                            }
-                        }
-                    }
+helper x [] = x
+helper x ys = x + sum ys
+";
 
-                    if language == "rust" && !visibility_modifier.contains("pub") {
-                        continue;
-                    }
+        let definitions = extract_definitions("haskell", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "func area(Shape) -> Double;func scale(Double, Shape) -> Shape;func double(a) -> a;func origin() -> Shape;func helper(x, []);class Describable{func describe(a) -> String;func label(a) -> Name;};enum Name{Name:String;};enum Shape{Circle:Double;Rect:{ width :: Double, height :: Double };};";
+        assert_eq!(stringified, expected);
+    }
 
-                    if language == "zig" {
-                        let variable_declaration_text =
-                            node.utf8_text(source.as_bytes()).unwrap_or("");
-                        if !variable_declaration_text.contains("pub") {
-                            continue;
-                        }
-                    }
+    #[test]
+    fn test_ocaml() {
+        let source = r#"
+type shape = Circle of float | Rect of float * float
 
-                    if !name.is_empty() && language == "go" && !is_first_letter_uppercase(&name) {
-                        continue;
-                    }
-                    let impl_item_node = find_ancestor_by_type(&node, "impl_item");
-                    if impl_item_node.is_some() {
-                        continue;
-                    }
-                    let class_specifier_node = find_ancestor_by_type(&node, "class_specifier");
-                    if class_specifier_node.is_some() {
-                        continue;
-                    }
-                    let struct_specifier_node = find_ancestor_by_type(&node, "struct_specifier");
-                    if struct_specifier_node.is_some() {
-                        continue;
-                    }
-                    let function_node = find_ancestor_by_type(&node, "function_declaration")
-                        .or_else(|| find_ancestor_by_type(&node, "function_definition"));
-                    if function_node.is_some() {
-                        continue;
-                    }
-                    let params_node = node
-                        .child_by_field_name("parameters")
-                        .or_else(|| find_descendant_by_type(&node, "parameter_list"));
-                    let params = params_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("()");
-
-                    let mut return_type = "void".to_string();
-                    let return_type_node = match language {
-                        "cpp" => node.child_by_field_name("type"),
-                        _ => node
-                            .child_by_field_name("return_type")
-                            .or_else(|| node.child_by_field_name("result")),
-                    };
-                    if return_type_node.is_some() {
-                        return_type = get_node_type(&return_type_node.unwrap(), source.as_bytes());
-                        if return_type.is_empty() {
-                            return_type = return_type_node
-                                .unwrap()
-                                .utf8_text(source.as_bytes())
-                                .unwrap_or("void")
-                                .to_string();
-                        }
-                    }
+type point = { x : float; y : float }
 
-                    let accessibility_modifier_node =
-                        find_descendant_by_type(&node, "accessibility_modifier");
-                    let accessibility_modifier = accessibility_modifier_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("");
+let pi = 3.14159
 
-                    let func = Func {
-                        name: name.to_string(),
-                        params: params.to_string(),
-                        return_type: return_type.to_string(),
-                        accessibility_modifier: if accessibility_modifier.is_empty() {
-                            None
-                        } else {
-                            Some(accessibility_modifier.to_string())
-                        },
-                    };
-                    definitions.push(Definition::Func(func));
-                }
-                "assignment" => {
-                    let visibility_modifier_node =
-                        find_descendant_by_type(&node, "visibility_modifier");
-                    let visibility_modifier = visibility_modifier_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("");
-                    if language == "swift" || language == "java" {
-                        if visibility_modifier.contains("private") {
-                            continue;
-                        }
-                    }
-                    if language == "java" {
-                        let modifier_node = find_descendant_by_type(&node, "modifiers");
-                        if modifier_node.is_some() {
-                            let modifier_text =
-                                modifier_node.unwrap().utf8_text(source.as_bytes()).unwrap();
-                            if modifier_text.contains("private") {
-                                continue;
-                            }
-                        }
-                    }
-                    if language == "rust" && !visibility_modifier.contains("pub") {
-                        continue;
-                    }
-                    let impl_item_node = find_ancestor_by_type(&node, "impl_item")
-                        .or_else(|| find_ancestor_by_type(&node, "class_declaration"))
-                        .or_else(|| find_ancestor_by_type(&node, "class_definition"));
-                    if impl_item_node.is_some() {
-                        continue;
-                    }
-                    let function_node = find_ancestor_by_type(&node, "function_declaration")
-                        .or_else(|| find_ancestor_by_type(&node, "function_definition"));
-                    if function_node.is_some() {
-                        continue;
-                    }
-                    let left_node = node.child_by_field_name("left");
-                    let left = left_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("");
-                    if !left.is_empty() && language == "go" && !is_first_letter_uppercase(left) {
-                        continue;
-                    }
+let area s = match s with Circle r -> pi *. r *. r | Rect (w, h) -> w *. h
 
-                    let value_type = get_node_type(&node, source.as_bytes());
-                    let variable = Variable {
-                        name: left.to_string(),
-                        value_type: value_type.to_string(),
-                    };
-                    definitions.push(Definition::Variable(variable));
-                }
-                "variable" => {
-                    let visibility_modifier_node =
-                        find_descendant_by_type(&node, "visibility_modifier");
-                    let visibility_modifier = visibility_modifier_node
-                        .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                        .unwrap_or("");
+let scale (k : float) s : shape = s
 
-                    if language == "swift" {
-                        if visibility_modifier.contains("private") {
-                            continue;
-                        }
-                    }
+let () = print_endline "loaded"
 
-                    if language == "java" {
-                        let modifier_node = find_descendant_by_type(&node, "modifiers");
-                        if modifier_node.is_some() {
-                            let modifier_text =
-                                modifier_node.unwrap().utf8_text(source.as_bytes()).unwrap();
-                            if modifier_text.contains("private") {
-                                continue;
-                            }
-                        }
-                    }
+module Geometry = struct
+  module Vec = struct
+    let add a b = a +. b
+    let zero = 0.0
+  end
 
-                    if language == "rust" && !visibility_modifier.contains("pub") {
-                        continue;
-                    }
+  let origin : point = { x = 0.0; y = 0.0 }
+end
 
-                    if language == "zig"
-                        && !zig_is_variable_declaration_public(&node, source.as_bytes())
-                    {
-                        continue;
-                    }
+module L = List
 
-                    let impl_item_node = find_ancestor_by_type(&node, "impl_item")
-                        .or_else(|| find_ancestor_by_type(&node, "class_declaration"))
-                        .or_else(|| find_ancestor_by_type(&node, "class_definition"));
-                    if impl_item_node.is_some() {
-                        continue;
-                    }
-                    let function_node = find_ancestor_by_type(&node, "function_declaration")
-                        .or_else(|| find_ancestor_by_type(&node, "function_definition"));
-                    if function_node.is_some() {
-                        continue;
-                    }
-                    let value_node = node.child_by_field_name("value");
-                    if value_node.is_some() {
-                        let value_type = value_node.unwrap().kind();
-                        if value_type == "arrow_function" {
-                            let params_node = value_node.unwrap().child_by_field_name("parameters");
-                            let params = params_node
-                                .map(|n| n.utf8_text(source.as_bytes()).unwrap())
-                                .unwrap_or("()");
-                            let mut return_type = "void".to_string();
-                            let return_type_node =
-                                value_node.unwrap().child_by_field_name("return_type");
-                            if return_type_node.is_some() {
-                                return_type =
-                                    get_node_type(&return_type_node.unwrap(), source.as_bytes());
-                            }
-                            let func = Func {
-                                name: name.to_string(),
-                                params: params.to_string(),
-                                return_type,
-                                accessibility_modifier: None,
-                            };
-                            definitions.push(Definition::Func(func));
-                            continue;
-                        }
-                    }
+module type SHAPE = sig
+  val area : shape -> float
+  val name : string
+end
+"#;
 
-                    let mut value_type = get_node_type(&node, source.as_bytes());
-                    if language == "zig" {
-                        if let Some(zig_type) = zig_find_type_in_parent(&node, source.as_bytes()) {
-                            value_type = zig_type;
-                        } else {
-                            continue;
-                        };
-                    }
-                    if !name.is_empty() && language == "go" && !is_first_letter_uppercase(&name) {
-                        continue;
-                    }
-                    let variable = Variable {
-                        name: name.to_string(),
-                        value_type: value_type.to_string(),
-                    };
-                    definitions.push(Definition::Variable(variable));
-                }
-                _ => {}
-            }
-        }
+        let definitions = extract_definitions("ocaml", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var pi;func area(s);func scale(k : float, s) -> shape;module Geometry{var origin:point;};module Geometry.Vec{func add(a, b);var zero;};module type SHAPE{func area() -> shape -> float;var name:string;};record point{var x:float;var y:float;};enum shape{Circle:float;Rect:float * float;};";
+        assert_eq!(stringified, expected);
+        assert!(definitions.iter().any(
+            |definition| matches!(definition, Definition::Module(module) if module.name == "Geometry.Vec")
+        ));
     }
 
-    for (_, def) in class_def_map {
-        let class_def = def.into_inner();
-        if language == "rust" {
-            if let Some(visibility_modifier) = &class_def.visibility_modifier {
-                if visibility_modifier.contains("pub") {
-                    definitions.push(Definition::Class(class_def));
-                }
-            }
-        } else {
-            definitions.push(Definition::Class(class_def));
-        }
-    }
+    #[test]
+    fn test_ocaml_interface() {
+        let source = r"
+type t
 
-    for (_, def) in enum_def_map {
-        definitions.push(Definition::Enum(def.into_inner()));
-    }
-    for (_, def) in union_def_map {
-        definitions.push(Definition::Union(def.into_inner()));
+val create : int -> t
+val default : t
+
+module type S = sig
+  val run : t -> unit
+end
+";
+
+        let definitions = extract_definitions("ocaml_interface", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected =
+            "func create() -> int -> t;var default:t;module type S{func run() -> t -> unit;};";
+        assert_eq!(stringified, expected);
+        assert_eq!(
+            get_language_from_extension(Path::new("shapes.mli")),
+            Some("ocaml_interface")
+        );
     }
 
-    Ok(definitions)
+    #[test]
+    fn test_bash() {
+        let source = r#"#!/usr/bin/env bash
+export APP_ENV=production
+export PATH
+declare -rx REGION="us-east-1"
+declare -r VERSION=1.0
+LOCAL_ONLY=1
+
+deploy() {
+  helper() {
+    echo "inner"
+  }
+  helper
 }
 
-fn stringify_function(func: &Func) -> String {
-    let mut res = format!("func {}", func.name);
-    if func.params.is_empty() {
-        res = format!("{res}()");
-    } else {
-        res = format!("{res}{}", func.params);
+function rollback {
+  echo "rolling back"
+}
+"#;
+
+        let definitions = extract_definitions("bash", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var APP_ENV;var PATH;var REGION;func deploy();func rollback();";
+        assert_eq!(stringified, expected);
     }
-    if !func.return_type.is_empty() {
-        res = format!("{res} -> {}", func.return_type);
+
+    #[test]
+    fn test_d() {
+        let source = r"
+module app;
+
+immutable int maxSize = 10;
+int counter = 0;
+
+enum Color { Red, Green = 2, Blue }
+
+T max(T)(T a, T b) if (isNumeric!T) {
+    return a > b ? a : b;
+}
+
+class Stack(T) {
+    private T[] items;
+    public size_t limit;
+
+    void push(T item) {
+        items ~= item;
     }
-    if let Some(modifier) = &func.accessibility_modifier {
-        res = format!("{modifier} {res}");
+
+    private void grow() {
     }
-    format!("{res};")
 }
 
-fn stringify_variable(variable: &Variable) -> String {
-    let mut res = format!("var {}", variable.name);
-    if !variable.value_type.is_empty() {
-        res = format!("{res}:{}", variable.value_type);
+struct Point {
+    int x;
+    int y;
+
+    double norm() const {
+        return 0;
     }
-    format!("{res};")
 }
+";
 
-fn stringify_enum_item(item: &Variable) -> String {
-    let mut res = item.name.clone();
-    if !item.value_type.is_empty() {
-        res = format!("{res}:{}", item.value_type);
+        let definitions = extract_definitions("d", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var maxSize:int;func max(T)(T a, T b) -> T;struct Point{func norm() -> double;var x:int;var y:int;};class Stack(T){func push(T item) -> void;var limit:size_t;};enum Color{Red;Green;Blue;};";
+        assert_eq!(stringified, expected);
     }
-    format!("{res};")
+
+    #[test]
+    fn test_gleam() {
+        let source = r#"
+import gleam/int
+
+pub const max_radius: Float = 100.0
+
+const secret = "hidden"
+
+pub type Shape {
+  Circle(radius: Float)
+  Rectangle(width: Float, height: Float)
+  Point
 }
 
-fn stringify_union_item(item: &Variable) -> String {
-    let mut res = item.name.clone();
-    if !item.value_type.is_empty() {
-        res = format!("{res}:{}", item.value_type);
-    }
-    format!("{res};")
+pub opaque type Token {
+  Token(value: String)
+}
+
+type Internal {
+  Internal
+}
+
+pub fn area(shape: Shape) -> Float {
+  case shape {
+    Circle(radius) -> radius *. radius *. 3.14
+    Rectangle(width, height) -> width *. height
+    Point -> 0.0
+  }
+}
+
+fn helper(x: Int) -> Int {
+  x + 1
 }
+"#;
+
+        let definitions = extract_definitions("gleam", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var max_radius:Float;func area(shape: Shape) -> Float;opaque type Token{};enum Shape{Circle:(radius: Float);Rectangle:(width: Float, height: Float);Point;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_crystal() {
+        let source = r#"
+VERSION = "1.0"
+
+class Circle
+  def initialize(@radius : Float64)
+  end
 
-fn stringify_class(class: &Class) -> String {
-    let mut res = format!("{} {}{{", class.type_name, class.name);
-    for method in &class.methods {
-        let method_str = stringify_function(method);
-        res = format!("{res}{method_str}");
+  def area : Float64
+    @radius * @radius
+  end
+
+  def describe
+    "circle"
+  end
+
+  private def scale(factor : Float64) : Float64
+    ratio = factor
+    @radius * ratio
+  end
+end
+
+def greet(name : String) : String
+  "Hello"
+end
+"#;
+
+        let definitions = extract_definitions("crystal", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var VERSION;func greet(name : String) -> String;class Circle{func initialize(@radius : Float64) -> void;func area() -> Float64;func describe() -> void;private func scale(factor : Float64) -> Float64;};";
+        assert_eq!(stringified, expected);
     }
-    for property in &class.properties {
-        let property_str = stringify_variable(property);
-        res = format!("{res}{property_str}");
+
+    #[test]
+    fn test_solidity() {
+        let source = r"
+pragma solidity ^0.8.0;
+
+interface IERC20 {
+    function totalSupply() external view returns (uint256);
+    event Transfer(address indexed from, address indexed to, uint256 value);
+}
+
+contract Ownable {
+    address public owner;
+    uint256 private secret;
+
+    function transferOwnership(address newOwner) public {
+        owner = newOwner;
     }
-    format!("{res}}};")
+
+    function _checkOwner() internal view {}
 }
 
-fn stringify_enum(enum_def: &Enum) -> String {
-    let mut res = format!("enum {}{{", enum_def.name);
-    for item in &enum_def.items {
-        let item_str = stringify_enum_item(item);
-        res = format!("{res}{item_str}");
+contract Token is Ownable, IERC20 {
+    string public name;
+    mapping(address => uint256) balances;
+
+    event Minted(address to, uint256 amount);
+
+    function totalSupply() external view override returns (uint256) {
+        return 0;
     }
-    format!("{res}}};")
-}
-fn stringify_union(union_def: &Union) -> String {
-    let mut res = format!("union {}{{", union_def.name);
-    for item in &union_def.items {
-        let item_str = stringify_union_item(item);
-        res = format!("{res}{item_str}");
+
+    function balanceOf(address account) public view returns (uint256 balance, bool ok) {
+        return (balances[account], true);
     }
-    format!("{res}}};")
-}
 
-fn stringify_definitions(definitions: &Vec<Definition>) -> String {
-    let mut res = String::new();
-    for definition in definitions {
-        match definition {
-            Definition::Class(class) => res = format!("{res}{}", stringify_class(class)),
-            Definition::Module(module) => res = format!("{res}{}", stringify_class(module)),
-            Definition::Enum(enum_def) => res = format!("{res}{}", stringify_enum(enum_def)),
-            Definition::Union(union_def) => res = format!("{res}{}", stringify_union(union_def)),
-            Definition::Func(func) => res = format!("{res}{}", stringify_function(func)),
-            Definition::Variable(variable) => {
-                let variable_str = stringify_variable(variable);
-                res = format!("{res}{variable_str}");
-            }
-        }
+    function mint(address to, uint256 amount) external payable {
+        emit Minted(to, amount);
     }
-    res
-}
 
-pub fn get_definitions_string(language: &str, source: &str) -> LuaResult<String> {
-    let definitions =
-        extract_definitions(language, source).map_err(|e| LuaError::RuntimeError(e.to_string()))?;
-    let stringified = stringify_definitions(&definitions);
-    Ok(stringified)
+    function burn(uint256 amount) private {}
 }
 
-#[mlua::lua_module]
-fn avante_repo_map(lua: &Lua) -> LuaResult<LuaTable> {
-    let exports = lua.create_table()?;
-    exports.set(
-        "stringify_definitions",
-        lua.create_function(move |_, (language, source): (String, String)| {
-            get_definitions_string(language.as_str(), source.as_str())
-        })?,
-    )?;
-    Ok(exports)
-}
+library SafeMath {
+    function add(uint256 a, uint256 b) public pure returns (uint256) {
+        return a + b;
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    function sub(uint256 a, uint256 b) internal pure returns (uint256) {
+        return a - b;
+    }
+}
+";
 
-    #[test]
-    fn test_rust() {
-        let source = r#"
-        // This is a test comment
-        pub const TEST_CONST: u32 = 1;
-        pub static TEST_STATIC: u32 = 2;
-        const INNER_TEST_CONST: u32 = 3;
-        static INNER_TEST_STATIC: u32 = 4;
-        pub(crate) struct TestStruct {
-            pub test_field: String,
-            inner_test_field: String,
-        }
-        impl TestStruct {
-            pub fn test_method(&self, a: u32, b: u32) -> u32 {
-                a + b
-            }
-            fn inner_test_method(&self, a: u32, b: u32) -> u32 {
-                a + b
-            }
-        }
-        struct InnerTestStruct {
-            pub test_field: String,
-            inner_test_field: String,
-        }
-        impl InnerTestStruct {
-            pub fn test_method(&self, a: u32, b: u32) -> u32 {
-                a + b
-            }
-            fn inner_test_method(&self, a: u32, b: u32) -> u32 {
-                a + b
-            }
-        }
-        pub enum TestEnum {
-            TestEnumField1,
-            TestEnumField2,
-        }
-        enum InnerTestEnum {
-            InnerTestEnumField1,
-            InnerTestEnumField2,
-        }
-        pub fn test_fn(a: u32, b: u32) -> u32 {
-            let inner_var_in_func = 1;
-            struct InnerStructInFunc {
-                c: u32,
-            }
-            a + b + c
-        }
-        fn inner_test_fn(a: u32, b: u32) -> u32 {
-            a + b
-        }
-        "#;
-        let definitions = extract_definitions("rust", source).unwrap();
+        let definitions = extract_definitions("solidity", source).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected = "var TEST_CONST:u32;var TEST_STATIC:u32;func test_fn(a: u32, b: u32) -> u32;class TestStruct{func test_method(&self, a: u32, b: u32) -> u32;var test_field:String;};";
+        let expected = "interface IERC20{external view func totalSupply() -> uint256;event func Transfer(address indexed from, address indexed to, uint256 value);};contract Ownable{public func transferOwnership(address newOwner);var owner:address;};library SafeMath{public pure func add(uint256 a, uint256 b) -> uint256;};contract Token{event func Minted(address to, uint256 amount);external view func totalSupply() -> uint256;public view func balanceOf(address account) -> (uint256 balance, bool ok);external payable func mint(address to, uint256 amount);var name:string;};";
         assert_eq!(stringified, expected);
     }
 
     #[test]
-    fn test_zig() {
+    fn test_nim() {
         let source = r#"
-          // This is a test comment
-          pub const TEST_CONST: u32 = 1;
-          pub var TEST_VAR: u32 = 2;
-          const INNER_TEST_CONST: u32 = 3;
-          var INNER_TEST_VAR: u32 = 4;
-          pub const TestStruct = struct {
-              test_field: []const u8,
-              test_field2: u64,
-
-              pub fn test_method(_: *TestStruct, a: u32, b: u32) u32 {
-                  return a + b;
-              }
-
-              fn inner_test_method(_: *TestStruct, a: u32, b: u32) u32 {
-                  return a + b;
-              }
-          };
-          const InnerTestStruct = struct {
-              test_field: []const u8,
-              test_field2: u64,
-
-              pub fn test_method(_: *InnerTestStruct, a: u32, b: u32) u32 {
-                  return a + b;
-              }
+const Version* = "1.0"
+const buildId = 42
 
-              fn inner_test_method(_: *InnerTestStruct, a: u32, b: u32) u32 {
-                  return a + b;
-              }
-          };
-          pub const TestEnum = enum {
-              TestEnumField1,
-              TestEnumField2,
-          };
-          const InnerTestEnum = enum {
-              InnerTestEnumField1,
-              InnerTestEnumField2,
-          };
+var counter*: int = 0
 
-          pub const TestUnion = union {
-              TestUnionField1: u32,
-              TestUnionField2: u64,
-          };
+proc area*(s: Shape): float =
+  s.x * s.y
 
-          const InnerTestUnion = union {
-              InnerTestUnionField1: u32,
-              InnerTestUnionField2: u64,
-          };
+func scale*(s: Shape, k: float): Shape =
+  s
 
-          pub fn test_fn(a: u32, b: u32) u32 {
-              const inner_var_in_func = 1;
-              const InnerStructInFunc = struct {
-                  c: u32,
-              };
-              _ = InnerStructInFunc;
-              return a + b + inner_var_in_func;
-          }
-          fn inner_test_fn(a: u32, b: u32) u32 {
-              return a + b;
-          }
-        "#;
+proc helper(x: int): int =
+  x * 2
+"#;
 
-        let definitions = extract_definitions("zig", source).unwrap();
+        let definitions = extract_definitions("nim", source).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected = "var TEST_CONST:u32;var TEST_VAR:u32;func test_fn() -> void;class TestStruct{func test_method(_: *TestStruct, a: u32, b: u32) -> void;var test_field:[]const u8;var test_field2:u64;};enum TestEnum{TestEnumField1;TestEnumField2;};union TestUnion{TestUnionField1;TestUnionField2;};";
+        let expected = "var Version;var counter:int;func area(s: Shape) -> float;func scale(s: Shape, k: float) -> Shape;";
         assert_eq!(stringified, expected);
     }
 
     #[test]
-    fn test_go() {
-        let source = r#"
-        // This is a test comment
-        package main
-        import "fmt"
-        const TestConst string = "test"
-        const innerTestConst string = "test"
-        var TestVar string
-        var innerTestVar string
-        type TestStruct struct {
-            TestField string
-            innerTestField string
-        }
-        func (t *TestStruct) TestMethod(a int, b int) (int, error) {
-            var InnerVarInFunc int = 1
-            type InnerStructInFunc struct {
-                C int
-            }
-            return a + b, nil
-        }
-        func (t *TestStruct) innerTestMethod(a int, b int) (int, error) {
-            return a + b, nil
-        }
-        type innerTestStruct struct {
-            innerTestField string
-        }
-        func (t *innerTestStruct) testMethod(a int, b int) (int, error) {
-            return a + b, nil
-        }
-        func (t *innerTestStruct) innerTestMethod(a int, b int) (int, error) {
-            return a + b, nil
-        }
-        func TestFunc(a int, b int) (int, error) {
-            return a + b, nil
-        }
-        func innerTestFunc(a int, b int) (int, error) {
-            return a + b, nil
-        }
-        "#;
-        let definitions = extract_definitions("go", source).unwrap();
+    fn test_svelte() {
+        let source = r#"<script lang="ts">
+  import { onMount } from 'svelte'
+
+  export let name: string
+  export let count = 0
+  let internal = 1
+
+  function greet(greeting: string): string {
+    return `${greeting}, ${name}`
+  }
+
+  const reset = () => {
+    count = 0
+  }
+
+  onMount(() => greet('Hello'))
+</script>
+
+<h1>{greet('Hello')}</h1>
+<button on:click={reset}>{count}</button>
+"#;
+
+        let definitions = extract_definitions("svelte", source).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected = "var TestConst:string;var TestVar:string;func TestFunc(a int, b int) -> (int, error);class TestStruct{func TestMethod(a int, b int) -> (int, error);var TestField:string;};";
+        let expected = "component default{func greet(greeting: string) -> string;func reset() -> void;var name:string;var count;};";
         assert_eq!(stringified, expected);
+        let Definition::Class(component) = &definitions[0] else {
+            panic!("expected a component, got {:?}", definitions[0]);
+        };
+        let greet = component
+            .methods
+            .iter()
+            .find(|method| method.name == "greet")
+            .unwrap();
+        assert!(source[greet.start_byte..].starts_with("function greet"));
+
+        let definitions = extract_definitions("svelte", "<h1>Hello</h1>\n").unwrap();
+        assert!(definitions.is_empty());
     }
 
     #[test]
-    fn test_python() {
-        let source = r#"
-        # This is a test comment
-        test_var: str = "test"
-        class TestClass:
-            def __init__(self, a, b):
-                self.a = a
-                self.b = b
-            def test_method(self, a: int, b: int) -> int:
-                inner_var_in_method: int = 1
-                return a + b
-        def test_func(a: int, b: int) -> int:
-            inner_var_in_func: str = "test"
-            class InnerClassInFunc:
-                def __init__(self, a, b):
-                    self.a = a
-                    self.b = b
-                def test_method(self, a: int, b: int) -> int:
-                    return a + b
-            def inne_func_in_func(a: int, b: int) -> int:
-                return a + b
-            return a + b
-        "#;
-        let definitions = extract_definitions("python", source).unwrap();
+    fn test_svelte_module_context() {
+        let source = r#"<script context="module" lang="ts">
+  export const MAX_COUNT = 10
+
+  export function formatCount(count: number): string {
+    return `${count}/${MAX_COUNT}`
+  }
+</script>
+
+<script lang="ts">
+  export let label: string
+  export let count = 0
+
+  export function increment(): void {
+    count = Math.min(count + 1, MAX_COUNT)
+  }
+</script>
+
+<button on:click={increment}>{label}: {formatCount(count)}</button>
+"#;
+
+        let options = ExtractOptions {
+            component_name: Some("Counter".to_string()),
+            ..Default::default()
+        };
+        let definitions = extract_definitions_with_options("svelte", source, &options).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected = "var test_var:str;func test_func(a: int, b: int) -> int;class TestClass{func __init__(self, a, b) -> void;func test_method(self, a: int, b: int) -> int;};";
+        let expected = "component Counter{func increment() -> void;var label:string;var count;};var MAX_COUNT;func formatCount(count: number) -> string;";
         assert_eq!(stringified, expected);
-    }
-
-    #[test]
-    fn test_typescript() {
-        let source = r#"
-        // This is a test comment
-        export const testVar: string = "test";
-        const innerTestVar: string = "test";
-        export class TestClass {
-            a: number;
-            b: number;
-            constructor(a: number, b: number) {
-                this.a = a;
-                this.b = b;
-            }
-            testMethod(a: number, b: number): number {
-                const innerConstInMethod: number = 1;
-                function innerFuncInMethod(a: number, b: number): number {
-                    return a + b;
-                }
-                return a + b;
-            }
-        }
-        class InnerTestClass {
-            a: number;
-            b: number;
-        }
-        export function testFunc(a: number, b: number) {
-            const innerConstInFunc: number = 1;
-            function innerFuncInFunc(a: number, b: number): number {
-                return a + b;
-            }
-            return a + b;
-        }
-        export const testFunc2 = (a: number, b: number) => {
-            return a + b;
-        }
-        export const testFunc3 = (a: number, b: number): number => {
-            return a + b;
-        }
-        function innerTestFunc(a: number, b: number) {
-            return a + b;
-        }
-        "#;
-        let definitions = extract_definitions("typescript", source).unwrap();
+
+        // Svelte 5 declares props with the `$props` rune
+        let source = r#"<script lang="ts">
+  let { label, count = 0 }: { label: string; count?: number } = $props()
+</script>
+"#;
+        let definitions = extract_definitions_with_options("svelte", source, &options).unwrap();
+        assert_eq!(
+            stringify_definitions(&definitions),
+            "component Counter{var label;var count;};"
+        );
+    }
+
+    #[test]
+    fn test_vue() {
+        let source = r#"<template>
+  <button @click="greet">{{ title }}</button>
+</template>
+
+<script lang="ts">
+import { defineComponent } from 'vue'
+
+export const VERSION = '1.0'
+
+export default defineComponent({
+  name: 'Greeting',
+  props: {
+    title: String,
+    count: { type: Number, default: 0 },
+  },
+  computed: {
+    upper() {
+      return this.title.toUpperCase()
+    },
+  },
+  methods: {
+    greet(name: string) {
+      console.log(name)
+    },
+    reset: () => {},
+  },
+})
+</script>
+"#;
+
+        let definitions = extract_definitions("vue", source).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected = "var testVar:string;func testFunc(a: number, b: number) -> void;func testFunc2(a: number, b: number) -> void;func testFunc3(a: number, b: number) -> number;class TestClass{func constructor(a: number, b: number) -> void;func testMethod(a: number, b: number) -> number;var a:number;var b:number;};"
-;
+        let expected = "component Greeting{func greet(name: string);func reset();var title:String;var count;var upper;};var VERSION;";
         assert_eq!(stringified, expected);
+
+        // Without a `name` option the component is named after its file
+        let source = r"<script>
+export default {
+  props: ['title'],
+}
+</script>
+";
+        let options = ExtractOptions {
+            component_name: Some("TitleBar".to_string()),
+            ..Default::default()
+        };
+        let definitions = extract_definitions_with_options("vue", source, &options).unwrap();
+        assert_eq!(
+            stringify_definitions(&definitions),
+            "component TitleBar{var title;};"
+        );
+
+        // `lang` is read from the attribute itself, unquoted or not, never from other attributes
+        let source = r#"<script lang=ts>
+export function greet(name: string): string { return name }
+</script>
+<script data-note="lang='ts'">
+export function wave(hand) {}
+</script>
+"#;
+        let definitions = extract_definitions("vue", source).unwrap();
+        assert_eq!(
+            stringify_definitions(&definitions),
+            "func greet(name: string) -> string;func wave(hand) -> void;"
+        );
     }
 
     #[test]
-    fn test_javascript() {
-        let source = r#"
-        // This is a test comment
-        export const testVar = "test";
-        const innerTestVar = "test";
-        export class TestClass {
-            constructor(a, b) {
-                this.a = a;
-                this.b = b;
-            }
-            testMethod(a, b) {
-                const innerConstInMethod = 1;
-                function innerFuncInMethod(a, b) {
-                    return a + b;
-                }
-                return a + b;
-            }
-        }
-        class InnerTestClass {
-            constructor(a, b) {
-                this.a = a;
-                this.b = b;
-            }
-        }
-        export const testFunc = function(a, b) {
-            const innerConstInFunc = 1;
-            function innerFuncInFunc(a, b) {
-                return a + b;
-            }
-            return a + b;
-        }
-        export const testFunc2 = (a, b) => {
-            return a + b;
-        }
-        export const testFunc3 = (a, b) => a + b;
-        function innerTestFunc(a, b) {
-            return a + b;
-        }
-        "#;
-        let definitions = extract_definitions("javascript", source).unwrap();
+    fn test_vue_script_setup() {
+        let source = r#"<template>
+  <div @click="select">{{ name }}</div>
+</template>
+
+<script setup lang="ts">
+interface Props {
+  name: string
+  age?: number
+}
+
+const props = withDefaults(defineProps<Props>(), { age: 0 })
+const emit = defineEmits<{ (e: 'select', id: number): void }>()
+</script>
+"#;
+
+        let options = ExtractOptions {
+            component_name: Some("UserCard".to_string()),
+            ..Default::default()
+        };
+        let definitions = extract_definitions_with_options("vue", source, &options).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected = "var testVar;var testFunc;func testFunc2(a, b) -> void;func testFunc3(a, b) -> void;class TestClass{func constructor(a, b) -> void;func testMethod(a, b) -> void;};";
+        let expected = "component UserCard{var name:string;var age:number;};class Props{var name:string;var age:number;};var emit:defineEmits<{ (e: 'select', id: number): void }>;";
         assert_eq!(stringified, expected);
+        let Definition::Class(component) = &definitions[0] else {
+            panic!("expected the component first, got {:?}", definitions[0]);
+        };
+        assert!(source[component.start_byte..].starts_with("const props"));
+
+        // Runtime declarations, and the fallback name without a file
+        let source = r"<script setup>
+defineProps({ title: String, count: { type: Number } })
+</script>
+";
+        let definitions = extract_definitions("vue", source).unwrap();
+        assert_eq!(
+            stringify_definitions(&definitions),
+            "component default{var title:String;var count;};"
+        );
     }
 
     #[test]
-    fn test_ruby() {
-        let source = r#"
-        # This is a test comment
-        test_var = "test"
-        def test_func(a, b)
-            inner_var_in_func = "test"
-            class InnerClassInFunc
-                attr_accessor :a, :b
-                def initialize(a, b)
-                    @a = a
-                    @b = b
-                end
-                def test_method(a, b)
-                    return a + b
-                end
-            end
-            return a + b
-        end
-        class TestClass
-            attr_accessor :a, :b
-            def initialize(a, b)
-                @a = a
-                @b = b
-            end
-            def test_method(a, b)
-                inner_var_in_method = 1
-                def inner_func_in_method(a, b)
-                    return a + b
-                end
-                return a + b
-            end
-        end
-        "#;
-        let definitions = extract_definitions("ruby", source).unwrap();
+    fn test_fsharp() {
+        let source = r"
+namespace Shapes
+
+type Shape =
+    | Circle of radius: float
+    | Rectangle of width: float * height: float
+    | Empty
+
+type Point =
+    { X: float
+      Y: float }
+    member this.Length = sqrt (this.X * this.X + this.Y * this.Y)
+    member this.Scale(factor: float) : Point = { X = this.X * factor; Y = this.Y * factor }
+    member private this.Norm = this.Length
+
+type internal Cache = { Entries: int }
+
+module Geometry =
+    let pi = 3.14159
+
+    let area (shape: Shape) : float =
+        match shape with
+        | Circle r -> pi * r * r
+        | Rectangle (w, h) -> w * h
+        | Empty -> 0.0
+
+    let private square x = x * x
+
+    let translate (p: Point) dx dy = { p with X = p.X + dx; Y = p.Y + dy }
+";
+
+        let definitions = extract_definitions("fsharp", source).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        // FIXME:
-        let expected = "var test_var;func test_func(a, b) -> void;class InnerClassInFunc{func initialize(a, b) -> void;func test_method(a, b) -> void;};class TestClass{func initialize(a, b) -> void;func test_method(a, b) -> void;};";
+        let expected = "module Shapes{};module Shapes.Geometry{func area(shape: Shape) -> float;func translate(p: Point) dx dy;var pi;};record Shapes.Point{func Scale(factor: float) -> Point;var X:float;var Y:float;var Length;};union Shapes.Shape{Circle:radius: float;Rectangle:width: float * height: float;Empty;};";
         assert_eq!(stringified, expected);
     }
 
     #[test]
-    fn test_ruby2() {
+    fn test_graphql() {
         let source = r#"
-        # frozen_string_literal: true
+"""A user of the system"""
+type User implements Node {
+  id: ID!
+  name: String
+  posts(first: Int = 10, after: String): [Post!]!
+}
 
-        require('jwt')
+interface Node {
+  id: ID!
+}
 
-        top_level_var = 1
+input CreateUserInput {
+  name: String!
+  email: String
+}
 
-        def top_level_func
-          inner_var_in_func = 2
-        end
+enum Role {
+  ADMIN
+  MEMBER
+}
 
-        module A
-          module B
-            @module_var = :foo
+type Query {
+  user(id: ID!): User
+  users: [User!]!
+}
 
-            def module_method
-              @module_var
-            end
+type Mutation {
+  createUser(input: CreateUserInput!): User!
+}
 
-            class C < Base
-              TEST_CONST = 1
-              @class_var = :bar
-              attr_accessor :a, :b
+type Post {
+  title: String! @deprecated(reason: "Use headline")
+}
 
-              def initialize(a, b)
-                @a = a
-                @b = b
-                super
-              end
+union SearchResult = User | Post
+"#;
 
-              def bar
-                inner_var_in_method = 1
-                true
-              end
+        let definitions = extract_definitions("graphql", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "input CreateUserInput{var name:String!;var email:String;};type Mutation{func createUser(input: CreateUserInput!) -> User!;};interface Node{var id:ID!;};type Post{var title:String!;};type Query{func user(id: ID!) -> User;var users:[User!]!;};type User{func posts(first: Int = 10, after: String) -> [Post!]!;var id:ID!;var name:String;};enum Role{ADMIN;MEMBER;};union SearchResult{User;Post;};";
+        assert_eq!(stringified, expected);
+    }
 
-              private
+    #[test]
+    fn test_powershell() {
+        let source = r#"
+param(
+    [Parameter(Mandatory)]
+    [string]$Path,
+    [int]$Depth = 2
+)
+
+$Global:Config = @{ Retries = 3 }
+[string]$global:LogPath = "C:\logs"
+$local = 1
+
+function Get-Greeting {
+    [CmdletBinding()]
+    param(
+        [string]$Name,
+        [switch]$Loud
+    )
+    $Global:LastName = $Name
+    "Hello, $Name"
+}
 
-              def baz(request, params)
-                auth_header = request.headers['Authorization']
-                parts = auth_header.try(:split, /\s+/)
-                JWT.decode(parts.last)
-              end
-            end
-          end
-        end
-        "#;
-        let definitions = extract_definitions("ruby", source).unwrap();
+function Add-Numbers([int]$a, [int]$b) {
+    $a + $b
+}
+
+class Greeter {
+    [string]$Greeting = "Hello"
+    hidden [int]$Count = 0
+
+    [string] Greet([string]$name) {
+        $this.Count++
+        return "$($this.Greeting), $name"
+    }
+
+    Reset() {
+        $this.Count = 0
+    }
+}
+"#;
+
+        let definitions = extract_definitions("powershell", source).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected = "var top_level_var;func top_level_func() -> void;module A{};module A::B{func module_method() -> void;var @module_var;};class A::B::C{func initialize(a, b) -> void;func bar() -> void;private func baz(request, params) -> void;var TEST_CONST;var @class_var;};";
+        let expected = "var $Path:string;var $Depth:int;var $Global:Config;var $global:LogPath:string;func Get-Greeting([string]$Name, [switch]$Loud);func Add-Numbers([int]$a, [int]$b);class Greeter{func Greet([string]$name) -> string;func Reset();var Greeting:string;};";
         assert_eq!(stringified, expected);
     }
 
     #[test]
-    fn test_lua() {
+    fn test_clojure() {
         let source = r#"
-        -- This is a test comment
-        local test_var = "test"
-        function test_func(a, b)
-            local inner_var_in_func = 1
-            function inner_func_in_func(a, b)
-                return a + b
-            end
-            return a + b
-        end
-        "#;
-        let definitions = extract_definitions("lua", source).unwrap();
+(ns geometry.shapes
+  (:require [clojure.string :as str]))
+
+(def pi 3.14159)
+(def ^:private cache (atom {}))
+
+(defn area
+  "Area of a circle"
+  [r]
+  (* pi r r))
+
+(defn- helper [x] x)
+(defn ^:private secret [] 42)
+
+(defn scale
+  ([s] (scale s 1))
+  ([s k] (* s k)))
+
+(defprotocol Shape
+  (perimeter [this])
+  (describe [this fmt] "Render the shape"))
+
+(defrecord Circle [radius]
+  Shape
+  (perimeter [this] (* 2 pi radius)))
+"#;
+
+        let definitions = extract_definitions("clojure", source).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected = "var test_var;func test_func(a, b) -> void;";
+        let expected = "record Circle{var radius;};protocol Shape{func perimeter[this];func describe[this fmt];};module geometry.shapes{func area[r];func scale[s] [s k];var pi;};";
         assert_eq!(stringified, expected);
+        assert!(definitions.iter().any(
+            |definition| matches!(definition, Definition::Module(module) if module.name == "geometry.shapes")
+        ));
     }
 
     #[test]
-    fn test_c() {
+    fn test_proto() {
         let source = r#"
-        #include <stdio.h>
+syntax = "proto3";
+
+package users.v1;
+
+message User {
+  string id = 1;
+  string name = 2;
+  repeated string tags = 3;
+  map<string, int32> scores = 4;
+  Role role = 5;
+
+  message Address {
+    string city = 1;
+  }
+
+  enum Status {
+    STATUS_UNKNOWN = 0;
+    STATUS_ACTIVE = 1;
+  }
+
+  oneof contact {
+    string email = 6;
+    string phone = 7;
+  }
+}
 
-        int test_var = 2;
-        extern int extern_test_var;
+enum Role {
+  ROLE_UNSPECIFIED = 0;
+  ROLE_ADMIN = 1;
+}
 
-        int TestFunc(bool b) { return b ? 42 : -1; }
-        extern void ExternTestFunc();
+message GetUserRequest {
+  string id = 1;
+}
 
-        struct Foo {
-            int a;
-            int b;
-        };
+service UserService {
+  rpc GetUser (GetUserRequest) returns (User);
+  rpc WatchUsers (GetUserRequest) returns (stream User);
+  rpc SyncUsers (stream User) returns (stream User);
+}
+"#;
 
-        typedef int my_int;
-        "#;
-        let definitions = extract_definitions("c", source).unwrap();
+        let definitions = extract_definitions("proto", source).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected = "var extern int extern_test_var;:int;var extern void ExternTestFunc();:void;class Foo{var int a;:int;var int b;:int;};";
+        let expected = "message GetUserRequest{var id:string;};message User{var id:string;var name:string;var tags:repeated string;var scores:map<string, int32>;var role:Role;var email:string;var phone:string;};message User.Address{var city:string;};service UserService{func GetUser(GetUserRequest) -> User;func WatchUsers(GetUserRequest) -> stream User;func SyncUsers(stream User) -> stream User;};enum Role{ROLE_UNSPECIFIED;ROLE_ADMIN;};enum User.Status{STATUS_UNKNOWN;STATUS_ACTIVE;};";
         assert_eq!(stringified, expected);
     }
 
     #[test]
-    fn test_cpp() {
+    fn test_sql() {
+        let source = r"
+CREATE TABLE users (
+    id SERIAL PRIMARY KEY,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    created_at TIMESTAMP DEFAULT now()
+);
+
+CREATE TYPE order_status AS ENUM ('pending', 'paid', 'shipped');
+
+CREATE TABLE public.orders (
+    id BIGINT PRIMARY KEY,
+    user_id INT REFERENCES users(id),
+    total NUMERIC(10, 2),
+    CONSTRAINT positive_total CHECK (total >= 0),
+    CONSTRAINT orders_user_fk FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+ALTER TABLE public.orders ADD COLUMN status order_status NOT NULL DEFAULT 'pending';
+ALTER TABLE audit_log ADD COLUMN note TEXT;
+
+CREATE VIEW active_users AS
+SELECT * FROM users WHERE email IS NOT NULL;
+
+CREATE FUNCTION order_total(order_id INT) RETURNS NUMERIC AS $$
+    SELECT total FROM orders WHERE id = order_id;
+$$ LANGUAGE sql;
+
+CREATE OR REPLACE FUNCTION mark_paid(target_id BIGINT) RETURNS void AS $$
+BEGIN
+    UPDATE orders SET status = 'paid' WHERE id = target_id;
+END;
+$$ LANGUAGE plpgsql;
+";
+
+        let definitions = extract_definitions("sql", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "func order_total(order_id INT) -> NUMERIC;func mark_paid(target_id BIGINT) -> void;view active_users{};table public.orders{var id:BIGINT;var user_id:INT;var total:NUMERIC(10, 2);var status:order_status;};table users{var id:SERIAL;var email:VARCHAR(255);var created_at:TIMESTAMP;};enum order_status{pending;paid;shipped;};";
+        assert_eq!(stringified, expected);
+    }
+
+    #[test]
+    fn test_groovy() {
         let source = r#"
-        // This is a test comment
-        #include <iostream>
+package com.example
 
-        namespace {
-        constexpr int TEST_CONSTEXPR = 1;
-        const int TEST_CONST = 1;
-        }; // namespace
+class Greeter {
+    String greeting = "Hello"
+    private int count = 0
 
-        int test_var = 2;
+    String greet(String name) {
+        count++
+        return "${greeting}, ${name}"
+    }
 
-        int TestFunc(bool b) { return b ? 42 : -1; }
+    private void reset() {
+        count = 0
+    }
 
-        template <typename T> class TestClass {
-        public:
-          TestClass();
-          TestClass(T a, T b);
-          ~TestClass();
-          bool operator==(const TestClass &other);
-          T testMethod(T x, T y) { return x + y; }
-          T c;
+    static Greeter create() {
+        new Greeter()
+    }
+}
 
-        private:
-          void privateMethod();
-          T a = 0;
-          T b;
-        };
+def helper(x) {
+    x * 2
+}
+"#;
 
-        struct TestStruct {
-        public:
-          TestStruct(int a, int b);
-          ~TestStruct();
-          bool operator==(const TestStruct &other);
-          int testMethod(int x, int y) { return x + y; }
-          static int c;
+        let definitions = extract_definitions("groovy", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "func helper(x);class Greeter{func greet(String name) -> String;func create() -> Greeter;var greeting:String;};";
+        assert_eq!(stringified, expected);
+    }
 
-        private:
-          int a = 0;
-          int b;
-        };
+    #[test]
+    fn test_groovy_build_script() {
+        let source = r"
+plugins {
+    id 'java'
+}
 
-        bool TestStruct::operator==(const TestStruct &other) { return true; }
+repositories {
+    mavenCentral()
+}
 
-        int TestStruct::c = 0;
+dependencies {
+    implementation 'org.slf4j:slf4j-api:2.0.9'
+}
 
-        int testFunction(int a, int b) { return a + b; }
+task hello {
+    doLast {
+        println 'Hello'
+    }
+}
+
+tasks.register('integrationTest') {
+    useJUnitPlatform()
+}
 
-        namespace TestNamespace {
-        class InnerClass {
-        public:
-          bool innerMethod(int a) const;
-        };
-        bool InnerClass::innerMethod(int a) const { return doSomething(a * 2); }
-        } // namespace TestNamespace
+apply plugin: 'idea'
+";
 
-        enum TestEnum { ENUM_VALUE_1, ENUM_VALUE_2 };
-        "#;
-        let definitions = extract_definitions("cpp", source).unwrap();
+        let definitions = extract_definitions("groovy", source).unwrap();
         let stringified = stringify_definitions(&definitions);
-        println!("{}", stringified);
-        let expected = "var TEST_CONSTEXPR:int;var TEST_CONST:int;var test_var:int;func TestFunc(bool b) -> int;func TestStruct::operator==(const TestStruct &other) -> bool;var TestStruct::c:int;func testFunction(int a, int b) -> int;func InnerClass::innerMethod(int a) -> bool;class InnerClass{func innerMethod(int a) -> bool;};class TestClass{func TestClass() -> TestClass;func operator==(const TestClass &other) -> bool;func testMethod(T x, T y) -> T;func privateMethod() -> void;func TestClass(T a, T b) -> TestClass;var c:T;var a:T;var b:T;};class TestStruct{func TestStruct(int a, int b) -> void;func operator==(const TestStruct &other) -> bool;func testMethod(int x, int y) -> int;var c:int;var a:int;var b:int;};enum TestEnum{ENUM_VALUE_1;ENUM_VALUE_2;};";
+        println!("{stringified}");
+        let expected =
+            "func plugins();func repositories();func dependencies();func hello();func integrationTest();";
         assert_eq!(stringified, expected);
     }
 
     #[test]
-    fn test_scala() {
+    fn test_groovy_jenkinsfile() {
         let source = r#"
-        object Main {
-          def main(args: Array[String]): Unit = {
-            println("Hello, World!")
-          }
-        }
+def notify(String status) {
+    slackSend message: "Build ${status}"
+}
 
-        class TestClass {
-          val testVal: String = "test"
-          var testVar = 42
+pipeline {
+    agent any
+    stages {
+        stage('Build') {
+            steps {
+                sh './gradlew build'
+            }
+        }
+    }
+}
+"#;
 
-          def testMethod(a: Int, b: Int): Int = {
-            a + b
-          }
+        let definitions = extract_definitions("groovy", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "func notify(String status);func pipeline();";
+        assert_eq!(stringified, expected);
+        for file_name in ["Jenkinsfile", "ci/deploy.Jenkinsfile", "build.gradle"] {
+            assert_eq!(
+                get_language_from_extension(Path::new(file_name)),
+                Some("groovy"),
+                "{file_name}"
+            );
         }
+    }
 
-        // braceless syntax is also supported
-        trait TestTrait:
-          def abstractMethod(x: Int): Int
-          def concreteMethod(y: Int): Int = y * 2
+    #[test]
+    fn test_objc() {
+        let source = r"
+#import <Foundation/Foundation.h>
 
-        case class TestCaseClass(name: String, age: Int)
+@interface Person : NSObject
 
-        enum TestEnum {
-          case First, Second, Third
-        }
+@property (nonatomic, copy) NSString *name;
+@property (nonatomic) NSInteger age;
 
-        val foo: TestClass = ???
-        "#;
++ (instancetype)personWithName:(NSString *)name;
+- (void)setName:(NSString *)name age:(NSInteger)age;
+- (NSString *)describe;
 
-        let definitions = extract_definitions("scala", source).unwrap();
+@end
+
+@implementation Person
+
++ (instancetype)personWithName:(NSString *)name {
+    Person *person = [[self alloc] init];
+    person.name = name;
+    return person;
+}
+
+- (void)setName:(NSString *)name age:(NSInteger)age {
+    _name = name;
+    _age = age;
+}
+
+- (NSString *)describe {
+    return self.name;
+}
+
+- (void)reset {
+}
+
+@end
+";
+
+        let definitions = extract_definitions("objc", source).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected = "var foo:TestClass;class Main{func main(args: Array[String]) -> Unit;};class TestCaseClass{};class TestClass{func testMethod(a: Int, b: Int) -> Int;var testVal:String;var testVar;};class TestTrait{func abstractMethod(x: Int) -> Int;func concreteMethod(y: Int) -> Int;};enum TestEnum{First;Second;Third;};";
+        let expected = "class Person{class func personWithName:(NSString *name) -> instancetype;func setName:age:(NSString *name, NSInteger age) -> void;func describe() -> NSString *;func reset() -> void;var name:NSString *;var age:NSInteger;};";
         assert_eq!(stringified, expected);
     }
 
     #[test]
-    fn test_elixir() {
+    fn test_objc_category_and_protocol() {
         let source = r#"
-        defmodule TestModule do
-          @moduledoc """
-          This is a test module
-          """
+@protocol Greeting <NSObject>
+- (NSString *)greet:(NSString *)name;
+@optional
+@property (nonatomic, readonly) NSString *salutation;
+@end
+
+@interface Person : NSObject <Greeting>
+@property (nonatomic, copy) NSString *name;
+- (void)walk;
+@end
+
+@interface Person (Formatting)
+- (NSString *)formattedName;
++ (NSString *)defaultName;
+@end
+
+@implementation Person (Formatting)
+- (NSString *)formattedName {
+    return self.name;
+}
++ (NSString *)defaultName {
+    return @"Anonymous";
+}
+@end
+"#;
 
-          @test_const "test"
-          @other_const 123
+        let definitions = extract_definitions("objc", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "protocol Greeting{func greet:(NSString *name) -> NSString *;var salutation:NSString *;};class Person{func walk() -> void;func formattedName() -> NSString *;class func defaultName() -> NSString *;var name:NSString *;};";
+        assert_eq!(stringified, expected);
+    }
 
-          def test_func(a, b) do
-            a + b
-          end
+    #[test]
+    fn test_perl() {
+        let source = r"
+package Animal;
+use strict;
+
+our $VERSION = '1.00';
+our @EXPORT_OK = qw(speak);
+my $count = 0;
+
+sub new {
+    my ($class, %args) = @_;
+    return bless {%args}, $class;
+}
 
-          defp private_func(x) do
-            x * 2
-          end
+sub speak {
+    my ($self) = @_;
+    return _sound($self);
+}
 
-          defmacro test_macro(expr) do
-            quote do
-              unquote(expr)
-            end
-          end
-        end
+sub _sound { 'generic' }
 
-        defmodule AnotherModule do
-          def another_func() do
-            :ok
-          end
-        end
-        "#;
-        let definitions = extract_definitions("elixir", source).unwrap();
+package Animal::Dog;
+use parent -norequire, 'Animal';
+
+our %DEFAULTS = (tricks => 0);
+
+sub fetch {
+    my ($self, $item) = @_;
+    return $item;
+}
+
+1;
+";
+
+        let definitions = extract_definitions("perl", source).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected =
-            "module AnotherModule{func another_func();};module TestModule{func test_func(a, b);};";
+        let expected = "module Animal{func new($class, %args);func speak($self);var $VERSION;var @EXPORT_OK;};module Animal::Dog{func fetch($self, $item);var %DEFAULTS;};";
         assert_eq!(stringified, expected);
+        assert!(definitions.iter().all(
+            |definition| matches!(definition, Definition::Module(module) if module.name.starts_with("Animal"))
+        ));
     }
 
     #[test]
-    fn test_csharp() {
-        let source = r#"
-      using System;
-
-      namespace TestNamespace;
+    fn test_julia() {
+        let source = r"
+module Geometry
 
-      public class TestClass(TestDependency m)
-      {
-
-        private int PrivateTestProperty { get; set; }
+export area, Point
 
-        private int _privateTestField;
+const ORIGIN = (0.0, 0.0)
+const SCALE::Float64 = 2.0
 
-        public int TestProperty { get; set; }
+struct Point
+    x::Float64
+    y::Float64
+end
 
-        public string TestField;
+mutable struct Counter
+    count::Int
+    label
+end
 
-        public TestClass()
-        {
-          TestProperty = 0;
-        }
+area(r::Float64) = pi * r^2
 
+function area(p::Point, q::Point)::Float64
+    abs(p.x - q.x) * abs(p.y - q.y)
+end
 
-        public void TestMethod(int a, int b)
-        {
-          var innerVarInMethod = 1;
-          return a + b;
-        }
+function helper(x)
+    x
+end
 
-        public int TestMethod(int a, int b, int c) => a + b + c;
+module Shapes
 
-        private void PrivateMethod()
-        {
-          return;
-        }
+export square
 
-        public class MyInnerClass(InnerClassDependency m) {}
+square(x) = x * x
 
-        public record MyInnerRecord(int a);
-      }
+end
 
-      public record TestRecord(int a, int b);
+end
+";
 
-      public enum TestEnum { Value1, Value2 }
-      "#;
+        let definitions = extract_definitions("julia", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "module Geometry{func area(r::Float64);func area(p::Point, q::Point) -> Float64;var ORIGIN;var SCALE:Float64;};mutable struct Geometry.Counter{var count:Int;var label;};struct Geometry.Point{var x:Float64;var y:Float64;};module Geometry.Shapes{func square(x);};";
+        assert_eq!(stringified, expected);
+        assert!(definitions.iter().any(
+            |definition| matches!(definition, Definition::Module(module) if module.name == "Geometry.Shapes")
+        ));
+    }
 
-        let definitions = extract_definitions("csharp", source).unwrap();
+    #[test]
+    fn test_julia_multiple_dispatch() {
+        let source = r"
+struct Point{T<:Real}
+    x::T
+    y::T
+end
+
+norm(p::Point) = sqrt(p.x^2 + p.y^2)
+norm(v::Vector) = sqrt(sum(v .^ 2))
+
+function scale(p::Point{T}, k::T) where {T<:Real}
+    Point(p.x * k, p.y * k)
+end
+";
+
+        let definitions = extract_definitions("julia", source).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected = "class MyInnerClass{func MyInnerClass(InnerClassDependency m) -> MyInnerClass;};class MyInnerRecord{func MyInnerRecord(int a) -> MyInnerRecord;};class TestClass{func TestClass(TestDependency m) -> TestClass;func TestClass() -> TestClass;func TestMethod(int a, int b) -> void;func TestMethod(int a, int b, int c) -> int;var TestProperty:int;var TestField:string;};class TestRecord{func TestRecord(int a, int b) -> TestRecord;};enum TestEnum{Value1;Value2;};";
+        let expected = "func norm(p::Point);func norm(v::Vector);func scale(p::Point{T}, k::T);struct Point{var x:T;var y:T;};";
         assert_eq!(stringified, expected);
     }
 
     #[test]
-    fn test_swift() {
+    fn test_r() {
         let source = r#"
-            import Foundation
+library(methods)
 
-            private var myVariable = 0
-            public var myPublicVariable = 0
+DEFAULT_ALPHA <- 0.05
+max_iter = 100
+"verbose" -> mode
+.cache <- new.env()
 
-            struct MyStruct {
-              public var myPublicVariable = 0
-              private var myPrivateVariable = 0
+.validate <- function(x) {
+  stopifnot(is.numeric(x))
+}
 
-              func myPublicMethod(with parameter: Int) -> {
-              }
+normalize <- function(x, na.rm = TRUE) {
+  inner <- function(y) y
+  (x - mean(x, na.rm = na.rm)) / sd(x, na.rm = na.rm)
+}
 
-              private func myPrivateMethod(with parameter: Int) -> {
-              }
-            }
+setClass("Person", representation(name = "character", age = "numeric"))
 
-            class MyClass {
-                public var myPublicVariable = 0
-                private var myPrivateVariable = 0
+setGeneric("greet", function(obj, ...) standardGeneric("greet"))
 
-                init(myParameter: Int, myOtherParameter: Int) {
-                }
+setMethod("greet", "Person", function(obj, ...) {
+  cat("Hello,", obj@name)
+})
+"#;
 
-                func myPublicMethod(with parameter: Int) -> {
-                }
+        let definitions = extract_definitions("r", source).unwrap();
+        let stringified = stringify_definitions(&definitions);
+        println!("{stringified}");
+        let expected = "var DEFAULT_ALPHA;var max_iter;var mode;func normalize(x, na.rm = TRUE);func greet(obj, ...);class Person{func greet(obj, ...);var name:character;var age:numeric;};";
+        assert_eq!(stringified, expected);
+    }
 
-                private func myPrivateMethod(with parameter: Int) -> {
-                }
+    #[test]
+    fn test_erlang() {
+        let source = r"
+-module(shapes).
+-export([area/1, new_point/2]).
 
-                func myMethod() {
-                    print("Hello, world!")
-                }
-            }
-        "#;
+-record(point, {x = 0, y = 0 :: integer()}).
+-record(circle, {center, radius}).
 
-        let definitions = extract_definitions("swift", source).unwrap();
+area({circle, R}) -> 3.14 * R * R;
+area({rect, W, H}) -> W * H;
+area(_) -> 0.
+
+new_point(X, Y) -> #point{x = X, y = Y}.
+
+new_point(X) -> new_point(X, X).
+
+helper(X) -> X.
+";
+
+        let definitions = extract_definitions("erlang", source).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected = "var myPublicVariable;class MyClass{func init() -> void;func myPublicMethod() -> void;func myMethod() -> void;var myPublicVariable;};class MyStruct{func myPublicMethod() -> void;var myPublicVariable;};";
+        let expected = "record circle{var center;var radius;};record point{var x;var y:integer();};module shapes{func area/1({circle, R});func new_point/2(X, Y);};";
         assert_eq!(stringified, expected);
     }
 
     #[test]
-    fn test_php() {
+    fn test_hcl() {
         let source = r#"
-        <?php
-        class MyClass {
-            public $myPublicVariable = 0;
-            private $myPrivateVariable = 0;
+provider "aws" {
+  region = var.region
+}
 
-            public function myPublicMethod($parameter) {
-            }
+variable "region" {
+  type    = string
+  default = "us-east-1"
+}
 
-            private function myPrivateMethod($parameter) {
-            }
+variable "tags" {
+  type = map(string)
+}
 
-            function myMethod() {
-                echo "Hello, world!";
-            }
-        }
-        ?>
-        "#;
+data "aws_ami" "ubuntu" {
+  most_recent = true
+  owners      = ["099720109477"]
+}
 
-        let definitions = extract_definitions("php", source).unwrap();
+resource "aws_instance" "web" {
+  ami           = data.aws_ami.ubuntu.id
+  instance_type = "t3.micro"
+  tags          = var.tags
+
+  lifecycle {
+    create_before_destroy = true
+  }
+}
+
+resource "aws_eip" "web" {
+  instance = aws_instance.web.id
+}
+
+module "vpc" {
+  source = "./modules/vpc"
+}
+
+output "instance_id" {
+  value = aws_instance.web.id
+}
+"#;
+
+        let definitions = extract_definitions("hcl", source).unwrap();
         let stringified = stringify_definitions(&definitions);
         println!("{stringified}");
-        let expected = "class MyClass{func myPublicMethod($parameter) -> void;func myPrivateMethod($parameter) -> void;func myMethod() -> void;var public $myPublicVariable = 0;;var private $myPrivateVariable = 0;;};";
+        let expected = "var var.region:string;var var.tags:map(string);var output.instance_id;data aws_ami.ubuntu{var most_recent;var owners;};resource aws_eip.web{var instance;};resource aws_instance.web{var ami;var instance_type;var tags;};module vpc{var source;};";
         assert_eq!(stringified, expected);
     }
 
     #[test]
-    fn test_java() {
-        let source = r#"
-        public class MyClass {
-            public void myPublicMethod(String parameter) {
-                System.out.println("Hello, world!");
-            }
+    fn test_map_repository_path_style() {
+        let root =
+            std::env::temp_dir().join(format!("avante-repo-map-path-style-{}", std::process::id()));
+        let nested = root.join("src").join("nested");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(nested.join("lib.rs"), "pub fn hello() {}").unwrap();
+        let canonical_root = std::fs::canonicalize(&root).unwrap();
+
+        let map_with_style = |path_style: PathStyle| {
+            let options = MapOptions {
+                path_style,
+                ..Default::default()
+            };
+            let file_maps = map_repository(&root, &options).unwrap();
+            assert_eq!(file_maps.len(), 1);
+            assert_eq!(file_maps[0].lang, "rust");
+            assert_eq!(file_maps[0].defs, "func hello() -> void;");
+            file_maps[0].path.clone()
+        };
 
-            private void myPrivateMethod(String parameter) {
-                System.out.println("Hello, world!");
-            }
+        let relative = Path::new("src").join("nested").join("lib.rs");
+        assert_eq!(
+            map_with_style(PathStyle::Absolute),
+            canonical_root.join(&relative).to_string_lossy()
+        );
+        assert_eq!(
+            map_with_style(PathStyle::Relative),
+            relative.to_string_lossy()
+        );
+        assert_eq!(map_with_style(PathStyle::Basename), "lib.rs");
+        let redacted = map_with_style(PathStyle::Redacted);
+        assert!(redacted.starts_with("redacted-"));
+        assert_eq!(
+            Path::new(&redacted).extension().and_then(|e| e.to_str()),
+            Some("rs")
+        );
+        assert!(!redacted.contains("nested"));
+        assert_eq!(MapOptions::default().path_style, PathStyle::Relative);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
 
-            void myMethod() {
-                System.out.println("Hello, world!");
-            }
+    #[test]
+    fn test_map_repository_module_doc() {
+        let root =
+            std::env::temp_dir().join(format!("avante-repo-map-module-doc-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join("utils.py"),
+            "#!/usr/bin/env python\n\"\"\"Helpers for parsing.\n\nKeep these pure.\n\"\"\"\n\ndef parse(a: int) -> int:\n    return a\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("lib.rs"),
+            "//! Crate banner.\n//! Second line.\n\n// Unrelated\npub fn hello() {}\n",
+        )
+        .unwrap();
+
+        let file_maps = map_repository(&root, &MapOptions::default()).unwrap();
+        assert!(file_maps.iter().all(|m| m.module_doc.is_none()));
+
+        let options = MapOptions {
+            include_module_doc: true,
+            ..Default::default()
+        };
+        let file_maps = map_repository(&root, &options).unwrap();
+        assert_eq!(file_maps[0].path, "lib.rs");
+        assert_eq!(
+            file_maps[0].module_doc.as_deref(),
+            Some("Crate banner.\nSecond line.")
+        );
+        assert_eq!(file_maps[1].path, "utils.py");
+        assert_eq!(
+            file_maps[1].module_doc.as_deref(),
+            Some("Helpers for parsing.\n\nKeep these pure.")
+        );
+
+        let options = MapOptions {
+            include_module_doc: true,
+            module_doc_max_chars: 7,
+            ..Default::default()
+        };
+        let file_maps = map_repository(&root, &options).unwrap();
+        assert_eq!(file_maps[1].module_doc.as_deref(), Some("Helpers..."));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_map_repository_checksum() {
+        let root =
+            std::env::temp_dir().join(format!("avante-repo-map-checksum-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let content = "pub fn hello() -> u32 {\n    1\n}\n";
+        std::fs::write(root.join("lib.rs"), content).unwrap();
+
+        let file_maps = map_repository(&root, &MapOptions::default()).unwrap();
+        assert!(file_maps[0].checksum.is_none());
+
+        let options = MapOptions {
+            include_checksum: true,
+            ..Default::default()
+        };
+        let file_maps = map_repository(&root, &options).unwrap();
+        assert_eq!(
+            file_maps[0].checksum.as_deref(),
+            Some("sha256:5ed918fab9534e881ebe5f02e0b44eca37b1629ecaa1bbd42dd56f6911658777")
+        );
+
+        std::fs::write(root.join("lib.rs"), "pub fn hello() -> u32 {\n    2\n}\n").unwrap();
+        let changed = map_repository(&root, &options).unwrap();
+        assert_eq!(changed[0].defs, file_maps[0].defs);
+        assert_ne!(changed[0].checksum, file_maps[0].checksum);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_map_repository_walk() {
+        let root =
+            std::env::temp_dir().join(format!("avante-repo-map-walk-{}", std::process::id()));
+        for directory in ["src", "generated", "node_modules/dep", "target/debug"] {
+            std::fs::create_dir_all(root.join(directory)).unwrap();
         }
-        "#;
+        std::fs::write(root.join(".gitignore"), "generated/\n*.min.js\n").unwrap();
+        std::fs::write(root.join("src").join("lib.rs"), "pub fn kept() {}").unwrap();
+        std::fs::write(root.join("src").join("app.min.js"), "function ignored() {}").unwrap();
+        std::fs::write(root.join("generated").join("out.rs"), "pub fn ignored() {}").unwrap();
+        std::fs::write(root.join("node_modules/dep/index.js"), "function dep() {}").unwrap();
+        std::fs::write(root.join("target/debug/build.rs"), "pub fn build() {}").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&root, root.join("src").join("cycle")).unwrap();
+
+        let file_maps = map_repository(&root, &MapOptions::default()).unwrap();
+        let paths: Vec<_> = file_maps.iter().map(|m| m.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![Path::new("src").join("lib.rs").to_string_lossy()]
+        );
+        assert!(file_maps[0].error.is_none());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
 
-        let definitions = extract_definitions("java", source).unwrap();
-        let stringified = stringify_definitions(&definitions);
-        println!("{stringified}");
-        let expected =
-            "class MyClass{func myPublicMethod(String parameter) -> void;func myMethod() -> void;};";
-        assert_eq!(stringified, expected);
+    #[test]
+    fn test_project_session_invalidation() {
+        let root =
+            std::env::temp_dir().join(format!("avante-repo-map-session-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src").join("a.rs"), "pub fn a() {}").unwrap();
+        std::fs::write(root.join("src").join("b.rs"), "pub fn b() {}").unwrap();
+
+        let mut state = State::default();
+        let session_id = state.open_session(&root, MapOptions::default()).unwrap();
+        let session = state.session(session_id).unwrap();
+        assert_eq!(session.parse_count, 2);
+
+        std::fs::write(root.join("src").join("b.rs"), "pub fn b2(x: u32) {}").unwrap();
+        session.invalidate(&["src/b.rs".to_string()]);
+        let project_map = session.map(None);
+        assert_eq!(session.parse_count, 3);
+        assert_eq!(project_map.reparsed, 1);
+        assert!(project_map.stale.is_empty());
+
+        let full_rescan = map_repository(&root, &MapOptions::default()).unwrap();
+        let paths = |files: &[FileMap]| {
+            files
+                .iter()
+                .map(|f| (f.path.clone(), f.defs.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(paths(&project_map.files), paths(&full_rescan));
+
+        std::fs::write(root.join("src").join("c.rs"), "pub fn c() {}").unwrap();
+        session.invalidate(&["src/a.rs".to_string(), "src/c.rs".to_string()]);
+        let project_map = session.map(Some(1));
+        assert_eq!(project_map.reparsed, 1);
+        assert_eq!(project_map.stale.len(), 1);
+
+        assert!(state.sessions.remove(&session_id).is_some());
+        assert!(state.session(session_id).is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_project_session_walk() {
+        let root = std::env::temp_dir().join(format!(
+            "avante-repo-map-session-walk-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("dist")).unwrap();
+        std::fs::write(root.join(".gitignore"), "dist/\n").unwrap();
+        std::fs::write(root.join("src").join("lib.rs"), "pub fn kept() {}").unwrap();
+        std::fs::write(root.join("dist").join("bundle.js"), "function ignored() {}").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&root, root.join("src").join("cycle")).unwrap();
+
+        let mut state = State::default();
+        let session_id = state.open_session(&root, MapOptions::default()).unwrap();
+        let session = state.session(session_id).unwrap();
+        assert_eq!(session.parse_count, 1);
+        let project_map = session.map(None);
+        assert_eq!(project_map.files.len(), 1);
+        assert!(project_map.files[0].defs.contains("kept"));
+        assert!(project_map.files[0].error.is_none());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_guess_language() {
+        let python = "#!/usr/bin/env python3\nprint('hello')\n";
+        assert_eq!(guess_language(python), Some("python"));
+
+        let go = "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt.Println(\"hi\")\n}\n";
+        assert_eq!(guess_language(go), Some("go"));
+
+        let php = "<?php\necho 'Hello';\n";
+        assert_eq!(guess_language(php), Some("php"));
+
+        let rust = "use std::io;\n\nfn main() {\n    println!(\"hi\");\n}\n";
+        assert_eq!(guess_language(rust), Some("rust"));
+
+        let javascript = "const template = \"<?php echo 1; ?>\";\nconst a = 1;\n";
+        assert_eq!(guess_language(javascript), Some("javascript"));
+
+        let typescript = "import { run } from './run';\nconst count: number = run();\n";
+        assert_eq!(guess_language(typescript), Some("typescript"));
+
+        let javascript = "import { run } from './run';\nconst count = run();\n";
+        assert_eq!(guess_language(javascript), Some("javascript"));
+
+        assert_eq!(guess_language("hello world"), None);
     }
 
     #[test]
@@ -1,116 +1,294 @@
 use minijinja::{context, Environment};
 use mlua::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
-struct State<'a> {
-    environment: Mutex<Option<Environment<'a>>>,
+const DEFAULT_RENDER_TIMEOUT: Duration = Duration::from_secs(2);
+// Fuel granted per millisecond of render timeout. minijinja burns through about 20k instructions
+// a millisecond, the margin leaves the timeout to end renders and the fuel to stop abandoned ones.
+const FUEL_PER_MS: u64 = 80_000;
+
+// Bounds the work a render keeps doing on the worker after it timed out
+fn render_fuel(timeout: Duration) -> u64 {
+    // minijinja keeps the remaining fuel in an isize
+    let max_fuel = u64::try_from(isize::MAX).unwrap_or(u64::MAX);
+    u64::try_from(timeout.as_millis())
+        .unwrap_or(u64::MAX)
+        .saturating_mul(FUEL_PER_MS)
+        .min(max_fuel)
+}
+
+pub use avante_error::Error;
+
+// Prefix of the errors raised to Lua
+const LUA_MODULE: &str = "avante_templates";
+
+fn template_error(error: &minijinja::Error) -> Error {
+    match error.kind() {
+        minijinja::ErrorKind::SyntaxError => Error::Parse(error.to_string()),
+        _ => Error::Render(error.to_string()),
+    }
+}
+
+// A render handed to the worker thread, its result comes back through `reply`
+struct RenderJob<'a> {
+    environment: Arc<Environment<'a>>,
+    template: String,
+    context: minijinja::Value,
+    reply: mpsc::Sender<Result<String, minijinja::Error>>,
+}
+
+// The thread renders are handed to, `busy` while it works on a job
+struct RenderWorker<'a> {
+    sender: mpsc::Sender<RenderJob<'a>>,
+    busy: Arc<AtomicBool>,
+}
+
+pub struct State<'a> {
+    environment: Mutex<Option<Arc<Environment<'a>>>>,
+    default_capabilities: Mutex<BTreeMap<String, bool>>,
+    render_timeout: Mutex<Duration>,
+    worker: Mutex<Option<RenderWorker<'a>>>,
+}
+
+impl Default for State<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl State<'_> {
-    fn new() -> Self {
+    pub fn new() -> Self {
         State {
             environment: Mutex::new(None),
+            default_capabilities: Mutex::new(BTreeMap::new()),
+            render_timeout: Mutex::new(DEFAULT_RENDER_TIMEOUT),
+            worker: Mutex::new(None),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct SelectedCode {
-    path: String,
-    content: Option<String>,
-    file_type: String,
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SelectedCode {
+    pub path: String,
+    pub content: Option<String>,
+    pub file_type: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct SelectedFile {
-    path: String,
-    content: Option<String>,
-    file_type: String,
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SelectedFile {
+    pub path: String,
+    pub content: Option<String>,
+    pub file_type: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TemplateContext {
-    ask: bool,
-    code_lang: String,
-    selected_files: Option<Vec<SelectedFile>>,
-    selected_code: Option<SelectedCode>,
-    recently_viewed_files: Option<Vec<String>>,
-    relevant_files: Option<Vec<String>>,
-    project_context: Option<String>,
-    diagnostics: Option<String>,
-    system_info: Option<String>,
-    model_name: Option<String>,
-    memory: Option<String>,
-    todos: Option<String>,
-    enable_fastapply: Option<bool>,
-    use_react_prompt: Option<bool>,
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TemplateContext {
+    pub ask: bool,
+    pub code_lang: String,
+    pub selected_files: Option<Vec<SelectedFile>>,
+    pub selected_code: Option<SelectedCode>,
+    pub recently_viewed_files: Option<Vec<String>>,
+    pub relevant_files: Option<Vec<String>>,
+    pub project_context: Option<String>,
+    pub diagnostics: Option<String>,
+    pub system_info: Option<String>,
+    pub model_name: Option<String>,
+    pub memory: Option<String>,
+    pub todos: Option<String>,
+    pub enable_fastapply: Option<bool>,
+    pub use_react_prompt: Option<bool>,
+    pub capabilities: Option<BTreeMap<String, bool>>,
 }
 
-// Given the file name registered after add, the context table in Lua, resulted in a formatted
-// Lua string
-#[allow(clippy::needless_pass_by_value)]
-fn render(state: &State, template: &str, context: TemplateContext) -> LuaResult<String> {
-    let environment = state.environment.lock().unwrap();
-    match environment.as_ref() {
-        Some(environment) => {
-            let jinja_template = environment
-                .get_template(template)
-                .map_err(LuaError::external)
-                .unwrap();
-
-            Ok(jinja_template
-                .render(context! {
-                  ask => context.ask,
-                  code_lang => context.code_lang,
-                  selected_files => context.selected_files,
-                  selected_code => context.selected_code,
-                  recently_viewed_files => context.recently_viewed_files,
-                  relevant_files => context.relevant_files,
-                  project_context => context.project_context,
-                  diagnostics => context.diagnostics,
-                  system_info => context.system_info,
-                  model_name => context.model_name,
-                  memory => context.memory,
-                  todos => context.todos,
-                  enable_fastapply => context.enable_fastapply,
-                  use_react_prompt => context.use_react_prompt,
-                })
-                .map_err(LuaError::external)
-                .unwrap())
+impl TemplateContext {
+    fn to_value(&self) -> minijinja::Value {
+        context! {
+          ask => self.ask,
+          code_lang => self.code_lang,
+          selected_files => self.selected_files,
+          selected_code => self.selected_code,
+          recently_viewed_files => self.recently_viewed_files,
+          relevant_files => self.relevant_files,
+          project_context => self.project_context,
+          diagnostics => self.diagnostics,
+          system_info => self.system_info,
+          model_name => self.model_name,
+          memory => self.memory,
+          todos => self.todos,
+          enable_fastapply => self.enable_fastapply,
+          use_react_prompt => self.use_react_prompt,
+        }
+    }
+}
+
+// Renders one job after the other until it is replaced, which happens when a job abandoned after
+// its timeout still runs. That job then goes on until it finishes or runs out of the fuel derived
+// from the render timeout.
+fn spawn_render_worker() -> RenderWorker<'static> {
+    let (sender, receiver) = mpsc::channel::<RenderJob<'static>>();
+    let busy = Arc::new(AtomicBool::new(false));
+    let worker_busy = Arc::clone(&busy);
+    thread::spawn(move || {
+        for job in receiver {
+            let result = job
+                .environment
+                .get_template(&job.template)
+                .and_then(|jinja_template| jinja_template.render(&job.context));
+            worker_busy.store(false, Ordering::SeqCst);
+            let _ = job.reply.send(result);
         }
-        None => Err(LuaError::RuntimeError(
+    });
+    RenderWorker { sender, busy }
+}
+
+// Given the file name registered after add, the context table in Lua, resulted in a formatted
+// Lua string. The render runs on a worker thread so that a runaway template can be abandoned
+// once the render timeout is exceeded.
+pub fn render(
+    state: &State<'static>,
+    template: &str,
+    mut context: TemplateContext,
+) -> Result<String, Error> {
+    let environment = state.environment.lock().unwrap().clone();
+    let Some(environment) = environment else {
+        return Err(Error::NotInitialized(
             "Environment not initialized".to_string(),
-        )),
+        ));
+    };
+    let mut capabilities = state.default_capabilities.lock().unwrap().clone();
+    capabilities.extend(context.capabilities.take().unwrap_or_default());
+    // Capabilities travel with the render, a timed out render still running keeps its own
+    let context = context! {
+        __avante_capabilities => capabilities,
+        ..context.to_value()
+    };
+
+    let timeout = *state.render_timeout.lock().unwrap();
+    let (reply, receiver) = mpsc::channel();
+    let job = RenderJob {
+        environment,
+        template: template.to_string(),
+        context,
+        reply,
+    };
+    {
+        let mut worker = state.worker.lock().unwrap();
+        if worker
+            .as_ref()
+            .is_none_or(|worker| worker.busy.load(Ordering::SeqCst))
+        {
+            *worker = Some(spawn_render_worker());
+        }
+        let mut current = worker.as_ref().unwrap();
+        current.busy.store(true, Ordering::SeqCst);
+        // A panicking render takes its worker down with it
+        if let Err(mpsc::SendError(job)) = current.sender.send(job) {
+            current = worker.insert(spawn_render_worker());
+            current.busy.store(true, Ordering::SeqCst);
+            let _ = current.sender.send(job);
+        }
+    }
+    let timed_out = || {
+        Error::Render(format!(
+            "template render timed out after {}ms in template {template}",
+            timeout.as_millis()
+        ))
+    };
+    match receiver.recv_timeout(timeout) {
+        Ok(Err(error)) if error.kind() == minijinja::ErrorKind::OutOfFuel => Err(timed_out()),
+        Ok(result) => result.map_err(|error| template_error(&error)),
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(timed_out()),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(Error::Render(format!(
+            "template render failed in template {template}"
+        ))),
+    }
+}
+
+// Also rescales the fuel of an initialized environment to the new timeout
+pub fn set_render_timeout(state: &State, timeout: Duration) {
+    *state.render_timeout.lock().unwrap() = timeout;
+    let mut environment = state.environment.lock().unwrap();
+    if let Some(current) = environment.as_ref() {
+        let mut env = Environment::clone(current);
+        env.set_fuel(Some(render_fuel(timeout)));
+        *environment = Some(Arc::new(env));
     }
 }
 
-fn initialize(state: &State, cache_directory: String, project_directory: String) {
+// Render a template source directly, without a loader or registered functions
+pub fn render_str(source: &str, context: &TemplateContext) -> Result<String, minijinja::Error> {
+    let env = Environment::new();
+    env.render_str(source, context.to_value())
+}
+
+// `{{ count | pluralize("file", "files") }}`, the singular only for a count of exactly one
+fn pluralize(count: i64, singular: String, plural: String) -> String {
+    if count == 1 {
+        singular
+    } else {
+        plural
+    }
+}
+
+pub fn initialize(
+    state: &State,
+    cache_directory: &str,
+    project_directory: &str,
+    capabilities: Option<BTreeMap<String, bool>>,
+) {
     let mut environment_mutex = state.environment.lock().unwrap();
     let mut env = Environment::new();
+    env.set_fuel(Some(render_fuel(*state.render_timeout.lock().unwrap())));
+
+    // Provider capabilities and the model name are read from the render context
+    *state.default_capabilities.lock().unwrap() = capabilities.unwrap_or_default();
+    env.add_function(
+        "has_capability",
+        |state: &minijinja::State, name: String| -> bool {
+            state
+                .lookup("__avante_capabilities")
+                .and_then(|capabilities| capabilities.get_attr(&name).ok())
+                .is_some_and(|capability| capability.is_true())
+        },
+    );
+    env.add_function(
+        "model_in",
+        |state: &minijinja::State, models: Vec<String>| -> bool {
+            state
+                .lookup("model_name")
+                .and_then(|model_name| model_name.as_str().map(str::to_string))
+                .is_some_and(|model_name| models.contains(&model_name))
+        },
+    );
+    env.add_filter("pluralize", pluralize);
 
     // Create a custom loader that searches both cache and project directories
-    let cache_dir = cache_directory.clone();
-    let project_dir = project_directory.clone();
+    let cache_dir = cache_directory.to_string();
+    let project_dir = project_directory.to_string();
 
     env.set_loader(
         move |name: &str| -> Result<Option<String>, minijinja::Error> {
             // First try the cache directory (for built-in templates)
             let cache_path = Path::new(&cache_dir).join(name);
             if cache_path.exists() {
-                match std::fs::read_to_string(&cache_path) {
-                    Ok(content) => return Ok(Some(content)),
-                    Err(_) => {} // Continue to try project directory
+                // Read errors fall through to the project directory
+                if let Ok(content) = std::fs::read_to_string(&cache_path) {
+                    return Ok(Some(content));
                 }
             }
 
             // Then try the project directory (for custom includes)
             let project_path = Path::new(&project_dir).join(name);
             if project_path.exists() {
-                match std::fs::read_to_string(&project_path) {
-                    Ok(content) => return Ok(Some(content)),
-                    Err(_) => {} // File not found or read error
+                // Read errors count as not found
+                if let Ok(content) = std::fs::read_to_string(&project_path) {
+                    return Ok(Some(content));
                 }
             }
 
@@ -119,7 +297,7 @@ fn initialize(state: &State, cache_directory: String, project_directory: String)
         },
     );
 
-    *environment_mutex = Some(env);
+    *environment_mutex = Some(Arc::new(env));
 }
 
 #[mlua::lua_module]
@@ -132,8 +310,17 @@ fn avante_templates(lua: &Lua) -> LuaResult<LuaTable> {
     exports.set(
         "initialize",
         lua.create_function(
-            move |_, (cache_directory, project_directory): (String, String)| {
-                initialize(&state, cache_directory, project_directory);
+            move |_,
+                  (cache_directory, project_directory, capabilities, render_timeout_ms): (
+                String,
+                String,
+                Option<BTreeMap<String, bool>>,
+                Option<u64>,
+            )| {
+                let render_timeout =
+                    render_timeout_ms.map_or(DEFAULT_RENDER_TIMEOUT, Duration::from_millis);
+                set_render_timeout(&state, render_timeout);
+                initialize(&state, &cache_directory, &project_directory, capabilities);
                 Ok(())
             },
         )?,
@@ -143,6 +330,7 @@ fn avante_templates(lua: &Lua) -> LuaResult<LuaTable> {
         lua.create_function_mut(move |lua, (template, context): (String, LuaValue)| {
             let ctx = lua.from_value(context)?;
             render(&state_clone, template.as_str(), ctx)
+                .map_err(|e| e.into_lua(LUA_MODULE, "render"))
         })?,
     )?;
     Ok(exports)
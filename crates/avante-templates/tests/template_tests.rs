@@ -0,0 +1,262 @@
+use avante_templates::{
+    initialize, render, render_str, set_render_timeout, Error, SelectedFile, State, TemplateContext,
+};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+fn test_context() -> TemplateContext {
+    TemplateContext {
+        ask: true,
+        code_lang: "rust".to_string(),
+        ..Default::default()
+    }
+}
+
+fn write_templates(name: &str, templates: &[(&str, &str)]) -> String {
+    let directory = std::env::temp_dir().join(format!(
+        "avante-template-tests-{name}-{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&directory).unwrap();
+    for (file, content) in templates {
+        std::fs::write(directory.join(file), content).unwrap();
+    }
+    directory.to_string_lossy().to_string()
+}
+
+#[test]
+fn test_template_context_serialization() {
+    let context = TemplateContext {
+        ask: true,
+        code_lang: "rust".to_string(),
+        model_name: Some("gpt-4o".to_string()),
+        use_react_prompt: Some(false),
+        ..Default::default()
+    };
+    let value = minijinja::Value::from_serialize(&context);
+    assert_eq!(value.get_attr("ask").unwrap(), minijinja::Value::from(true));
+    assert_eq!(
+        value.get_attr("code_lang").unwrap(),
+        minijinja::Value::from("rust")
+    );
+    assert_eq!(
+        value.get_attr("use_react_prompt").unwrap(),
+        minijinja::Value::from(false)
+    );
+    assert!(value.get_attr("memory").unwrap().is_none());
+}
+
+#[test]
+fn test_variable_substitution() {
+    let context = TemplateContext {
+        code_lang: "python".to_string(),
+        selected_files: Some(vec![
+            SelectedFile {
+                path: "a.py".to_string(),
+                content: Some("print(1)".to_string()),
+                file_type: "python".to_string(),
+            },
+            SelectedFile {
+                path: "b.py".to_string(),
+                content: None,
+                file_type: "python".to_string(),
+            },
+        ]),
+        ..Default::default()
+    };
+    let rendered = render_str(
+        "{{ code_lang }}:{% for file in selected_files %} {{ file.path }}{% endfor %}",
+        &context,
+    )
+    .unwrap();
+    assert_eq!(rendered, "python: a.py b.py");
+}
+
+#[test]
+fn test_render_from_directories() {
+    let cache_directory = write_templates(
+        "cache",
+        &[(
+            "base.avanterules",
+            "{% include \"custom.avanterules\" %} in {{ code_lang }}",
+        )],
+    );
+    let project_directory = write_templates(
+        "project",
+        &[(
+            "custom.avanterules",
+            "{% if ask %}asking{% else %}editing{% endif %}",
+        )],
+    );
+    let state = State::new();
+    initialize(&state, &cache_directory, &project_directory, None);
+
+    let context = TemplateContext {
+        ask: true,
+        code_lang: "lua".to_string(),
+        ..Default::default()
+    };
+    let rendered = render(&state, "base.avanterules", context).unwrap();
+    assert_eq!(rendered, "asking in lua");
+
+    std::fs::remove_dir_all(&cache_directory).unwrap();
+    std::fs::remove_dir_all(&project_directory).unwrap();
+}
+
+#[test]
+fn test_render_errors() {
+    let state = State::new();
+    let error = render(&state, "base.avanterules", TemplateContext::default()).unwrap_err();
+    assert!(error.to_string().contains("Environment not initialized"));
+
+    let directory = write_templates("errors", &[]);
+    initialize(&state, &directory, &directory, None);
+    assert!(render(&state, "missing.avanterules", TemplateContext::default()).is_err());
+
+    assert!(render_str("{% if %}", &TemplateContext::default()).is_err());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_use_react_prompt() {
+    let template = "{% if use_react_prompt %}react{% else %}native{% endif %}";
+    let render_with = |use_react_prompt: Option<bool>| {
+        let context = TemplateContext {
+            use_react_prompt,
+            ..Default::default()
+        };
+        render_str(template, &context).unwrap()
+    };
+    assert_eq!(render_with(Some(true)), "react");
+    assert_eq!(render_with(Some(false)), "native");
+    assert_eq!(render_with(None), "native");
+    assert_eq!(TemplateContext::default().use_react_prompt, None);
+}
+
+#[test]
+fn test_render_timeout() {
+    let directory = write_templates(
+        "timeout",
+        &[
+            (
+                "slow.avanterules",
+                "{% for i in range(10000) %}{% for j in range(10000) %}{{ i * j }}{% endfor %}{% endfor %}",
+            ),
+            ("fast.avanterules", "{{ code_lang }}"),
+        ],
+    );
+    let state = State::new();
+    initialize(&state, &directory, &directory, None);
+    set_render_timeout(&state, Duration::from_millis(50));
+
+    let started = Instant::now();
+    let error = render(&state, "slow.avanterules", TemplateContext::default()).unwrap_err();
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert!(error
+        .to_string()
+        .contains("template render timed out after 50ms in template slow.avanterules"));
+
+    let context = TemplateContext {
+        code_lang: "rust".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(render(&state, "fast.avanterules", context).unwrap(), "rust");
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_render_fuel_follows_timeout() {
+    // About 28M instructions, more than a fixed fuel budget sized for the 2s default allows
+    let directory = write_templates(
+        "fuel",
+        &[(
+            "slow.avanterules",
+            "{% for i in range(2000) %}{% for j in range(2000) %}{{ i * j }}{% endfor %}{% endfor %}",
+        )],
+    );
+    let state = State::new();
+    initialize(&state, &directory, &directory, None);
+    set_render_timeout(&state, Duration::from_secs(30));
+
+    let rendered = render(&state, "slow.avanterules", TemplateContext::default()).unwrap();
+    assert!(rendered.ends_with(&(1999 * 1999).to_string()));
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_capability_functions() {
+    let directory = write_templates(
+        "capabilities",
+        &[
+            (
+                "base.avanterules",
+                "{% import \"macros.avanterules\" as m %}{% include \"images.avanterules\" %}|{{ m.tools() }}",
+            ),
+            (
+                "images.avanterules",
+                "{% if has_capability(\"vision\") %}image instructions{% endif %}",
+            ),
+            (
+                "macros.avanterules",
+                "{% macro tools() %}{% if model_in([\"gpt-4o\", \"claude\"]) %}native tools{% endif %}{% endmacro %}",
+            ),
+        ],
+    );
+    let state = State::new();
+    initialize(&state, &directory, &directory, None);
+
+    let rendered = render(&state, "base.avanterules", test_context()).unwrap();
+    assert_eq!(rendered, "|");
+
+    let mut context = test_context();
+    context.capabilities = Some(BTreeMap::from([("vision".to_string(), true)]));
+    context.model_name = Some("gpt-4o".to_string());
+    let rendered = render(&state, "base.avanterules", context).unwrap();
+    assert_eq!(rendered, "image instructions|native tools");
+
+    let defaults = BTreeMap::from([("vision".to_string(), true)]);
+    initialize(&state, &directory, &directory, Some(defaults));
+    let rendered = render(&state, "base.avanterules", test_context()).unwrap();
+    assert_eq!(rendered, "image instructions|");
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_pluralize_filter() {
+    let directory = write_templates(
+        "pluralize",
+        &[(
+            "selection.avanterules",
+            "{% for count in [1, 3] %}{{ count }} {{ count | pluralize(\"file\", \"files\") }} selected;{% endfor %}",
+        )],
+    );
+    let state = State::new();
+    initialize(&state, &directory, &directory, None);
+
+    let rendered = render(&state, "selection.avanterules", test_context()).unwrap();
+    assert_eq!(rendered, "1 file selected;3 files selected;");
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn test_lua_error_prefix() {
+    let state = State::new();
+    let error = render(&state, "base.avanterules", test_context()).unwrap_err();
+    assert!(matches!(error, Error::NotInitialized(_)));
+    assert!(error
+        .into_lua("avante_templates", "render")
+        .to_string()
+        .contains("avante_templates: render: Environment not initialized"));
+
+    let directory = write_templates("lua-error", &[("broken.avanterules", "{% if %}")]);
+    initialize(&state, &directory, &directory, None);
+    let error = render(&state, "broken.avanterules", test_context()).unwrap_err();
+    assert!(matches!(error, Error::Parse(_)));
+    assert!(error
+        .into_lua("avante_templates", "render")
+        .to_string()
+        .contains("avante_templates: render: syntax error"));
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -1,19 +1,28 @@
+use avante_error::Error;
 use hf_hub::{api::sync::ApiBuilder, Repo, RepoType};
 use mlua::prelude::*;
 use regex::Regex;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
 use tiktoken_rs::{get_bpe_from_model, CoreBPE};
+use tokenizers::models::bpe::BPE;
+use tokenizers::pre_tokenizers::byte_level::ByteLevel;
 use tokenizers::Tokenizer;
 
+// Prefix of the errors raised to Lua
+const LUA_MODULE: &str = "avante_tokenizers";
+
 struct Tiktoken {
     bpe: CoreBPE,
 }
 
 impl Tiktoken {
-    fn new(model: &str) -> Self {
-        let bpe = get_bpe_from_model(model).unwrap();
-        Self { bpe }
+    fn new(model: &str) -> Result<Self, Error> {
+        let bpe = get_bpe_from_model(model).map_err(|e| Error::Unsupported(e.to_string()))?;
+        Ok(Self { bpe })
     }
 
     fn encode(&self, text: &str) -> (Vec<u32>, usize, usize) {
@@ -33,21 +42,137 @@ fn is_valid_url(url: &str) -> bool {
     url_regex.is_match(url)
 }
 
+// Files a model may ship its tokenizer as, either consolidated or as a GPT-2 style BPE pair
+const TOKENIZER_FILE: &str = "tokenizer.json";
+const BPE_FILES: [&str; 2] = ["vocab.json", "merges.txt"];
+
+const DEFAULT_HF_ENDPOINT: &str = "https://huggingface.co";
+
+// An explicit endpoint wins over `HF_ENDPOINT`, which wins over huggingface.co
+fn resolve_endpoint(endpoint: Option<&str>) -> String {
+    endpoint
+        .map(str::to_string)
+        .or_else(|| std::env::var("HF_ENDPOINT").ok())
+        .filter(|endpoint| !endpoint.is_empty())
+        .map_or_else(
+            || DEFAULT_HF_ENDPOINT.to_string(),
+            |endpoint| endpoint.trim_end_matches('/').to_string(),
+        )
+}
+
+// Point huggingface.co download links at the configured mirror
+fn rewrite_hf_url(url: &str, endpoint: &str) -> String {
+    url.strip_prefix(DEFAULT_HF_ENDPOINT)
+        .filter(|rest| rest.starts_with('/'))
+        .map_or_else(|| url.to_string(), |rest| format!("{endpoint}{rest}"))
+}
+
+fn hub_api_builder(endpoint: &str) -> ApiBuilder {
+    ApiBuilder::new()
+        .with_progress(false)
+        .with_endpoint(endpoint.to_string())
+}
+
+// Whether loading the model goes over the network rather than reading local files
+fn is_remote_model(model: &str) -> bool {
+    is_valid_url(model) || !Path::new(model).exists()
+}
+
+// Hub ids are `name` or `owner/name`, anything else with a separator is meant as a local path
+fn looks_like_path(model: &str) -> bool {
+    model.starts_with(['.', '/', '~'])
+        || model.contains('\\')
+        || model.matches('/').count() > 1
+        || Path::new(model).is_absolute()
+}
+
 impl HuggingFaceTokenizer {
-    fn new(model: &str) -> Self {
-        let tokenizer_path = if is_valid_url(model) {
-            Self::get_cached_tokenizer(model)
-        } else {
-            // Use existing HuggingFace Hub logic for model names
-            let identifier = model.to_string();
-            let api = ApiBuilder::new().with_progress(false).build().unwrap();
-            let repo = Repo::new(identifier, RepoType::Model);
-            let api = api.repo(repo);
-            api.get("tokenizer.json").unwrap()
+    fn new(model: &str, endpoint: &str) -> Result<Self, Error> {
+        if is_valid_url(model) {
+            let url = rewrite_hf_url(model, endpoint);
+            let tokenizer_path = Self::get_cached_tokenizer(&url).map_err(|e| {
+                let message = format!("Failed to download {url}: {e} (endpoint {endpoint})");
+                match e {
+                    Error::Network(_) => Error::Network(message),
+                    _ => Error::Download(message),
+                }
+            })?;
+            return Self::from_file(&tokenizer_path);
+        }
+        let path = Path::new(model);
+        if path.is_file() {
+            return Self::from_file(path);
+        }
+        if path.is_dir() {
+            return Self::from_dir(path);
+        }
+        if looks_like_path(model) {
+            return Err(Error::NotFound(format!("Tokenizer path {model} not found")));
+        }
+
+        // Use existing HuggingFace Hub logic for model names
+        let identifier = model.to_string();
+        let api = hub_api_builder(endpoint)
+            .build()
+            .map_err(|e| Error::Network(format!("{e} (endpoint {endpoint})")))?;
+        let repo = Repo::new(identifier, RepoType::Model);
+        let api = api.repo(repo);
+        let tokenizer_error = match api.get(TOKENIZER_FILE) {
+            Ok(tokenizer_path) => return Self::from_file(&tokenizer_path),
+            Err(e) => e.to_string(),
         };
+        let mut found = Vec::new();
+        let mut missing = vec![TOKENIZER_FILE];
+        for file in BPE_FILES {
+            match api.get(file) {
+                Ok(file_path) => found.push(file_path),
+                Err(_) => missing.push(file),
+            }
+        }
+        if let [vocab, merges] = found.as_slice() {
+            return Self::from_bpe_files(vocab, merges);
+        }
+        Err(Error::Download(format!(
+            "No tokenizer found for {model} ({tokenizer_error}, endpoint {endpoint}): {}",
+            describe_files(&found, &missing)
+        )))
+    }
+
+    fn from_file(tokenizer_path: &Path) -> Result<Self, Error> {
+        let tokenizer =
+            Tokenizer::from_file(tokenizer_path).map_err(|e| Error::Parse(e.to_string()))?;
+        Ok(Self { tokenizer })
+    }
+
+    fn from_dir(dir: &Path) -> Result<Self, Error> {
+        let tokenizer_path = dir.join(TOKENIZER_FILE);
+        if tokenizer_path.is_file() {
+            return Self::from_file(&tokenizer_path);
+        }
+        let (found, missing): (Vec<_>, Vec<_>) =
+            BPE_FILES.iter().partition(|file| dir.join(file).is_file());
+        if !missing.is_empty() {
+            let found: Vec<PathBuf> = found.iter().map(|file| dir.join(file)).collect();
+            let mut missing: Vec<&str> = missing.into_iter().copied().collect();
+            missing.insert(0, TOKENIZER_FILE);
+            return Err(Error::NotFound(format!(
+                "No tokenizer found in {}: {}",
+                dir.display(),
+                describe_files(&found, &missing)
+            )));
+        }
+        Self::from_bpe_files(&dir.join(BPE_FILES[0]), &dir.join(BPE_FILES[1]))
+    }
 
-        let tokenizer = Tokenizer::from_file(tokenizer_path).unwrap();
-        Self { tokenizer }
+    // Byte-level BPE as used by GPT-2, spaces are folded into the following token
+    fn from_bpe_files(vocab: &Path, merges: &Path) -> Result<Self, Error> {
+        let bpe = BPE::from_file(&vocab.to_string_lossy(), &merges.to_string_lossy())
+            .build()
+            .map_err(|e| Error::Parse(format!("Failed to load {}: {e}", vocab.display())))?;
+        let mut tokenizer = Tokenizer::new(bpe);
+        tokenizer.with_pre_tokenizer(Some(ByteLevel::new(false, true, true)));
+        tokenizer.with_decoder(Some(ByteLevel::new(false, true, true)));
+        Ok(Self { tokenizer })
     }
 
     fn encode(&self, text: &str) -> (Vec<u32>, usize, usize) {
@@ -58,61 +183,261 @@ impl HuggingFaceTokenizer {
         (tokens, num_tokens, num_chars)
     }
 
-    fn get_cached_tokenizer(url: &str) -> PathBuf {
+    fn get_cached_tokenizer(url: &str) -> Result<PathBuf, Error> {
         let cache_dir = dirs::home_dir()
             .map(|h| h.join(".cache").join("avante"))
-            .unwrap();
-        std::fs::create_dir_all(&cache_dir).unwrap();
+            .ok_or_else(|| Error::NotFound("Failed to locate the home directory".to_string()))?;
+        std::fs::create_dir_all(&cache_dir).map_err(|e| Error::Io(e.to_string()))?;
 
         // Extract filename from URL
-        let filename = url.split('/').last().unwrap();
+        let filename = url.split('/').next_back().unwrap_or_default();
 
         let cached_path = cache_dir.join(filename);
 
         if !cached_path.exists() {
-            let response = ureq::get(url).call().unwrap();
-            let mut file = std::fs::File::create(&cached_path).unwrap();
+            let response = ureq::get(url)
+                .call()
+                .map_err(|e| Error::Network(e.to_string()))?;
+            let mut file =
+                std::fs::File::create(&cached_path).map_err(|e| Error::Io(e.to_string()))?;
             let mut reader = response.into_reader();
-            std::io::copy(&mut reader, &mut file).unwrap();
+            std::io::copy(&mut reader, &mut file).map_err(|e| Error::Download(e.to_string()))?;
         }
-        cached_path
+        Ok(cached_path)
     }
 }
 
+fn describe_files(found: &[PathBuf], missing: &[&str]) -> String {
+    let found: Vec<String> = found
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .collect();
+    let found = if found.is_empty() {
+        "none".to_string()
+    } else {
+        found.join(", ")
+    };
+    format!("found {found}, missing {}", missing.join(", "))
+}
+
 enum TokenizerType {
     Tiktoken(Tiktoken),
     HuggingFace(Box<HuggingFaceTokenizer>),
 }
 
+impl TokenizerType {
+    fn load(model: &str, endpoint: &str) -> Result<Self, Error> {
+        match model {
+            "gpt-4o" => Ok(TokenizerType::Tiktoken(Tiktoken::new(model)?)),
+            _ => Ok(TokenizerType::HuggingFace(Box::new(
+                HuggingFaceTokenizer::new(model, endpoint)?,
+            ))),
+        }
+    }
+
+    fn backend(&self) -> &'static str {
+        match self {
+            TokenizerType::Tiktoken(_) => "tiktoken",
+            TokenizerType::HuggingFace(_) => "huggingface",
+        }
+    }
+
+    fn encode(&self, text: &str) -> (Vec<u32>, usize, usize) {
+        match self {
+            TokenizerType::Tiktoken(tokenizer) => tokenizer.encode(text),
+            TokenizerType::HuggingFace(tokenizer) => tokenizer.encode(text),
+        }
+    }
+
+    // tiktoken-rs doesn't expose the size of its ranks
+    fn vocab_size(&self) -> Option<usize> {
+        match self {
+            TokenizerType::Tiktoken(_) => None,
+            TokenizerType::HuggingFace(tokenizer) => Some(tokenizer.tokenizer.get_vocab_size(true)),
+        }
+    }
+}
+
+type LoadResult = Result<Arc<TokenizerType>, Error>;
+
+struct TokenizerInfo {
+    model: String,
+    backend: &'static str,
+    // Only set for tokenizers downloaded from the hub or a URL
+    endpoint: Option<String>,
+}
+
 struct State {
-    tokenizer: Mutex<Option<TokenizerType>>,
+    tokenizer: Mutex<Option<Arc<TokenizerType>>>,
+    info: Mutex<Option<TokenizerInfo>>,
+    // One cell per model and resolved endpoint, so concurrent loads of the same model wait for a
+    // single download and a model fetched from another mirror is loaded again
+    loaded: Mutex<HashMap<(String, String), Arc<OnceLock<LoadResult>>>>,
+    load_count: AtomicUsize,
 }
 
 impl State {
     fn new() -> Self {
         State {
             tokenizer: Mutex::new(None),
+            info: Mutex::new(None),
+            loaded: Mutex::new(HashMap::new()),
+            load_count: AtomicUsize::new(0),
         }
     }
+
+    fn load(&self, model: &str, endpoint: &str) -> LoadResult {
+        let key = (model.to_string(), endpoint.to_string());
+        let cell = {
+            let mut loaded = self.loaded.lock().unwrap();
+            Arc::clone(loaded.entry(key.clone()).or_default())
+        };
+        let result = cell
+            .get_or_init(|| {
+                self.load_count.fetch_add(1, Ordering::SeqCst);
+                TokenizerType::load(model, endpoint).map(Arc::new)
+            })
+            .clone();
+        if result.is_err() {
+            // Forget failed loads so that a later call can retry
+            let mut loaded = self.loaded.lock().unwrap();
+            if loaded.get(&key).is_some_and(|c| Arc::ptr_eq(c, &cell)) {
+                loaded.remove(&key);
+            }
+        }
+        result
+    }
 }
 
-fn encode(state: &State, text: &str) -> LuaResult<(Vec<u32>, usize, usize)> {
+fn encode(state: &State, text: &str) -> Result<(Vec<u32>, usize, usize), Error> {
     let tokenizer = state.tokenizer.lock().unwrap();
     match tokenizer.as_ref() {
-        Some(TokenizerType::Tiktoken(tokenizer)) => Ok(tokenizer.encode(text)),
-        Some(TokenizerType::HuggingFace(tokenizer)) => Ok(tokenizer.encode(text)),
-        None => Err(LuaError::RuntimeError(
+        Some(tokenizer) => Ok(tokenizer.encode(text)),
+        None => Err(Error::NotInitialized(
             "Tokenizer not initialized".to_string(),
         )),
     }
 }
 
-fn from_pretrained(state: &State, model: &str) {
-    let mut tokenizer_mutex = state.tokenizer.lock().unwrap();
-    *tokenizer_mutex = Some(match model {
-        "gpt-4o" => TokenizerType::Tiktoken(Tiktoken::new(model)),
-        _ => TokenizerType::HuggingFace(Box::new(HuggingFaceTokenizer::new(model))),
+fn from_pretrained(state: &State, model: &str, endpoint: Option<&str>) -> Result<(), Error> {
+    let endpoint = resolve_endpoint(endpoint);
+    let tokenizer = state.load(model, &endpoint)?;
+    let backend = tokenizer.backend();
+    *state.info.lock().unwrap() = Some(TokenizerInfo {
+        model: model.to_string(),
+        backend,
+        endpoint: (backend == "huggingface" && is_remote_model(model)).then_some(endpoint),
     });
+    *state.tokenizer.lock().unwrap() = Some(tokenizer);
+    Ok(())
+}
+
+// Pay the first-call cost of the current tokenizer up front by encoding a tiny sample
+fn warmup_current(state: &State) -> Result<(), Error> {
+    if state.tokenizer.lock().unwrap().is_none() {
+        return Err(Error::NotInitialized(
+            "Cannot warm up: no tokenizer loaded, call from_pretrained first".to_string(),
+        ));
+    }
+    encode(state, "warmup").map(|_| ())
+}
+
+// Count the tokens of `text` with each model, leaving the current tokenizer untouched
+fn compare_tokenizers(
+    state: &State,
+    text: &str,
+    models: &[String],
+) -> Result<HashMap<String, usize>, Error> {
+    let endpoint = resolve_endpoint(None);
+    let mut counts = HashMap::new();
+    for model in models {
+        let tokenizer = state.load(model, &endpoint)?;
+        let (_, num_tokens, _) = tokenizer.encode(text);
+        counts.insert(model.clone(), num_tokens);
+    }
+    Ok(counts)
+}
+
+#[derive(Debug)]
+struct TokenizerValidation {
+    backend: &'static str,
+    vocab_size: Option<usize>,
+}
+
+// Build the tokenizer once to check it, bypassing the cache and the current tokenizer of `State`
+fn validate_tokenizer(model: &str, endpoint: Option<&str>) -> Result<TokenizerValidation, Error> {
+    let tokenizer = TokenizerType::load(model, &resolve_endpoint(endpoint))?;
+    Ok(TokenizerValidation {
+        backend: tokenizer.backend(),
+        vocab_size: tokenizer.vocab_size(),
+    })
+}
+
+struct Warmup {
+    pending: AtomicUsize,
+    finished: Mutex<Vec<(String, Result<(), String>)>>,
+}
+
+impl Warmup {
+    fn is_done(&self) -> bool {
+        self.pending.load(Ordering::SeqCst) == 0
+    }
+}
+
+// Load the given models on background threads without changing the current tokenizer
+fn warmup(state: &Arc<State>, models: &[String]) -> Arc<Warmup> {
+    let mut unique_models = models.to_vec();
+    unique_models.sort();
+    unique_models.dedup();
+    let warmup = Arc::new(Warmup {
+        pending: AtomicUsize::new(unique_models.len()),
+        finished: Mutex::new(Vec::new()),
+    });
+    for model in unique_models {
+        let state = Arc::clone(state);
+        let warmup = Arc::clone(&warmup);
+        thread::spawn(move || {
+            let result = state
+                .load(&model, &resolve_endpoint(None))
+                .map(|_| ())
+                .map_err(|e| e.to_string());
+            warmup.finished.lock().unwrap().push((model, result));
+            warmup.pending.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+    warmup
+}
+
+// Polled from a Lua timer so that `callback(model, err)` runs on the main thread
+struct WarmupHandle {
+    warmup: Arc<Warmup>,
+    callback: Option<LuaFunction>,
+}
+
+impl LuaUserData for WarmupHandle {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("poll", |_, this, ()| {
+            let done = this.warmup.is_done();
+            let finished = std::mem::take(&mut *this.warmup.finished.lock().unwrap());
+            if let Some(callback) = &this.callback {
+                for (model, result) in finished {
+                    callback.call::<()>((model, result.err()))?;
+                }
+            }
+            Ok(done)
+        });
+    }
+}
+
+// The `endpoint` of a Lua options table, a value of the wrong type is an invalid argument
+fn endpoint_option(options: Option<&LuaTable>) -> Result<Option<String>, Error> {
+    let Some(options) = options else {
+        return Ok(None);
+    };
+    options
+        .get::<Option<String>>("endpoint")
+        .map_err(|e| Error::InvalidArgument(format!("Invalid endpoint: {e}")))
 }
 
 #[mlua::lua_module]
@@ -120,18 +445,82 @@ fn avante_tokenizers(lua: &Lua) -> LuaResult<LuaTable> {
     let core = State::new();
     let state = Arc::new(core);
     let state_clone = Arc::clone(&state);
+    let warmup_state = Arc::clone(&state);
+    let info_state = Arc::clone(&state);
+    let compare_state = Arc::clone(&state);
 
     let exports = lua.create_table()?;
     exports.set(
         "from_pretrained",
-        lua.create_function(move |_, model: String| {
-            from_pretrained(&state, model.as_str());
-            Ok(())
+        lua.create_function(move |_, (model, options): (String, Option<LuaTable>)| {
+            let endpoint = endpoint_option(options.as_ref())
+                .map_err(|e| e.into_lua(LUA_MODULE, "from_pretrained"))?;
+            from_pretrained(&state, model.as_str(), endpoint.as_deref())
+                .map_err(|e| e.into_lua(LUA_MODULE, "from_pretrained"))
+        })?,
+    )?;
+    exports.set(
+        "get_tokenizer_info",
+        lua.create_function(move |lua, ()| {
+            let info = info_state.info.lock().unwrap();
+            let Some(info) = info.as_ref() else {
+                return Ok(None);
+            };
+            let table = lua.create_table()?;
+            table.set("model", info.model.as_str())?;
+            table.set("backend", info.backend)?;
+            table.set("endpoint", info.endpoint.as_deref())?;
+            Ok(Some(table))
         })?,
     )?;
     exports.set(
         "encode",
-        lua.create_function(move |_, text: String| encode(&state_clone, text.as_str()))?,
+        lua.create_function(move |_, text: String| {
+            encode(&state_clone, text.as_str()).map_err(|e| e.into_lua(LUA_MODULE, "encode"))
+        })?,
+    )?;
+    exports.set(
+        "compare_tokenizers",
+        lua.create_function(move |_, (text, models): (String, Vec<String>)| {
+            compare_tokenizers(&compare_state, &text, &models)
+                .map_err(|e| e.into_lua(LUA_MODULE, "compare_tokenizers"))
+        })?,
+    )?;
+    exports.set(
+        "validate_tokenizer",
+        lua.create_function(|lua, (model, options): (String, Option<LuaTable>)| {
+            let endpoint = endpoint_option(options.as_ref())
+                .map_err(|e| e.into_lua(LUA_MODULE, "validate_tokenizer"))?;
+            let table = lua.create_table()?;
+            match validate_tokenizer(&model, endpoint.as_deref()) {
+                Ok(validation) => {
+                    table.set("ok", true)?;
+                    table.set("backend", validation.backend)?;
+                    table.set("vocab_size", validation.vocab_size)?;
+                }
+                Err(error) => {
+                    table.set("ok", false)?;
+                    let error = error.into_lua(LUA_MODULE, "validate_tokenizer");
+                    table.set("error", error.to_string())?;
+                }
+            }
+            Ok(table)
+        })?,
+    )?;
+    exports.set(
+        "warmup",
+        lua.create_function(
+            move |_, (models, callback): (Option<Vec<String>>, Option<LuaFunction>)| {
+                let Some(models) = models else {
+                    warmup_current(&warmup_state).map_err(|e| e.into_lua(LUA_MODULE, "warmup"))?;
+                    return Ok(None);
+                };
+                Ok(Some(WarmupHandle {
+                    warmup: warmup(&warmup_state, &models),
+                    callback,
+                }))
+            },
+        )?,
     )?;
     Ok(exports)
 }
@@ -144,7 +533,7 @@ mod tests {
     fn test_tiktoken() {
         let model = "gpt-4o";
         let source = "Hello, world!";
-        let tokenizer = Tiktoken::new(model);
+        let tokenizer = Tiktoken::new(model).unwrap();
         let (tokens, num_tokens, num_chars) = tokenizer.encode(source);
         assert_eq!(tokens, vec![13225, 11, 2375, 0]);
         assert_eq!(num_tokens, 4);
@@ -155,7 +544,7 @@ mod tests {
     fn test_hf() {
         let model = "gpt2";
         let source = "Hello, world!";
-        let tokenizer = HuggingFaceTokenizer::new(model);
+        let tokenizer = HuggingFaceTokenizer::new(model, DEFAULT_HF_ENDPOINT).unwrap();
         let (tokens, num_tokens, num_chars) = tokenizer.encode(source);
         assert_eq!(tokens, vec![15496, 11, 995, 0]);
         assert_eq!(num_tokens, 4);
@@ -168,7 +557,7 @@ mod tests {
         let source = "Hello, world!";
         let model = "gpt2";
 
-        from_pretrained(&state, model);
+        from_pretrained(&state, model, None).unwrap();
         let (tokens, num_tokens, num_chars) = encode(&state, "Hello, world!").unwrap();
         assert_eq!(tokens, vec![15496, 11, 995, 0]);
         assert_eq!(num_tokens, 4);
@@ -187,10 +576,231 @@ mod tests {
         let model =
             "https://storage.googleapis.com/cohere-public/tokenizers/command-r-08-2024.json";
 
-        from_pretrained(&state, model);
+        from_pretrained(&state, model, None).unwrap();
         let (tokens, num_tokens, num_chars) = encode(&state, "Hello, world!").unwrap();
         assert_eq!(tokens, vec![28339, 19, 3845, 8]);
         assert_eq!(num_tokens, 4);
         assert_eq!(num_chars, source.chars().count());
     }
+
+    #[test]
+    fn test_endpoint_override() {
+        assert_eq!(
+            resolve_endpoint(Some("https://hf-mirror.com/")),
+            "https://hf-mirror.com"
+        );
+        let api = hub_api_builder("https://hf-mirror.com").build().unwrap();
+        let url = api.model("gpt2".to_string()).url("tokenizer.json");
+        assert_eq!(
+            url,
+            "https://hf-mirror.com/gpt2/resolve/main/tokenizer.json"
+        );
+
+        assert_eq!(
+            rewrite_hf_url(
+                "https://huggingface.co/gpt2/resolve/main/tokenizer.json",
+                "https://hf-mirror.com"
+            ),
+            "https://hf-mirror.com/gpt2/resolve/main/tokenizer.json"
+        );
+        let other = "https://storage.googleapis.com/cohere-public/tokenizers/command-r.json";
+        assert_eq!(rewrite_hf_url(other, "https://hf-mirror.com"), other);
+        assert_eq!(
+            rewrite_hf_url("https://huggingface.community/x", "https://hf-mirror.com"),
+            "https://huggingface.community/x"
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_info() {
+        let state = State::new();
+        let model = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tokenizer.json");
+        from_pretrained(&state, model, Some("https://hf-mirror.com")).unwrap();
+        let info = state.info.lock().unwrap();
+        let info = info.as_ref().unwrap();
+        assert_eq!(info.model, model);
+        assert_eq!(info.backend, "huggingface");
+        assert_eq!(info.endpoint, None);
+    }
+
+    #[test]
+    #[ignore = "hits the network, run with `cargo test -- --ignored`"]
+    fn test_hf_mirror() {
+        let state = State::new();
+        from_pretrained(&state, "gpt2", Some("https://hf-mirror.com")).unwrap();
+        let (tokens, _, _) = encode(&state, "Hello, world!").unwrap();
+        assert_eq!(tokens, vec![15496, 11, 995, 0]);
+        let info = state.info.lock().unwrap();
+        assert_eq!(
+            info.as_ref().unwrap().endpoint.as_deref(),
+            Some("https://hf-mirror.com")
+        );
+    }
+
+    #[test]
+    fn test_bpe_vocab_and_merges() {
+        let state = State::new();
+        let model = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/gpt2");
+        from_pretrained(&state, model, None).unwrap();
+        let (tokens, num_tokens, num_chars) = encode(&state, "hello world").unwrap();
+        assert_eq!(tokens, vec![11, 16]);
+        assert_eq!(num_tokens, 2);
+        assert_eq!(num_chars, 11);
+        let (tokens, num_tokens, _) = encode(&state, "hello hello").unwrap();
+        assert_eq!(tokens, vec![11, 7, 11]);
+        assert_eq!(num_tokens, 3);
+    }
+
+    #[test]
+    fn test_bpe_missing_merges() {
+        let dir = std::env::temp_dir().join(format!("avante-bpe-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy(
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/gpt2/vocab.json"
+            ),
+            dir.join("vocab.json"),
+        )
+        .unwrap();
+        let error = HuggingFaceTokenizer::new(dir.to_str().unwrap(), DEFAULT_HF_ENDPOINT)
+            .err()
+            .unwrap()
+            .to_string();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(error.contains("found vocab.json"), "{error}");
+        assert!(
+            error.contains("missing tokenizer.json, merges.txt"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_compare_tokenizers() {
+        let state = State::new();
+        let models = vec!["gpt-4o".to_string(), "gpt2".to_string()];
+        let counts = compare_tokenizers(&state, "Hello, world!", &models).unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["gpt-4o"], 4);
+        assert_eq!(counts["gpt2"], 4);
+        assert!(state.tokenizer.lock().unwrap().is_none());
+
+        let missing = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        let error = compare_tokenizers(&state, "Hello", &[missing.to_string()]).unwrap_err();
+        assert!(matches!(error, Error::Parse(_)));
+    }
+
+    #[test]
+    fn test_validate_tokenizer() {
+        let validation = validate_tokenizer("gpt2", None).unwrap();
+        assert_eq!(validation.backend, "huggingface");
+        assert_eq!(validation.vocab_size, Some(50257));
+
+        let validation = validate_tokenizer("gpt-4o", None).unwrap();
+        assert_eq!(validation.backend, "tiktoken");
+        assert_eq!(validation.vocab_size, None);
+    }
+
+    #[test]
+    fn test_validate_missing_tokenizer_path() {
+        // Rejected before the hub is consulted, so this needs no network
+        let missing = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/missing.json");
+        assert!(matches!(
+            validate_tokenizer(missing, None),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_looks_like_path() {
+        assert!(looks_like_path("/tmp/tokenizer.json"));
+        assert!(looks_like_path("./tokenizer.json"));
+        assert!(looks_like_path("../models/gpt2"));
+        assert!(looks_like_path("models/gpt2/tokenizer.json"));
+        assert!(looks_like_path("C:\\models\\tokenizer.json"));
+        assert!(!looks_like_path("gpt2"));
+        assert!(!looks_like_path("Xenova/claude-tokenizer"));
+    }
+
+    #[test]
+    fn test_warmup_deduplicates_concurrent_loads() {
+        let state = Arc::new(State::new());
+        let model = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tokenizer.json");
+        let models = vec![model.to_string(), model.to_string()];
+
+        let warmup = warmup(&state, &models);
+        from_pretrained(&state, model, None).unwrap();
+        while !warmup.is_done() {
+            thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        assert_eq!(state.load_count.load(Ordering::SeqCst), 1);
+        let finished = warmup.finished.lock().unwrap();
+        assert_eq!(finished.len(), 1);
+        assert!(finished[0].1.is_ok());
+        let (tokens, num_tokens, _) = encode(&state, "Hello, world!").unwrap();
+        assert_eq!(tokens, vec![1, 3, 2, 4]);
+        assert_eq!(num_tokens, 4);
+    }
+
+    #[test]
+    fn test_load_cache_per_endpoint() {
+        let state = State::new();
+        let model = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tokenizer.json");
+        state.load(model, "https://huggingface.co").unwrap();
+        state.load(model, "https://huggingface.co").unwrap();
+        assert_eq!(state.load_count.load(Ordering::SeqCst), 1);
+        state.load(model, "https://hf-mirror.com").unwrap();
+        assert_eq!(state.load_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_warmup_current() {
+        let state = State::new();
+        let error = warmup_current(&state).unwrap_err();
+        assert!(error.to_string().contains("no tokenizer loaded"));
+
+        let model = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tokenizer.json");
+        from_pretrained(&state, model, None).unwrap();
+        warmup_current(&state).unwrap();
+        let (tokens, _, _) = encode(&state, "hello world").unwrap();
+        assert_eq!(tokens, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_warmup_reports_failures() {
+        let state = Arc::new(State::new());
+        let model = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        let warmup = warmup(&state, &[model.to_string()]);
+        while !warmup.is_done() {
+            thread::sleep(std::time::Duration::from_millis(5));
+        }
+        let finished = warmup.finished.lock().unwrap();
+        assert!(finished[0].1.is_err());
+        assert!(state.tokenizer.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_lua_error_prefix() {
+        let state = State::new();
+        let error = encode(&state, "hello").unwrap_err();
+        assert!(matches!(error, Error::NotInitialized(_)));
+        assert!(error
+            .into_lua(LUA_MODULE, "encode")
+            .to_string()
+            .contains("avante_tokenizers: encode: Tokenizer not initialized"));
+
+        let model = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        let error = from_pretrained(&state, model, None).unwrap_err();
+        assert!(matches!(error, Error::Parse(_)));
+        assert!(error
+            .into_lua(LUA_MODULE, "from_pretrained")
+            .to_string()
+            .contains("avante_tokenizers: from_pretrained: "));
+
+        assert!(matches!(
+            Tiktoken::new("unknown-model"),
+            Err(Error::Unsupported(_))
+        ));
+    }
 }
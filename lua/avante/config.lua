@@ -369,6 +369,10 @@ M._defaults = {
   -- For most providers that we support we will determine this automatically.
   -- If you wish to use a given implementation, then you can override it here.
   tokenizer = "tiktoken",
+  -- Tokenizers to load in the background during setup, so the first count with them doesn't wait for a download.
+  -- e.g. { "gpt-4o", "meta-llama/Llama-3.1-8B" }
+  ---@type string[]
+  tokenizer_warmup = {},
   system_prompt = nil,
   override_prompt_dir = nil,
   rules = {
@@ -1,5 +1,19 @@
+---@class AvanteHtml2MdOptions
+---@field link_style? "inline" | "reference"
+---@field strip_code_comments? boolean
+---@field keep_only_tags? string[]
+---@field main_content? boolean convert only the main content, like `analyze_page`
+
 ---@class AvanteHtml2Md
----@field fetch_md fun(url: string): string
+---@field fetch_md fun(url: string, opts?: AvanteHtml2MdOptions): string
+---@field html2md fun(html: string, opts?: AvanteHtml2MdOptions): string
+---@field analyze_page fun(html: string): { text: string, word_count: integer, reading_time_minutes: integer, language: string }
+---@field fetch_md_async fun(url: string, callback: fun(md: string|nil, err: string|nil), on_progress?: fun(bytes: integer), opts?: AvanteHtml2MdOptions): AvanteHtml2MdFetchHandle
+
+---@class AvanteHtml2MdFetchHandle
+---@field poll fun(self: AvanteHtml2MdFetchHandle): boolean
+---@field cancel fun(self: AvanteHtml2MdFetchHandle): boolean
+---@field downloaded fun(self: AvanteHtml2MdFetchHandle): integer
 local _html2md_lib = nil
 
 local M = {}
@@ -17,13 +31,52 @@ end
 
 function M.setup() vim.defer_fn(M._init_html2md_lib, 1000) end
 
-function M.fetch_md(url)
+---@param url string
+---@param opts? AvanteHtml2MdOptions
+function M.fetch_md(url, opts)
   local html2md_lib = M._init_html2md_lib()
   if not html2md_lib then return nil, "Failed to load avante_html2md" end
 
-  local ok, res = pcall(html2md_lib.fetch_md, url)
+  local ok, res = pcall(html2md_lib.fetch_md, url, opts)
   if not ok then return nil, res end
   return res, nil
 end
 
+---@param url string
+---@param callback fun(md: string|nil, err: string|nil)
+---@param on_progress? fun(bytes: integer)
+---@param opts? AvanteHtml2MdOptions
+---@return AvanteHtml2MdFetchHandle|nil handle call `handle:cancel()` to abort the download
+function M.fetch_md_async(url, callback, on_progress, opts)
+  local html2md_lib = M._init_html2md_lib()
+  if not html2md_lib then
+    callback(nil, "Failed to load avante_html2md")
+    return nil
+  end
+
+  -- An error raised by the caller's callback must not escape the timer and keep it running
+  local function safe_callback(md, err)
+    local ok, cb_err = pcall(callback, md, err)
+    if not ok then vim.notify("html2md fetch callback failed: " .. tostring(cb_err), vim.log.levels.ERROR) end
+  end
+
+  local handle = html2md_lib.fetch_md_async(url, safe_callback, on_progress, opts)
+  local timer = vim.uv.new_timer()
+  if timer then
+    timer:start(
+      0,
+      50,
+      vim.schedule_wrap(function()
+        local ok, done = pcall(handle.poll, handle)
+        if (not ok or done) and not timer:is_closing() then
+          timer:stop()
+          timer:close()
+        end
+        if not ok then vim.notify("html2md fetch failed: " .. tostring(done), vim.log.levels.ERROR) end
+      end)
+    )
+  end
+  return handle
+end
+
 return M
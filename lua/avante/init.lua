@@ -610,6 +610,7 @@ function M.setup(opts)
   require("avante.diff").setup()
   require("avante.providers").setup()
   require("avante.clipboard").setup()
+  require("avante.tokenizers").warmup(Config.tokenizer_warmup)
 
   -- setup helpers
   H.autocmds()
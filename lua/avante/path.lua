@@ -274,7 +274,7 @@ function Prompt.get_custom_prompts_filepath(mode) return string.format("custom.%
 function Prompt.get_builtin_prompts_filepath(mode) return string.format("%s.avanterules", mode) end
 
 ---@class AvanteTemplates
----@field initialize fun(cache_directory: string, project_directory: string): nil
+---@field initialize fun(cache_directory: string, project_directory: string, capabilities?: table<string, boolean>, render_timeout_ms?: integer): nil
 ---@field render fun(template: string, context: AvanteTemplateOptions): string
 local _templates_lib = nil
 
@@ -412,8 +412,10 @@ function Prompt.render_mode(mode, opts)
   return _templates_lib.render(filepath, opts)
 end
 
-function Prompt.initialize(cache_directory, project_directory)
-  _templates_lib.initialize(cache_directory, project_directory)
+---@param capabilities? table<string, boolean> default capabilities for `has_capability()` in templates
+---@param render_timeout_ms? integer abort renders taking longer than this, defaults to 2000
+function Prompt.initialize(cache_directory, project_directory, capabilities, render_timeout_ms)
+  _templates_lib.initialize(cache_directory, project_directory, capabilities, render_timeout_ms)
 end
 
 P.prompts = Prompt
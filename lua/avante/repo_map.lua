@@ -6,10 +6,57 @@ local filetype_map = {
   ["javascriptreact"] = "javascript",
   ["typescriptreact"] = "typescript",
   ["cs"] = "csharp",
+  ["terraform"] = "hcl",
+  ["sh"] = "bash",
+  ["ps1"] = "powershell",
 }
 
+---@class AvanteRepoMapFunc
+---@field name string
+---@field params string
+---@field return_type string
+---@field accessibility_modifier? string
+---@field generics? string type parameters such as `<T>`
+---@field start_line integer
+---@field end_line integer
+
+---@class AvanteRepoMapTest: AvanteRepoMapFunc params and return_type are empty
+---@field start_byte integer
+
+---@class AvanteRepoMapVariable
+---@field name string
+---@field value_type string
+---@field value? string
+---@field attributes? string[] e.g. `#[serde(default)]` of a Rust field
+---@field start_line integer
+---@field end_line integer
+
+---@class AvanteRepoMapDefinition: AvanteRepoMapFunc, AvanteRepoMapVariable
+---@field kind "func" | "class" | "module" | "enum" | "union" | "variable" | "import" | "namespace"
+---@field type_name? string `class`, `struct`, `interface`... of a class
+---@field visibility_modifier? string
+---@field methods? AvanteRepoMapFunc[]
+---@field properties? AvanteRepoMapVariable[]
+---@field items? AvanteRepoMapVariable[] members of an enum or union
+---@field children? AvanteRepoMapDefinition[] definitions of a namespace
+
 ---@class AvanteRepoMap
----@field stringify_definitions fun(lang: string, source: string): string
+---@field stringify_definitions fun(lang: string, source: string, opts?: { member_order?: "kind" | "source", format?: "compact" | "pretty", max_line_width?: integer, kinds?: ("func" | "class" | "module" | "enum" | "union" | "variable" | "import" | "namespace")[], const_values?: boolean, component_name?: string, docs?: boolean, include_private?: boolean, include_anonymous?: boolean, with_locations?: boolean }): string
+---@field stringify_definitions_with_ranges fun(lang: string, source: string, opts?: { member_order?: "kind" | "source", format?: "compact" | "pretty", max_line_width?: integer, kinds?: ("func" | "class" | "module" | "enum" | "union" | "variable" | "import" | "namespace")[], const_values?: boolean, component_name?: string, docs?: boolean, include_private?: boolean, include_anonymous?: boolean, with_locations?: boolean }): { name: string, kind: string, text: string, start_line: integer, end_line: integer }[]
+---@field project_open fun(root: string, opts?: table): integer
+---@field project_invalidate fun(session_id: integer, paths: string[])
+---@field project_map fun(session_id: integer, budget?: integer): { files: { path: string, lang: string, defs: string, error?: string }[], stale: string[], reparsed: integer }
+---@field project_close fun(session_id: integer): boolean
+---@field get_definitions fun(lang: string, source: string, opts?: { include_calls?: boolean, max_calls?: integer, signatures?: boolean, include_references?: boolean, component_name?: string, include_anonymous?: boolean, small_body_lines?: integer, include_docs?: boolean, include_private?: boolean }): AvanteRepoMapDefinition[]
+---@field get_tests fun(lang: string, source: string): AvanteRepoMapTest[]
+---@field list_exports fun(lang: string, source: string): { name: string, kind: string, line: integer }[]
+---@field definition_stats fun(lang: string, source: string): table<"func" | "class" | "module" | "enum" | "union" | "variable" | "import" | "namespace", integer>
+---@field definitions_to_msgpack fun(lang: string, source: string): string
+---@field definitions_to_json fun(lang: string, source: string): string
+---@field signature_hashes fun(lang: string, source: string): table<string, string>
+---@field chunk_by_definitions fun(lang: string, source: string, max_tokens: integer, count_tokens?: fun(text: string): integer): { start_line: integer, end_line: integer, text: string, tokens: integer }[]
+---@field guess_language fun(source: string): string|nil
+---@field map_repository fun(root: string, opts?: { path_style?: "absolute" | "relative" | "basename" | "redacted", include_module_doc?: boolean, module_doc_max_chars?: integer, include_checksum?: boolean }): { path: string, lang: string, defs: string, module_doc?: string, checksum?: string, error?: string }[] Walks the tree like git, skipping ignored files and symlinks; `checksum` is the `sha256:` prefixed digest of the content, `error` reports a file whose definitions could not be extracted
 local repo_map_lib = nil
 
 local RepoMap = {}
@@ -29,6 +76,8 @@ function RepoMap.setup() vim.defer_fn(RepoMap._init_repo_map_lib, 1000) end
 
 function RepoMap.get_ts_lang(filepath)
   local filetype = Utils.get_filetype(filepath)
+  -- OCaml interfaces share the filetype of implementations but need their own grammar
+  if filetype == "ocaml" and vim.endswith(filepath, ".mli") then return "ocaml_interface" end
   return filetype_map[filetype] or filetype
 end
 
@@ -48,7 +97,9 @@ function RepoMap._build_repo_map(project_root, file_ext)
     local filetype = RepoMap.get_ts_lang(filepath)
     local lines = Utils.read_file_from_buf_or_disk(filepath)
     local content = lines and table.concat(lines, "\n") or ""
-    local definitions = filetype and repo_map_lib.stringify_definitions(filetype, content) or ""
+    -- Vue components without a `name` are known by their file name
+    local opts = { component_name = vim.fn.fnamemodify(filepath, ":t:r"), include_anonymous = true }
+    local definitions = filetype and repo_map_lib.stringify_definitions(filetype, content, opts) or ""
     if definitions == "" then return end
     table.insert(output, {
       path = Utils.relative_path(filepath),
@@ -124,7 +175,8 @@ function RepoMap._get_repo_map(file_ext)
       local abs_filepath = vim.fs.abspath(vim.fs.joinpath(project_root, rel_filepath))
       local lines = Utils.read_file_from_buf_or_disk(abs_filepath)
       local content = lines and table.concat(lines, "\n") or ""
-      local definitions = repo_map_lib.stringify_definitions(RepoMap.get_ts_lang(abs_filepath), content)
+      local opts = { component_name = vim.fn.fnamemodify(abs_filepath, ":t:r"), include_anonymous = true }
+      local definitions = repo_map_lib.stringify_definitions(RepoMap.get_ts_lang(abs_filepath), content, opts)
       if definitions == "" then return end
       local found = false
       for _, m in ipairs(repo_map) do
@@ -1,8 +1,12 @@
 local Utils = require("avante.utils")
 
 ---@class AvanteTokenizer
----@field from_pretrained fun(model: string): nil
+---@field from_pretrained fun(model: string, opts?: { endpoint?: string }): nil
+---@field get_tokenizer_info fun(): { model: string, backend: "tiktoken" | "huggingface", endpoint?: string }|nil
 ---@field encode fun(string): integer[]
+---@field warmup fun(models?: string[], callback?: fun(model: string, err: string|nil)): { poll: fun(self): boolean }|nil
+---@field compare_tokenizers fun(text: string, models: string[]): table<string, integer>
+---@field validate_tokenizer fun(model: string, opts?: { endpoint?: string }): { ok: boolean, backend?: "tiktoken" | "huggingface", vocab_size?: integer, error?: string }
 local tokenizers = nil
 
 ---@type "gpt-4o" | string
@@ -22,6 +26,8 @@ function M._init_tokenizers_lib(model)
   tokenizers = core
 
   core.from_pretrained(model)
+  -- Encode a tiny sample now so the first real count doesn't stall
+  pcall(core.warmup)
 
   return tokenizers
 end
@@ -44,6 +50,60 @@ function M.setup(model, warning)
   end
 end
 
+---Load tokenizers in the background so the first encode doesn't pay for the download
+---@param models string[]
+---@param callback? fun(model: string, err: string|nil)
+function M.warmup(models, callback)
+  if not models or #models == 0 then return end
+
+  local ok, core = pcall(require, "avante_tokenizers")
+  if not ok then return end
+
+  local handle = core.warmup(models, callback)
+  if not handle then return end
+  local timer = vim.uv.new_timer()
+  if not timer then return end
+  timer:start(
+    0,
+    100,
+    vim.schedule_wrap(function()
+      local ok, done = pcall(handle.poll, handle)
+      if (not ok or done) and not timer:is_closing() then
+        timer:stop()
+        timer:close()
+      end
+      if not ok then Utils.warn("Tokenizer warmup failed: " .. tostring(done)) end
+    end)
+  )
+end
+
+---Count the tokens of the same text with several tokenizers, e.g. to compare provider costs
+---@param text string
+---@param models string[]
+---@return table<string, integer>|nil
+function M.compare(text, models)
+  local ok, core = pcall(require, "avante_tokenizers")
+  if not ok then return nil end
+
+  local success, result = pcall(core.compare_tokenizers, text, models)
+  if not success then
+    Utils.warn("Failed to compare tokenizers: " .. result)
+    return nil
+  end
+  return result
+end
+
+---Check that a model, path or URL yields a tokenizer without switching to it
+---@param model string
+---@param opts? { endpoint?: string }
+---@return { ok: boolean, backend?: "tiktoken" | "huggingface", vocab_size?: integer, error?: string }|nil
+function M.validate(model, opts)
+  local ok, core = pcall(require, "avante_tokenizers")
+  if not ok then return nil end
+
+  return core.validate_tokenizer(model, opts)
+end
+
 function M.available() return M._init_tokenizers_lib(current_model) ~= nil end
 
 ---@param prompt string
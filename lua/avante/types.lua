@@ -417,6 +417,7 @@ vim.g.avante_login = vim.g.avante_login
 ---@field update_todos? fun(todos: avante.TODO[]): nil
 ---@field memory string | nil
 ---@field get_tokens_usage? fun(): avante.LLMTokenUsage | nil
+---@field capabilities? table<string, boolean> provider capabilities for `has_capability()` in templates
 ---
 ---@class AvanteGeneratePromptsOptions: AvanteTemplateOptions
 ---@field instructions? string